@@ -21,16 +21,16 @@ use crate::core::core::Block;
 use crate::handlers::blocks_api::{BlockHandler, HeaderHandler};
 use crate::handlers::chain_api::{ChainHandler, KernelHandler, OutputHandler};
 use crate::handlers::mining_api::{
-	BlockTemplate, CoinbaseData, FinalizedBlockTemplate, MiningHandler,
+	BlockTemplate, BlockTemplates, CoinbaseData, FinalizedBlockTemplate, MiningHandler,
 };
 use crate::handlers::pool_api::PoolHandler;
 use crate::handlers::transactions_api::TxHashSetHandler;
 use crate::handlers::version_api::VersionHandler;
-use crate::pool::{self, BlockChain, PoolAdapter, PoolEntry};
+use crate::pool::{self, BlockChain, FeeEstimate, PoolAdapter, PoolEntry, TxAcceptance};
 use crate::rest::*;
 use crate::types::{
-	BlockHeaderPrintable, BlockPrintable, LocatedTxKernel, OutputListing, OutputPrintable, Tip,
-	Version,
+	AlgoStatsListing, BlockFilterPrintable, BlockHeaderPrintable, BlockPrintable, LocatedTxKernel,
+	OutputHistory, OutputListing, OutputPrintable, PolicySchedule, Tip, Version,
 };
 use crate::util::RwLock;
 use epic_core::core::TxKernel;
@@ -51,6 +51,7 @@ where
 	pub chain: Weak<Chain>,
 	pub tx_pool: Weak<RwLock<pool::TransactionPool<B, P>>>,
 	pub sync_state: Weak<SyncState>,
+	pub block_templates: Weak<BlockTemplates>,
 }
 
 impl<B, P> Foreign<B, P>
@@ -66,6 +67,7 @@ where
 	/// * `tx_pool` - A non-owning reference of the transaction pool.
 	/// * `peers` - A non-owning reference of the peers.
 	/// * `sync_state` - A non-owning reference of the `sync_state`.
+	/// * `block_templates` - A non-owning reference of the issued block templates.
 	///
 	/// # Returns
 	/// * An instance of the Node holding references to the current chain, transaction pool, peers and sync_state.
@@ -75,11 +77,13 @@ where
 		chain: Weak<Chain>,
 		tx_pool: Weak<RwLock<pool::TransactionPool<B, P>>>,
 		sync_state: Weak<SyncState>,
+		block_templates: Weak<BlockTemplates>,
 	) -> Self {
 		Foreign {
 			chain,
 			tx_pool,
 			sync_state,
+			block_templates,
 		}
 	}
 
@@ -137,6 +141,78 @@ where
 		block_handler.get_block(&hash, true, true)
 	}
 
+	/// Gets the compact filter of a block given either its height or its hash.
+	/// Light clients match their output commitments and kernel excesses against
+	/// it to find the blocks they need, and verify it with the filter header.
+	///
+	/// # Arguments
+	/// * `height` - block height.
+	/// * `hash` - block hash.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`BlockFilterPrintable`](types/struct.BlockFilterPrintable.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_block_filter(
+		&self,
+		height: Option<u64>,
+		hash: Option<Hash>,
+	) -> Result<BlockFilterPrintable, Error> {
+		let block_handler = BlockHandler {
+			chain: self.chain.clone(),
+		};
+		let hash = block_handler.parse_inputs(height, hash, None)?;
+		block_handler.get_block_filter(&hash)
+	}
+
+	/// Returns the blocks of the current chain with a timestamp in the given
+	/// range, in timestamp order. Blocks pruned from the node are left out and
+	/// at most 1000 blocks are returned, narrow the range to get the rest.
+	///
+	/// # Arguments
+	/// * `start` - start of the range, in seconds since the unix epoch.
+	/// * `end` - end of the range (inclusive), in seconds since the unix epoch.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`BlockPrintable`](types/struct.BlockPrintable.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_blocks_by_time(&self, start: i64, end: i64) -> Result<Vec<BlockPrintable>, Error> {
+		let block_handler = BlockHandler {
+			chain: self.chain.clone(),
+		};
+		block_handler.get_blocks_by_time(start, end)
+	}
+
+	/// Returns, for each PoW algorithm, the number of blocks, average solve
+	/// time and difficulty and the total fees of the blocks in the given
+	/// height range (at most 10000 blocks).
+	///
+	/// # Arguments
+	/// * `from_height` - first height of the range.
+	/// * `to_height` - last height of the range, capped to the chain head.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * An [`AlgoStatsListing`](types/struct.AlgoStatsListing.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_algo_stats(
+		&self,
+		from_height: u64,
+		to_height: u64,
+	) -> Result<AlgoStatsListing, Error> {
+		let chain_handler = ChainHandler {
+			chain: self.chain.clone(),
+		};
+		chain_handler.get_algo_stats(from_height, to_height)
+	}
+
 	/// Returns a block template for mining (header, txs, difficulty, etc.)
 	///
 	/// # Returns
@@ -148,10 +224,33 @@ where
 		let mining_handler = MiningHandler {
 			chain: self.chain.clone(),
 			tx_pool: self.tx_pool.clone(),
+			block_templates: self.block_templates.clone(),
 		};
 		mining_handler.get_block_template()
 	}
 
+	/// Waits until the block template is outdated, as the chain tip changed or
+	/// new transactions came into the mempool, and returns the new one. Returns
+	/// a template after a minute at most. Fails when too many long polls are
+	/// already waiting.
+	///
+	/// # Arguments
+	/// * `template_id` - id of the current block template.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`BlockTemplate`](struct.BlockTemplate.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+	pub fn get_block_template_long_poll(&self, template_id: &str) -> Result<BlockTemplate, Error> {
+		let mining_handler = MiningHandler {
+			chain: self.chain.clone(),
+			tx_pool: self.tx_pool.clone(),
+			block_templates: self.block_templates.clone(),
+		};
+		mining_handler.get_block_template_long_poll(template_id)
+	}
+
 	/// Returns a finalized block template for mining (header, txs, difficulty, etc.)
 	/// This includes the coinbase output and kernel.
 	/// # Returns
@@ -165,6 +264,7 @@ where
 		let mining_handler = MiningHandler {
 			chain: self.chain.clone(),
 			tx_pool: self.tx_pool.clone(),
+			block_templates: self.block_templates.clone(),
 		};
 		// Pass the required argument, e.g., None if the argument is Option<T>
 		mining_handler.finalize_block_template(coinbase)
@@ -181,10 +281,32 @@ where
 		let mining_handler = MiningHandler {
 			chain: self.chain.clone(),
 			tx_pool: self.tx_pool.clone(),
+			block_templates: self.block_templates.clone(),
 		};
 		mining_handler.submit_block(block)
 	}
 
+	/// Submits a block mined from a block template to the node for
+	/// processing, rejecting it if the template is stale.
+	///
+	/// # Arguments
+	/// * `block` - the mined block.
+	/// * `template_id` - id of the block template the block was mined from.
+	///
+	/// #Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the block was submitted successfully
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+	pub fn submit_block_with_template(&self, block: Block, template_id: &str) -> Result<(), Error> {
+		let mining_handler = MiningHandler {
+			chain: self.chain.clone(),
+			tx_pool: self.tx_pool.clone(),
+			block_templates: self.block_templates.clone(),
+		};
+		mining_handler.submit_block_with_template(block, template_id)
+	}
+
 	/// Returns the node version and block header version (used by epic-wallet).
 	///
 	/// # Returns
@@ -215,6 +337,22 @@ where
 		chain_handler.get_tip()
 	}
 
+	/// Returns the block policy schedule: the target algorithm mix of every
+	/// era, the height it applies from and the policy of the next block.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`PolicySchedule`](types/struct.PolicySchedule.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_policy_schedule(&self) -> Result<PolicySchedule, Error> {
+		let chain_handler = ChainHandler {
+			chain: self.chain.clone(),
+		};
+		chain_handler.get_policy_schedule()
+	}
+
 	/// Returns a [`LocatedTxKernel`](types/struct.LocatedTxKernel.html) based on the kernel excess.
 	/// The `min_height` and `max_height` parameters are both optional.
 	/// If not supplied, `min_height` will be set to 0 and `max_height` will be set to the head of the chain.
@@ -291,6 +429,26 @@ where
 		)
 	}
 
+	/// Returns where every output created with the given commitment was
+	/// created and, if spent, where it was spent. Only available on archive
+	/// nodes.
+	///
+	/// # Arguments
+	/// * `commit` - the output commitment, as hex.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`OutputHistory`](types/struct.OutputHistory.html), in creation order
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_output_history(&self, commit: String) -> Result<Vec<OutputHistory>, Error> {
+		let output_handler = OutputHandler {
+			chain: self.chain.clone(),
+		};
+		output_handler.get_output_history(commit)
+	}
+
 	/// UTXO traversal. Retrieves last utxos since a `start_index` until a `max`.
 	///
 	/// # Arguments
@@ -387,6 +545,46 @@ where
 		pool_handler.get_unconfirmed_transactions()
 	}
 
+	/// Estimates the fee for a transaction to confirm within a number of
+	/// blocks, from the fees of the transactions recently confirmed out of the
+	/// transaction pool and the current pool backlog.
+	///
+	/// # Arguments
+	/// * `target_blocks` - number of blocks to confirm within, from 1 to 24.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`FeeEstimate`](types/struct.FeeEstimate.html), the fee per weight
+	/// unit with a histogram of the transactions in the pool by fee
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+	pub fn estimate_fee(&self, target_blocks: u64) -> Result<FeeEstimate, Error> {
+		let pool_handler = PoolHandler {
+			tx_pool: self.tx_pool.clone(),
+		};
+		pool_handler.estimate_fee(target_blocks)
+	}
+
+	/// Runs a transaction through the checks of the transaction pool without
+	/// adding it: validation, lock height, coinbase maturity, fee, pool
+	/// capacity and chain state, and looks for conflicting pool transactions.
+	///
+	/// # Arguments
+	/// * `tx` - the Epic transaction to check.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`TxAcceptance`](types/struct.TxAcceptance.html), the outcome of
+	/// each check and the transactions of the pool the transaction conflicts with
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+	pub fn test_transaction_accept(&self, tx: Transaction) -> Result<TxAcceptance, Error> {
+		let pool_handler = PoolHandler {
+			tx_pool: self.tx_pool.clone(),
+		};
+		pool_handler.test_transaction_accept(tx)
+	}
+
 	/// Push new transaction to our local transaction pool.
 	///
 	/// # Arguments
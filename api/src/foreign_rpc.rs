@@ -20,11 +20,11 @@ use crate::core::core::Block;
 use crate::foreign::Foreign;
 
 use crate::handlers::mining_api::{BlockTemplate, CoinbaseData, FinalizedBlockTemplate};
-use crate::pool::{BlockChain, PoolAdapter, PoolEntry};
+use crate::pool::{BlockChain, FeeEstimate, PoolAdapter, PoolEntry, TxAcceptance};
 use crate::rest::Error;
 use crate::types::{
-	BlockHeaderPrintable, BlockPrintable, LocatedTxKernel, OutputListing, OutputPrintable, Tip,
-	Version,
+	AlgoStatsListing, BlockFilterPrintable, BlockHeaderPrintable, BlockPrintable, LocatedTxKernel,
+	OutputHistory, OutputListing, OutputPrintable, PolicySchedule, Tip, Version,
 };
 use crate::util;
 use epic_core::core::TxKernel;
@@ -269,6 +269,125 @@ pub trait ForeignRpc: Sync + Send {
 		commit: Option<String>,
 	) -> Result<Vec<BlockPrintable>, Error>;
 
+	/**
+	Networked version of [Foreign::get_block_filter](struct.Node.html#method.get_block_filter).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_block_filter",
+		"params": [374568, null],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"block_hash": "0a2ee3f5b5f5b2fb1d1e2a5e0d1be6a4b1b9bbd3e7c3ab59c6b1b93b6f8c4e21",
+				"filter": "8a6f3c1ae3d080",
+				"filter_header": "6be1f4b0a1c3e58e2d0f9a3c7b46d2e1f0c8a9b7d6e5f4a3b2c1d0e9f8a7b6c5",
+				"height": 374568,
+				"n": 2
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_block_filter(
+		&self,
+		height: Option<u64>,
+		hash: Option<String>,
+	) -> Result<BlockFilterPrintable, Error>;
+
+	/**
+	Networked version of [Foreign::get_blocks_by_time](struct.Node.html#method.get_blocks_by_time).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_blocks_by_time",
+		"params": [1735689600, 1735689660],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": []
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_blocks_by_time(&self, start: i64, end: i64) -> Result<Vec<BlockPrintable>, Error>;
+
+	/**
+	Networked version of [Foreign::get_algo_stats](struct.Node.html#method.get_algo_stats).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_algo_stats",
+		"params": [1000, 1009],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"from_height": 1000,
+				"to_height": 1009,
+				"algos": {
+					"randomx": {
+						"blocks": 6,
+						"avg_solve_time": 58.5,
+						"avg_difficulty": 18446744,
+						"total_fees": 8000000
+					},
+					"progpow": {
+						"blocks": 4,
+						"avg_solve_time": 61.25,
+						"avg_difficulty": 2199023255,
+						"total_fees": 0
+					}
+				},
+				"total_fees": 8000000,
+				"pruned_blocks": 0
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_algo_stats(&self, from_height: u64, to_height: u64) -> Result<AlgoStatsListing, Error>;
+
 	/**
 	Networked version of [Foreign::get_version](struct.Node.html#method.get_version).
 
@@ -337,6 +456,57 @@ pub trait ForeignRpc: Sync + Send {
 	 */
 	fn get_tip(&self) -> Result<Tip, Error>;
 
+	/**
+	Networked version of [Foreign::get_policy_schedule](struct.Node.html#method.get_policy_schedule).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_policy_schedule",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"height": 1200,
+				"next_policy": 1,
+				"eras": [
+					{
+						"policy": 0,
+						"start_height": 0,
+						"end_height": 999,
+						"weights": {"randomx": 60, "progpow": 38, "cuckatoo": 2, "cuckaroo": 0}
+					},
+					{
+						"policy": 1,
+						"start_height": 1000,
+						"end_height": null,
+						"weights": {"randomx": 50, "progpow": 0, "cuckatoo": 50, "cuckaroo": 0}
+					}
+				],
+				"allowed_policies": [
+					{"height": 0, "value": 1},
+					{"height": 1000, "value": 3}
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_policy_schedule(&self) -> Result<PolicySchedule, Error>;
+
 	/**
 	Networked version of [Foreign::get_kernel](struct.Node.html#method.get_kernel).
 
@@ -502,6 +672,46 @@ pub trait ForeignRpc: Sync + Send {
 		include_merkle_proof: Option<bool>,
 	) -> Result<Vec<OutputPrintable>, Error>;
 
+	/**
+	Networked version of [Foreign::get_output_history](struct.Node.html#method.get_output_history).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_output_history",
+		"params": [
+			"09bab2bdba2e6aed690b5eda11accc13c06723ca5965bb460c5f2383655989af3f"
+		],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				{
+					"commit": "09bab2bdba2e6aed690b5eda11accc13c06723ca5965bb460c5f2383655989af3f",
+					"created_block": "0a2ee3f5b5f5b2fb1d1e2a5e0d1be6a4b1b9bbd3e7c3ab59c6b1b93b6f8c4e21",
+					"created_height": 374568,
+					"spent_block": "4f0a3e8cd1b6a5d0b3b6c1e6f7a3d2c9e3b2f1a0d9c8b7a6f5e4d3c2b1a09f8e",
+					"spent_height": 376160
+				}
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_output_history(&self, commit: String) -> Result<Vec<OutputHistory>, Error>;
+
 	/**
 	Networked version of [Foreign::get_unspent_outputs](struct.Node.html#method.get_unspent_outputs).
 
@@ -733,6 +943,150 @@ pub trait ForeignRpc: Sync + Send {
 	 */
 	fn get_unconfirmed_transactions(&self) -> Result<Vec<PoolEntry>, Error>;
 
+	/**
+	Networked version of [Foreign::estimate_fee](struct.Node.html#method.estimate_fee).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "estimate_fee",
+		"params": [3],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"target_blocks": 3,
+				"fee_base": 100000,
+				"from_history": false,
+				"histogram": []
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn estimate_fee(&self, target_blocks: u64) -> Result<FeeEstimate, Error>;
+
+	/**
+	Networked version of [Foreign::test_transaction_accept](struct.Node.html#method.test_transaction_accept).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "test_transaction_accept",
+		"params": [ {
+		"body": {
+				"inputs": [
+				{
+					"commit": "0904cbd34d0745eb00ffc3e95c9f4746738794d00268e243e9b57163a73b384102",
+					"features": "Coinbase"
+				}
+				],
+				"kernels": [
+				{
+					"excess": "08385257d22f1b8a758903f78ae12545245d620cffc50e7ee7bc852c5815513dc7",
+					"excess_sig": "e001a7349fd40d4a9dfc1df275d30906fb3b304f8c7892a20ed5c9b10923c871cbabedcf322511a9ce56f10113b48855441f681280133e121b25ea1ff7efad9e",
+					"features": {
+					"Plain": {
+						"fee": 8000000
+					}
+					}
+				}
+				],
+				"outputs": [
+				{
+					"commit": "087c3ca7419751e96cdae4908bb8a92fc2826f2ad36690420b905d51beb7409ca0",
+					"features": "Plain",
+					"proof": "379ae236937883c2e1e613fb30f1b18d2a44d4173360e94bcd07862aafaf81b3aaa1154d67287cc03efde0d3981c6da8a18e2e426f5c30afc0f2e3a75012448402d8d56df52b87f4815575a56d4da174f8187e4faae64bf883b249ceed694271f84ef62a3711d36c997dff7a11111419011e36e3a070b7552415a55faaa3999f99439edccdfe5313277147fdb42be1798442bb225c2b546f5347920584b365aa81a0365b4a706c97c89617b0e6218d2c9bc15805caab27c438ed06340cc4f8dc7bfca0e9d38864c88bb0c834372f6b662b9159134f3f8ec9b8a87878739a7e516b97419ac29e1d4a2b250321470a9a6b98d07065bb7e79afc25a5ab6fc47108f53223078a64502bd4af1a109641447dab82741ebe3fbdbd803ee7a42fe2554e78fa86bd1d1e6e3b913118e9419b0be6f976b2404447d943b5f1bac19a5809fd6834797945a62d21b1ecb6ddebbc5ef94ca9e704d033bd64afde67bd3e06e2cca3bb10190188afc0af80b48dd862b86753d8b4af314763324deb1c97cf020cb87285a47cd28874bb91c6cdf858965e8b9daafbcbc1b4817d334a97d7e25e01b2d072d8dcc6418e3dc7b8e7712632f939238e65ed0731c7af02d55a8884cd8f7f88dc0f63a21955a7364562532f5716c89e14f8f23ad78f6fe2f1649e13ea8f8185f3ee63cc174684d1ef8d8c33fb25bc802f8e05e53fe200b1ea5231f588a020942e6fd7eec67301700088dae8816c16a337120063c21e1604e009df932032812f88be6473af13f802b42d8ad6fc14230fbe13ede178319a7b6540656234ec1f2fcfa70f6faa9c4b6b8150b81fe0fdc273a9bb385d766a02041a5c3f58471d42059c17d84d13ad592aa0ccf337970e7eef06f306b13288795123c9c005b815d848f359b23450656b310f09cda9ad4b7b6931805d47dcd10a8745d834a984e2055168ac3"
+				},
+				{
+					"commit": "09a7b2c1d4b346c4ebe9c6c979e32e7740446624d5439d9d7abb82166c2545e5be",
+					"features": "Plain",
+					"proof": "5fb0ee4093a153e2ed173207dbfa02b4d185f1f313ea4cbf222558819074543f19e9bcdb595a23d4ee971aafcc614b6d2774e22cee6627bc4388297fe6ebf03e0d422f3eb8003cc8516417a6b32eb22f87e1745e0ae5bf1733f2ea253399719b1ef0067934dc548c58729604d24a44040165b32d05e82c9efc9a1f30151dd73ce893ae94709ec2fe5d0f409bb54a86604f0e92915b4f93e7adde823eccf87830ae91d71a7b99967dbcc8531fee44c20c24fb6fe2a34fe86ba5da3a9235cbcdcde033ead57d65c03903a9c9ed877bf0fab9f26d08552c64ea668d5408c84b74bc3ac8335aaaa04ebcf523d36d2207fb8770e976b6fde7d04e2148de5a4169c60b1958bb840b79a8c8f356e1f1fadc35a5a7e276fcd67c354cde546548c9bf788981f38edf5a406977826aa4524004e770b3d3cd6b26f0dc99729ffd9929fa4509b145ef0c3e4293e71b964da731a47cc9f082350acf32afb64b3b12f8383c8f2cc9880131a80ea957b2908c92f21d2db7aa5d67bafb11eb07674e52b920e67a86259dd9c5dcdd18bad182fd85ec4b659c47ea2e2e8a89c57e4d2cde87958fc2ab932e169f6805d2fb14549ac93807bc426eb4cf6d29ff6a4cf22e35dbb27f04211b06b65173501c17a3bb3ff0eecc9bb05dca23379abe457ca3010ebea69e1a2f7f3ed6531bf766007cdd1ac7d6c762785fb56f36194cc2ccaee76a499a7383288e84981b103d76cbe007f66c913eacb277746e78ae08627b279ac1f9a43ab284d8a3b32c6edcd2ea99e8ea836b31a1e2582be6c41f2282cf5fc7bdb95e4b412a5eeccad29670197873a888a100c4b2704ce75137fc997a5632d81001f9b57300a9bf99edd857065be83f835e4c49d852165ba18e1c96316c153459a913773d5d86ddc26c5cd1fff38a8fbb62506b0aef6076382674c0fa95a50a03b0c3df0a688a2cbf"
+				}
+				]
+			},
+			"offset": "0ec14d3875ad5a366418256fe65bad2a4d4ff1914e1b9488db72dd355138ca3a"
+			}
+		],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"accepted": false,
+				"fee": 8000000,
+				"min_fee": 800000,
+				"checks": [
+				{
+					"check": "Weight",
+					"passed": true,
+					"error": null
+				},
+				{
+					"check": "LockHeight",
+					"passed": true,
+					"error": null
+				},
+				{
+					"check": "CoinbaseMaturity",
+					"passed": true,
+					"error": null
+				},
+				{
+					"check": "Fee",
+					"passed": true,
+					"error": null
+				},
+				{
+					"check": "Capacity",
+					"passed": true,
+					"error": null
+				},
+				{
+					"check": "ChainState",
+					"passed": false,
+					"error": "General pool error: failed to validate tx: Duplicate commitment"
+				}
+				],
+				"conflicts": [
+				{
+					"tx_hash": "4f12a36a2a33cce1d6ae82e2c3a3ea1dbb7d0ba35fd0a2a5b6d18d1da7c0e5d4",
+					"kernels": [
+						"08385257d22f1b8a758903f78ae12545245d620cffc50e7ee7bc852c5815513dc7"
+					],
+					"conflict": "DuplicateTx"
+				}
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn test_transaction_accept(&self, tx: Transaction) -> Result<TxAcceptance, Error>;
+
 	/**
 	Networked version of [Foreign::push_transaction](struct.Node.html#method.push_transaction).
 
@@ -799,7 +1153,174 @@ pub trait ForeignRpc: Sync + Send {
 	fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), Error>;
 
 	fn get_block_template(&self) -> Result<BlockTemplate, Error>;
+	/**
+	Networked version of [Foreign::get_block_template_long_poll](struct.Foreign.html#method.get_block_template_long_poll).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_block_template_long_poll",
+		"params": [
+			"036d53a2a2f70c68c65f5d785bd1155b45b13255701f6daace2cded6de629d71-3"
+		],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"template_id": "9f2e6d33a3717ee826353a404ba4618d1aeeb6879ad7936bce8ed5f46814924d-4",
+				"header": {
+					"version": 7,
+					"height": 1001,
+					"prev_hash": [159, 46, 109, 51, 163, 113, 126, 232, 38, 53, 58, 64, 75, 164, 97, 141, 26, 238, 182, 135, 154, 215, 147, 107, 206, 142, 213, 244, 104, 20, 146, 77],
+					"prev_root": [190, 107, 27, 32, 108, 74, 171, 44, 44, 23, 118, 212, 136, 0, 62, 25, 187, 7, 75, 203, 26, 179, 113, 24, 217, 110, 95, 142, 132, 140, 178, 65],
+					"timestamp": "2019-10-03T16:09:11+00:00",
+					"output_root": [107, 93, 184, 75, 103, 31, 89, 196, 123, 34, 25, 73, 35, 126, 154, 73, 216, 207, 122, 187, 221, 179, 181, 152, 172, 233, 115, 217, 32, 233, 12, 200],
+					"range_proof_root": [211, 23, 254, 134, 9, 175, 36, 222, 2, 224, 108, 13, 25, 7, 156, 115, 193, 172, 242, 111, 10, 43, 170, 252, 199, 60, 182, 53, 42, 145, 189, 179],
+					"kernel_root": [195, 167, 245, 59, 200, 126, 206, 142, 165, 44, 156, 72, 139, 108, 106, 66, 139, 0, 117, 179, 59, 18, 7, 9, 99, 231, 90, 88, 179, 100, 160, 203],
+					"total_kernel_offset": [159, 167, 150, 70, 128, 147, 13, 197, 225, 153, 184, 72, 80, 30, 42, 159, 146, 222, 66, 85, 60, 215, 144, 130, 249, 92, 178, 251, 160, 103, 40, 23],
+					"output_mmr_size": 5000,
+					"kernel_mmr_size": 2501,
+					"pow": {
+						"total_difficulty": {
+							"cuckatoo": 2180330,
+							"randomx": 50102378193,
+							"progpow": 2119061232094
+						},
+						"secondary_scaling": 0,
+						"nonce": 1337,
+						"proof": {
+							"RandomXProof": {
+								"hash": [231, 254, 86, 42, 77, 67, 219, 93, 58, 94, 126, 208, 191, 74, 236, 14, 137, 178, 206, 83, 125, 74, 12, 79, 234, 83, 26, 197, 105, 103, 30, 20]
+							}
+						},
+						"seed": [25, 178, 88, 86, 225, 193, 80, 202, 131, 76, 255, 200, 181, 155, 35, 173, 189, 14, 192, 56, 158, 88, 235, 34, 179, 182, 71, 104, 9, 141, 0, 43]
+					},
+					"policy": 0,
+					"bottles": {
+						"cuckatoo": 1,
+						"randomx": 47,
+						"progpow": 52
+					}
+				},
+				"transactions": [],
+				"height": 1001,
+				"algorithm": "randomx",
+				"block_difficulty": {
+					"cuckatoo": 1,
+					"randomx": 48019611,
+					"progpow": 2013372117
+				},
+				"epochs": [
+					[
+						0,
+						4096,
+						[25, 178, 88, 86, 225, 193, 80, 202, 131, 76, 255, 200, 181, 155, 35, 173, 189, 14, 192, 56, 158, 88, 235, 34, 179, 182, 71, 104, 9, 141, 0, 43]
+					]
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_block_template_long_poll(&self, template_id: String) -> Result<BlockTemplate, Error>;
 	fn submit_block(&self, block: Block) -> Result<(), Error>;
+
+	/**
+	Networked version of [Foreign::submit_block_with_template](struct.Foreign.html#method.submit_block_with_template).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "submit_block_with_template",
+		"params": [
+			{
+			"header": {
+				"version": 7,
+				"height": 1001,
+				"prev_hash": [159, 46, 109, 51, 163, 113, 126, 232, 38, 53, 58, 64, 75, 164, 97, 141, 26, 238, 182, 135, 154, 215, 147, 107, 206, 142, 213, 244, 104, 20, 146, 77],
+				"prev_root": [190, 107, 27, 32, 108, 74, 171, 44, 44, 23, 118, 212, 136, 0, 62, 25, 187, 7, 75, 203, 26, 179, 113, 24, 217, 110, 95, 142, 132, 140, 178, 65],
+				"timestamp": "2019-10-03T16:09:11+00:00",
+				"output_root": [107, 93, 184, 75, 103, 31, 89, 196, 123, 34, 25, 73, 35, 126, 154, 73, 216, 207, 122, 187, 221, 179, 181, 152, 172, 233, 115, 217, 32, 233, 12, 200],
+				"range_proof_root": [211, 23, 254, 134, 9, 175, 36, 222, 2, 224, 108, 13, 25, 7, 156, 115, 193, 172, 242, 111, 10, 43, 170, 252, 199, 60, 182, 53, 42, 145, 189, 179],
+				"kernel_root": [195, 167, 245, 59, 200, 126, 206, 142, 165, 44, 156, 72, 139, 108, 106, 66, 139, 0, 117, 179, 59, 18, 7, 9, 99, 231, 90, 88, 179, 100, 160, 203],
+				"total_kernel_offset": [159, 167, 150, 70, 128, 147, 13, 197, 225, 153, 184, 72, 80, 30, 42, 159, 146, 222, 66, 85, 60, 215, 144, 130, 249, 92, 178, 251, 160, 103, 40, 23],
+				"output_mmr_size": 5003,
+				"kernel_mmr_size": 2502,
+				"pow": {
+					"total_difficulty": {
+						"cuckatoo": 2180330,
+						"randomx": 50102378193,
+						"progpow": 2119061232094
+					},
+					"secondary_scaling": 0,
+					"nonce": 8106172381526461,
+					"proof": {
+						"RandomXProof": {
+							"hash": [110, 108, 245, 5, 16, 171, 217, 54, 12, 21, 194, 182, 231, 233, 231, 49, 169, 35, 80, 85, 84, 32, 42, 219, 165, 179, 232, 125, 17, 106, 168, 36]
+						}
+					},
+					"seed": [25, 178, 88, 86, 225, 193, 80, 202, 131, 76, 255, 200, 181, 155, 35, 173, 189, 14, 192, 56, 158, 88, 235, 34, 179, 182, 71, 104, 9, 141, 0, 43]
+				},
+				"policy": 0,
+				"bottles": {
+					"cuckatoo": 1,
+					"randomx": 47,
+					"progpow": 52
+				}
+			},
+			"body": {
+				"inputs": [],
+				"outputs": [
+				{
+					"features": "Coinbase",
+					"commit": "087c3ca7419751e96cdae4908bb8a92fc2826f2ad36690420b905d51beb7409ca0",
+					"proof": "379ae236937883c2e1e613fb30f1b18d2a44d4173360e94bcd07862aafaf81b3aaa1154d67287cc03efde0d3981c6da8a18e2e426f5c30afc0f2e3a75012448402d8d56df52b87f4815575a56d4da174f8187e4faae64bf883b249ceed694271f84ef62a3711d36c997dff7a11111419011e36e3a070b7552415a55faaa3999f99439edccdfe5313277147fdb42be1798442bb225c2b546f5347920584b365aa81a0365b4a706c97c89617b0e6218d2c9bc15805caab27c438ed06340cc4f8dc7bfca0e9d38864c88bb0c834372f6b662b9159134f3f8ec9b8a87878739a7e516b97419ac29e1d4a2b250321470a9a6b98d07065bb7e79afc25a5ab6fc47108f53223078a64502bd4af1a109641447dab82741ebe3fbdbd803ee7a42fe2554e78fa86bd1d1e6e3b913118e9419b0be6f976b2404447d943b5f1bac19a5809fd6834797945a62d21b1ecb6ddebbc5ef94ca9e704d033bd64afde67bd3e06e2cca3bb10190188afc0af80b48dd862b86753d8b4af314763324deb1c97cf020cb87285a47cd28874bb91c6cdf858965e8b9daafbcbc1b4817d334a97d7e25e01b2d072d8dcc6418e3dc7b8e7712632f939238e65ed0731c7af02d55a8884cd8f7f88dc0f63a21955a7364562532f5716c89e14f8f23ad78f6fe2f1649e13ea8f8185f3ee63cc174684d1ef8d8c33fb25bc802f8e05e53fe200b1ea5231f588a020942e6fd7eec67301700088dae8816c16a337120063c21e1604e009df932032812f88be6473af13f802b42d8ad6fc14230fbe13ede178319a7b6540656234ec1f2fcfa70f6faa9c4b6b8150b81fe0fdc273a9bb385d766a02041a5c3f58471d42059c17d84d13ad592aa0ccf337970e7eef06f306b13288795123c9c005b815d848f359b23450656b310f09cda9ad4b7b6931805d47dcd10a8745d834a984e2055168ac3"
+				}
+				],
+				"kernels": [
+				{
+					"features": "Coinbase",
+					"excess": "08385257d22f1b8a758903f78ae12545245d620cffc50e7ee7bc852c5815513dc7",
+					"excess_sig": "e001a7349fd40d4a9dfc1df275d30906fb3b304f8c7892a20ed5c9b10923c871cbabedcf322511a9ce56f10113b48855441f681280133e121b25ea1ff7efad9e"
+				}
+				]
+			}
+			},
+			"9f2e6d33a3717ee826353a404ba4618d1aeeb6879ad7936bce8ed5f46814924d-4"
+		],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn submit_block_with_template(&self, block: Block, template_id: String) -> Result<(), Error>;
 	fn finalize_block_template(
 		&self,
 		coinbase: CoinbaseData,
@@ -882,6 +1403,10 @@ where
 		Foreign::get_block_template(self)
 	}
 
+	fn get_block_template_long_poll(&self, template_id: String) -> Result<BlockTemplate, Error> {
+		Foreign::get_block_template_long_poll(self, &template_id)
+	}
+
 	fn finalize_block_template(
 		&self,
 		coinbase: CoinbaseData,
@@ -893,6 +1418,10 @@ where
 		Foreign::submit_block(self, block)
 	}
 
+	fn submit_block_with_template(&self, block: Block, template_id: String) -> Result<(), Error> {
+		Foreign::submit_block_with_template(self, block, &template_id)
+	}
+
 	fn get_last_n_kernels(&self, distance: u64) -> Result<Vec<TxKernel>, Error> {
 		match Foreign::get_last_n_kernels(self, distance) {
 			Ok(k) => Ok(k),
@@ -900,6 +1429,32 @@ where
 		}
 	}
 
+	fn get_block_filter(
+		&self,
+		height: Option<u64>,
+		hash: Option<String>,
+	) -> Result<BlockFilterPrintable, Error> {
+		let mut parsed_hash: Option<Hash> = None;
+		if let Some(hash) = hash {
+			let vec = util::from_hex(hash)
+				.map_err(|e| Error::Argument(format!("invalid block hash: {}", e)))?;
+			parsed_hash = Some(Hash::from_vec(&vec));
+		}
+		Foreign::get_block_filter(self, height, parsed_hash)
+	}
+
+	fn get_blocks_by_time(&self, start: i64, end: i64) -> Result<Vec<BlockPrintable>, Error> {
+		Foreign::get_blocks_by_time(self, start, end)
+	}
+
+	fn get_algo_stats(
+		&self,
+		from_height: u64,
+		to_height: u64,
+	) -> Result<AlgoStatsListing, Error> {
+		Foreign::get_algo_stats(self, from_height, to_height)
+	}
+
 	fn get_version(&self) -> Result<Version, Error> {
 		Foreign::get_version(self)
 	}
@@ -908,6 +1463,10 @@ where
 		Foreign::get_tip(self)
 	}
 
+	fn get_policy_schedule(&self) -> Result<PolicySchedule, Error> {
+		Foreign::get_policy_schedule(self)
+	}
+
 	fn get_kernel(
 		&self,
 		excess: String,
@@ -935,6 +1494,10 @@ where
 		)
 	}
 
+	fn get_output_history(&self, commit: String) -> Result<Vec<OutputHistory>, Error> {
+		Foreign::get_output_history(self, commit)
+	}
+
 	fn get_unspent_outputs(
 		&self,
 		start_index: u64,
@@ -964,6 +1527,13 @@ where
 	fn get_unconfirmed_transactions(&self) -> Result<Vec<PoolEntry>, Error> {
 		Foreign::get_unconfirmed_transactions(self)
 	}
+
+	fn estimate_fee(&self, target_blocks: u64) -> Result<FeeEstimate, Error> {
+		Foreign::estimate_fee(self, target_blocks)
+	}
+	fn test_transaction_accept(&self, tx: Transaction) -> Result<TxAcceptance, Error> {
+		Foreign::test_transaction_accept(self, tx)
+	}
 	fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), Error> {
 		Foreign::push_transaction(self, tx, fluff)
 	}
@@ -14,6 +14,7 @@
 
 pub mod blocks_api;
 pub mod chain_api;
+pub mod health_api;
 pub mod peers_api;
 pub mod pool_api;
 pub mod server_api;
@@ -29,7 +30,8 @@ use self::chain_api::ChainHandler;
 use self::chain_api::ChainValidationHandler;
 use self::chain_api::KernelHandler;
 use self::chain_api::OutputHandler;
-use self::mining_api::MiningHandler;
+use self::health_api::{HealthHandler, HealthProbe};
+use self::mining_api::{BlockTemplates, MiningHandler};
 use self::peers_api::PeerHandler;
 use self::peers_api::PeersAllHandler;
 use self::peers_api::PeersConnectedHandler;
@@ -56,6 +58,7 @@ use crate::pool::{BlockChain, PoolAdapter};
 use crate::rest::{ApiServer, Error, TLSConfig};
 use crate::tor::Tor;
 use crate::tor_rpc::TorRpc;
+use crate::types::{HealthReport, ShareLedger, TxPoolManager};
 
 use crate::router::{ResponseFuture, Router};
 use crate::util::to_base64;
@@ -83,6 +86,9 @@ pub fn node_apis<B, P>(
     tx_pool: Arc<RwLock<pool::TransactionPool<B, P>>>,
     peers: Arc<p2p::Peers>,
     sync_state: Arc<chain::SyncState>,
+    health: Arc<RwLock<HealthReport>>,
+    share_ledger: Option<Arc<dyn ShareLedger>>,
+    block_templates: Arc<BlockTemplates>,
     api_secret: Option<String>,
     foreign_api_secret: Option<String>,
     tls_config: Option<TLSConfig>,
@@ -101,6 +107,7 @@ where
 		tx_pool.clone(),
 		peers.clone(),
 		sync_state.clone(),
+		block_templates.clone(),
 	)
 	.expect("unable to build API router");
 
@@ -120,20 +127,42 @@ where
 		router.add_middleware(v1_auth);
 		
 		let basic_auth_middleware = Arc::new(BasicAuthURIMiddleware::new(
-			api_basic_auth,
+			api_basic_auth.clone(),
 			&EPIC_BASIC_REALM,
 			"/v2/owner".into(),
 		));
 		router.add_middleware(basic_auth_middleware);
+
+		let health_basic_auth = Arc::new(BasicAuthURIMiddleware::new(
+			api_basic_auth.clone(),
+			&EPIC_BASIC_REALM,
+			"/v2/health".into(),
+		));
+		router.add_middleware(health_basic_auth);
 	}
 
 	let owner_api_handler = OwnerAPIHandlerV2::new(
 		Arc::downgrade(&chain),
+		Arc::downgrade(&tx_pool),
 		Arc::downgrade(&peers),
 		Arc::downgrade(&sync_state),
+		Arc::downgrade(&health),
+		share_ledger.as_ref().map(Arc::downgrade),
 	);
 	router.add_route("/v2/owner", Arc::new(owner_api_handler))?;
 
+	for (route, probe) in [
+		("/v2/health", HealthProbe::Report),
+		("/v2/health/live", HealthProbe::Live),
+		("/v2/health/ready", HealthProbe::Ready),
+	] {
+		let health_handler = HealthHandler {
+			health: Arc::downgrade(&health),
+			probe,
+		};
+		router.add_route(route, Arc::new(health_handler))?;
+	}
+
 
 	// Add basic auth to v2 foreign API
 	if let Some(api_secret) = foreign_api_secret {
@@ -151,6 +180,7 @@ where
 		Arc::downgrade(&chain),
 		Arc::downgrade(&tx_pool),
 		Arc::downgrade(&sync_state),
+		Arc::downgrade(&block_templates),
 	);
 	router.add_route("/v2/foreign", Arc::new(foreign_api_handler))?;
 	
@@ -193,17 +223,30 @@ where
 /// V2 API Handler/Wrapper for owner functions
 pub struct OwnerAPIHandlerV2 {
     pub chain: Weak<Chain>,
+    pub tx_pool: Weak<dyn TxPoolManager>,
     pub peers: Weak<p2p::Peers>,
     pub sync_state: Weak<SyncState>,
+    pub health: Weak<RwLock<HealthReport>>,
+    pub share_ledger: Option<Weak<dyn ShareLedger>>,
 }
 
 impl OwnerAPIHandlerV2 {
     /// Create a new owner API handler for GET methods
-    pub fn new(chain: Weak<Chain>, peers: Weak<p2p::Peers>, sync_state: Weak<SyncState>) -> Self {
+    pub fn new(
+        chain: Weak<Chain>,
+        tx_pool: Weak<dyn TxPoolManager>,
+        peers: Weak<p2p::Peers>,
+        sync_state: Weak<SyncState>,
+        health: Weak<RwLock<HealthReport>>,
+        share_ledger: Option<Weak<dyn ShareLedger>>,
+    ) -> Self {
         OwnerAPIHandlerV2 {
             chain,
+            tx_pool,
             peers,
             sync_state,
+            health,
+            share_ledger,
         }
     }
 }
@@ -212,8 +255,11 @@ impl crate::router::Handler<Full<Bytes>> for OwnerAPIHandlerV2 {
     fn post(&self, req: Request<hyper::body::Incoming>) -> ResponseFuture {
         let api = Owner::new(
             self.chain.clone(),
+            self.tx_pool.clone(),
             self.peers.clone(),
             self.sync_state.clone(),
+            self.health.clone(),
+            self.share_ledger.clone(),
         );
 
 		Box::pin(async move {
@@ -312,6 +358,7 @@ where
     pub chain: Weak<Chain>,
     pub tx_pool: Weak<RwLock<pool::TransactionPool<B, P>>>,
     pub sync_state: Weak<SyncState>,
+    pub block_templates: Weak<BlockTemplates>,
 }
 
 impl<B, P> ForeignAPIHandlerV2<B, P>
@@ -324,11 +371,13 @@ where
         chain: Weak<Chain>,
         tx_pool: Weak<RwLock<pool::TransactionPool<B, P>>>,
         sync_state: Weak<SyncState>,
+        block_templates: Weak<BlockTemplates>,
     ) -> Self {
         ForeignAPIHandlerV2 {
             chain,
             tx_pool,
             sync_state,
+            block_templates,
         }
     }
 }
@@ -343,6 +392,7 @@ where
             self.chain.clone(),
             self.tx_pool.clone(),
             self.sync_state.clone(),
+            self.block_templates.clone(),
         );
 
         Box::pin(async move {
@@ -439,6 +489,7 @@ pub fn build_router<B, P>(
     tx_pool: Arc<RwLock<pool::TransactionPool<B, P>>>,
     peers: Arc<p2p::Peers>,
     sync_state: Arc<chain::SyncState>,
+    block_templates: Arc<BlockTemplates>,
 ) -> Result<Router, Error>
 where
     B: BlockChain + 'static,
@@ -470,6 +521,7 @@ where
 		"get:  /v1/peers/onion_addresses".to_string(),
 		"get:  /v1/version".to_string(),
 		"get:  /v1/mining/block_template".to_string(),
+		"get:  /v1/mining/block_template?longpoll=xxx".to_string(),
 	];
 	let index_handler = IndexHandler { list: route_list };
 
@@ -529,6 +581,7 @@ where
 	let mining_handler = MiningHandler {
 		chain: Arc::downgrade(&chain),
 		tx_pool: Arc::downgrade(&tx_pool),
+		block_templates: Arc::downgrade(&block_templates),
 	};
 
     let mut router = Router::new();
@@ -124,6 +124,9 @@ pub struct BlockHandler {
 	pub chain: Weak<chain::Chain>,
 }
 
+/// Most blocks returned by a single `get_blocks_by_time` call.
+pub const MAX_BLOCKS_BY_TIME: usize = 1000;
+
 impl BlockHandler {
 	pub fn get_block(
 		&self,
@@ -137,6 +140,36 @@ impl BlockHandler {
 			.map_err(|_| Error::Internal("chain error".to_owned()))
 	}
 
+	/// Blocks on the current chain with a timestamp between `start` and `end`
+	/// (unix seconds, inclusive), in timestamp order. Pruned blocks are left
+	/// out, at most `MAX_BLOCKS_BY_TIME` blocks are returned.
+	pub fn get_blocks_by_time(&self, start: i64, end: i64) -> Result<Vec<BlockPrintable>, Error> {
+		if start > end {
+			return Err(Error::Argument("start must be lower or equal than end".to_owned()));
+		}
+		let chain = w(&self.chain)?;
+		let headers = chain
+			.get_headers_by_time(start, end, MAX_BLOCKS_BY_TIME)
+			.map_err(|e| Error::Internal(format!("can't search the timestamp index: {}", e)))?;
+		let mut blocks = vec![];
+		for header in headers {
+			match self.get_block(&header.hash(), false, false) {
+				Ok(block) => blocks.push(block),
+				Err(Error::NotFound) => {}
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(blocks)
+	}
+
+	pub fn get_block_filter(&self, h: &Hash) -> Result<BlockFilterPrintable, Error> {
+		let chain = w(&self.chain)?;
+		let header = chain.get_block_header(h).map_err(|_| Error::NotFound)?;
+		let filter = chain.get_block_filter(h).map_err(|_| Error::NotFound)?;
+		let filter_header = chain.get_block_filter_header(h).ok();
+		Ok(BlockFilterPrintable::from_filter(&filter, header.height, filter_header))
+	}
+
 	fn get_compact_block(&self, h: &Hash) -> Result<CompactBlockPrintable, Error> {
 		let chain = w(&self.chain)?;
 		let block = chain.get_block(h).map_err(|_| Error::NotFound)?;
@@ -15,6 +15,8 @@
 use super::utils::{get_output, get_output_v2, w};
 use crate::chain;
 use crate::core::core::hash::Hashed;
+use crate::core::global;
+use crate::core::pow::PoWType;
 use crate::rest::*;
 use crate::router::{Handler, ResponseFuture};
 use crate::types::*;
@@ -24,11 +26,15 @@ use crate::web::*;
 use epic_core::core::TxKernel;
 
 use hyper::{Request, StatusCode};
+use std::collections::HashMap;
 use std::sync::Weak;
 
 use bytes::Bytes;
 use http_body_util::Full;
 
+/// Widest height range `get_algo_stats` aggregates in one call.
+pub const MAX_ALGO_STATS_RANGE: u64 = 10_000;
+
 /// Chain handler. Get the head details.
 /// GET /v1/chain
 pub struct ChainHandler {
@@ -42,6 +48,106 @@ impl ChainHandler {
 			.map_err(|e| Error::Internal(format!("can't get head: {}", e)))?;
 		Ok(Tip::from_tip(head))
 	}
+
+	/// Block counts, average solve times and difficulties, and fee totals
+	/// per algorithm for the blocks between `from_height` and `to_height`.
+	pub fn get_algo_stats(
+		&self,
+		from_height: u64,
+		to_height: u64,
+	) -> Result<AlgoStatsListing, Error> {
+		if from_height > to_height {
+			return Err(Error::Argument(
+				"from_height must be lower or equal than to_height".to_owned(),
+			));
+		}
+		if to_height - from_height >= MAX_ALGO_STATS_RANGE {
+			return Err(Error::Argument(format!(
+				"at most {} blocks can be aggregated at once",
+				MAX_ALGO_STATS_RANGE
+			)));
+		}
+		let chain = w(&self.chain)?;
+		let head = chain
+			.head()
+			.map_err(|e| Error::Internal(format!("can't get head: {}", e)))?;
+		let to_height = to_height.min(head.height);
+		let header_at = |height: u64| {
+			chain
+				.get_header_by_height(height)
+				.map_err(|e| Error::Internal(format!("can't get header {}: {}", height, e)))
+		};
+
+		// (blocks, blocks with a previous header, solve time, difficulty, fees)
+		let mut sums: HashMap<PoWType, (u64, u64, i64, u128, u64)> = HashMap::new();
+		let mut total_fees = 0;
+		let mut pruned_blocks = 0;
+		let mut prev = match from_height {
+			0 => None,
+			h => Some(header_at(h - 1)?),
+		};
+		for height in from_height..=to_height {
+			let header = header_at(height)?;
+			let algo = PoWType::from(&header.pow.proof);
+			let entry = sums.entry(algo).or_insert((0, 0, 0, 0, 0));
+			entry.0 += 1;
+			if let Some(prev) = prev {
+				entry.1 += 1;
+				entry.2 += (header.timestamp - prev.timestamp).num_seconds();
+				entry.3 += header
+					.total_difficulty()
+					.to_num(algo)
+					.saturating_sub(prev.total_difficulty().to_num(algo)) as u128;
+			}
+			match chain.get_block(&header.hash()) {
+				Ok(block) => {
+					entry.4 += block.total_fees();
+					total_fees += block.total_fees();
+				}
+				Err(_) => pruned_blocks += 1,
+			}
+			prev = Some(header);
+		}
+
+		let algos = sums
+			.into_iter()
+			.map(|(algo, (blocks, timed, time, difficulty, fees))| {
+				let stats = AlgoStats {
+					blocks,
+					avg_solve_time: if timed > 0 {
+						time as f64 / timed as f64
+					} else {
+						0.0
+					},
+					avg_difficulty: if timed > 0 {
+						(difficulty / timed as u128) as u64
+					} else {
+						0
+					},
+					total_fees: fees,
+				};
+				(algo, stats)
+			})
+			.collect();
+		Ok(AlgoStatsListing {
+			from_height,
+			to_height,
+			algos,
+			total_fees,
+			pruned_blocks,
+		})
+	}
+
+	pub fn get_policy_schedule(&self) -> Result<PolicySchedule, Error> {
+		let head = w(&self.chain)?
+			.head()
+			.map_err(|e| Error::Internal(format!("can't get head: {}", e)))?;
+		Ok(PolicySchedule::from_config(
+			&global::get_policy_config(),
+			global::get_emitted_policy(head.height + 1),
+			head.height,
+		))
+	}
 }
 
 impl Handler<Full<Bytes>> for ChainHandler {
@@ -127,6 +233,22 @@ impl OutputHandler {
 		Ok(res.0)
 	}
 
+	pub fn get_output_history(&self, commit: String) -> Result<Vec<OutputHistory>, Error> {
+		let c = util::from_hex(commit.clone())
+			.map_err(|_| Error::Argument(format!("Not a valid commitment {}", commit)))?;
+		if c.len() != 33 {
+			return Err(Error::Argument(format!("invalid commit length for {}", commit)));
+		}
+		let commit = Commitment::from_vec(c);
+		let history = w(&self.chain)?
+			.get_output_history(&commit)
+			.map_err(|e| Error::Internal(format!("{}", e)))?;
+		Ok(history
+			.iter()
+			.map(|h| OutputHistory::from_history(&commit, h))
+			.collect())
+	}
+
 	pub fn get_outputs_v2(
 		&self,
 		commits: Option<Vec<String>>,
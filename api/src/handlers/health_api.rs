@@ -0,0 +1,79 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::utils::w;
+use crate::rest::*;
+use crate::router::{Handler, ResponseFuture};
+use crate::types::*;
+use crate::util::RwLock;
+use crate::web::*;
+
+use chrono::Utc;
+use hyper::{Request, StatusCode};
+use std::sync::Weak;
+
+use bytes::Bytes;
+use http_body_util::Full;
+
+/// Which health probe a request is for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HealthProbe {
+	/// Full report, 200 when ready
+	Report,
+	/// 200 unless the node needs a restart
+	Live,
+	/// 200 when the node is synced and fit to serve
+	Ready,
+}
+
+/// Node health report, as computed by the server health monitor.
+/// GET /v2/health
+/// GET /v2/health/live
+/// GET /v2/health/ready
+pub struct HealthHandler {
+	pub health: Weak<RwLock<HealthReport>>,
+	pub probe: HealthProbe,
+}
+
+impl HealthHandler {
+	pub fn get_health(&self) -> Result<HealthReport, Error> {
+		let report = w(&self.health)?.read().at(Utc::now().timestamp());
+		Ok(report)
+	}
+}
+
+impl Handler<Full<Bytes>> for HealthHandler {
+	fn get(&self, _req: Request<hyper::body::Incoming>) -> ResponseFuture {
+		let report = match self.get_health() {
+			Ok(r) => r,
+			Err(e) => return result_to_response::<HealthReport>(Err(e)),
+		};
+		let healthy = match self.probe {
+			HealthProbe::Report | HealthProbe::Ready => report.ready,
+			HealthProbe::Live => report.live,
+		};
+		let status = if healthy {
+			StatusCode::OK
+		} else {
+			StatusCode::SERVICE_UNAVAILABLE
+		};
+		match serde_json::to_string_pretty(&report) {
+			Ok(json) => response(status, json),
+			Err(e) => response(
+				StatusCode::INTERNAL_SERVER_ERROR,
+				format!("can't create json response: {}", e),
+			),
+		}
+	}
+}
@@ -18,17 +18,21 @@ use crate::rest::*;
 use bytes::Bytes;
 
 use crate::core::consensus;
-use crate::core::core::hash::Hashed;
+use crate::core::core::hash::{Hash, Hashed};
 use crate::core::core::{Block, BlockHeader, Output, Transaction, TxKernel};
 use crate::core::pow;
 use crate::core::pow::randomx::rx_current_seed_height;
 use crate::pool;
 use crate::router::{Handler, ResponseFuture};
+use crate::types::{ShareLedger, ShareWindow};
 use crate::util::RwLock;
 use crate::web::*;
 use http_body_util::Full;
 use hyper::Request;
-use std::sync::Weak;
+use std::cmp;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{self, Instant};
 
 use super::utils::w;
 use crate::chain::Options;
@@ -36,6 +40,21 @@ use crate::core::global::get_emitted_policy;
 use crate::pool::{BlockChain, PoolAdapter};
 use chrono::Duration;
 use rand::{rng, Rng};
+
+/// Longest a long poll waits for a new block template.
+const LONG_POLL_TIMEOUT: time::Duration = time::Duration::from_secs(60);
+
+/// Age a template must reach before new mempool transactions alone make it
+/// worth replacing.
+const POOL_REFRESH_INTERVAL: time::Duration = time::Duration::from_secs(10);
+
+/// Long polls waiting at the same time, each holds a thread of the api
+/// runtime. The ones above are rejected.
+const MAX_LONG_POLLS: usize = 16;
+
+/// Templates remembered for finalize_block_template and submit_block.
+const MAX_ISSUED_TEMPLATES: usize = 64;
+
 pub struct MiningHandler<B, P>
 where
 	B: BlockChain,
@@ -43,11 +62,14 @@ where
 {
 	pub chain: Weak<chain::Chain>,
 	pub tx_pool: Weak<RwLock<pool::TransactionPool<B, P>>>,
+	pub block_templates: Weak<BlockTemplates>,
 }
 
 /// Step 1: Get a block template (without coinbase)
 #[derive(Serialize, Deserialize)]
 pub struct BlockTemplate {
+	/// Id to long poll for the next template and to finalize this one with
+	pub template_id: String,
 	pub header: BlockHeader,
 	pub transactions: Vec<Transaction>,
 	pub height: u64,
@@ -61,11 +83,15 @@ pub struct BlockTemplate {
 pub struct CoinbaseData {
 	pub output: Output,
 	pub kernel: TxKernel,
+	/// Template to finalize, the current mempool is used without it
+	#[serde(default)]
+	pub template_id: Option<String>,
 }
 
 /// Step 2: Finalized block template (with coinbase, roots, pre_pow)
 #[derive(Serialize, Deserialize)]
 pub struct FinalizedBlockTemplate {
+	pub template_id: Option<String>,
 	pub header: BlockHeader,
 	pub pre_pow: String,
 	pub height: u64,
@@ -81,6 +107,9 @@ where
 {
 	/// Step 1: Returns a block template for mining, without coinbase output/kernel.
 	pub fn get_block_template(&self) -> Result<BlockTemplate, Error> {
+		let block_templates = w(&self.block_templates)?;
+		// taken first so a tip or mempool change while we build is not missed
+		let updates = block_templates.updates();
 		let chain = w(&self.chain)?;
 		let head = chain.head_header()?;
 		let height = head.height + 1;
@@ -150,7 +179,9 @@ where
 			current_hash,
 		)];
 
+		let template_id = block_templates.issue(updates, header.prev_hash, txs.clone());
 		Ok(BlockTemplate {
+			template_id,
 			header,
 			transactions: txs,
 			height,
@@ -160,6 +191,47 @@ where
 		})
 	}
 
+	/// Step 1 (long poll): Waits until the template is outdated, as the chain
+	/// tip changed or new transactions came into the mempool, and returns the
+	/// new one. Returns a template after a minute at most.
+	pub fn get_block_template_long_poll(&self, template_id: &str) -> Result<BlockTemplate, Error> {
+		let prev_hash = template_tip(template_id)?;
+		if prev_hash == w(&self.chain)?.head()?.last_block_h {
+			let block_templates = w(&self.block_templates)?;
+			let _slot = block_templates.long_poll_slot().ok_or_else(|| {
+				Error::Internal(format!(
+					"too many block template long polls, at most {}",
+					MAX_LONG_POLLS
+				))
+			})?;
+			// do not hold up the other requests of the api runtime while waiting
+			tokio::task::block_in_place(|| {
+				block_templates.wait_for_update(template_id, LONG_POLL_TIMEOUT);
+			});
+		}
+		self.get_block_template()
+	}
+
+	// Checks the template is still on the chain tip, returns its transactions
+	fn template_transactions(&self, template_id: &str) -> Result<Vec<Transaction>, Error> {
+		self.check_template_tip(template_id)?;
+		w(&self.block_templates)?
+			.transactions(template_id)
+			.ok_or_else(|| Error::Argument(format!("unknown block template {}", template_id)))
+	}
+
+	fn check_template_tip(&self, template_id: &str) -> Result<(), Error> {
+		let prev_hash = template_tip(template_id)?;
+		let head = w(&self.chain)?.head()?;
+		if prev_hash != head.last_block_h {
+			return Err(Error::Argument(format!(
+				"stale block template {}, the chain tip moved to {} at height {}",
+				template_id, head.last_block_h, head.height
+			)));
+		}
+		Ok(())
+	}
+
 	/// Step 2: Finalize block with coinbase, set roots, return header+pre_pow
 	pub fn finalize_block_template(
 		&self,
@@ -169,12 +241,17 @@ where
 		let head = chain.head_header()?;
 		let height = head.height + 1;
 
-		// Get txs from pool again (must match what was given in template)
-		let pool_arc = w(&self.tx_pool)?;
-		let pool = pool_arc.read();
-		let txs = match pool.prepare_mineable_transactions() {
-			Ok(txs) => txs,
-			Err(_) => vec![],
+		// Use the txs of the template, or get them from the pool again
+		let txs = match &coinbase.template_id {
+			Some(template_id) => self.template_transactions(template_id)?,
+			None => {
+				let pool_arc = w(&self.tx_pool)?;
+				let pool = pool_arc.read();
+				match pool.prepare_mineable_transactions() {
+					Ok(txs) => txs,
+					Err(_) => vec![],
+				}
+			}
 		};
 
 		// Build block with txs + coinbase
@@ -202,6 +279,7 @@ where
 		};
 
 		Ok(FinalizedBlockTemplate {
+			template_id: coinbase.template_id,
 			header: block.header.clone(),
 			pre_pow,
 			height,
@@ -211,6 +289,20 @@ where
 		})
 	}
 
+	/// Step 3: Submit a mined block (with valid PoW) to the node, rejecting it
+	/// if the template it was mined from is stale.
+	pub fn submit_block_with_template(&self, block: Block, template_id: &str) -> Result<(), Error> {
+		if template_tip(template_id)? != block.header.prev_hash {
+			return Err(Error::Argument(format!(
+				"block {} was not mined from block template {}",
+				block.hash(),
+				template_id
+			)));
+		}
+		self.check_template_tip(template_id)?;
+		self.submit_block(block)
+	}
+
 	/// Step 3: Submit a mined block (with valid PoW) to the node.
 	pub fn submit_block(&self, block: Block) -> Result<(), Error> {
 		let chain = w(&self.chain)?;
@@ -227,8 +319,244 @@ where
 	B: BlockChain,
 	P: PoolAdapter,
 {
-	fn get(&self, _req: Request<hyper::body::Incoming>) -> ResponseFuture {
-		result_to_response(self.get_block_template())
+	fn get(&self, req: Request<hyper::body::Incoming>) -> ResponseFuture {
+		let params = QueryParams::from(req.uri().query());
+		match params.get("longpoll") {
+			Some(template_id) => {
+				result_to_response(self.get_block_template_long_poll(template_id))
+			}
+			None => result_to_response(self.get_block_template()),
+		}
 	}
 	// Add a POST endpoint for finalize_block_template as needed
 }
+
+// Templates are identified by the block they build on and a sequence number
+fn template_tip(template_id: &str) -> Result<Hash, Error> {
+	let invalid = || Error::Argument(format!("invalid block template id {}", template_id));
+	let (prev_hash, _) = template_id.split_once('-').ok_or_else(invalid)?;
+	Hash::from_hex(prev_hash).map_err(|_| invalid())
+}
+
+struct IssuedTemplate {
+	id: String,
+	transactions: Vec<Transaction>,
+	updates: TemplateUpdates,
+	issued_at: Instant,
+}
+
+/// Tip and mempool changes seen so far.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(crate) struct TemplateUpdates {
+	tip: u64,
+	pool: u64,
+}
+
+#[derive(Default)]
+struct TemplateState {
+	updates: TemplateUpdates,
+	next_id: u64,
+	issued: VecDeque<IssuedTemplate>,
+	long_polls: usize,
+}
+
+/// One of the long polls allowed at a time, given back on drop.
+pub(crate) struct LongPollSlot<'a>(&'a BlockTemplates);
+
+impl Drop for LongPollSlot<'_> {
+	fn drop(&mut self) {
+		self.0.state.lock().unwrap().long_polls -= 1;
+	}
+}
+
+/// Block templates handed out by the mining API. The chain and pool adapters
+/// report the tip and mempool changes, waking up the long polls.
+#[derive(Default)]
+pub struct BlockTemplates {
+	state: Mutex<TemplateState>,
+	changed: Condvar,
+}
+
+impl BlockTemplates {
+	pub fn new() -> BlockTemplates {
+		BlockTemplates::default()
+	}
+
+	/// The chain tip changed, all the templates are stale.
+	pub fn tip_changed(&self) {
+		self.state.lock().unwrap().updates.tip += 1;
+		self.changed.notify_all();
+	}
+
+	/// Transactions were added to the mempool.
+	pub fn pool_changed(&self) {
+		self.state.lock().unwrap().updates.pool += 1;
+		self.changed.notify_all();
+	}
+
+	pub(crate) fn updates(&self) -> TemplateUpdates {
+		self.state.lock().unwrap().updates
+	}
+
+	/// Remembers a template, returning its id.
+	pub(crate) fn issue(
+		&self,
+		updates: TemplateUpdates,
+		prev_hash: Hash,
+		transactions: Vec<Transaction>,
+	) -> String {
+		let mut state = self.state.lock().unwrap();
+		let id = format!("{}-{}", prev_hash.to_hex(), state.next_id);
+		state.next_id += 1;
+		state.issued.push_back(IssuedTemplate {
+			id: id.clone(),
+			transactions,
+			updates,
+			issued_at: Instant::now(),
+		});
+		if state.issued.len() > MAX_ISSUED_TEMPLATES {
+			state.issued.pop_front();
+		}
+		id
+	}
+
+	/// Transactions of a template we still remember.
+	pub(crate) fn transactions(&self, template_id: &str) -> Option<Vec<Transaction>> {
+		let state = self.state.lock().unwrap();
+		state
+			.issued
+			.iter()
+			.find(|t| t.id == template_id)
+			.map(|t| t.transactions.clone())
+	}
+
+	/// Takes a long poll slot, none when MAX_LONG_POLLS are already waiting.
+	pub(crate) fn long_poll_slot(&self) -> Option<LongPollSlot<'_>> {
+		let mut state = self.state.lock().unwrap();
+		if state.long_polls >= MAX_LONG_POLLS {
+			return None;
+		}
+		state.long_polls += 1;
+		Some(LongPollSlot(self))
+	}
+
+	/// Waits until the template is outdated: the tip changed or, once it is
+	/// old enough, the mempool did. Templates we no longer remember, issued
+	/// on the current tip, wait for the next tip change.
+	pub(crate) fn wait_for_update(&self, template_id: &str, timeout: time::Duration) {
+		let deadline = Instant::now() + timeout;
+		let mut state = self.state.lock().unwrap();
+		let (updates, issued_at) = match state.issued.iter().find(|t| t.id == template_id) {
+			Some(template) => (template.updates, Some(template.issued_at)),
+			None => (state.updates, None),
+		};
+		loop {
+			let now = Instant::now();
+			if state.updates.tip != updates.tip || now >= deadline {
+				return;
+			}
+			let mut wake_at = deadline;
+			let pool_changed = state.updates.pool != updates.pool;
+			if let Some(issued_at) = issued_at.filter(|_| pool_changed) {
+				let refresh_at = issued_at + POOL_REFRESH_INTERVAL;
+				if now >= refresh_at {
+					return;
+				}
+				wake_at = cmp::min(wake_at, refresh_at);
+			}
+			state = self.changed.wait_timeout(state, wake_at - now).unwrap().0;
+		}
+	}
+}
+
+/// Payout windows over the shares logged by the stratum server.
+pub struct ShareLedgerHandler {
+	pub share_ledger: Option<Weak<dyn ShareLedger>>,
+}
+
+impl ShareLedgerHandler {
+	fn share_ledger(&self) -> Result<Arc<dyn ShareLedger>, Error> {
+		match &self.share_ledger {
+			Some(share_ledger) => w(share_ledger),
+			None => Err(Error::Internal(
+				"share ledger is only kept with the stratum server enabled".to_owned(),
+			)),
+		}
+	}
+
+	pub fn get_pplns_window(
+		&self,
+		algorithm: String,
+		window_difficulty: u64,
+		height: Option<u64>,
+	) -> Result<ShareWindow, Error> {
+		self.share_ledger()?
+			.pplns_window(&algorithm, window_difficulty, height)
+	}
+
+	pub fn get_pps_window(&self, from: i64, to: i64) -> Result<ShareWindow, Error> {
+		if from > to {
+			return Err(Error::Argument(format!(
+				"window start {} is after its end {}",
+				from, to
+			)));
+		}
+		self.share_ledger()?.pps_window(from, to)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::thread;
+
+	#[test]
+	fn long_poll_block_templates() {
+		let templates = Arc::new(BlockTemplates::new());
+		let prev_hash = Hash::from_vec(&[1; 32]);
+		let id = templates.issue(templates.updates(), prev_hash, vec![]);
+		assert_eq!(template_tip(&id).unwrap(), prev_hash);
+		assert!(template_tip("not-a-template").is_err());
+		assert_eq!(templates.transactions(&id), Some(vec![]));
+
+		// times out without any change
+		let start = Instant::now();
+		templates.wait_for_update(&id, time::Duration::from_millis(50));
+		assert!(start.elapsed() >= time::Duration::from_millis(50));
+
+		// a new tip wakes the long poll up right away
+		let notifier = templates.clone();
+		let handle = thread::spawn(move || {
+			thread::sleep(time::Duration::from_millis(50));
+			notifier.tip_changed();
+		});
+		let start = Instant::now();
+		templates.wait_for_update(&id, time::Duration::from_secs(30));
+		assert!(start.elapsed() < time::Duration::from_secs(30));
+		handle.join().unwrap();
+
+		// unknown templates wait for the next tip, not for the mempool
+		let notifier = templates.clone();
+		let handle = thread::spawn(move || {
+			notifier.pool_changed();
+			thread::sleep(time::Duration::from_millis(50));
+			notifier.tip_changed();
+		});
+		let start = Instant::now();
+		templates.wait_for_update("00-1", time::Duration::from_secs(30));
+		assert!(start.elapsed() >= time::Duration::from_millis(50));
+		assert!(start.elapsed() < time::Duration::from_secs(30));
+		handle.join().unwrap();
+	}
+
+	#[test]
+	fn limit_long_polls() {
+		let templates = BlockTemplates::new();
+		let slots: Vec<_> = (0..MAX_LONG_POLLS)
+			.map(|_| templates.long_poll_slot().unwrap())
+			.collect();
+		assert!(templates.long_poll_slot().is_none());
+		drop(slots);
+		assert!(templates.long_poll_slot().is_some());
+	}
+}
@@ -13,14 +13,15 @@
 // limitations under the License.
 
 use super::utils::w;
-use crate::core::core::hash::Hashed;
+use crate::core::core::hash::{Hash, Hashed};
 use crate::core::core::Transaction;
 
-use crate::pool::{self, BlockChain, PoolAdapter, PoolEntry};
+use crate::pool::{self, BlockChain, FeeEstimate, PoolAdapter, PoolEntry, TxAcceptance};
 use crate::rest::*;
 use crate::router::{Handler, ResponseFuture};
 use crate::types::*;
-use crate::util::RwLock;
+use crate::util::secp::pedersen::Commitment;
+use crate::util::{self, RwLock};
 use crate::web::*;
 
 use hyper::{Request, StatusCode};
@@ -86,6 +87,24 @@ where
 		let txpool = pool_arc.read();
 		Ok(txpool.txpool.entries.clone())
 	}
+	pub fn estimate_fee(&self, target_blocks: u64) -> Result<FeeEstimate, Error> {
+		if target_blocks == 0 || target_blocks > pool::fee_estimator::MAX_TARGET_BLOCKS {
+			return Err(Error::Argument(format!(
+				"target blocks must be between 1 and {}",
+				pool::fee_estimator::MAX_TARGET_BLOCKS
+			)));
+		}
+		let pool_arc = w(&self.tx_pool)?;
+		let pool = pool_arc.read();
+		Ok(pool.estimate_fee(target_blocks))
+	}
+	pub fn test_transaction_accept(&self, tx: Transaction) -> Result<TxAcceptance, Error> {
+		let pool_arc = w(&self.tx_pool)?;
+		let tx_pool = pool_arc.read();
+		tx_pool
+			.test_accept(&tx)
+			.map_err(|e| Error::Internal(format!("Failed to check transaction: {}", e)))
+	}
 	pub fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), Error> {
 		let pool_arc = w(&self.tx_pool)?;
 		let source = pool::TxSource::PushApi;
@@ -108,7 +127,85 @@ where
 			.map_err(|e| Error::Internal(format!("Failed to update pool: {}", e)))?;
 		Ok(res)
 	}
+	/// Adds a transaction received in the onion stem phase to the stempool,
+	/// to be relayed `hops` more hops before being fluffed.
+	pub fn push_onion_stem_transaction(&self, tx: Transaction, hops: u8) -> Result<(), Error> {
+		let pool_arc = w(&self.tx_pool)?;
+		info!(
+			"Received onion stem transaction {}, {} hops left",
+			tx.hash(),
+			hops
+		);
+
+		let mut tx_pool = pool_arc.write();
+		let header = tx_pool
+			.blockchain
+			.chain_head()
+			.map_err(|e| Error::Internal(format!("Failed to get chain head: {}", e)))?;
+		tx_pool
+			.add_to_pool(pool::TxSource::OnionStem(hops), tx, true, &header)
+			.map_err(|e| Error::Internal(format!("Failed to update pool: {}", e)))
+	}
+}
+
+impl<B, P> TxPoolManager for RwLock<pool::TransactionPool<B, P>>
+where
+	B: BlockChain,
+	P: PoolAdapter,
+{
+	fn stempool_entries(&self) -> Vec<PoolEntry> {
+		self.read().stempool.entries.clone()
+	}
+
+	fn kernel_excess(&self, kernel_hash: Hash) -> Option<Commitment> {
+		let pool = self.read();
+		let tx = pool
+			.txpool
+			.retrieve_tx_by_kernel_hash(kernel_hash)
+			.or_else(|| pool.stempool.retrieve_tx_by_kernel_hash(kernel_hash))?;
+		tx.kernels()
+			.iter()
+			.find(|k| k.hash() == kernel_hash)
+			.map(|k| k.excess())
+	}
+
+	fn remove_transaction(&self, kernel_excess: Commitment) -> Result<usize, Error> {
+		let removed = self
+			.write()
+			.remove_tx_by_kernel_excess(&kernel_excess)
+			.map_err(|e| Error::Internal(format!("Failed to update pool: {}", e)))?;
+		if removed == 0 {
+			return Err(Error::NotFound);
+		}
+		info!(
+			"Removed {} transactions from the pool, from kernel {}",
+			removed,
+			util::to_hex(kernel_excess.0.to_vec())
+		);
+		Ok(removed)
+	}
+
+	fn fluff_transaction(&self, kernel_excess: Commitment) -> Result<(), Error> {
+		let fluffed = self
+			.write()
+			.fluff_stem_tx(&kernel_excess)
+			.map_err(|e| Error::Internal(format!("Failed to update pool: {}", e)))?;
+		if !fluffed {
+			return Err(Error::NotFound);
+		}
+		info!(
+			"Fluffed stem transaction with kernel {}",
+			util::to_hex(kernel_excess.0.to_vec())
+		);
+		Ok(())
+	}
+
+	fn clear(&self) {
+		self.write().clear();
+		info!("Cleared the transaction pool");
+	}
 }
+
 /// Dummy wrapper for the hex-encoded serialized transaction.
 #[derive(Serialize, Deserialize)]
 struct TxWrapper {
@@ -48,6 +48,7 @@ pub use crate::auth::{
 };
 pub use crate::foreign::Foreign;
 pub use crate::foreign_rpc::ForeignRpc;
+pub use crate::handlers::mining_api::BlockTemplates;
 pub use crate::handlers::node_apis;
 pub use crate::owner::Owner;
 pub use crate::owner_rpc::OwnerRpc;
@@ -15,13 +15,21 @@
 //! Owner API External Definition
 
 use crate::chain::{Chain, SyncState};
+use crate::handlers::utils::w;
 use crate::handlers::chain_api::{ChainCompactHandler, ChainValidationHandler};
+use crate::handlers::health_api::{HealthHandler, HealthProbe};
+use crate::handlers::mining_api::ShareLedgerHandler;
 use crate::handlers::peers_api::{PeerHandler, PeersConnectedHandler, PeersOnionAddressesHandler};
 use crate::handlers::server_api::StatusHandler;
 use crate::p2p::types::PeerInfoDisplay;
 use crate::p2p::{self, PeerData};
 use crate::rest::*;
-use crate::types::Status;
+use crate::types::{
+	HealthReport, PoolEntryPrintable, ShareLedger, ShareWindow, Status, TxPoolManager,
+};
+use crate::util::secp::pedersen::Commitment;
+use crate::util::RwLock;
+use chrono::Utc;
 use std::net::SocketAddr;
 use std::sync::Weak;
 
@@ -34,8 +42,11 @@ use std::sync::Weak;
 
 pub struct Owner {
 	pub chain: Weak<Chain>,
+	pub tx_pool: Weak<dyn TxPoolManager>,
 	pub peers: Weak<p2p::Peers>,
 	pub sync_state: Weak<SyncState>,
+	pub health: Weak<RwLock<HealthReport>>,
+	pub share_ledger: Option<Weak<dyn ShareLedger>>,
 }
 
 impl Owner {
@@ -47,16 +58,28 @@ impl Owner {
 	/// * `tx_pool` - A non-owning reference of the transaction pool.
 	/// * `peers` - A non-owning reference of the peers.
 	/// * `sync_state` - A non-owning reference of the `sync_state`.
+	/// * `health` - A non-owning reference of the latest health report.
+	/// * `share_ledger` - A non-owning reference of the stratum share ledger, if any.
 	///
 	/// # Returns
 	/// * An instance of the Node holding references to the current chain, transaction pool, peers and sync_state.
 	///
 
-	pub fn new(chain: Weak<Chain>, peers: Weak<p2p::Peers>, sync_state: Weak<SyncState>) -> Self {
+	pub fn new(
+		chain: Weak<Chain>,
+		tx_pool: Weak<dyn TxPoolManager>,
+		peers: Weak<p2p::Peers>,
+		sync_state: Weak<SyncState>,
+		health: Weak<RwLock<HealthReport>>,
+		share_ledger: Option<Weak<dyn ShareLedger>>,
+	) -> Self {
 		Owner {
 			chain,
+			tx_pool,
 			peers,
 			sync_state,
+			health,
+			share_ledger,
 		}
 	}
 
@@ -177,6 +200,24 @@ impl Owner {
 		peer_handler.unban_peer(addr)
 	}
 
+	/// Returns the latest result of the node health checks (sync lag, header/body
+	/// divergence, db space, tor, stratum and pool), with overall liveness and
+	/// readiness.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`HealthReport`](types/struct.HealthReport.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_health(&self) -> Result<HealthReport, Error> {
+		let health_handler = HealthHandler {
+			health: self.health.clone(),
+			probe: HealthProbe::Report,
+		};
+		health_handler.get_health()
+	}
+
 	/// Retrieves a list of all onion addresses from connected peers.
 	pub fn get_onion_addresses(&self) -> Result<Vec<String>, Error> {
 		let peers_onion_addresses_handler = PeersOnionAddressesHandler {
@@ -184,4 +225,117 @@ impl Owner {
 		};
 		peers_onion_addresses_handler.get_onion_addresses()
 	}
+
+	/// Returns the last shares of an algorithm accepted by the stratum server
+	/// adding up to a difficulty, the window of a PPLNS payout.
+	///
+	/// # Arguments
+	/// * `algorithm` - the algorithm of the shares ("cuckoo", "randomx" or "progpow").
+	/// * `window_difficulty` - the sum of share difficulties the window spans.
+	/// * `height` - the window ends at the shares of this block, the latest share if `None`.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`ShareWindow`](types/struct.ShareWindow.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_share_window_pplns(
+		&self,
+		algorithm: String,
+		window_difficulty: u64,
+		height: Option<u64>,
+	) -> Result<ShareWindow, Error> {
+		let share_ledger_handler = ShareLedgerHandler {
+			share_ledger: self.share_ledger.clone(),
+		};
+		share_ledger_handler.get_pplns_window(algorithm, window_difficulty, height)
+	}
+
+	/// Returns the shares accepted by the stratum server between two unix
+	/// timestamps, the window of a PPS payout.
+	///
+	/// # Arguments
+	/// * `from` - the start of the window, inclusive.
+	/// * `to` - the end of the window, inclusive.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`ShareWindow`](types/struct.ShareWindow.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_share_window_pps(&self, from: i64, to: i64) -> Result<ShareWindow, Error> {
+		let share_ledger_handler = ShareLedgerHandler {
+			share_ledger: self.share_ledger.clone(),
+		};
+		share_ledger_handler.get_pps_window(from, to)
+	}
+
+	/// Returns the transactions of the stempool, still under Dandelion embargo,
+	/// with where they came from and how long ago.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`PoolEntryPrintable`](types/struct.PoolEntryPrintable.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_stempool_transactions(&self) -> Result<Vec<PoolEntryPrintable>, Error> {
+		let now = Utc::now();
+		let entries = w(&self.tx_pool)?.stempool_entries();
+		Ok(entries
+			.iter()
+			.map(|x| PoolEntryPrintable::from_pool_entry(x, now))
+			.collect())
+	}
+
+	/// Removes a transaction from the txpool and the stempool, along with the
+	/// transactions spending its outputs.
+	///
+	/// # Arguments
+	/// * `kernel_excess` - the excess of one of the transaction kernels. The
+	/// networked version also accepts the kernel hash, see
+	/// [OwnerRpc::remove_pool_transaction](trait.OwnerRpc.html#tymethod.remove_pool_transaction).
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * The number of transactions removed
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn remove_pool_transaction(&self, kernel_excess: Commitment) -> Result<usize, Error> {
+		w(&self.tx_pool)?.remove_transaction(kernel_excess)
+	}
+
+	/// Fluffs a stempool transaction without waiting for its embargo to
+	/// expire, moving it to the txpool and broadcasting it to our peers.
+	///
+	/// # Arguments
+	/// * `kernel_excess` - the excess of one of the transaction kernels. The
+	/// networked version also accepts the kernel hash, see
+	/// [OwnerRpc::fluff_stem_transaction](trait.OwnerRpc.html#tymethod.fluff_stem_transaction).
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the transaction was fluffed
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn fluff_stem_transaction(&self, kernel_excess: Commitment) -> Result<(), Error> {
+		w(&self.tx_pool)?.fluff_transaction(kernel_excess)
+	}
+
+	/// Drops all the transactions of the txpool and the stempool.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the pools were cleared
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn clear_pool(&self) -> Result<(), Error> {
+		w(&self.tx_pool)?.clear();
+		Ok(())
+	}
 }
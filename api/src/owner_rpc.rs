@@ -14,11 +14,15 @@
 
 //! JSON-RPC Stub generation for the Owner API
 
+use crate::core::core::hash::Hash;
+use crate::handlers::utils::w;
 use crate::owner::Owner;
 use crate::p2p::types::PeerInfoDisplay;
 use crate::p2p::PeerData;
 use crate::rest::Error;
-use crate::types::Status;
+use crate::types::{HealthReport, PoolEntryPrintable, ShareWindow, Status};
+use crate::util;
+use crate::util::secp::{self, pedersen::Commitment};
 use std::net::SocketAddr;
 
 /// Public definition used to generate Node jsonrpc api.
@@ -353,6 +357,55 @@ pub trait OwnerRpc: Sync + Send {
 	 */
 	fn unban_peer(&self, peer_addr: SocketAddr) -> Result<(), Error>;
 
+	/**
+	Networked version of [Owner::get_health](struct.Node.html#method.get_health).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_health",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"live": true,
+				"ready": false,
+				"checked_at": 1735689600,
+				"expires_at": 1735689690,
+				"checks": [
+					{
+						"name": "store",
+						"state": "Ok",
+						"affects_liveness": true,
+						"message": "chain db readable"
+					},
+					{
+						"name": "sync_lag",
+						"state": "Critical",
+						"affects_liveness": false,
+						"message": "12 blocks behind best peer (height 371565)"
+					}
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_health(&self) -> Result<HealthReport, Error>;
+
 	/// Returns all known onion addresses of connected peers, excluding our own.
 	///
 	/// # Json rpc example
@@ -385,6 +438,259 @@ pub trait OwnerRpc: Sync + Send {
 	/// # );
 	/// ```
 	fn get_onion_addresses(&self) -> Result<Vec<String>, Error>;
+
+	/**
+	Networked version of [Owner::get_share_window_pplns](struct.Node.html#method.get_share_window_pplns).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_share_window_pplns",
+		"params": ["randomx", 200000, null],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"from": 1735689600,
+				"to": 1735693200,
+				"difficulty": 200000,
+				"workers": [
+					{
+						"login": "rig1",
+						"algorithm": "randomx",
+						"shares": 120,
+						"difficulty": 150000,
+						"blocks_found": 1
+					},
+					{
+						"login": "rig2",
+						"algorithm": "randomx",
+						"shares": 50,
+						"difficulty": 50000,
+						"blocks_found": 0
+					}
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_share_window_pplns(
+		&self,
+		algorithm: String,
+		window_difficulty: u64,
+		height: Option<u64>,
+	) -> Result<ShareWindow, Error>;
+
+	/**
+	Networked version of [Owner::get_share_window_pps](struct.Node.html#method.get_share_window_pps).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_share_window_pps",
+		"params": [1735689600, 1735693200],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"from": 1735689600,
+				"to": 1735693200,
+				"difficulty": 52000,
+				"workers": [
+					{
+						"login": "rig1",
+						"algorithm": "progpow",
+						"shares": 360,
+						"difficulty": 52000,
+						"blocks_found": 0
+					}
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_share_window_pps(&self, from: i64, to: i64) -> Result<ShareWindow, Error>;
+
+	/**
+	Networked version of [Owner::get_stempool_transactions](struct.Node.html#method.get_stempool_transactions).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_stempool_transactions",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+					{
+						"tx_hash": "4f12a36a2a33cce1d6ae82e2c3a3ea1dbb7d0ba35fd0a2a5b6d18d1da7c0e5d4",
+						"kernels": [
+							"08761e9cb1eea5bfcf771d1218b5ec802798d6eecaf75faae50ba3a1997aaef009"
+						],
+						"src": "PushApi",
+						"tx_at": "2025-01-01T00:00:00Z",
+						"age": 42,
+						"fee": 8000000,
+						"weight": 8
+					}
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_stempool_transactions(&self) -> Result<Vec<PoolEntryPrintable>, Error>;
+
+	/**
+	Networked version of [Owner::remove_pool_transaction](struct.Node.html#method.remove_pool_transaction).
+
+	The transaction is given by one of its kernels, either as the hex kernel
+	excess (33 bytes, as listed by `get_stempool_transactions`) or as the hex
+	kernel hash (32 bytes).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "remove_pool_transaction",
+		"params": ["08761e9cb1eea5bfcf771d1218b5ec802798d6eecaf75faae50ba3a1997aaef009"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": 1
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn remove_pool_transaction(&self, kernel: String) -> Result<usize, Error>;
+
+	/**
+	Networked version of [Owner::fluff_stem_transaction](struct.Node.html#method.fluff_stem_transaction).
+
+	The transaction is given by one of its kernels, either as the hex kernel
+	excess (33 bytes) or as the hex kernel hash (32 bytes).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "fluff_stem_transaction",
+		"params": ["08761e9cb1eea5bfcf771d1218b5ec802798d6eecaf75faae50ba3a1997aaef009"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn fluff_stem_transaction(&self, kernel: String) -> Result<(), Error>;
+
+	/**
+	Networked version of [Owner::clear_pool](struct.Node.html#method.clear_pool).
+
+	# Json rpc example
+
+	```
+	# epic_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "clear_pool",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn clear_pool(&self) -> Result<(), Error>;
+}
+
+/// Resolves a hex kernel excess or kernel hash to the kernel excess.
+fn parse_kernel(owner: &Owner, kernel: String) -> Result<Commitment, Error> {
+	let vec =
+		util::from_hex(kernel).map_err(|e| Error::Argument(format!("invalid kernel: {}", e)))?;
+	match vec.len() {
+		secp::constants::PEDERSEN_COMMITMENT_SIZE => Ok(Commitment::from_vec(vec)),
+		Hash::LEN => w(&owner.tx_pool)?
+			.kernel_excess(Hash::from_vec(&vec))
+			.ok_or(Error::NotFound),
+		len => Err(Error::Argument(format!(
+			"invalid kernel length: {} bytes, expected a kernel excess or hash",
+			len
+		))),
+	}
 }
 
 impl OwnerRpc for Owner {
@@ -416,9 +722,42 @@ impl OwnerRpc for Owner {
 		Owner::unban_peer(self, addr)
 	}
 
+	fn get_health(&self) -> Result<HealthReport, Error> {
+		Owner::get_health(self)
+	}
+
 	fn get_onion_addresses(&self) -> Result<Vec<String>, Error> {
 		Owner::get_onion_addresses(self)
 	}
+
+	fn get_share_window_pplns(
+		&self,
+		algorithm: String,
+		window_difficulty: u64,
+		height: Option<u64>,
+	) -> Result<ShareWindow, Error> {
+		Owner::get_share_window_pplns(self, algorithm, window_difficulty, height)
+	}
+
+	fn get_share_window_pps(&self, from: i64, to: i64) -> Result<ShareWindow, Error> {
+		Owner::get_share_window_pps(self, from, to)
+	}
+
+	fn get_stempool_transactions(&self) -> Result<Vec<PoolEntryPrintable>, Error> {
+		Owner::get_stempool_transactions(self)
+	}
+
+	fn remove_pool_transaction(&self, kernel: String) -> Result<usize, Error> {
+		Owner::remove_pool_transaction(self, parse_kernel(self, kernel)?)
+	}
+
+	fn fluff_stem_transaction(&self, kernel: String) -> Result<(), Error> {
+		Owner::fluff_stem_transaction(self, parse_kernel(self, kernel)?)
+	}
+
+	fn clear_pool(&self) -> Result<(), Error> {
+		Owner::clear_pool(self)
+	}
 }
 
 #[doc(hidden)]
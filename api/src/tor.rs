@@ -85,6 +85,25 @@ where
 		pool_handler.push_transaction(tx, fluff)
 	}
 
+	/// Receive a transaction in the onion stem phase, from the previous hop.
+	///
+	/// # Arguments
+	/// * `tx` - the Epic transaction to stem.
+	/// * `hops` - number of hops the transaction is relayed after us before
+	/// being fluffed.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the transaction was added to the stempool
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+	pub fn stem_transaction(&self, tx: Transaction, hops: u8) -> Result<(), Error> {
+		let pool_handler = PoolHandler {
+			tx_pool: self.tx_pool.clone(),
+		};
+		pool_handler.push_onion_stem_transaction(tx, hops)
+	}
+
 
 
 
@@ -28,6 +28,8 @@ pub trait TorRpc: Sync + Send {
 
     fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), Error>;
 
+	/// Onion stem phase relay, see [Tor::stem_transaction](struct.Tor.html#method.stem_transaction).
+	fn stem_transaction(&self, tx: Transaction, hops: u8) -> Result<(), Error>;
 }
 
 impl<B, P> TorRpc for Tor<B, P>
@@ -38,4 +40,8 @@ where
     fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), Error> {
 		Tor::push_transaction(self, tx, fluff)
 	}
+
+	fn stem_transaction(&self, tx: Transaction, hops: u8) -> Result<(), Error> {
+		Tor::stem_transaction(self, tx, hops)
+	}
 }
\ No newline at end of file
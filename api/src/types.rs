@@ -17,13 +17,17 @@ use std::sync::Arc;
 use crate::chain;
 use crate::core::consensus::HeaderInfo;
 use crate::core::core::hash::Hashed;
+use crate::core::core::feijoada::{AllowPolicy, PolicyConfig};
 use crate::core::core::merkle_proof::MerkleProof;
 use crate::core::core::{BlockHeader, KernelFeatures, Transaction, TxKernel};
 use crate::core::pow::PoWType;
 use crate::core::{core, ser};
 use crate::p2p;
+use crate::pool::{PoolEntry, TxSource};
+use crate::rest::Error;
 use crate::util;
 use crate::util::secp::pedersen;
+use chrono::prelude::{DateTime, Utc};
 use epic_core::pow::Proof;
 use serde;
 use serde::de::MapAccess;
@@ -72,6 +76,88 @@ impl Tip {
 	}
 }
 
+/// One era of the block policy schedule
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyEra {
+	/// Index of the policy, as found in the block headers
+	pub policy: u8,
+	/// First height at which new blocks follow this policy
+	pub start_height: u64,
+	/// Last height of the era, none for the last one
+	pub end_height: Option<u64>,
+	/// Target percentage of blocks for each algorithm
+	pub weights: HashMap<PoWType, u32>,
+}
+
+/// The block policy schedule followed by the node, so miners can see the
+/// upcoming algorithm mixes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicySchedule {
+	/// Height of the chain head
+	pub height: u64,
+	/// Policy the next block will follow
+	pub next_policy: u8,
+	/// Policy eras, in height order
+	pub eras: Vec<PolicyEra>,
+	/// Bitmask of the policies accepted in blocks, from each height on
+	pub allowed_policies: Vec<AllowPolicy>,
+}
+
+impl PolicySchedule {
+	pub fn from_config(config: &PolicyConfig, next_policy: u8, height: u64) -> PolicySchedule {
+		let heights = config.era_heights();
+		let eras = config
+			.policies
+			.iter()
+			.zip(heights.iter())
+			.enumerate()
+			.map(|(i, (weights, &start_height))| PolicyEra {
+				policy: i as u8,
+				start_height,
+				end_height: heights
+					.get(i + 1)
+					.filter(|_| i + 1 < config.policies.len())
+					.map(|next| next - 1),
+				weights: weights.clone(),
+			})
+			.collect();
+		PolicySchedule {
+			height,
+			next_policy,
+			eras,
+			allowed_policies: config.allowed_policies.clone(),
+		}
+	}
+}
+
+/// Aggregates over the blocks mined with one algorithm
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AlgoStats {
+	/// Number of blocks
+	pub blocks: u64,
+	/// Average time since the previous block, in seconds
+	pub avg_solve_time: f64,
+	/// Average difficulty of the blocks
+	pub avg_difficulty: u64,
+	/// Sum of the transaction fees of the blocks
+	pub total_fees: u64,
+}
+
+/// Per algorithm block statistics over a range of heights
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AlgoStatsListing {
+	/// First height of the range
+	pub from_height: u64,
+	/// Last height of the range
+	pub to_height: u64,
+	/// Statistics for each algorithm that mined at least one block
+	pub algos: HashMap<PoWType, AlgoStats>,
+	/// Sum of the transaction fees of all the blocks
+	pub total_fees: u64,
+	/// Blocks whose body was pruned, their fees are not counted
+	pub pruned_blocks: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Status {
 	// The protocol version
@@ -812,6 +898,69 @@ pub struct LocatedTxKernel {
 	pub mmr_index: u64,
 }
 
+/// Compact filter of a block (a Golomb-coded set of its output commitments
+/// and kernel excesses), for light clients
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BlockFilterPrintable {
+	/// Hash of the block
+	pub block_hash: String,
+	/// Height of the block
+	pub height: u64,
+	/// Number of items in the filter
+	pub n: u32,
+	/// The Golomb-Rice coded filter data, as hex
+	pub filter: String,
+	/// Filter header of the block, committing to the filters of the block and
+	/// every block before it. Unknown if the node lacks blocks down to genesis.
+	pub filter_header: Option<String>,
+}
+
+impl BlockFilterPrintable {
+	pub fn from_filter(
+		filter: &core::BlockFilter,
+		height: u64,
+		filter_header: Option<core::hash::Hash>,
+	) -> BlockFilterPrintable {
+		BlockFilterPrintable {
+			block_hash: filter.block_hash.to_hex(),
+			height,
+			n: filter.n,
+			filter: util::to_hex(filter.data.clone()),
+			filter_header: filter_header.map(|h| h.to_hex()),
+		}
+	}
+}
+
+/// Where an output was created and, if spent, where it was spent
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OutputHistory {
+	/// The output commitment
+	pub commit: String,
+	/// Height of the block creating the output
+	pub created_height: u64,
+	/// Hash of the block creating the output
+	pub created_block: String,
+	/// Height of the block spending the output, if spent
+	pub spent_height: Option<u64>,
+	/// Hash of the block spending the output, if spent
+	pub spent_block: Option<String>,
+}
+
+impl OutputHistory {
+	pub fn from_history(
+		commit: &pedersen::Commitment,
+		history: &chain::OutputHistory,
+	) -> OutputHistory {
+		OutputHistory {
+			commit: util::to_hex(commit.0.to_vec()),
+			created_height: history.created_height,
+			created_block: history.created_block.to_hex(),
+			spent_height: history.spent.map(|(height, _)| height),
+			spent_block: history.spent.map(|(_, hash)| hash.to_hex()),
+		}
+	}
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PoolInfo {
 	/// Size of the pool
@@ -820,6 +969,193 @@ pub struct PoolInfo {
 	pub txs: Vec<Transaction>,
 }
 
+/// Outcome of a single node health check
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HealthState {
+	Ok,
+	Warning,
+	Critical,
+}
+
+/// Result of a single node health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+	/// Name of the check
+	pub name: String,
+	/// Outcome of the check
+	pub state: HealthState,
+	/// Whether a critical outcome means the node is not live (needs a restart)
+	/// rather than just not ready to serve
+	pub affects_liveness: bool,
+	/// Human readable detail
+	pub message: String,
+}
+
+impl HealthCheck {
+	pub fn new(name: &str, state: HealthState, affects_liveness: bool, message: String) -> Self {
+		HealthCheck {
+			name: name.to_owned(),
+			state,
+			affects_liveness,
+			message,
+		}
+	}
+}
+
+/// Liveness and readiness of the node, refreshed periodically by the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+	/// The node is up and does not need a restart
+	pub live: bool,
+	/// The node is synced and fit to serve requests
+	pub ready: bool,
+	/// Unix timestamp of the last run of the checks
+	pub checked_at: i64,
+	/// Unix timestamp after which this report is considered stale
+	pub expires_at: i64,
+	/// Individual check results
+	pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+	pub fn new(checks: Vec<HealthCheck>, checked_at: i64, expires_at: i64) -> Self {
+		let critical = |c: &&HealthCheck| c.state == HealthState::Critical;
+		let live = !checks.iter().filter(critical).any(|c| c.affects_liveness);
+		let ready = live && !checks.iter().any(|c| critical(&c));
+		HealthReport {
+			live,
+			ready,
+			checked_at,
+			expires_at,
+			checks,
+		}
+	}
+
+	/// The report as seen at time `now`. A stale report means the health
+	/// monitor itself is stuck, so the node is reported as neither live nor ready.
+	pub fn at(&self, now: i64) -> HealthReport {
+		if now <= self.expires_at {
+			return self.clone();
+		}
+		let mut checks = self.checks.clone();
+		checks.push(HealthCheck::new(
+			"health_monitor",
+			HealthState::Critical,
+			true,
+			format!("last health check ran at {}, report is stale", self.checked_at),
+		));
+		HealthReport::new(checks, self.checked_at, self.expires_at)
+	}
+}
+
+impl Default for HealthReport {
+	fn default() -> HealthReport {
+		HealthReport::new(vec![], 0, 0)
+	}
+}
+
+/// Shares of a worker for one algorithm in a payout window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerShares {
+	/// Worker login, or its connection id if it did not log in
+	pub login: String,
+	/// Algorithm the shares were mined with
+	pub algorithm: String,
+	/// Number of shares
+	pub shares: u64,
+	/// Sum of the difficulties the shares were requested at
+	pub difficulty: u64,
+	/// Number of shares that were blocks
+	pub blocks_found: u64,
+}
+
+/// Shares accepted by the stratum server in a payout window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShareWindow {
+	/// Unix timestamp of the oldest share in the window
+	pub from: i64,
+	/// Unix timestamp of the newest share in the window
+	pub to: i64,
+	/// Sum of the difficulties of all the shares
+	pub difficulty: u64,
+	/// Shares by worker and algorithm
+	pub workers: Vec<WorkerShares>,
+}
+
+/// Persistent log of the shares accepted by the stratum server, for pools to
+/// compute payouts from
+pub trait ShareLedger: Send + Sync {
+	/// The last shares of an algorithm adding up to `window_difficulty`,
+	/// ending at the shares of block `height` or at the latest (PPLNS).
+	fn pplns_window(
+		&self,
+		algorithm: &str,
+		window_difficulty: u64,
+		height: Option<u64>,
+	) -> Result<ShareWindow, Error>;
+
+	/// All the shares accepted between two unix timestamps, inclusive (PPS).
+	fn pps_window(&self, from: i64, to: i64) -> Result<ShareWindow, Error>;
+}
+
+/// A transaction of the pool, as listed to the node operator
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PoolEntryPrintable {
+	/// Hash of the transaction
+	pub tx_hash: String,
+	/// Excesses of the transaction kernels, to remove or fluff it by
+	pub kernels: Vec<String>,
+	/// Where the transaction came from
+	pub src: TxSource,
+	/// When the transaction entered the pool
+	pub tx_at: DateTime<Utc>,
+	/// Seconds since the transaction entered the pool
+	pub age: i64,
+	/// Fee paid by the transaction
+	pub fee: u64,
+	/// Weight of the transaction
+	pub weight: u64,
+}
+
+impl PoolEntryPrintable {
+	pub fn from_pool_entry(entry: &PoolEntry, now: DateTime<Utc>) -> PoolEntryPrintable {
+		PoolEntryPrintable {
+			tx_hash: entry.tx.hash().to_hex(),
+			kernels: entry
+				.tx
+				.kernels()
+				.iter()
+				.map(|k| util::to_hex(k.excess().0.to_vec()))
+				.collect(),
+			src: entry.src.clone(),
+			tx_at: entry.tx_at,
+			age: (now - entry.tx_at).num_seconds(),
+			fee: entry.tx.fee(),
+			weight: entry.tx.tx_weight() as u64,
+		}
+	}
+}
+
+/// Operator access to the transaction pool, for the owner api
+pub trait TxPoolManager: Send + Sync {
+	/// The transactions of the stempool, still under embargo.
+	fn stempool_entries(&self) -> Vec<PoolEntry>;
+
+	/// Excess of the kernel with the given hash, looked up in both pools.
+	fn kernel_excess(&self, kernel_hash: core::hash::Hash) -> Option<pedersen::Commitment>;
+
+	/// Removes the transaction with the given kernel excess from both pools,
+	/// along with the transactions spending its outputs, returning how many
+	/// were.
+	fn remove_transaction(&self, kernel_excess: pedersen::Commitment) -> Result<usize, Error>;
+
+	/// Fluffs the stempool transaction with the given kernel excess right away.
+	fn fluff_transaction(&self, kernel_excess: pedersen::Commitment) -> Result<(), Error>;
+
+	/// Drops all the transactions of both pools.
+	fn clear(&self);
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -842,6 +1178,32 @@ mod test {
 		assert_eq!(serialized, hex_output);
 	}
 
+	#[test]
+	fn health_report_state() {
+		let checks = vec![
+			HealthCheck::new("a", HealthState::Ok, true, "".to_owned()),
+			HealthCheck::new("b", HealthState::Warning, true, "".to_owned()),
+		];
+		let report = HealthReport::new(checks.clone(), 10, 20);
+		assert!(report.live && report.ready);
+
+		let mut not_ready = checks.clone();
+		not_ready.push(HealthCheck::new("c", HealthState::Critical, false, "".to_owned()));
+		let report = HealthReport::new(not_ready, 10, 20);
+		assert!(report.live && !report.ready);
+
+		let mut not_live = checks.clone();
+		not_live.push(HealthCheck::new("d", HealthState::Critical, true, "".to_owned()));
+		let report = HealthReport::new(not_live, 10, 20);
+		assert!(!report.live && !report.ready);
+
+		// Stale reports are neither live nor ready.
+		let report = HealthReport::new(checks, 10, 20);
+		assert!(report.at(20).ready);
+		let stale = report.at(21);
+		assert!(!stale.live && !stale.ready);
+	}
+
 	#[test]
 	fn serialize_output() {
 		let hex_commit = "{\
@@ -33,7 +33,10 @@ fn test_external_coinbase_and_set_txhashset_roots() {
 
 	// Setup a test chain and tx pool (pseudo-code, adapt to your test infra)
 	let pool_adapter = Arc::new(PoolToChainAdapter::new());
-	let pool_net_adapter = Arc::new(PoolToNetAdapter::new(config.dandelion_config.clone()));
+	let pool_net_adapter = Arc::new(PoolToNetAdapter::new(
+		config.dandelion_config.clone(),
+		Arc::new(epic_api::BlockTemplates::new()),
+	));
 	let _tx_pool = Arc::new(RwLock::new(TransactionPool::new(
 		config.pool_config.clone(),
 		pool_adapter.clone(),
@@ -0,0 +1,294 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Portable chain archive, used to seed a node from a file instead of
+//! syncing the full history over p2p.
+//!
+//! Layout of an archive file:
+//!
+//! ```text
+//! magic (8) | version (u16) | protocol version (u32) | genesis hash (32)
+//! | from_height (u64) | to_height (u64)
+//! { RECORD_BLOCK (u8) | len (u64) | block bytes } *
+//! RECORD_END (u8) | block count (u64) | checksum (32)
+//! ```
+//!
+//! The checksum is a blake2b hash over all the block records, so a corrupted
+//! or truncated archive is detected before anything is written to the db.
+
+use crate::chain::Chain;
+use crate::core::core::hash::{Hash, HashWriter, Hashed};
+use crate::core::core::Block;
+use crate::core::ser::{self, ProtocolVersion, Writer};
+use crate::error::Error;
+use crate::types::Options;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Magic bytes at the start of every chain archive.
+pub const ARCHIVE_MAGIC: [u8; 8] = *b"EPICARCH";
+
+/// Current version of the archive file format.
+pub const ARCHIVE_VERSION: u16 = 1;
+
+const RECORD_BLOCK: u8 = 0x01;
+const RECORD_END: u8 = 0xff;
+
+/// Refuse to allocate for a single block record larger than this.
+const MAX_RECORD_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Fixed size header at the start of a chain archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveHeader {
+	/// Archive file format version
+	pub version: u16,
+	/// Protocol version used to serialize the blocks
+	pub protocol_version: ProtocolVersion,
+	/// Genesis hash of the chain the blocks belong to
+	pub genesis: Hash,
+	/// Height of the first block in the archive
+	pub from_height: u64,
+	/// Height of the last block in the archive
+	pub to_height: u64,
+}
+
+impl ArchiveHeader {
+	fn write(&self, sink: &mut dyn Write) -> Result<(), Error> {
+		sink.write_all(&ARCHIVE_MAGIC)?;
+		sink.write_all(&self.version.to_be_bytes())?;
+		sink.write_all(&self.protocol_version.value().to_be_bytes())?;
+		sink.write_all(self.genesis.as_bytes())?;
+		sink.write_all(&self.from_height.to_be_bytes())?;
+		sink.write_all(&self.to_height.to_be_bytes())?;
+		Ok(())
+	}
+
+	fn read(source: &mut dyn Read) -> Result<ArchiveHeader, Error> {
+		let mut magic = [0u8; 8];
+		source.read_exact(&mut magic)?;
+		if magic != ARCHIVE_MAGIC {
+			return Err(Error::ArchiveErr("not a chain archive".to_owned()));
+		}
+		let version = u16::from_be_bytes(read_array(source)?);
+		if version != ARCHIVE_VERSION {
+			return Err(Error::ArchiveErr(format!(
+				"unsupported archive version {}",
+				version
+			)));
+		}
+		let protocol_version = ProtocolVersion(u32::from_be_bytes(read_array(source)?));
+		let genesis = Hash::from_vec(&read_array::<32>(source)?);
+		let from_height = u64::from_be_bytes(read_array(source)?);
+		let to_height = u64::from_be_bytes(read_array(source)?);
+		Ok(ArchiveHeader {
+			version,
+			protocol_version,
+			genesis,
+			from_height,
+			to_height,
+		})
+	}
+}
+
+/// Summary of an archive export or import.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveSummary {
+	/// The archive header
+	pub header: ArchiveHeader,
+	/// Number of blocks written to (or read from) the archive
+	pub blocks: u64,
+	/// Number of blocks skipped on import because we already had them
+	pub skipped: u64,
+	/// Checksum over all block records
+	pub checksum: Hash,
+}
+
+fn read_array<const N: usize>(source: &mut dyn Read) -> Result<[u8; N], Error> {
+	let mut buf = [0u8; N];
+	source.read_exact(&mut buf)?;
+	Ok(buf)
+}
+
+/// Streams full blocks from `from_height` up to the current chain head into
+/// the archive at `path`. Requires the full blocks to still be in the db, so
+/// exporting from below the horizon only works on an archive node.
+pub fn export(chain: &Chain, path: &Path, from_height: u64) -> Result<ArchiveSummary, Error> {
+	let head = chain.head()?;
+	if from_height > head.height {
+		return Err(Error::ArchiveErr(format!(
+			"from height {} is above chain head {}",
+			from_height, head.height
+		)));
+	}
+
+	let header = ArchiveHeader {
+		version: ARCHIVE_VERSION,
+		protocol_version: ProtocolVersion::local(),
+		genesis: chain.get_header_by_height(0)?.hash(),
+		from_height,
+		to_height: head.height,
+	};
+
+	let mut sink = BufWriter::new(File::create(path)?);
+	header.write(&mut sink)?;
+
+	let mut hasher = HashWriter::default();
+	let mut blocks = 0;
+	for height in from_height..=head.height {
+		let hash = chain.get_header_by_height(height)?.hash();
+		let block = chain.get_block(&hash).map_err(|_| {
+			Error::ArchiveErr(format!(
+				"full block at height {} is not available (pruned node?)",
+				height
+			))
+		})?;
+		let bytes = ser::ser_vec(&block, header.protocol_version).map_err(Error::SerErr)?;
+
+		let mut record = Vec::with_capacity(bytes.len() + 9);
+		record.push(RECORD_BLOCK);
+		record.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+		record.extend_from_slice(&bytes);
+
+		hasher.write_fixed_bytes(&record).map_err(Error::SerErr)?;
+		sink.write_all(&record)?;
+		blocks += 1;
+
+		if blocks % 10_000 == 0 {
+			info!("export: {} blocks written, at height {}", blocks, height);
+		}
+	}
+
+	let checksum = hasher.into_hash();
+	sink.write_all(&[RECORD_END])?;
+	sink.write_all(&blocks.to_be_bytes())?;
+	sink.write_all(checksum.as_bytes())?;
+	sink.flush()?;
+
+	Ok(ArchiveSummary {
+		header,
+		blocks,
+		skipped: 0,
+		checksum,
+	})
+}
+
+/// Walks the archive calling `f` for every block record, then checks the
+/// trailer (block count and checksum). Blocks are only deserialized when
+/// `f` asks for them, so a verification pass stays cheap.
+fn read_records<F>(path: &Path, mut f: F) -> Result<ArchiveSummary, Error>
+where
+	F: FnMut(&ArchiveHeader, &[u8]) -> Result<(), Error>,
+{
+	let mut source = BufReader::new(File::open(path)?);
+	let header = ArchiveHeader::read(&mut source)?;
+
+	let mut hasher = HashWriter::default();
+	let mut blocks = 0;
+	loop {
+		let [tag] = read_array::<1>(&mut source)?;
+		match tag {
+			RECORD_BLOCK => {
+				let len_bytes = read_array::<8>(&mut source)?;
+				let len = u64::from_be_bytes(len_bytes);
+				if len > MAX_RECORD_SIZE {
+					return Err(Error::ArchiveErr(format!(
+						"block record of {} bytes is too large",
+						len
+					)));
+				}
+				let mut bytes = vec![0u8; len as usize];
+				source.read_exact(&mut bytes)?;
+
+				hasher.write_fixed_bytes(&[tag]).map_err(Error::SerErr)?;
+				hasher.write_fixed_bytes(&len_bytes).map_err(Error::SerErr)?;
+				hasher.write_fixed_bytes(&bytes).map_err(Error::SerErr)?;
+
+				f(&header, &bytes)?;
+				blocks += 1;
+			}
+			RECORD_END => break,
+			_ => {
+				return Err(Error::ArchiveErr(format!(
+					"unexpected record type {:#x}",
+					tag
+				)))
+			}
+		}
+	}
+
+	let count = u64::from_be_bytes(read_array(&mut source)?);
+	let checksum = Hash::from_vec(&read_array::<32>(&mut source)?);
+	if count != blocks {
+		return Err(Error::ArchiveErr(format!(
+			"archive claims {} blocks but contains {}",
+			count, blocks
+		)));
+	}
+	if checksum != hasher.into_hash() {
+		return Err(Error::ArchiveErr("checksum mismatch".to_owned()));
+	}
+
+	Ok(ArchiveSummary {
+		header,
+		blocks,
+		skipped: 0,
+		checksum,
+	})
+}
+
+/// Checks the archive at `path` is well formed and its checksum matches,
+/// without touching the chain.
+pub fn verify(path: &Path) -> Result<ArchiveSummary, Error> {
+	read_records(path, |_, _| Ok(()))
+}
+
+/// Verifies the archive at `path` and replays its blocks through the regular
+/// block processing pipeline with the given options (normally
+/// `Options::SYNC`). Blocks we already know about are skipped.
+pub fn import(chain: &Chain, path: &Path, opts: Options) -> Result<ArchiveSummary, Error> {
+	let verified = verify(path)?;
+
+	let genesis = chain.get_header_by_height(0)?.hash();
+	if verified.header.genesis != genesis {
+		return Err(Error::ArchiveErr(format!(
+			"archive genesis {} does not match our genesis {}",
+			verified.header.genesis, genesis
+		)));
+	}
+
+	let mut skipped = 0;
+	let mut summary = read_records(path, |header, mut bytes| {
+		let block: Block = ser::deserialize(&mut bytes, header.protocol_version)
+			.map_err(Error::SerErr)?;
+		let height = block.header.height;
+		match chain.process_block(block, opts) {
+			Ok(_) => {}
+			Err(Error::Unfit(_)) => skipped += 1,
+			Err(e) => {
+				return Err(Error::ArchiveErr(format!(
+					"failed to import block at height {}: {}",
+					height, e
+				)))
+			}
+		}
+		if height % 10_000 == 0 {
+			info!("import: processed block at height {}", height);
+		}
+		Ok(())
+	})?;
+
+	summary.skipped = skipped;
+	Ok(summary)
+}
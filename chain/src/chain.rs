@@ -18,7 +18,8 @@
 use crate::core::core::hash::{Hash, Hashed, ZERO_HASH};
 use crate::core::core::merkle_proof::MerkleProof;
 use crate::core::core::{
-	Block, BlockHeader, BlockSums, Committed, Output, OutputIdentifier, Transaction, TxKernel,
+	Block, BlockFilter, BlockHeader, BlockSums, Committed, Output, OutputIdentifier, Transaction,
+	TxKernel,
 };
 use crate::core::global;
 use crate::core::pow;
@@ -30,8 +31,8 @@ use crate::store;
 use crate::txhashset;
 use crate::txhashset::{PMMRHandle, TxHashSet};
 use crate::types::{
-	BlockStatus, BlockchainCheckpoints, ChainAdapter, CommitPos, NoStatus, Options, Tip,
-	TxHashsetWriteStatus,
+	BlockStatus, BlockchainCheckpoints, ChainAdapter, CommitPos, NoStatus, Options,
+	OutputHistory, Reorg, Tip, TxHashsetWriteStatus,
 };
 use crate::util::secp::pedersen::{Commitment, RangeProof};
 use crate::util::RwLock;
@@ -40,7 +41,7 @@ use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::Read;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -165,6 +166,10 @@ pub struct Chain {
 	pow_verifier: fn(&BlockHeader) -> Result<(), pow::Error>,
 	archive_mode: bool,
 	genesis: BlockHeader,
+	checkpoints: RwLock<BlockchainCheckpoints>,
+	// skip rangeproof and kernel signature checks up to the last checkpoint
+	assume_valid: AtomicBool,
+	max_reorg_depth: RwLock<Option<u64>>,
 }
 
 impl Chain {
@@ -228,12 +233,24 @@ impl Chain {
 			pow_verifier,
 			archive_mode,
 			genesis: genesis.header.clone(),
+			checkpoints: RwLock::new(BlockchainCheckpoints::new()),
+			assume_valid: AtomicBool::new(false),
+			max_reorg_depth: RwLock::new(None),
 		};
 
 		// DB migrations to be run prior to the chain being used.
 		{
 			// Migrate full blocks to protocol version v2.
 			chain.migrate_db_v1_v2()?;
+
+			// Index the headers by timestamp if the db predates the index.
+			chain.init_timestamp_index()?;
+
+			// Archive nodes keep the history of every output.
+			chain.init_output_history()?;
+
+			// Build the compact block filters if the db predates them.
+			chain.init_block_filters()?;
 		}
 
 		chain.log_heads()?;
@@ -340,23 +357,25 @@ impl Chain {
 		res
 	}
 
-	fn determine_status(&self, head: Option<Tip>, prev_head: Tip) -> BlockStatus {
+	fn determine_status(
+		&self,
+		head: Option<Tip>,
+		prev_head: Tip,
+		reorg: Option<&Reorg>,
+	) -> BlockStatus {
 		// We have more work if the chain head is updated.
 		let is_more_work = head.is_some();
 
 		let mut is_next_block = false;
-		let mut reorg_depth = None;
 		if let Some(head) = head {
 			if head.prev_block_h == prev_head.last_block_h {
 				is_next_block = true;
-			} else {
-				reorg_depth = Some(prev_head.height.saturating_sub(head.height) + 1);
 			}
 		}
 
 		match (is_more_work, is_next_block) {
 			(true, true) => BlockStatus::Next,
-			(true, false) => BlockStatus::Reorg(reorg_depth.unwrap_or(0)),
+			(true, false) => BlockStatus::Reorg(reorg.map_or(0, |reorg| reorg.depth())),
 			(false, _) => BlockStatus::Fork,
 		}
 	}
@@ -431,7 +450,13 @@ impl Chain {
 		// Only do this once we know the header PoW is valid.
 		self.check_orphan(&b, opts)?;
 
-		let (maybe_new_head, prev_head) = {
+		let opts = if self.is_assumed_valid(&b.header)? {
+			opts | Options::ASSUME_VALID
+		} else {
+			opts
+		};
+
+		let (maybe_new_head, prev_head, reorg) = {
 			let mut header_pmmr = self.header_pmmr.write();
 			let mut txhashset = self.txhashset.write();
 			let batch = self.store.batch()?;
@@ -440,6 +465,7 @@ impl Chain {
 			let prev_head = ctx.batch.head()?;
 
 			let maybe_new_head = pipe::process_block(&b, &mut ctx)?;
+			let reorg = ctx.reorg.take();
 
 			// We have flushed txhashset extension changes to disk
 			// but not yet committed the batch.
@@ -448,10 +474,14 @@ impl Chain {
 			ctx.batch.commit()?;
 
 			// release the lock and let the batch go before post-processing
-			(maybe_new_head, prev_head)
+			(maybe_new_head, prev_head, reorg)
 		};
 
-		let status = self.determine_status(maybe_new_head.clone(), prev_head);
+		let status = self.determine_status(maybe_new_head.clone(), prev_head, reorg.as_ref());
+
+		if let Some(ref reorg) = reorg {
+			self.adapter.reorg(reorg);
+		}
 
 		// notifying other parts of the system of the update
 		self.adapter.block_accepted(&b, status, opts);
@@ -512,6 +542,8 @@ impl Chain {
 			header_pmmr,
 			txhashset,
 			batch,
+			max_reorg_depth: *self.max_reorg_depth.read(),
+			reorg: None,
 		})
 	}
 
@@ -1119,6 +1151,11 @@ impl Chain {
 		// Rebuild our output_pos index in the db based on fresh UTXO set.
 		txhashset.init_output_pos_index(&header_pmmr, &batch)?;
 
+		// The kernel MMR was replaced as a whole, so is the kernel excess index.
+		if batch.kernel_index_enabled()? {
+			txhashset.init_kernel_pos_index(&header_pmmr, &batch)?;
+		}
+
 		// Commit all the changes to the db.
 		batch.commit()?;
 
@@ -1428,6 +1465,179 @@ impl Chain {
 		Ok(())
 	}
 
+	/// Builds the header timestamp index for the current header chain, when
+	/// missing. Headers saved from then on are indexed as they are stored.
+	fn init_timestamp_index(&self) -> Result<(), Error> {
+		let header_head = self.header_head()?;
+		let head_header = self.get_block_header(&header_head.last_block_h)?;
+		if self.store.is_timestamp_indexed(&head_header)? {
+			return Ok(());
+		}
+
+		info!("init_timestamp_index: indexing {} headers", header_head.height + 1);
+		let mut batch = self.store.batch()?;
+		for height in 0..=header_head.height {
+			let header = self.get_header_by_height(height)?;
+			batch.save_timestamp_index(&header)?;
+			if height % 10_000 == 0 && height > 0 {
+				batch.commit()?;
+				batch = self.store.batch()?;
+			}
+		}
+		batch.commit()?;
+		Ok(())
+	}
+
+	/// Builds the output history index from our full blocks when running as
+	/// an archive node, and drops it otherwise.
+	fn init_output_history(&self) -> Result<(), Error> {
+		if !self.archive_mode {
+			if self.store.output_history_enabled()? {
+				let batch = self.store.batch()?;
+				batch.set_output_history_enabled(false)?;
+				batch.commit()?;
+			}
+			return Ok(());
+		}
+		if self.store.output_history_enabled()? {
+			return Ok(());
+		}
+
+		let tail = self.tail()?;
+		let head = self.head()?;
+		info!(
+			"init_output_history: indexing outputs of {} blocks",
+			head.height - tail.height + 1
+		);
+		let mut batch = self.store.batch()?;
+		// Clean up after a previous, interrupted, build.
+		batch.clear_output_history()?;
+		// Heights of the outputs not spent yet, the whole history is built by
+		// this run and the db does not see what the open batch wrote.
+		let mut unspent: HashMap<Commitment, Vec<u64>> = HashMap::new();
+		for height in tail.height..=head.height {
+			let header = self.get_header_by_height(height)?;
+			let block = batch.get_block(&header.hash())?;
+			for out in block.outputs() {
+				batch.save_output_history(&out.commitment(), &OutputHistory::created(&header))?;
+				unspent.entry(out.commitment()).or_default().push(height);
+			}
+			let spent = batch.get_spent_index(&header.hash()).ok();
+			for (i, input) in block.inputs().iter().enumerate() {
+				let commit = input.commitment();
+				let heights = unspent.entry(commit).or_default();
+				let created_height = match spent {
+					Some(ref spent) => {
+						heights.retain(|h| *h != spent[i].height);
+						Some(spent[i].height)
+					}
+					// No spent index for old blocks, spend the latest unspent output.
+					None => heights.pop(),
+				};
+				if heights.is_empty() {
+					unspent.remove(&commit);
+				}
+				if let Some(created_height) = created_height {
+					let spent_at = Some((height, header.hash()));
+					batch.update_output_history_spent(&commit, created_height, spent_at)?;
+				}
+			}
+			if height % 1_000 == 0 && height > tail.height {
+				batch.commit()?;
+				batch = self.store.batch()?;
+			}
+		}
+		batch.set_output_history_enabled(true)?;
+		batch.commit()?;
+		Ok(())
+	}
+
+	/// Builds the compact filters of the blocks we have if the db predates
+	/// them. Their filter headers are only built when we still have every
+	/// block down to genesis.
+	fn init_block_filters(&self) -> Result<(), Error> {
+		let head = self.head()?;
+		if self.store.get_block_filter(&head.last_block_h).is_ok() {
+			return Ok(());
+		}
+
+		let tail = self.tail()?;
+		info!(
+			"init_block_filters: building filters of {} blocks",
+			head.height - tail.height + 1
+		);
+		let mut batch = self.store.batch()?;
+		for height in tail.height..=head.height {
+			let header = self.get_header_by_height(height)?;
+			let block = batch.get_block(&header.hash())?;
+			pipe::add_block_filter(&block, &batch)?;
+			if height % 1_000 == 0 && height > tail.height {
+				batch.commit()?;
+				batch = self.store.batch()?;
+			}
+		}
+		batch.commit()?;
+		Ok(())
+	}
+
+	/// Whether we have the filter header of our head, i.e. the filters of
+	/// every block down to genesis. Pruned and fast-synced nodes only have
+	/// the filters from their tail on and cannot serve filter headers.
+	pub fn has_block_filter_headers(&self) -> bool {
+		self.head()
+			.and_then(|head| self.get_block_filter_header(&head.last_block_h))
+			.is_ok()
+	}
+
+	/// Gets the compact filter of the block with the given hash.
+	pub fn get_block_filter(&self, h: &Hash) -> Result<BlockFilter, Error> {
+		self.store
+			.get_block_filter(h)
+			.map_err(|e| Error::ChainStoreErr(e, "chain get block filter".to_owned()))
+	}
+
+	/// Gets the filter header of the block with the given hash. Only known
+	/// when we have the filters of every block down to genesis.
+	pub fn get_block_filter_header(&self, h: &Hash) -> Result<Hash, Error> {
+		self.store
+			.get_block_filter_header(h)
+			.map_err(|e| Error::ChainStoreErr(e, "chain get block filter header".to_owned()))
+	}
+
+	/// Gets the history (creation and, if spent, spending block) of every
+	/// output created with the given commitment. Only archive nodes keep it.
+	pub fn get_output_history(&self, commit: &Commitment) -> Result<Vec<OutputHistory>, Error> {
+		if !self.store.output_history_enabled()? {
+			return Err(Error::Other("output history is only kept in archive mode".to_owned()));
+		}
+		Ok(self.store.output_history_iter(commit)?.collect())
+	}
+
+	/// Gets the headers on the current chain with a timestamp between `start`
+	/// and `end` (inclusive, in seconds), in timestamp order. Stops after `max`
+	/// headers.
+	pub fn get_headers_by_time(
+		&self,
+		start: i64,
+		end: i64,
+		max: usize,
+	) -> Result<Vec<BlockHeader>, Error> {
+		let head = self.head()?;
+		let mut headers = vec![];
+		for (timestamp, hash) in self.store.timestamp_iter(start)? {
+			if timestamp > end || headers.len() >= max {
+				break;
+			}
+			let header = self.get_block_header(&hash)?;
+			// Skip fork headers and headers we don't have the block of yet.
+			if header.height > head.height || self.is_on_current_chain(&header).is_err() {
+				continue;
+			}
+			headers.push(header);
+		}
+		Ok(headers)
+	}
+
 	/// Gets the block header in which a given output appears in the txhashset.
 	pub fn get_header_for_output(
 		&self,
@@ -1447,6 +1657,10 @@ impl Chain {
 		min_height: Option<u64>,
 		max_height: Option<u64>,
 	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		if self.store.kernel_index_enabled()? {
+			return self.get_kernel_height_indexed(excess, min_height, max_height);
+		}
+
 		let min_index = match min_height {
 			Some(h) => Some(self.get_header_by_height(h - 1)?.kernel_mmr_size + 1),
 			None => None,
@@ -1470,6 +1684,61 @@ impl Chain {
 
 		Ok(Some((kernel, header.height, mmr_index)))
 	}
+
+	/// Kernel lookup through the kernel excess index, no MMR scan required.
+	fn get_kernel_height_indexed(
+		&self,
+		excess: &Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		let (pos, height) = match self.store.get_kernel_pos_height(excess) {
+			Ok(pos_height) => pos_height,
+			Err(NotFoundErr(_)) => return Ok(None),
+			Err(e) => return Err(Error::StoreErr(e)),
+		};
+		if min_height.map_or(false, |h| height < h) || max_height.map_or(false, |h| height > h) {
+			return Ok(None);
+		}
+
+		match self.txhashset.read().get_kernel(pos) {
+			Some(kernel) if &kernel.excess == excess => Ok(Some((kernel, height, pos))),
+			_ => Err(Error::Other(format!(
+				"kernel index entry for {:?} does not match the kernel MMR at {}",
+				excess, pos
+			))),
+		}
+	}
+
+	/// Turn the kernel excess index on or off. Turning it on builds the index
+	/// unless it is already maintained, turning it off deletes it.
+	pub fn set_kernel_index(&self, enabled: bool) -> Result<(), Error> {
+		if self.store.kernel_index_enabled()? == enabled {
+			return Ok(());
+		}
+		if enabled {
+			self.rebuild_kernel_index()?;
+		} else {
+			let batch = self.store.batch()?;
+			batch.set_kernel_index_enabled(false)?;
+			batch.commit()?;
+			info!("Kernel index removed");
+		}
+		Ok(())
+	}
+
+	/// Build the kernel excess index from scratch and maintain it from now on.
+	/// Returns the number of kernels indexed.
+	pub fn rebuild_kernel_index(&self) -> Result<u64, Error> {
+		let header_pmmr = self.header_pmmr.read();
+		let txhashset = self.txhashset.read();
+		let batch = self.store.batch()?;
+		batch.set_kernel_index_enabled(true)?;
+		let count = txhashset.init_kernel_pos_index(&header_pmmr, &batch)?;
+		batch.commit()?;
+		Ok(count)
+	}
+
 	/// Gets the block header in which a given kernel mmr index appears in the txhashset.
 	pub fn get_header_for_kernel_index(
 		&self,
@@ -1562,15 +1831,61 @@ impl Chain {
 			.map_err(|e| Error::ChainStoreErr(e, "chain block exists".to_owned()).into())
 	}
 
+	/// Replaces the built-in checkpoints, typically with the ones of a
+	/// trusted checkpoints file.
+	pub fn set_checkpoints(&self, checkpoints: BlockchainCheckpoints) {
+		if let Some(last) = checkpoints.last() {
+			info!(
+				"Using {} checkpoints, the last at height {} ({})",
+				checkpoints.checkpoints.len(),
+				last.height,
+				last.block_hash
+			);
+		}
+		*self.checkpoints.write() = checkpoints;
+	}
+
+	/// Enables or disables "assume-valid" mode, where blocks at or below the
+	/// last checkpoint skip rangeproof and kernel signature verification.
+	pub fn set_assume_valid(&self, assume_valid: bool) {
+		self.assume_valid.store(assume_valid, Ordering::Relaxed);
+	}
+
+	/// Refuses blocks that would rewind more than `max_reorg_depth` blocks of
+	/// the chain, no limit when `None`.
+	pub fn set_max_reorg_depth(&self, max_reorg_depth: Option<u64>) {
+		*self.max_reorg_depth.write() = max_reorg_depth;
+	}
+
+	/// Whether the block with this header can skip rangeproof and kernel
+	/// signature verification: assume-valid is enabled and the header is an
+	/// ancestor of the last checkpoint on our header chain.
+	fn is_assumed_valid(&self, header: &BlockHeader) -> Result<bool, Error> {
+		if !self.assume_valid.load(Ordering::Relaxed) {
+			return Ok(false);
+		}
+		let last = match self.checkpoints.read().last() {
+			Some(last) if header.height <= last.height => last.clone(),
+			_ => return Ok(false),
+		};
+		let header_pmmr = self.header_pmmr.read();
+		let on_chain = |height, hash| match header_pmmr.get_header_hash_by_height(height) {
+			Ok(h) => h == hash,
+			Err(_) => false,
+		};
+		Ok(on_chain(last.height, last.block_hash) && on_chain(header.height, header.hash()))
+	}
+
 	/// Check block headers against checkpoints hash and height. Returns
 	/// boolean in Result with 'false' once we are out of checkpointed range.
 	pub fn check_header_against_checkpoints(&self, header: &BlockHeader) -> Result<bool, Error> {
-		let checkpoints = BlockchainCheckpoints::new().checkpoints;
+		let checkpoints = self.checkpoints.read();
+		let checkpoints = &checkpoints.checkpoints;
 		let mut within_checkpointed_range = true;
 		if header.height > checkpoints.last().unwrap().height {
 			within_checkpointed_range = false;
 		} else {
-			for c in &checkpoints {
+			for c in checkpoints {
 				if header.height == c.height {
 					if header.hash() == c.block_hash {
 						info!("Checkpoint successfully passed at height({})! Hashes: header({:?}), checkpoint({:?})",
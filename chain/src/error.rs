@@ -165,6 +165,15 @@ pub enum Error {
 
 	#[error("Checkpoint Integrity Failure: Mismatched hashes")]
 	CheckpointFailure,
+	/// Block refused as it would rewind more blocks than allowed
+	#[error("Reorg of depth {0} refused, max reorg depth is {1}")]
+	ReorgTooDeep(u64, u64),
+	/// Checkpoints file could not be loaded
+	#[error("Invalid checkpoints file {0}")]
+	CheckpointsFile(String),
+	/// Error reading or writing a chain archive
+	#[error("Chain archive error: {0}")]
+	ArchiveErr(String),
 }
 impl Error {
 	pub fn is_bad_data(&self) -> bool {
@@ -175,6 +184,8 @@ impl Error {
 			| Error::SerErr(_)
 			| Error::TxHashSetErr(_)
 			| Error::GenesisBlockRequired
+			| Error::ArchiveErr(_)
+			| Error::ReorgTooDeep(_, _)
 			| Error::Other(_) => false,
 			_ => true,
 		}
@@ -32,6 +32,7 @@ use epic_core as core;
 use epic_keychain as keychain;
 use epic_util as util;
 
+pub mod archive;
 mod chain;
 mod error;
 pub mod pipe;
@@ -45,5 +46,6 @@ pub use crate::chain::{Chain, MAX_ORPHAN_SIZE};
 pub use crate::error::Error;
 pub use crate::store::ChainStore;
 pub use crate::types::{
-	BlockStatus, ChainAdapter, Options, SyncState, SyncStatus, Tip, TxHashsetWriteStatus,
+	BlockStatus, BlockchainCheckpoints, ChainAdapter, Options, OutputHistory, Reorg, SyncState,
+	SyncStatus, Tip, TxHashsetWriteStatus,
 };
@@ -18,16 +18,16 @@
 use crate::core::consensus;
 use crate::core::core::block;
 use crate::core::core::feijoada::{is_allowed_policy, PoWType};
-use crate::core::core::hash::{Hash, Hashed};
+use crate::core::core::hash::{Hash, Hashed, ZERO_HASH};
 use crate::core::core::Committed;
-use crate::core::core::{Block, BlockHeader, BlockSums};
+use crate::core::core::{Block, BlockFilter, BlockHeader, BlockSums};
 use crate::core::global;
 use crate::core::pow;
 use crate::error::Error;
 use crate::store;
 use crate::store::BottleIter;
 use crate::txhashset;
-use crate::types::{CommitPos, Options, Tip};
+use crate::types::{CommitPos, Options, Reorg, Tip};
 use chrono::prelude::Utc;
 use chrono::Duration;
 use epic_store;
@@ -47,6 +47,10 @@ pub struct BlockContext<'a> {
     pub header_pmmr: &'a mut txhashset::PMMRHandle<BlockHeader>,
     /// The active batch to use for block processing.
     pub batch: store::Batch<'a>,
+    /// Refuse blocks rewinding more than this many blocks of the chain.
+    pub max_reorg_depth: Option<u64>,
+    /// Set when the processed block caused a reorg.
+    pub reorg: Option<Reorg>,
 }
 
 // Check if we already know about this block for various reasons
@@ -120,6 +124,28 @@ pub fn process_block(b: &Block, ctx: &mut BlockContext<'_>) -> Result<Option<Tip
     // Use the verifier_cache for verifying rangeproofs and kernel signatures.
     validate_block(b, ctx)?;
 
+    // Find out which blocks a fork with more work would detach, refusing the
+    // block if that is more than we allow.
+    let reorg = if has_more_work(&b.header, &head) && b.header.prev_hash != head.last_block_h {
+        let reorg = find_reorg(&ctx.batch.head_header()?, &b.header, &ctx.batch)?;
+        if let Some(max_depth) = ctx.max_reorg_depth {
+            if reorg.depth() > max_depth {
+                error!(
+                    "pipe: refusing reorg of depth {} (max {}) to {} at {}, fork at {}",
+                    reorg.depth(),
+                    max_depth,
+                    b.hash(),
+                    b.header.height,
+                    reorg.fork_height,
+                );
+                return Err(Error::ReorgTooDeep(reorg.depth(), max_depth));
+            }
+        }
+        Some(reorg)
+    } else {
+        None
+    };
+
     // Start a chain extension unit of work dependent on the success of the
     // internal validation and saving operations
     let ref mut header_pmmr = &mut ctx.header_pmmr;
@@ -174,6 +200,7 @@ pub fn process_block(b: &Block, ctx: &mut BlockContext<'_>) -> Result<Option<Tip
     if has_more_work(&b.header, &head) {
         let head = Tip::from_header(&b.header);
         update_head(&head, &mut ctx.batch)?;
+        ctx.reorg = reorg;
         Ok(Some(head))
     } else {
         Ok(None)
@@ -491,9 +518,15 @@ fn validate_header(header: &BlockHeader, ctx: &mut BlockContext<'_>) -> Result<(
 
 fn validate_block(block: &Block, ctx: &mut BlockContext<'_>) -> Result<(), Error> {
     let prev = ctx.batch.get_previous_header(&block.header)?;
-    block
-        .validate(&prev.total_kernel_offset)
-        .map_err(Error::InvalidBlockProof)?;
+    if ctx.opts.contains(Options::ASSUME_VALID) {
+        block
+            .validate_assumed_valid(&prev.total_kernel_offset)
+            .map_err(Error::InvalidBlockProof)?;
+    } else {
+        block
+            .validate(&prev.total_kernel_offset)
+            .map_err(Error::InvalidBlockProof)?;
+    }
     Ok(())
 }
 
@@ -558,6 +591,23 @@ fn add_block(
     batch.save_block(b)?;
     batch.save_block_sums(&b.hash(), block_sums)?;
     batch.save_spent_index(&b.hash(), spent)?;
+    add_block_filter(b, batch)?;
+    Ok(())
+}
+
+/// Adds the compact filter of the block, and its filter header when we have
+/// the filter header of the previous block.
+pub fn add_block_filter(b: &Block, batch: &store::Batch<'_>) -> Result<(), Error> {
+    let filter = BlockFilter::from_block(b);
+    let prev_header = if b.header.height == 0 {
+        Ok(ZERO_HASH)
+    } else {
+        batch.get_block_filter_header(&b.header.prev_hash)
+    };
+    if let Ok(prev_header) = prev_header {
+        batch.save_block_filter_header(&b.hash(), &filter.header(&prev_header))?;
+    }
+    batch.save_block_filter(&filter)?;
     Ok(())
 }
 
@@ -694,6 +744,36 @@ pub fn rewind_and_apply_fork(
     Ok(())
 }
 
+/// Finds the last block common to the branches of the old and the new head
+/// and the blocks of both branches above it.
+pub fn find_reorg(
+    old_head: &BlockHeader,
+    new_head: &BlockHeader,
+    batch: &store::Batch<'_>,
+) -> Result<Reorg, Error> {
+    let mut old = old_head.clone();
+    let mut new = new_head.clone();
+    let mut detached = vec![];
+    let mut attached = vec![];
+    while old.hash() != new.hash() {
+        if old.height >= new.height {
+            detached.push((old.height, old.hash()));
+            old = batch.get_previous_header(&old)?;
+        } else {
+            attached.push((new.height, new.hash()));
+            new = batch.get_previous_header(&new)?;
+        }
+    }
+    detached.reverse();
+    attached.reverse();
+    Ok(Reorg {
+        fork_height: old.height,
+        fork_hash: old.hash(),
+        detached,
+        attached,
+    })
+}
+
 fn validate_utxo(
     block: &Block,
     ext: &mut txhashset::ExtensionPair<'_>,
@@ -17,10 +17,10 @@
 use crate::core::consensus::HeaderInfo;
 use crate::core::core::feijoada::Policy;
 use crate::core::core::hash::{Hash, Hashed};
-use crate::core::core::{Block, BlockHeader, BlockSums};
+use crate::core::core::{Block, BlockFilter, BlockHeader, BlockSums};
 use crate::core::pow::{Difficulty, PoWType};
 use crate::core::ser::ProtocolVersion;
-use crate::types::{CommitPos, Tip};
+use crate::types::{CommitPos, OutputHistory, Tip};
 use crate::util::secp::pedersen::Commitment;
 use croaring::{Bitmap, Portable};
 use epic_store as store;
@@ -40,6 +40,13 @@ const OUTPUT_POS_PREFIX: u8 = b'p';
 const BLOCK_INPUT_BITMAP_PREFIX: u8 = b'B';
 const BLOCK_SUMS_PREFIX: u8 = b'M';
 const BLOCK_SPENT_PREFIX: u8 = b'S';
+const BLOCK_TIMESTAMP_PREFIX: u8 = b't';
+const KERNEL_POS_PREFIX: u8 = b'k';
+const KERNEL_INDEX_PREFIX: u8 = b'K';
+const OUTPUT_HISTORY_PREFIX: u8 = b'o';
+const OUTPUT_HISTORY_INDEX_PREFIX: u8 = b'O';
+const BLOCK_FILTER_PREFIX: u8 = b'F';
+const BLOCK_FILTER_HEADER_PREFIX: u8 = b'f';
 
 /// All chain-related database operations
 pub struct ChainStore {
@@ -63,6 +70,12 @@ impl ChainStore {
 			db: db_with_version,
 		}
 	}
+
+	/// Whether the underlying LMDB environment is close to full and will be
+	/// resized on the next write batch.
+	pub fn needs_resize(&self) -> Result<bool, Error> {
+		self.db.needs_resize()
+	}
 }
 
 impl ChainStore {
@@ -130,6 +143,24 @@ impl ChainStore {
 		)
 	}
 
+	/// Get the compact filter of the block.
+	pub fn get_block_filter(&self, h: &Hash) -> Result<BlockFilter, Error> {
+		option_to_not_found(
+			self.db
+				.get_ser(&to_key(BLOCK_FILTER_PREFIX, &mut h.to_vec())),
+			|| format!("Block filter for block: {}", h),
+		)
+	}
+
+	/// Get the filter header of the block.
+	pub fn get_block_filter_header(&self, h: &Hash) -> Result<Hash, Error> {
+		option_to_not_found(
+			self.db
+				.get_ser(&to_key(BLOCK_FILTER_HEADER_PREFIX, &mut h.to_vec())),
+			|| format!("Block filter header for block: {}", h),
+		)
+	}
+
 	/// Get PMMR pos for the given output commitment.
 	pub fn get_output_pos(&self, commit: &Commitment) -> Result<u64, Error> {
 		self.get_output_pos_height(commit).map(|(pos, _)| pos)
@@ -144,6 +175,57 @@ impl ChainStore {
 		)
 	}
 
+	/// Whether the optional kernel excess index is maintained.
+	pub fn kernel_index_enabled(&self) -> Result<bool, Error> {
+		self.db.exists(&kernel_index_key())
+	}
+
+	/// Get kernel MMR pos and block height for the given kernel excess.
+	pub fn get_kernel_pos_height(&self, excess: &Commitment) -> Result<(u64, u64), Error> {
+		option_to_not_found(
+			self.db
+				.get_ser(&to_key(KERNEL_POS_PREFIX, &mut excess.as_ref().to_vec())),
+			|| format!("Kernel position for: {:?}", excess),
+		)
+	}
+
+	/// Whether the output history index is maintained.
+	pub fn output_history_enabled(&self) -> Result<bool, Error> {
+		self.db.exists(&output_history_index_key())
+	}
+
+	/// Iterates over the history of every output created with the given
+	/// commitment, in creation height order.
+	pub fn output_history_iter(
+		&self,
+		commit: &Commitment,
+	) -> Result<impl Iterator<Item = OutputHistory>, Error> {
+		let prefix = to_key(OUTPUT_HISTORY_PREFIX, &mut commit.as_ref().to_vec());
+		let iter = self.db.iter::<OutputHistory>(&prefix)?;
+		Ok(iter.map(|(_, history)| history))
+	}
+
+	/// Whether the header is in the timestamp index.
+	pub fn is_timestamp_indexed(&self, header: &BlockHeader) -> Result<bool, Error> {
+		self.db.exists(&timestamp_key(header))
+	}
+
+	/// Iterates over the timestamp index in timestamp order, starting at the
+	/// first header with a timestamp greater or equal to `from`. Yields the
+	/// (timestamp, hash) of every header we know about, fork headers included.
+	pub fn timestamp_iter(&self, from: i64) -> Result<impl Iterator<Item = (i64, Hash)>, Error> {
+		let prefix = to_key(BLOCK_TIMESTAMP_PREFIX, &mut vec![]);
+		let start = to_key(
+			BLOCK_TIMESTAMP_PREFIX,
+			&mut (from.max(0) as u64).to_be_bytes().to_vec(),
+		);
+		let iter = self.db.iter_from::<Hash>(&prefix, &start)?;
+		Ok(iter.map(|(key, hash)| {
+			let ts = u64::from_be_bytes(key[2..10].try_into().unwrap());
+			(ts as i64, hash)
+		}))
+	}
+
 	/// Builds a new batch to be used with this store.
 	pub fn batch(&self) -> Result<Batch<'_>, Error> {
 		Ok(Batch {
@@ -276,6 +358,7 @@ impl<'a> Batch<'a> {
 		{
 			let _ = self.delete_block_sums(bh);
 			let _ = self.delete_spent_index(bh);
+			let _ = self.delete_block_filter(bh);
 		}
 
 		Ok(())
@@ -289,9 +372,16 @@ impl<'a> Batch<'a> {
 		self.db
 			.put_ser(&to_key(BLOCK_HEADER_PREFIX, &mut hash.to_vec())[..], header)?;
 
+		self.save_timestamp_index(header)?;
+
 		Ok(())
 	}
 
+	/// Add the header to the timestamp index.
+	pub fn save_timestamp_index(&self, header: &BlockHeader) -> Result<(), Error> {
+		self.db.put_ser(&timestamp_key(header)[..], &header.hash())
+	}
+
 	/// Save output_pos and block height to index.
 	pub fn save_output_pos_height(
 		&self,
@@ -325,6 +415,145 @@ impl<'a> Batch<'a> {
 		self.db.iter(&key)
 	}
 
+	/// Whether the optional kernel excess index is maintained.
+	pub fn kernel_index_enabled(&self) -> Result<bool, Error> {
+		self.db.exists(&kernel_index_key())
+	}
+
+	/// Turn the kernel excess index on or off. Turning it off deletes every
+	/// index entry, turning it on expects the caller to (re)build the index.
+	pub fn set_kernel_index_enabled(&self, enabled: bool) -> Result<(), Error> {
+		if enabled {
+			self.db.put(&kernel_index_key(), &[1])
+		} else {
+			self.clear_kernel_index()?;
+			if self.kernel_index_enabled()? {
+				self.db.delete(&kernel_index_key())?;
+			}
+			Ok(())
+		}
+	}
+
+	/// Delete every entry of the kernel excess index.
+	pub fn clear_kernel_index(&self) -> Result<(), Error> {
+		let prefix = to_key(KERNEL_POS_PREFIX, &mut vec![]);
+		for (key, _) in self.db.iter::<(u64, u64)>(&prefix)? {
+			self.db.delete(&key)?;
+		}
+		Ok(())
+	}
+
+	/// Save kernel MMR pos and block height to the kernel excess index.
+	pub fn save_kernel_pos_height(
+		&self,
+		excess: &Commitment,
+		pos: u64,
+		height: u64,
+	) -> Result<(), Error> {
+		self.db.put_ser(
+			&to_key(KERNEL_POS_PREFIX, &mut excess.as_ref().to_vec())[..],
+			&(pos, height),
+		)
+	}
+
+	/// Delete the kernel excess index entry for a rewound kernel.
+	pub fn delete_kernel_pos_height(&self, excess: &Commitment) -> Result<(), Error> {
+		self.db
+			.delete(&to_key(KERNEL_POS_PREFIX, &mut excess.as_ref().to_vec()))
+	}
+
+	/// Get kernel MMR pos and block height from the kernel excess index.
+	pub fn get_kernel_pos_height(&self, excess: &Commitment) -> Result<(u64, u64), Error> {
+		option_to_not_found(
+			self.db
+				.get_ser(&to_key(KERNEL_POS_PREFIX, &mut excess.as_ref().to_vec())),
+			|| format!("Kernel position for: {:?}", excess),
+		)
+	}
+
+	/// Whether the output history index is maintained.
+	pub fn output_history_enabled(&self) -> Result<bool, Error> {
+		self.db.exists(&output_history_index_key())
+	}
+
+	/// Turn the output history index on or off. Turning it off deletes every
+	/// index entry, turning it on expects the caller to build the index.
+	pub fn set_output_history_enabled(&self, enabled: bool) -> Result<(), Error> {
+		if enabled {
+			self.db.put(&output_history_index_key(), &[1])
+		} else {
+			self.clear_output_history()?;
+			if self.output_history_enabled()? {
+				self.db.delete(&output_history_index_key())?;
+			}
+			Ok(())
+		}
+	}
+
+	/// Delete every entry of the output history index.
+	pub fn clear_output_history(&self) -> Result<(), Error> {
+		let prefix = to_key(OUTPUT_HISTORY_PREFIX, &mut vec![]);
+		for (key, _) in self.db.iter::<OutputHistory>(&prefix)? {
+			self.db.delete(&key)?;
+		}
+		Ok(())
+	}
+
+	/// Save the history of the output created with the given commitment.
+	pub fn save_output_history(
+		&self,
+		commit: &Commitment,
+		history: &OutputHistory,
+	) -> Result<(), Error> {
+		self.db
+			.put_ser(&output_history_key(commit, history.created_height)[..], history)
+	}
+
+	/// Get the history of the output created with the given commitment at
+	/// `created_height`.
+	pub fn get_output_history(
+		&self,
+		commit: &Commitment,
+		created_height: u64,
+	) -> Result<OutputHistory, Error> {
+		option_to_not_found(
+			self.db
+				.get_ser(&output_history_key(commit, created_height)),
+			|| format!("Output history for: {:?} at {}", commit, created_height),
+		)
+	}
+
+	/// Delete the history of a rewound output.
+	pub fn delete_output_history(
+		&self,
+		commit: &Commitment,
+		created_height: u64,
+	) -> Result<(), Error> {
+		let key = output_history_key(commit, created_height);
+		if self.db.exists(&key)? {
+			self.db.delete(&key)?;
+		}
+		Ok(())
+	}
+
+	/// Record where the output created at `created_height` was spent, `None`
+	/// marking it unspent again. Outputs without history are ignored.
+	pub fn update_output_history_spent(
+		&self,
+		commit: &Commitment,
+		created_height: u64,
+		spent: Option<(u64, Hash)>,
+	) -> Result<(), Error> {
+		match self.get_output_history(commit, created_height) {
+			Ok(mut history) => {
+				history.spent = spent;
+				self.save_output_history(commit, &history)
+			}
+			Err(Error::NotFoundErr(_)) => Ok(()),
+			Err(e) => Err(e),
+		}
+	}
+
 	/// Get output_pos from index.
 	pub fn get_output_pos(&self, commit: &Commitment) -> Result<u64, Error> {
 		self.get_output_pos_height(commit).map(|(pos, _)| pos)
@@ -383,6 +612,47 @@ impl<'a> Batch<'a> {
 		self.db.delete(&to_key(BLOCK_SUMS_PREFIX, &mut bh.to_vec()))
 	}
 
+	/// Save the compact filter of a block.
+	pub fn save_block_filter(&self, filter: &BlockFilter) -> Result<(), Error> {
+		self.db.put_ser(
+			&to_key(BLOCK_FILTER_PREFIX, &mut filter.block_hash.to_vec())[..],
+			filter,
+		)
+	}
+
+	/// Get the compact filter of the block.
+	pub fn get_block_filter(&self, h: &Hash) -> Result<BlockFilter, Error> {
+		option_to_not_found(
+			self.db
+				.get_ser(&to_key(BLOCK_FILTER_PREFIX, &mut h.to_vec())),
+			|| format!("Block filter for block: {}", h),
+		)
+	}
+
+	/// Delete the compact filter of the block. Its filter header is kept, the
+	/// filter headers of later blocks build on it.
+	fn delete_block_filter(&self, bh: &Hash) -> Result<(), Error> {
+		self.db
+			.delete(&to_key(BLOCK_FILTER_PREFIX, &mut bh.to_vec()))
+	}
+
+	/// Save the filter header of the block.
+	pub fn save_block_filter_header(&self, h: &Hash, filter_header: &Hash) -> Result<(), Error> {
+		self.db.put_ser(
+			&to_key(BLOCK_FILTER_HEADER_PREFIX, &mut h.to_vec())[..],
+			filter_header,
+		)
+	}
+
+	/// Get the filter header of the block.
+	pub fn get_block_filter_header(&self, h: &Hash) -> Result<Hash, Error> {
+		option_to_not_found(
+			self.db
+				.get_ser(&to_key(BLOCK_FILTER_HEADER_PREFIX, &mut h.to_vec())),
+			|| format!("Block filter header for block: {}", h),
+		)
+	}
+
 	/// Get the block input bitmap based on our spent index.
 	/// Fallback to legacy block input bitmap from the db.
 	pub fn get_block_input_bitmap(&self, bh: &Hash) -> Result<Bitmap, Error> {
@@ -439,6 +709,30 @@ impl<'a> Batch<'a> {
 	}
 }
 
+// Timestamp index keys sort by timestamp first, the hash only keeps headers
+// sharing a timestamp apart.
+fn timestamp_key(header: &BlockHeader) -> Vec<u8> {
+	let mut id = (header.timestamp.timestamp().max(0) as u64).to_be_bytes().to_vec();
+	id.extend_from_slice(header.hash().as_bytes());
+	to_key(BLOCK_TIMESTAMP_PREFIX, &mut id)
+}
+
+// Output history keys sort by commitment then creation height, so a reused
+// commitment keeps one entry per output.
+fn output_history_key(commit: &Commitment, created_height: u64) -> Vec<u8> {
+	let mut key = commit.as_ref().to_vec();
+	key.extend_from_slice(&created_height.to_be_bytes());
+	to_key(OUTPUT_HISTORY_PREFIX, &mut key)
+}
+
+fn output_history_index_key() -> Vec<u8> {
+	to_key(OUTPUT_HISTORY_INDEX_PREFIX, &mut vec![])
+}
+
+fn kernel_index_key() -> Vec<u8> {
+	to_key(KERNEL_INDEX_PREFIX, &mut vec![])
+}
+
 /// An iterator on blocks, from latest to earliest, specialized to return
 /// information pertaining to block difficulty calculation (timestamp and
 /// previous difficulties). It searches for blocks with the same type as the
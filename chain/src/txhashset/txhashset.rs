@@ -25,7 +25,9 @@ use crate::error::Error;
 use crate::store::{Batch, ChainStore};
 use crate::txhashset::bitmap_accumulator::BitmapAccumulator;
 use crate::txhashset::{RewindableKernelView, UTXOView};
-use crate::types::{CommitPos, OutputRoots, Tip, TxHashSetRoots, TxHashsetWriteStatus};
+use crate::types::{
+	CommitPos, OutputHistory, OutputRoots, Tip, TxHashSetRoots, TxHashsetWriteStatus,
+};
 use crate::util::secp::pedersen::{Commitment, RangeProof};
 use crate::util::{file, secp_static, zip};
 use croaring::Bitmap;
@@ -283,6 +285,11 @@ impl TxHashSet {
 			.elements_from_pmmr_index(start_index, max_count, max_index)
 	}
 
+	/// Get the kernel at the given kernel MMR position.
+	pub fn get_kernel(&self, pos: u64) -> Option<TxKernel> {
+		ReadonlyPMMR::at(&self.kernel_pmmr_h.backend, self.kernel_pmmr_h.last_pos).get_data(pos)
+	}
+
 	/// Find a kernel with a given excess. Work backwards from `max_index` to `min_index`
 	pub fn find_kernel(
 		&self,
@@ -443,6 +450,46 @@ impl TxHashSet {
 		);
 		Ok(())
 	}
+
+	/// Rebuild the kernel excess index from scratch, mapping the excess of every
+	/// kernel in the kernel MMR to its MMR position and block height.
+	/// Returns the number of kernels indexed.
+	pub fn init_kernel_pos_index(
+		&self,
+		header_pmmr: &PMMRHandle<BlockHeader>,
+		batch: &Batch<'_>,
+	) -> Result<u64, Error> {
+		let now = Instant::now();
+
+		batch.clear_kernel_index()?;
+
+		let kernel_pmmr =
+			ReadonlyPMMR::at(&self.kernel_pmmr_h.backend, self.kernel_pmmr_h.last_pos);
+		let max_height = batch.head()?.height;
+
+		let mut header = batch.get_block_header(&header_pmmr.get_header_hash_by_height(0)?)?;
+		let mut count = 0;
+		for pos in 1..=self.kernel_pmmr_h.last_pos {
+			if !pmmr::is_leaf(pos) {
+				continue;
+			}
+			// The kernel belongs to the first block whose kernel MMR covers its pos.
+			while pos > header.kernel_mmr_size && header.height < max_height {
+				let hash = header_pmmr.get_header_hash_by_height(header.height + 1)?;
+				header = batch.get_block_header(&hash)?;
+			}
+			if let Some(kernel) = kernel_pmmr.get_data(pos) {
+				batch.save_kernel_pos_height(&kernel.excess, pos, header.height)?;
+				count += 1;
+			}
+		}
+		info!(
+			"Init kernel index: added entries for {} kernels, took {}s",
+			count,
+			now.elapsed().as_secs(),
+		);
+		Ok(count)
+	}
 }
 
 /// Starts a new unit of work to extend (or rewind) the chain with additional
@@ -903,6 +950,7 @@ impl<'a> Extension<'a> {
 	pub fn apply_block(&mut self, b: &Block, batch: &Batch<'_>) -> Result<Vec<CommitPos>, Error> {
 		let mut affected_pos = vec![];
 		let mut spent = vec![];
+		let record_history = batch.output_history_enabled()?;
 
 		// Apply the output to the output and rangeproof MMRs.
 		// Add pos to affected_pos to update the accumulator later on.
@@ -911,6 +959,9 @@ impl<'a> Extension<'a> {
 			let pos = self.apply_output(out, batch)?;
 			affected_pos.push(pos);
 			batch.save_output_pos_height(&out.commitment(), pos, b.header.height)?;
+			if record_history {
+				batch.save_output_history(&out.commitment(), &OutputHistory::created(&b.header))?;
+			}
 		}
 
 		// Remove the output from the output and rangeproof MMRs.
@@ -920,11 +971,23 @@ impl<'a> Extension<'a> {
 			let spent_pos = self.apply_input(input, batch)?;
 			affected_pos.push(spent_pos.pos);
 			batch.delete_output_pos_height(&input.commitment())?;
+			if record_history {
+				batch.update_output_history_spent(
+					&input.commitment(),
+					spent_pos.height,
+					Some((b.header.height, b.hash())),
+				)?;
+			}
 			spent.push(spent_pos);
 		}
 
+		// Add the kernels to the kernel excess index, if we maintain one.
+		let index_kernels = batch.kernel_index_enabled()?;
 		for kernel in b.kernels() {
-			self.apply_kernel(kernel)?;
+			let pos = self.apply_kernel(kernel)?;
+			if index_kernels {
+				batch.save_kernel_pos_height(&kernel.excess, pos, b.header.height)?;
+			}
 		}
 
 		// Update our BitmapAccumulator based on affected outputs (both spent and created).
@@ -1017,11 +1080,12 @@ impl<'a> Extension<'a> {
 	}
 
 	/// Push kernel onto MMR (hash and data files).
-	fn apply_kernel(&mut self, kernel: &TxKernel) -> Result<(), Error> {
-		self.kernel_pmmr
+	fn apply_kernel(&mut self, kernel: &TxKernel) -> Result<u64, Error> {
+		let pos = self
+			.kernel_pmmr
 			.push(kernel)
 			.map_err(&Error::TxHashSetErr)?;
-		Ok(())
+		Ok(pos)
 	}
 
 	/// Build a Merkle proof for the given output and the block
@@ -1151,6 +1215,26 @@ impl<'a> Extension<'a> {
 			);
 		}
 
+		// Forget the outputs created by the block being rewound and mark the
+		// outputs it spent as unspent again in the output history.
+		if batch.output_history_enabled()? {
+			for out in block.outputs() {
+				batch.delete_output_history(&out.commitment(), header.height)?;
+			}
+			if let Ok(ref spent) = spent {
+				for (x, y) in block.inputs().iter().zip(spent) {
+					batch.update_output_history_spent(&x.commitment(), y.height, None)?;
+				}
+			}
+		}
+
+		// Remove the kernels of the block being rewound from the kernel excess index.
+		if batch.kernel_index_enabled()? {
+			for kernel in block.kernels() {
+				let _ = batch.delete_kernel_pos_height(&kernel.excess);
+			}
+		}
+
 		// Update output_pos based on "unspending" all spent pos from this block.
 		// This is necessary to ensure the output_pos index correclty reflects a
 		// reused output commitment. For example an output at pos 1, spent, reused at pos 2.
@@ -18,7 +18,7 @@
 use chrono::prelude::{DateTime, Utc};
 use std::sync::Arc;
 
-use crate::core::core::hash::{Hash, Hashed, ZERO_HASH};
+use crate::core::core::hash::{Hash, HashWriter, Hashed, ZERO_HASH};
 use crate::core::core::{Block, BlockHeader, HeaderVersion};
 use crate::core::pow::Difficulty;
 use crate::core::ser::{self, PMMRIndexHashable, Readable, Reader, Writeable, Writer};
@@ -37,6 +37,9 @@ bitflags! {
 		const SYNC = 0b0000_0010;
 		/// Block validation on a block we mined ourselves
 		const MINE = 0b0000_0100;
+		/// Block is at or below a trusted checkpoint, skips rangeproof and
+		/// kernel signature verification.
+		const ASSUME_VALID = 0b0000_1000;
 	}
 }
 
@@ -287,6 +290,62 @@ impl Writeable for CommitPos {
 	}
 }
 
+/// Where an output was created and, once spent, where it was spent.
+/// Archive nodes keep one of these for every output commitment.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OutputHistory {
+	/// Height of the block creating the output
+	pub created_height: u64,
+	/// Hash of the block creating the output
+	pub created_block: Hash,
+	/// Height and hash of the block spending the output, if spent
+	pub spent: Option<(u64, Hash)>,
+}
+
+impl OutputHistory {
+	/// History of an output created, and not yet spent, in the given block.
+	pub fn created(header: &BlockHeader) -> OutputHistory {
+		OutputHistory {
+			created_height: header.height,
+			created_block: header.hash(),
+			spent: None,
+		}
+	}
+}
+
+impl Readable for OutputHistory {
+	fn read(reader: &mut dyn Reader) -> Result<OutputHistory, ser::Error> {
+		let created_height = reader.read_u64()?;
+		let created_block = Hash::read(reader)?;
+		let spent = match reader.read_u8()? {
+			0 => None,
+			1 => Some((reader.read_u64()?, Hash::read(reader)?)),
+			_ => return Err(ser::Error::CorruptedData),
+		};
+		Ok(OutputHistory {
+			created_height,
+			created_block,
+			spent,
+		})
+	}
+}
+
+impl Writeable for OutputHistory {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_u64(self.created_height)?;
+		self.created_block.write(writer)?;
+		match self.spent {
+			None => writer.write_u8(0)?,
+			Some((height, ref hash)) => {
+				writer.write_u8(1)?;
+				writer.write_u64(height)?;
+				hash.write(writer)?;
+			}
+		}
+		Ok(())
+	}
+}
+
 /// The tip of a fork. A handle to the fork ancestry from its leaf in the
 /// blockchain tree. References the max height and the latest and previous
 /// blocks
@@ -365,6 +424,10 @@ pub trait ChainAdapter {
 	/// The blockchain pipeline has accepted this block as valid and added
 	/// it to our chain.
 	fn block_accepted(&self, block: &Block, status: BlockStatus, opts: Options);
+
+	/// Accepting a block switched the chain to another branch. Called before
+	/// `block_accepted` for the block that became the new head.
+	fn reorg(&self, _reorg: &Reorg) {}
 }
 
 /// Inform the caller of the current status of a txhashset write operation,
@@ -415,14 +478,35 @@ pub enum BlockStatus {
 	Reorg(u64),
 }
 
+/// Blocks detached from and attached to the chain by a reorg, as (height,
+/// hash) pairs ordered by height, starting right above the fork point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reorg {
+	/// Height of the last block common to both branches
+	pub fork_height: u64,
+	/// Hash of the last block common to both branches
+	pub fork_hash: Hash,
+	/// Blocks of the old branch, no longer part of the chain
+	pub detached: Vec<(u64, Hash)>,
+	/// Blocks of the new branch, the last one being the new head
+	pub attached: Vec<(u64, Hash)>,
+}
+
+impl Reorg {
+	/// Number of blocks detached from the chain.
+	pub fn depth(&self) -> u64 {
+		self.detached.len() as u64
+	}
+}
+
 // Elements in checkpoint data vector
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Checkpoint {
 	pub height: u64,
 	pub block_hash: Hash,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BlockchainCheckpoints {
 	pub checkpoints: Vec<Checkpoint>,
 }
@@ -517,4 +601,63 @@ impl BlockchainCheckpoints {
 		];
 		return BlockchainCheckpoints { checkpoints };
 	}
+
+	/// Loads checkpoints from a file with one `<height> <block hash>` pair per
+	/// line, blank lines and lines starting with `#` are ignored.
+	/// The file is pinned by its blake2b-256 hash (as computed by
+	/// `b2sum -l 256`), it is refused unless the hash of its content matches.
+	pub fn from_file(path: &str, expected_hash: &str) -> Result<BlockchainCheckpoints, Error> {
+		let err = |msg: String| Error::CheckpointsFile(format!("{}: {}", path, msg));
+		let expected_hash = Hash::from_hex(expected_hash)
+			.map_err(|_| err(format!("invalid file hash {}", expected_hash)))?;
+		let content = std::fs::read(path).map_err(|e| err(e.to_string()))?;
+
+		let mut hasher = HashWriter::default();
+		hasher
+			.write_fixed_bytes(&content)
+			.map_err(|e| err(e.to_string()))?;
+		let hash = hasher.into_hash();
+		if hash != expected_hash {
+			return Err(err(format!("file hash {} does not match", hash)));
+		}
+
+		let content = String::from_utf8(content).map_err(|e| err(e.to_string()))?;
+		BlockchainCheckpoints::parse(&content).map_err(err)
+	}
+
+	/// Parses checkpoints in the file format of `from_file`, sorted by height.
+	pub fn parse(content: &str) -> Result<BlockchainCheckpoints, String> {
+		let mut checkpoints = vec![];
+		for (n, line) in content.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			let mut fields = line.split_whitespace();
+			let (height, hash) = match (fields.next(), fields.next(), fields.next()) {
+				(Some(height), Some(hash), None) => (height, hash),
+				_ => return Err(format!("line {}: expected <height> <block hash>", n + 1)),
+			};
+			checkpoints.push(Checkpoint {
+				height: height
+					.parse()
+					.map_err(|_| format!("line {}: invalid height {}", n + 1, height))?,
+				block_hash: Hash::from_hex(hash)
+					.map_err(|_| format!("line {}: invalid block hash {}", n + 1, hash))?,
+			});
+		}
+		checkpoints.sort_by_key(|c| c.height);
+		if checkpoints.is_empty() {
+			return Err("no checkpoints".to_owned());
+		}
+		if checkpoints.windows(2).any(|w| w[0].height == w[1].height) {
+			return Err("duplicate checkpoint height".to_owned());
+		}
+		Ok(BlockchainCheckpoints { checkpoints })
+	}
+
+	/// The checkpoint with the highest height.
+	pub fn last(&self) -> Option<&Checkpoint> {
+		self.checkpoints.last()
+	}
 }
@@ -0,0 +1,91 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+use crate::chain_test_helper::{
+	clean_output_dir, init_chain, prepare_block, process_block, set_foundation_path_for_test,
+};
+use epic_chain as chain;
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_util as util;
+
+use self::chain::archive;
+use self::chain::Options;
+use self::core::pow;
+use self::keychain::{ExtKeychain, Keychain};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_chain_archive_roundtrip() {
+	util::init_test_logger();
+
+	let src_dir = ".epic_archive_src";
+	let dst_dir = ".epic_archive_dst";
+	let archive_file = ".epic_archive_test.epicarchive";
+	clean_output_dir(src_dir);
+	clean_output_dir(dst_dir);
+	let _ = fs::remove_file(archive_file);
+
+	set_foundation_path_for_test("foundation_floonet.json");
+
+	let genesis = pow::mine_genesis_block().unwrap();
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	{
+		let chain = init_chain(src_dir, genesis.clone());
+		let mut prev = chain.head_header().unwrap();
+		for n in 1..=5 {
+			let b = prepare_block(&kc, &prev, &chain, n + 1, vec![], 1);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+		}
+
+		let summary = archive::export(&chain, Path::new(archive_file), 1).unwrap();
+		assert_eq!(summary.blocks, 5);
+		assert_eq!(summary.header.from_height, 1);
+		assert_eq!(summary.header.to_height, 5);
+
+		// Exporting from above the head is an error.
+		assert!(archive::export(&chain, Path::new(archive_file), 6).is_err());
+	}
+
+	let verified = archive::verify(Path::new(archive_file)).unwrap();
+	assert_eq!(verified.blocks, 5);
+
+	{
+		let chain = init_chain(dst_dir, genesis.clone());
+		let opts = Options::SYNC | Options::SKIP_POW;
+		let summary = archive::import(&chain, Path::new(archive_file), opts).unwrap();
+		assert_eq!(summary.blocks, 5);
+		assert_eq!(summary.skipped, 0);
+		assert_eq!(chain.head().unwrap().height, 5);
+
+		// Importing the same archive again is a no-op.
+		let summary = archive::import(&chain, Path::new(archive_file), opts).unwrap();
+		assert_eq!(summary.skipped, 5);
+		assert_eq!(chain.head().unwrap().height, 5);
+	}
+
+	// Flip a byte in the last block record, the checksum must catch it.
+	let mut bytes = fs::read(archive_file).unwrap();
+	let idx = bytes.len() - 50;
+	bytes[idx] ^= 0xff;
+	fs::write(archive_file, &bytes).unwrap();
+	assert!(archive::verify(Path::new(archive_file)).is_err());
+
+	clean_output_dir(src_dir);
+	clean_output_dir(dst_dir);
+	let _ = fs::remove_file(archive_file);
+}
@@ -0,0 +1,77 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+use crate::chain_test_helper::{
+	clean_output_dir, init_chain, prepare_block, process_block, set_foundation_path_for_test,
+};
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_util as util;
+
+use self::core::core::hash::{Hashed, ZERO_HASH};
+use self::core::core::BlockFilter;
+use self::core::pow;
+use self::keychain::{ExtKeychain, Keychain};
+
+#[test]
+fn test_block_filters() {
+	util::init_test_logger();
+
+	let chain_dir = ".epic_block_filters";
+	clean_output_dir(chain_dir);
+
+	set_foundation_path_for_test("foundation_floonet.json");
+
+	let genesis = pow::mine_genesis_block().unwrap();
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	{
+		let chain = init_chain(chain_dir, genesis.clone());
+		let mut blocks = vec![genesis.clone()];
+		let mut prev = chain.head_header().unwrap();
+		for n in 1..=4 {
+			let b = prepare_block(&kc, &prev, &chain, n + 1, vec![], 1);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+			blocks.push(b);
+		}
+
+		let mut prev_filter_header = ZERO_HASH;
+		for b in &blocks {
+			let filter = chain.get_block_filter(&b.hash()).unwrap();
+			assert_eq!(filter, BlockFilter::from_block(b));
+			for out in b.outputs() {
+				assert!(filter.matches(out.commitment()));
+			}
+			for kernel in b.kernels() {
+				assert!(filter.matches(kernel.excess));
+			}
+
+			// Each filter header commits to the previous one.
+			let filter_header = chain.get_block_filter_header(&b.hash()).unwrap();
+			assert_eq!(filter_header, filter.header(&prev_filter_header));
+			prev_filter_header = filter_header;
+		}
+		assert!(chain.has_block_filter_headers());
+
+		// An output of another block is (almost certainly) not matched.
+		let other = blocks[2].outputs()[0].commitment();
+		assert!(!chain
+			.get_block_filter(&blocks[1].hash())
+			.unwrap()
+			.matches(other));
+	}
+
+	clean_output_dir(chain_dir);
+}
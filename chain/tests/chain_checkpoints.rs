@@ -0,0 +1,164 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+use crate::chain_test_helper::{
+	clean_output_dir, init_chain, prepare_block, process_block, set_foundation_path_for_test,
+};
+use epic_chain as chain;
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_util as util;
+
+use self::chain::{BlockchainCheckpoints, Chain};
+use self::core::core::hash::{HashWriter, Hashed};
+use self::core::core::Block;
+use self::core::pow;
+use self::core::ser::Writer;
+use self::keychain::{ExtKeychain, Keychain};
+use self::util::secp::Signature;
+use std::fs;
+
+// Replaces the coinbase kernel signature with an invalid one. The roots are
+// updated, only the signature verification skipped when assuming validity
+// can tell.
+fn break_kernel_signature(chain: &Chain, b: &mut Block) {
+	b.kernels_mut()[0].excess_sig = Signature::from_raw_data(&[0; 64]).unwrap();
+	b.kernels_mut().sort_unstable();
+	chain.set_txhashset_roots(b).unwrap();
+}
+
+#[test]
+fn parse_checkpoints() {
+	let hash = "e835eb9ebc9f2e13b11061691cb268f44b20001f081003169b634497eb730848";
+	let checkpoints = BlockchainCheckpoints::parse(&format!(
+		"# trusted checkpoints\n\n20 {}\n10 {}\n",
+		hash, hash
+	))
+	.unwrap();
+	assert_eq!(checkpoints.checkpoints.len(), 2);
+	assert_eq!(checkpoints.last().unwrap().height, 20);
+	assert_eq!(checkpoints.checkpoints[0].height, 10);
+
+	assert!(BlockchainCheckpoints::parse("").is_err());
+	assert!(BlockchainCheckpoints::parse("10").is_err());
+	assert!(BlockchainCheckpoints::parse("ten 00").is_err());
+	assert!(BlockchainCheckpoints::parse(&format!("10 {}\n10 {}", hash, hash)).is_err());
+}
+
+#[test]
+fn test_checkpoints_file() {
+	util::init_test_logger();
+
+	let chain_dir = ".epic_checkpoints";
+	let chain_dir2 = ".epic_checkpoints2";
+	let chain_dir3 = ".epic_checkpoints3";
+	clean_output_dir(chain_dir);
+	clean_output_dir(chain_dir2);
+	clean_output_dir(chain_dir3);
+
+	set_foundation_path_for_test("foundation_floonet.json");
+
+	let genesis = pow::mine_genesis_block().unwrap();
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	let mut blocks = vec![];
+	{
+		let chain = init_chain(chain_dir, genesis.clone());
+		let mut prev = chain.head_header().unwrap();
+		for n in 1..=3 {
+			let b = prepare_block(&kc, &prev, &chain, n + 1, vec![], 1);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+			blocks.push(b);
+		}
+		// The checkpointed block itself is invalid, the checkpoint vouches for it.
+		let mut b = prepare_block(&kc, &prev, &chain, 5, vec![], 1);
+		break_kernel_signature(&chain, &mut b);
+		assert!(chain
+			.process_block(b.clone(), chain::Options::SKIP_POW)
+			.is_err());
+		blocks.push(b);
+	}
+
+	let content = format!("4 {}\n", blocks[3].hash());
+	let path = format!("{}/checkpoints.txt", chain_dir);
+	fs::write(&path, &content).unwrap();
+	let mut hasher = HashWriter::default();
+	hasher.write_fixed_bytes(&content).unwrap();
+	let file_hash = hasher.into_hash().to_hex();
+
+	// The file is pinned by its hash.
+	assert!(BlockchainCheckpoints::from_file(&path, &blocks[0].hash().to_hex()).is_err());
+	let checkpoints = BlockchainCheckpoints::from_file(&path, &file_hash).unwrap();
+	assert_eq!(checkpoints.last().unwrap().block_hash, blocks[3].hash());
+	let headers: Vec<_> = blocks.iter().map(|b| b.header.clone()).collect();
+
+	{
+		let chain = init_chain(chain_dir2, genesis.clone());
+		chain.set_checkpoints(checkpoints.clone());
+		chain.set_assume_valid(true);
+
+		assert!(chain
+			.check_header_against_checkpoints(&blocks[3].header)
+			.unwrap());
+		let mut bad = blocks[3].header.clone();
+		bad.timestamp = blocks[2].header.timestamp;
+		assert!(chain.check_header_against_checkpoints(&bad).is_err());
+
+		// Once the header chain passed the checkpoint the blocks up to it are
+		// assumed valid, skipping their signature verification.
+		chain
+			.sync_block_headers(&headers, chain::Options::SKIP_POW)
+			.unwrap();
+		for b in &blocks {
+			process_block(&chain, b);
+		}
+		assert_eq!(chain.head().unwrap().last_block_h, blocks[3].hash());
+
+		// The following ones are fully validated.
+		let prev = blocks[3].header.clone();
+		let mut bad = prepare_block(&kc, &prev, &chain, 6, vec![], 2);
+		break_kernel_signature(&chain, &mut bad);
+		assert!(!chain
+			.check_header_against_checkpoints(&bad.header)
+			.unwrap());
+		assert!(chain
+			.process_block(bad, chain::Options::SKIP_POW)
+			.is_err());
+		let b = prepare_block(&kc, &prev, &chain, 6, vec![], 1);
+		process_block(&chain, &b);
+		assert_eq!(chain.head().unwrap().last_block_h, b.hash());
+	}
+
+	// Without assume valid the same block is rejected.
+	{
+		let chain = init_chain(chain_dir3, genesis.clone());
+		chain.set_checkpoints(checkpoints);
+		chain.set_assume_valid(false);
+		chain
+			.sync_block_headers(&headers, chain::Options::SKIP_POW)
+			.unwrap();
+		for b in &blocks[..3] {
+			process_block(&chain, b);
+		}
+		assert!(chain
+			.process_block(blocks[3].clone(), chain::Options::SKIP_POW)
+			.is_err());
+		assert_eq!(chain.head().unwrap().last_block_h, blocks[2].hash());
+	}
+
+	clean_output_dir(chain_dir);
+	clean_output_dir(chain_dir2);
+	clean_output_dir(chain_dir3);
+}
@@ -0,0 +1,113 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+use crate::chain_test_helper::{
+	clean_output_dir, init_chain, prepare_block, process_block, set_foundation_path_for_test,
+};
+use epic_chain as chain;
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_util as util;
+
+use self::chain::Chain;
+use self::core::core::hash::Hashed;
+use self::core::core::Block;
+use self::core::pow;
+use self::keychain::{ExtKeychain, Keychain};
+
+fn assert_kernels_found(chain: &Chain, blocks: &[Block]) {
+	for b in blocks {
+		for kernel in b.kernels() {
+			let (found, height, _) = chain
+				.get_kernel_height(&kernel.excess, None, None)
+				.unwrap()
+				.unwrap();
+			assert_eq!(found.excess, kernel.excess);
+			assert_eq!(height, b.header.height);
+		}
+	}
+}
+
+#[test]
+fn test_kernel_index() {
+	util::init_test_logger();
+
+	let chain_dir = ".epic_kernel_index";
+	clean_output_dir(chain_dir);
+
+	set_foundation_path_for_test("foundation_floonet.json");
+
+	let genesis = pow::mine_genesis_block().unwrap();
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	{
+		let chain = init_chain(chain_dir, genesis.clone());
+		let mut blocks = vec![];
+		let mut prev = chain.head_header().unwrap();
+		for n in 1..=3 {
+			let b = prepare_block(&kc, &prev, &chain, n + 1, vec![], 1);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+			blocks.push(b);
+		}
+
+		// Enabling the index builds it from the existing kernel MMR.
+		assert!(chain.rebuild_kernel_index().unwrap() > 0);
+		assert_kernels_found(&chain, &blocks);
+
+		// Kernels of new blocks are indexed as the blocks are applied.
+		for n in 4..=5 {
+			let b = prepare_block(&kc, &prev, &chain, n + 1, vec![], 1);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+			blocks.push(b);
+		}
+		assert_kernels_found(&chain, &blocks);
+
+		// Height bounds are respected.
+		let excess = blocks[1].kernels()[0].excess;
+		assert!(chain
+			.get_kernel_height(&excess, Some(3), None)
+			.unwrap()
+			.is_none());
+		assert!(chain
+			.get_kernel_height(&excess, Some(1), Some(2))
+			.unwrap()
+			.is_some());
+
+		// A fork from height 3 rewinds blocks 4 and 5, their kernels leave the index.
+		let fork = prepare_block(&kc, &blocks[2].header, &chain, 20, vec![], 2);
+		process_block(&chain, &fork);
+		assert_eq!(chain.head().unwrap().last_block_h, fork.hash());
+		for b in &blocks[3..] {
+			for kernel in b.kernels() {
+				assert!(chain
+					.get_kernel_height(&kernel.excess, None, None)
+					.unwrap()
+					.is_none());
+			}
+		}
+		blocks.truncate(3);
+		blocks.push(fork);
+		assert_kernels_found(&chain, &blocks);
+
+		// Without the index we fall back to scanning the kernel MMR.
+		chain.set_kernel_index(false).unwrap();
+		assert_kernels_found(&chain, &blocks);
+		chain.set_kernel_index(true).unwrap();
+		assert_kernels_found(&chain, &blocks);
+	}
+
+	clean_output_dir(chain_dir);
+}
@@ -0,0 +1,140 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+use crate::chain_test_helper::{
+	clean_output_dir, prepare_block, process_block, set_foundation_path_for_test,
+};
+use epic_chain as chain;
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_util as util;
+
+use self::chain::types::NoopAdapter;
+use self::chain::{Chain, OutputHistory};
+use self::core::core::hash::Hashed;
+use self::core::core::{Block, KernelFeatures};
+use self::core::libtx::{build, ProofBuilder};
+use self::core::{consensus, pow};
+use self::keychain::{ExtKeychain, ExtKeychainPath, Keychain};
+use std::sync::Arc;
+
+fn init_chain(dir_name: &str, genesis: Block, archive_mode: bool) -> Chain {
+	Chain::init(
+		dir_name.to_string(),
+		Arc::new(NoopAdapter {}),
+		genesis,
+		pow::verify_size,
+		archive_mode,
+	)
+	.unwrap()
+}
+
+#[test]
+fn test_output_history() {
+	util::init_test_logger();
+
+	let chain_dir = ".epic_output_history";
+	clean_output_dir(chain_dir);
+
+	set_foundation_path_for_test("foundation_floonet.json");
+
+	let genesis = pow::mine_genesis_block().unwrap();
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	let pb = ProofBuilder::new(&kc);
+
+	let (spent_commit, new_commit, spent_created, spending_block) = {
+		let chain = init_chain(chain_dir, genesis.clone(), true);
+		let mut prev = chain.head_header().unwrap();
+		for n in 2..6 {
+			let b = prepare_block(&kc, &prev, &chain, n, vec![], 1);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+		}
+		let fork_head = prev.clone();
+
+		let key_id2 = ExtKeychainPath::new(1, 2, 0, 0, 0).to_identifier();
+		let key_id30 = ExtKeychainPath::new(1, 30, 0, 0, 0).to_identifier();
+		let tx = build::transaction(
+			KernelFeatures::Plain { fee: 20000 },
+			vec![
+				build::coinbase_input(consensus::reward_at_height(1), key_id2),
+				build::output(consensus::reward_at_height(1) - 20000, key_id30),
+			],
+			&kc,
+			&pb,
+		)
+		.unwrap();
+		let spent_commit = tx.inputs()[0].commitment();
+		let new_commit = tx.outputs()[0].commitment();
+
+		let history = chain.get_output_history(&spent_commit).unwrap();
+		assert_eq!(history.len(), 1);
+		assert_eq!(history[0].spent, None);
+		let spent_created = history[0].clone();
+
+		let b = prepare_block(&kc, &prev, &chain, 7, vec![&tx], 4);
+		process_block(&chain, &b);
+
+		let history = chain.get_output_history(&spent_commit).unwrap();
+		assert_eq!(history[0].spent, Some((b.header.height, b.header.hash())));
+		assert_eq!(
+			chain.get_output_history(&new_commit).unwrap(),
+			vec![OutputHistory::created(&b.header)]
+		);
+
+		// A fork without the transaction rewinds the spend.
+		let fork = prepare_block(&kc, &fork_head, &chain, 20, vec![], 2);
+		process_block(&chain, &fork);
+		assert_eq!(chain.head().unwrap().last_block_h, fork.hash());
+		assert_eq!(
+			chain.get_output_history(&spent_commit).unwrap(),
+			vec![spent_created.clone()]
+		);
+		assert!(chain.get_output_history(&new_commit).unwrap().is_empty());
+
+		// Spending it again on the fork records the new spending block.
+		let b = prepare_block(&kc, &fork, &chain, 20, vec![&tx], 4);
+		process_block(&chain, &b);
+		assert_eq!(chain.head().unwrap().last_block_h, b.hash());
+
+		(spent_commit, new_commit, spent_created, b.header)
+	};
+
+	let expected_spent = OutputHistory {
+		spent: Some((spending_block.height, spending_block.hash())),
+		..spent_created
+	};
+
+	// Non archive nodes drop the index.
+	{
+		let chain = init_chain(chain_dir, genesis.clone(), false);
+		assert!(chain.get_output_history(&spent_commit).is_err());
+	}
+
+	// Reopening in archive mode rebuilds it from the blocks.
+	{
+		let chain = init_chain(chain_dir, genesis.clone(), true);
+		assert_eq!(
+			chain.get_output_history(&spent_commit).unwrap(),
+			vec![expected_spent]
+		);
+		assert_eq!(
+			chain.get_output_history(&new_commit).unwrap(),
+			vec![OutputHistory::created(&spending_block)]
+		);
+	}
+
+	clean_output_dir(chain_dir);
+}
@@ -33,7 +33,7 @@ use self::core::libtx::proof::{self, ProofBuild};
 use self::core::libtx::{self, Error, ProofBuilder};
 use self::core::pow::Difficulty;
 use chrono::Duration;
-use epic_chain::{BlockStatus, ChainAdapter, Options};
+use epic_chain::{BlockStatus, ChainAdapter, Options, Reorg};
 use epic_core::core::block::feijoada::{next_block_bottles, Deterministic, Feijoada};
 use epic_core::core::foundation::load_foundation_output;
 use epic_core::core::TxKernel;
@@ -42,15 +42,19 @@ pub fn clean_output_dir(dir_name: &str) {
 	let _ = fs::remove_dir_all(dir_name);
 }
 
-/// Adapter to retrieve last status
+/// Adapter to retrieve last status and reorg
 pub struct StatusAdapter {
 	pub last_status: RwLock<Option<BlockStatus>>,
+	pub last_reorg: RwLock<Option<Reorg>>,
 }
 
 impl StatusAdapter {
 	#[allow(dead_code)]
 	pub fn new(last_status: RwLock<Option<BlockStatus>>) -> Self {
-		StatusAdapter { last_status }
+		StatusAdapter {
+			last_status,
+			last_reorg: RwLock::new(None),
+		}
 	}
 }
 
@@ -58,6 +62,10 @@ impl ChainAdapter for StatusAdapter {
 	fn block_accepted(&self, _b: &Block, status: BlockStatus, _opts: Options) {
 		*self.last_status.write() = Some(status);
 	}
+
+	fn reorg(&self, reorg: &Reorg) {
+		*self.last_reorg.write() = Some(reorg.clone());
+	}
 }
 
 /// Sets the foundation path for tests, cross-platform and robust for CI.
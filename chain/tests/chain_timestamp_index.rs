@@ -0,0 +1,78 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+use crate::chain_test_helper::{
+	clean_output_dir, init_chain, prepare_block, process_block, set_foundation_path_for_test,
+};
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_util as util;
+
+use self::core::core::BlockHeader;
+use self::core::pow;
+use self::keychain::{ExtKeychain, Keychain};
+
+fn heights(headers: &[BlockHeader]) -> Vec<u64> {
+	headers.iter().map(|h| h.height).collect()
+}
+
+#[test]
+fn test_headers_by_time() {
+	util::init_test_logger();
+
+	let chain_dir = ".epic_timestamp_index";
+	clean_output_dir(chain_dir);
+
+	set_foundation_path_for_test("foundation_floonet.json");
+
+	let genesis = pow::mine_genesis_block().unwrap();
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	let mut times = vec![];
+	{
+		let chain = init_chain(chain_dir, genesis.clone());
+		let mut prev = chain.head_header().unwrap();
+		times.push(prev.timestamp.timestamp());
+		for n in 1..=5 {
+			let b = prepare_block(&kc, &prev, &chain, n + 1, vec![], 1);
+			prev = b.header.clone();
+			process_block(&chain, &b);
+			times.push(prev.timestamp.timestamp());
+		}
+
+		let all = chain.get_headers_by_time(times[0], times[5], 100).unwrap();
+		assert_eq!(heights(&all), vec![0, 1, 2, 3, 4, 5]);
+
+		// Both ends of the range are inclusive.
+		let some = chain.get_headers_by_time(times[2], times[4], 100).unwrap();
+		assert_eq!(heights(&some), vec![2, 3, 4]);
+
+		let capped = chain.get_headers_by_time(times[0], times[5], 2).unwrap();
+		assert_eq!(heights(&capped), vec![0, 1]);
+
+		let after = chain
+			.get_headers_by_time(times[5] + 1, times[5] + 3600, 100)
+			.unwrap();
+		assert!(after.is_empty());
+	}
+
+	// The index survives a restart.
+	{
+		let chain = init_chain(chain_dir, genesis.clone());
+		let all = chain.get_headers_by_time(times[0], times[5], 100).unwrap();
+		assert_eq!(heights(&all), vec![0, 1, 2, 3, 4, 5]);
+	}
+
+	clean_output_dir(chain_dir);
+}
@@ -378,6 +378,14 @@ fn mine_reorg() {
 			Some(BlockStatus::Reorg(REORG_DEPTH))
 		);
 
+		// The detached and attached branches are reported too
+		let reorg = adapter.last_reorg.read().clone().unwrap();
+		assert_eq!(reorg.fork_height, fork_head.height);
+		assert_eq!(reorg.fork_hash, fork_head.hash());
+		assert_eq!(reorg.depth(), REORG_DEPTH);
+		assert_eq!(reorg.detached.last(), Some(&(head.height, head.hash())));
+		assert_eq!(reorg.attached, vec![(reorg_head.height, reorg_head.hash())]);
+
 		// Chain should be switched to the reorganized chain
 		let head = chain.head_header().unwrap();
 		assert_eq!(head.height, NUM_BLOCKS_MAIN - REORG_DEPTH + 1);
@@ -388,6 +396,43 @@ fn mine_reorg() {
 	clean_output_dir(DIR_NAME);
 }
 
+#[test]
+fn mine_reorg_max_depth() {
+	const DIR_NAME: &str = ".epic_reorg_max_depth";
+	clean_output_dir(DIR_NAME);
+
+	set_foundation_path_for_test("foundation_floonet.json");
+
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+
+	let genesis = pow::mine_genesis_block().unwrap();
+	{
+		let chain = init_chain(DIR_NAME, genesis.clone());
+		let mut prev = chain.head_header().unwrap();
+		for n in 1..=4 {
+			let b = prepare_block(&kc, &prev, &chain, n, vec![], 1);
+			prev = b.header.clone();
+			chain.process_block(b, chain::Options::SKIP_POW).unwrap();
+		}
+
+		// A fork off height 1 with more work would detach 3 blocks.
+		chain.set_max_reorg_depth(Some(2));
+		let fork_head = chain.get_header_by_height(1).unwrap();
+		let b = prepare_block(&kc, &fork_head, &chain, 20, vec![], 2);
+		match chain.process_block(b.clone(), chain::Options::SKIP_POW) {
+			Err(chain::Error::ReorgTooDeep(3, 2)) => {}
+			res => panic!("expected the reorg to be refused, got {:?}", res),
+		}
+		assert_eq!(chain.head_header().unwrap().hash(), prev.hash());
+
+		chain.set_max_reorg_depth(Some(3));
+		chain.process_block(b.clone(), chain::Options::SKIP_POW).unwrap();
+		assert_eq!(chain.head_header().unwrap().hash(), b.hash());
+	}
+
+	clean_output_dir(DIR_NAME);
+}
+
 #[test]
 fn mine_forks() {
 	clean_output_dir(".epic2");
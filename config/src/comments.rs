@@ -53,6 +53,15 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"metrics_http_addr".to_string(),
+		"
+#the address of the Prometheus metrics listener (GET /metrics)
+#comment it to disable the metrics listener
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"api_secret_path".to_string(),
 		"
@@ -121,6 +130,45 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"checkpoints_file".to_string(),
+		"
+#file of trusted checkpoints replacing the built-in ones, one
+#\"<height> <block hash>\" pair per line
+#checkpoints_file = \"checkpoints.txt\"
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"checkpoints_file_hash".to_string(),
+		"
+#blake2b-256 hash (b2sum -l 256) of the checkpoints file, required with
+#checkpoints_file, the file is refused unless its hash matches
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"assume_valid".to_string(),
+		"
+#skip rangeproof and kernel signature verification of blocks at or below the
+#last checkpoint during body sync, blocks must still be on the checkpointed
+#header chain
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"max_reorg_depth".to_string(),
+		"
+#refuse (and log an error for) blocks that would rewind more than this many
+#blocks of our chain, no limit when not set
+#max_reorg_depth = 10
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"archive_mode".to_string(),
 		"
@@ -129,6 +177,16 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"kernel_index".to_string(),
+		"
+#maintain an index of kernel excess commitments (kernel lookups, e.g. to verify
+#payment proofs, no longer scan the kernel MMR). Built on startup when enabled,
+#can be rebuilt with 'epic server rebuild-kernel-index'
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"skip_sync_wait".to_string(),
 		"
@@ -226,6 +284,42 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"control_addr".to_string(),
+		"
+#control port of a running tor daemon (e.g. \"127.0.0.1:9051\"), when set an
+#ephemeral onion service is published on it instead of launching tor
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"control_password".to_string(),
+		"
+#password of the tor control port, only needed with HashedControlPassword
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"persist_onion_key".to_string(),
+		"
+#save the key of our onion service under db_root so the onion address stays
+#the same across restarts, by default a new key is used on every start and
+#nothing is written to disk
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"onion_stem_hops".to_string(),
+		"
+#number of onion peers our stem transactions are relayed through when tor is
+#enabled, replacing the dandelion relay peer (0 disables the onion stem)
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"[server.p2p_config]".to_string(),
 		"#test miner wallet URL (burns if this doesn't exist)
@@ -298,6 +392,12 @@ seeds = [\"95.217.197.180:3414\",\"5.161.127.56:3414\",\"5.75.242.4:3414\",\"5.7
 # A preferred dandelion_peer, mainly used for testing dandelion
 # dandelion_peer = \"10.0.0.1:13144\"
 
+#only connect to onion peers through the tor socks proxy and only accept peers
+#through our own onion service, never exposing our IP (needs a tor control_addr)
+#onion_only = true
+#onion peers to seed from in onion only mode
+#onion_seeds = [\"<56 char onion address>.onion:3414\"]
+
 "
 		.to_string(),
 	);
@@ -328,6 +428,15 @@ seeds = [\"95.217.197.180:3414\",\"5.161.127.56:3414\",\"5.75.242.4:3414\",\"5.7
 		.to_string(),
 	);
 
+	retval.insert(
+		"max_pushed_pool_size".to_string(),
+		"
+#maximum number of transactions pushed through our api allowed in the pool,
+#on top of max_pool_size, they are never evicted for transactions from peers
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"max_stempool_size".to_string(),
 		"
@@ -366,6 +475,16 @@ seeds = [\"95.217.197.180:3414\",\"5.161.127.56:3414\",\"5.75.242.4:3414\",\"5.7
 		"stratum_server_addr".to_string(),
 		"
 #what port and address for the stratum server to listen on
+#to also accept TLS connections, e.g.
+#stratum_tls_server_addr = \"0.0.0.0:3417\"
+#stratum_tls_certificate_file = \"/path/to/cert.pem\"
+#stratum_tls_certificate_key = \"/path/to/key.pem\"
+#to only accept workers logging in with a password, listed as login:password
+#or in a file of login:salt:hex(pbkdf2_hmac_sha256(password, salt)) lines,
+#hashed with 600000 rounds, e.g.
+#worker_credentials = [\"rig1:secret\"]
+#worker_password_file = \"/path/to/worker_passwords\"
+#workers failing to log in too often are refused for a while
 "
 		.to_string(),
 	);
@@ -387,6 +506,37 @@ seeds = [\"95.217.197.180:3414\",\"5.161.127.56:3414\",\"5.75.242.4:3414\",\"5.7
 		.to_string(),
 	);
 
+	retval.insert(
+		"vardiff_target_share_time".to_string(),
+		"
+#seconds between shares the difficulty requested from each worker is
+#adjusted for (vardiff), 0 to have all workers mine at the minimum
+#share difficulty. The maximum share difficulty of each algorithm is the
+#network difficulty unless set, e.g.
+#cuckatoo_maximum_share_difficulty = 1000000
+#randomx_maximum_share_difficulty = 1000000
+#progpow_maximum_share_difficulty = 1000000
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"vardiff_retarget_time".to_string(),
+		"
+#seconds of shares to look at before adjusting the difficulty of a worker
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"share_retention_secs".to_string(),
+		"
+#seconds accepted shares are kept for the pplns and pps windows of the owner
+#api, older shares are pruned (default a week)
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"wallet_listener_url".to_string(),
 		"
@@ -403,6 +553,82 @@ seeds = [\"95.217.197.180:3414\",\"5.161.127.56:3414\",\"5.75.242.4:3414\",\"5.7
 		.to_string(),
 	);
 
+	retval.insert(
+		"[server.health_check_config]".to_string(),
+		"
+#########################################
+### HEALTH CHECK CONFIGURATION        ###
+#########################################
+
+#results are served at /v2/health, /v2/health/live and /v2/health/ready
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"check_interval".to_string(),
+		"
+#seconds between two runs of the health checks
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"max_sync_lag".to_string(),
+		"
+#the node is not ready when the best peer is more than this many blocks ahead
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"max_header_lag".to_string(),
+		"
+#the node is not ready when the header head is more than this many blocks
+#ahead of the body head
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"pool_warn_percent".to_string(),
+		"
+#warn when the transaction pool is filled above this percentage
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"[server.event_socket_config]".to_string(),
+		"
+#########################################
+### EVENT SOCKET CONFIGURATION        ###
+#########################################
+
+#subscribers connecting to the event socket receive one JSON object per line
+#for every block_accepted, reorg, header_received and tx_received event,
+#each with a sequence number to detect missed events
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"listen_addr".to_string(),
+		"
+#TCP address (e.g. 127.0.0.1:3420) or Unix domain socket path prefixed with
+#unix: (e.g. unix:/tmp/epic_events.sock) to publish events on
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"max_queued_events".to_string(),
+		"
+#events queued for slow subscribers before new events are dropped
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"[logging]".to_string(),
 		"
@@ -303,4 +303,369 @@ impl GlobalConfig {
 		file.write_all(conf_out.as_bytes())?;
 		Ok(())
 	}
+
+	/// Upgrade a config file written by an older version. Keys missing from
+	/// the file are filled in, with their comments, from the defaults for its
+	/// chain type. The rest of the file, values, comments and keys unknown to
+	/// this version, is kept as is. When keys are added the original file is
+	/// kept next to it with a `.bak` extension. Returns the migrated config
+	/// and the list of keys that were added.
+	pub fn migrate(
+		file_path: &str,
+		chain_type: &global::ChainTypes,
+	) -> Result<(GlobalConfig, Vec<String>), ConfigError> {
+		let path = PathBuf::from(file_path);
+		if !path.exists() {
+			return Err(ConfigError::FileNotFoundError(file_path.to_owned()));
+		}
+		let contents = fs::read_to_string(&path)?;
+		let parse_err = |e: String| ConfigError::ParseError(file_path.to_owned(), e);
+		let mut current: toml::Table = contents
+			.parse()
+			.map_err(|e| parse_err(format!("{}", e)))?;
+
+		// Defaults follow the chain type of the file, not the command line.
+		let chain_type = current
+			.get("server")
+			.and_then(|s| s.get("chain_type"))
+			.and_then(|c| c.clone().try_into::<global::ChainTypes>().ok())
+			.unwrap_or(chain_type.clone());
+		let mut defaults = GlobalConfig::for_chain(&chain_type);
+		if let Some(dir) = path.parent() {
+			defaults.update_paths(&dir.to_path_buf(), &chain_type);
+		}
+		let defaults = toml::Value::try_from(defaults.members.as_ref().unwrap())
+			.map_err(|e| ConfigError::SerializationError(format!("{}", e)))?;
+
+		let mut added = vec![];
+		let mut contents = contents;
+		if let toml::Value::Table(defaults) = defaults {
+			merge_missing(&mut current, &defaults, "", &mut added);
+			contents = insert_missing(&contents, &current, &added)?;
+		}
+
+		let members: ConfigMembers = toml::from_str(&contents)
+			.map_err(|e| parse_err(format!("{}", e)))?;
+
+		if !added.is_empty() {
+			let mut backup = path.clone().into_os_string();
+			backup.push(".bak");
+			fs::copy(&path, &backup)?;
+			fs::write(&path, contents)?;
+		}
+
+		let migrated = GlobalConfig {
+			config_file_path: Some(path),
+			members: Some(members),
+		};
+		Ok((migrated, added))
+	}
+
+	/// Write the values this config holds for `keys`, dotted paths such as
+	/// `server.archive_mode`, to an existing config file. Only the lines of
+	/// those keys change, the rest of the file, comments and keys unknown to
+	/// this version included, is kept as is. A key without a value is
+	/// removed from the file, a key missing from it is added with its comment.
+	pub fn update_file(&self, name: &str, keys: &[&str]) -> Result<(), ConfigError> {
+		let contents = fs::read_to_string(name)?;
+		let values = toml::Value::try_from(self.members.as_ref().unwrap())
+			.map_err(|e| ConfigError::SerializationError(format!("{}", e)))?;
+		if let toml::Value::Table(values) = values {
+			let contents = update_keys(&contents, &values, keys)?;
+			fs::write(name, contents)?;
+		}
+		Ok(())
+	}
+}
+
+/// Value held by `values` at the dotted `path`, if any.
+fn value_at<'a>(values: &'a toml::Table, path: &str) -> Option<&'a toml::Value> {
+	let mut keys: Vec<&str> = path.split('.').collect();
+	let key = keys.pop().unwrap();
+	let mut table = values;
+	for k in keys {
+		table = table.get(k)?.as_table()?;
+	}
+	table.get(key)
+}
+
+/// Start and end line of the section of `table` in a config file, the top
+/// of the file for the root table.
+fn section(lines: &[String], table: &str) -> Option<(usize, usize)> {
+	let start = if table.is_empty() {
+		0
+	} else {
+		let header = format!("[{}]", table);
+		lines.iter().position(|l| l.trim() == header)? + 1
+	};
+	let end = lines[start..]
+		.iter()
+		.position(|l| l.trim_start().starts_with('['))
+		.map_or(lines.len(), |i| start + i);
+	Some((start, end))
+}
+
+/// Set the `keys` of a config file text to the values `values` holds for
+/// them, editing their lines in place. Tables are updated key by key.
+fn update_keys(
+	contents: &str,
+	values: &toml::Table,
+	keys: &[&str],
+) -> Result<String, ConfigError> {
+	let mut keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+	let mut contents = contents.to_owned();
+	while let Some(path) = keys.pop() {
+		let value = value_at(values, &path);
+		if let Some(toml::Value::Table(table)) = value {
+			keys.extend(table.keys().map(|k| format!("{}.{}", path, k)));
+			continue;
+		}
+
+		let (parent, key) = match path.rfind('.') {
+			Some(i) => (&path[..i], &path[i + 1..]),
+			None => ("", path.as_str()),
+		};
+		let mut lines: Vec<String> = contents.lines().map(|l| l.to_owned()).collect();
+		let line = section(&lines, parent).and_then(|(start, end)| {
+			lines[start..end]
+				.iter()
+				.position(|l| {
+					l.trim_start()
+						.strip_prefix(key)
+						.map_or(false, |rest| rest.trim_start().starts_with('='))
+				})
+				.map(|i| start + i)
+		});
+		match (line, value) {
+			(Some(i), Some(value)) => lines[i] = format!("{} = {}", key, value),
+			(Some(i), None) => {
+				lines.remove(i);
+			}
+			(None, Some(_)) => {
+				contents = insert_missing(&contents, values, &[path.clone()])?;
+				continue;
+			}
+			(None, None) => continue,
+		}
+		contents = lines.join("\n");
+		contents.push('\n');
+	}
+	Ok(contents)
+}
+
+/// Add the `added` keys, with the values `merged` holds for them, to the
+/// text of a config file. Keys of an existing table go at the end of its
+/// section, new tables at the end of the file. Both get the comments of a
+/// freshly generated config file.
+fn insert_missing(
+	contents: &str,
+	merged: &toml::Table,
+	added: &[String],
+) -> Result<String, ConfigError> {
+	let ser_err = |e: toml::ser::Error| ConfigError::SerializationError(format!("{}", e));
+	let mut lines: Vec<String> = contents.lines().map(|l| l.to_owned()).collect();
+	let mut new_tables = String::new();
+
+	for path in added {
+		let mut keys: Vec<&str> = path.split('.').collect();
+		let key = keys.pop().unwrap();
+		let mut table = merged;
+		for k in &keys {
+			table = table[*k].as_table().unwrap();
+		}
+		let value = &table[key];
+		let is_table = match value {
+			toml::Value::Table(_) => true,
+			toml::Value::Array(a) => a.iter().any(|v| v.is_table()),
+			_ => false,
+		};
+
+		// Wrap the value in its parent tables to get the full section headers.
+		let mut wrapped = toml::Table::new();
+		wrapped.insert(key.to_owned(), value.clone());
+		let parent = keys.join(".");
+		if is_table {
+			for k in keys.iter().rev() {
+				let mut outer = toml::Table::new();
+				outer.insert(k.to_string(), toml::Value::Table(wrapped));
+				wrapped = outer;
+			}
+		}
+		// Headers of the parent tables are already in the file.
+		let parents: Vec<String> = (1..=keys.len())
+			.map(|i| format!("[{}]", keys[..i].join(".")))
+			.collect();
+		let text = toml::to_string(&wrapped).map_err(ser_err)?;
+		let text: Vec<&str> = text
+			.lines()
+			.filter(|l| !parents.iter().any(|p| p == l.trim()))
+			.collect();
+		let text: Vec<String> = insert_comments(text.join("\n"))
+			.lines()
+			.map(|l| l.to_owned())
+			.collect();
+
+		if is_table {
+			new_tables.push('\n');
+			new_tables.push_str(&text.join("\n"));
+			new_tables.push('\n');
+			continue;
+		}
+
+		// End of the section of the parent table, after its last key. The
+		// comments below it already belong to the next section.
+		match section(&lines, &parent) {
+			Some((start, end)) => {
+				let end = lines[start..end]
+					.iter()
+					.rposition(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+					.map_or(start, |i| start + i + 1);
+				for (i, line) in text.into_iter().enumerate() {
+					lines.insert(end + i, line);
+				}
+			}
+			None => {
+				let header = format!("[{}]", parent);
+				new_tables.push_str(&format!("\n{}\n{}\n", header, text.join("\n")));
+			}
+		}
+	}
+
+	let mut out = lines.join("\n");
+	out.push('\n');
+	out.push_str(&new_tables);
+	Ok(out)
+}
+
+/// Recursively copy the keys of `defaults` missing from `current`, recording
+/// the dotted path of every key added.
+fn merge_missing(
+	current: &mut toml::Table,
+	defaults: &toml::Table,
+	prefix: &str,
+	added: &mut Vec<String>,
+) {
+	for (key, default) in defaults {
+		let path = if prefix.is_empty() {
+			key.clone()
+		} else {
+			format!("{}.{}", prefix, key)
+		};
+		match current.get_mut(key) {
+			None => {
+				current.insert(key.clone(), default.clone());
+				added.push(path);
+			}
+			Some(toml::Value::Table(table)) => {
+				if let toml::Value::Table(default) = default {
+					merge_missing(table, default, &path, added);
+				}
+			}
+			Some(_) => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn merge_keeps_user_values() {
+		let mut current: toml::Table = "[server]\napi_http_addr = \"0.0.0.0:3413\"\n"
+			.parse()
+			.unwrap();
+		let defaults: toml::Table =
+			"[server]\napi_http_addr = \"127.0.0.1:3413\"\narchive_mode = false\n[logging]\nlog_to_file = true\n"
+				.parse()
+				.unwrap();
+		let mut added = vec![];
+		merge_missing(&mut current, &defaults, "", &mut added);
+
+		assert_eq!(
+			current["server"]["api_http_addr"].as_str(),
+			Some("0.0.0.0:3413")
+		);
+		assert_eq!(current["server"]["archive_mode"].as_bool(), Some(false));
+		assert_eq!(current["logging"]["log_to_file"].as_bool(), Some(true));
+		assert_eq!(added, vec!["logging", "server.archive_mode"]);
+	}
+
+	#[test]
+	fn insert_keeps_comments_and_unknown_keys() {
+		let contents = "# my node\n[server]\n# public api\napi_http_addr = \"0.0.0.0:3413\"\n\
+			old_setting = 1\n\n#the next section\n[server.pool_config]\n\
+			accept_fee_base = 1000\n";
+		let mut current: toml::Table = contents.parse().unwrap();
+		let defaults: toml::Table = "[server]\napi_http_addr = \"127.0.0.1:3413\"\n\
+			archive_mode = false\n[server.pool_config]\naccept_fee_base = 500\n\
+			[logging]\nlog_to_file = true\n"
+			.parse()
+			.unwrap();
+		let mut added = vec![];
+		merge_missing(&mut current, &defaults, "", &mut added);
+		let migrated = insert_missing(contents, &current, &added).unwrap();
+
+		assert!(migrated.starts_with("# my node\n[server]\n# public api\n"));
+		assert!(migrated.contains("old_setting = 1\n\n#run the node in \"full archive\""));
+		assert!(
+			migrated.contains("archive_mode = false\n\n#the next section\n[server.pool_config]\n")
+		);
+		assert!(migrated.contains("### LOGGING CONFIGURATION"));
+		assert!(migrated.contains("#whether to log to a file\nlog_to_file = true\n"));
+		assert!(!migrated.contains("[server]\n[server]"));
+
+		let reparsed: toml::Table = migrated.parse().unwrap();
+		assert_eq!(reparsed, current);
+	}
+
+	#[test]
+	fn update_keeps_comments_and_unknown_keys() {
+		let dir = env::temp_dir().join("epic_config_update_test");
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		let file = dir.join("epic-server.toml");
+		let name = file.to_str().unwrap();
+
+		let chain_type = global::ChainTypes::Mainnet;
+		let mut config = GlobalConfig::for_chain(&chain_type);
+		config.update_paths(&dir, &chain_type);
+		config.members.as_mut().unwrap().server.tls_certificate_file = Some("cert".to_owned());
+		config.write_to_file(name).unwrap();
+		let contents = fs::read_to_string(&file).unwrap().replace(
+			"[server]\n",
+			"[server]\n# my own node\nold_setting = 1\n",
+		);
+		fs::write(&file, contents).unwrap();
+
+		// Answers of an interactive update
+		let (mut config, _) = GlobalConfig::migrate(name, &chain_type).unwrap();
+		{
+			let server = &mut config.members.as_mut().unwrap().server;
+			server.archive_mode = Some(true);
+			server.api_http_addr = "0.0.0.0:3413".to_owned();
+			server.tls_certificate_file = None;
+		}
+		let keys = [
+			"server.archive_mode",
+			"server.api_http_addr",
+			"server.tls_certificate_file",
+			"server.p2p_config.capabilities",
+		];
+		config.update_file(name, &keys).unwrap();
+
+		let updated = fs::read_to_string(&file).unwrap();
+		assert!(updated.contains("[server]\n# my own node\nold_setting = 1\n"));
+		assert!(updated.contains("api_http_addr = \"0.0.0.0:3413\"\n"));
+		assert!(updated.contains("archive_mode = true\n"));
+		assert!(!updated.contains("tls_certificate_file = \"cert\""));
+		let members: ConfigMembers = toml::from_str(&updated).unwrap();
+		assert_eq!(members.server.archive_mode, Some(true));
+		assert_eq!(members.server.api_http_addr, "0.0.0.0:3413");
+		assert_eq!(
+			members.server.p2p_config.capabilities,
+			config.members.as_ref().unwrap().server.p2p_config.capabilities
+		);
+		let _ = fs::remove_dir_all(&dir);
+	}
 }
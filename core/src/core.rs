@@ -16,6 +16,7 @@
 
 pub mod block;
 pub mod block_fees;
+pub mod block_filter;
 pub mod block_sums;
 pub mod committed;
 pub mod compact_block;
@@ -31,6 +32,7 @@ use crate::consensus::EPIC_BASE;
 use crate::util::secp::pedersen::Commitment;
 
 pub use self::block::*;
+pub use self::block_filter::BlockFilter;
 pub use self::block_sums::*;
 pub use self::committed::Committed;
 pub use self::compact_block::*;
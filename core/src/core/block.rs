@@ -867,6 +867,25 @@ impl Block {
 		Ok(kernel_sum)
 	}
 
+	/// Validates a block known to be on a trusted (checkpointed) chain.
+	/// Same as `validate` but skips the expensive rangeproof and kernel
+	/// signature verification.
+	pub fn validate_assumed_valid(
+		&self,
+		prev_kernel_offset: &BlindingFactor,
+	) -> Result<Commitment, Error> {
+		self.validate_read()?;
+
+		self.verify_coinbase()?;
+
+		let (_utxo_sum, kernel_sum) = self.verify_kernel_sums(
+			self.header.overage(),
+			self.block_kernel_offset(prev_kernel_offset.clone())?,
+		)?;
+
+		Ok(kernel_sum)
+	}
+
 	/// Validate the coinbase.body.outputs generated by miners.
 	/// Check the sum of coinbase-marked outputs match
 	/// the sum of coinbase-marked kernels accounting for fees.
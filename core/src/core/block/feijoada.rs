@@ -80,9 +80,140 @@ impl FuzzySearch for AllowedPolicy {
 pub struct PolicyConfig {
 	pub allowed_policies: AllowedPolicy,
 	pub policies: Vec<Policy>,
+	/// Height at which new blocks start using each of the `policies`, one
+	/// entry per policy. When empty the mainnet era heights are used.
+	#[serde(default)]
+	pub emission_heights: Vec<u64>,
+}
+
+/// Errors found when validating a policy schedule
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PolicyError {
+	#[error("No policy defined")]
+	NoPolicies,
+	#[error("Too many policies: {0}, at most 64 are supported")]
+	TooManyPolicies(usize),
+	#[error("Weights of policy {0} sum to {1}, they must sum to 100")]
+	WeightsSum(usize, u32),
+	#[error("No allowed policies defined")]
+	NoAllowedPolicies,
+	#[error("The first allowed policy must start at height 0")]
+	AllowedPoliciesStart,
+	#[error("Allowed policy heights must be strictly increasing, {0} follows {1}")]
+	AllowedPoliciesOrder(u64, u64),
+	#[error("Allowed policy value {0:#b} at height {1} refers to an undefined policy")]
+	UnknownAllowedPolicy(u64, u64),
+	#[error("Expected {0} emission heights, one per policy, got {1}")]
+	EmissionHeightsCount(usize, usize),
+	#[error("No emission heights, the {1} mainnet eras need {1} policies, got {0}")]
+	DefaultEmissionHeights(usize, usize),
+	#[error("The first emission height must be 0")]
+	EmissionHeightsStart,
+	#[error("Emission heights must be strictly increasing, {0} follows {1}")]
+	EmissionHeightsOrder(u64, u64),
+	#[error("Policy {0} is emitted from height {1} but not allowed there")]
+	EmittedNotAllowed(usize, u64),
+}
+
+/// Mainnet heights at which the policy eras start.
+pub fn default_emission_heights() -> Vec<u64> {
+	vec![
+		0,
+		consensus::BLOCK_ERA_1 + 1,
+		consensus::BLOCK_ERA_2 + 1,
+		consensus::BLOCK_ERA_3 + 1,
+		consensus::BLOCK_ERA_4 + 1,
+		consensus::BLOCK_ERA_5 + 1,
+	]
 }
 
 impl PolicyConfig {
+	/// Checks the schedule is usable: weights of every policy sum to 100,
+	/// heights are monotonic, and every emitted policy is also allowed.
+	/// Without `emission_heights` the mainnet eras are checked instead.
+	pub fn validate(&self) -> Result<(), PolicyError> {
+		if self.policies.is_empty() {
+			return Err(PolicyError::NoPolicies);
+		}
+		// Allowed policies are a u64 bitmask indexed by policy
+		if self.policies.len() > 64 {
+			return Err(PolicyError::TooManyPolicies(self.policies.len()));
+		}
+		for (i, policy) in self.policies.iter().enumerate() {
+			let sum = policy.values().sum::<u32>();
+			if sum != 100 {
+				return Err(PolicyError::WeightsSum(i, sum));
+			}
+		}
+
+		match self.allowed_policies.first() {
+			None => return Err(PolicyError::NoAllowedPolicies),
+			Some(first) if first.height != 0 => return Err(PolicyError::AllowedPoliciesStart),
+			_ => {}
+		}
+		for pair in self.allowed_policies.windows(2) {
+			if pair[1].height <= pair[0].height {
+				return Err(PolicyError::AllowedPoliciesOrder(pair[1].height, pair[0].height));
+			}
+		}
+		for allowed in &self.allowed_policies {
+			if allowed.value == 0
+				|| (self.policies.len() < 64 && allowed.value >> self.policies.len() != 0)
+			{
+				return Err(PolicyError::UnknownAllowedPolicy(allowed.value, allowed.height));
+			}
+		}
+
+		let emission_heights = self.era_heights();
+		if emission_heights.len() != self.policies.len() {
+			if self.emission_heights.is_empty() {
+				return Err(PolicyError::DefaultEmissionHeights(
+					self.policies.len(),
+					emission_heights.len(),
+				));
+			}
+			return Err(PolicyError::EmissionHeightsCount(
+				self.policies.len(),
+				emission_heights.len(),
+			));
+		}
+		if emission_heights[0] != 0 {
+			return Err(PolicyError::EmissionHeightsStart);
+		}
+		for pair in emission_heights.windows(2) {
+			if pair[1] <= pair[0] {
+				return Err(PolicyError::EmissionHeightsOrder(pair[1], pair[0]));
+			}
+		}
+		for (i, &height) in emission_heights.iter().enumerate() {
+			if !is_allowed_policy(self.allowed_policies.clone(), height, i as u8) {
+				return Err(PolicyError::EmittedNotAllowed(i, height));
+			}
+		}
+		Ok(())
+	}
+
+	/// Heights at which each policy starts being emitted, the mainnet eras
+	/// unless the schedule defines its own.
+	pub fn era_heights(&self) -> Vec<u64> {
+		if self.emission_heights.is_empty() {
+			default_emission_heights()
+		} else {
+			self.emission_heights.clone()
+		}
+	}
+
+	/// Index of the policy emitted at `height` according to
+	/// `emission_heights`, which must not be empty.
+	pub fn emitted_policy(&self, height: u64) -> u8 {
+		let started = self
+			.emission_heights
+			.iter()
+			.filter(|&&start| start <= height)
+			.count();
+		started.saturating_sub(1) as u8
+	}
+
 	pub fn no_progpow() -> Self {
 		let mut policy_era1 = get_bottles_default();
 		policy_era1.insert(PoWType::RandomX, 60);
@@ -161,6 +292,7 @@ impl PolicyConfig {
 				policy_era5,
 				policy_era6,
 			],
+			emission_heights: vec![],
 		}
 	}
 
@@ -242,6 +374,7 @@ impl PolicyConfig {
 				policy_era5,
 				policy_era6,
 			],
+			emission_heights: vec![],
 		}
 	}
 }
@@ -325,6 +458,7 @@ impl Default for PolicyConfig {
 				policy_era5,
 				policy_era6,
 			],
+			emission_heights: vec![],
 		}
 	}
 }
@@ -0,0 +1,235 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compact block filters (BIP158 style), letting light clients find out which
+//! blocks touch their outputs without downloading every block.
+//! A filter is a Golomb-coded set of the output commitments and kernel
+//! excesses of a block. The filter header of a block commits to its filter
+//! and to the filter header of the previous block, so a client trusting the
+//! filter header of a block can verify the filters of every block before it.
+
+use byteorder::{ByteOrder, LittleEndian};
+use siphasher::sip::SipHasher24;
+
+use crate::core::hash::{DefaultHashable, Hash, Hashed};
+use crate::core::Block;
+use crate::ser::{self, Readable, Reader, Writeable, Writer};
+
+/// Golomb-Rice coding parameter, the number of remainder bits per item.
+pub const FILTER_P: u8 = 19;
+
+/// Inverse of the false positive rate of a filter match.
+pub const FILTER_M: u64 = 784_931;
+
+/// Golomb-coded set of the output commitments and kernel excesses of a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockFilter {
+	/// Hash of the block the filter was built from, also keys the item hashes
+	pub block_hash: Hash,
+	/// Number of (distinct) items in the set
+	pub n: u32,
+	/// Golomb-Rice coded deltas between the sorted item hashes
+	pub data: Vec<u8>,
+}
+
+impl DefaultHashable for BlockFilter {}
+
+impl BlockFilter {
+	/// Builds the filter of a block.
+	pub fn from_block(block: &Block) -> BlockFilter {
+		let items: Vec<_> = block
+			.outputs()
+			.iter()
+			.map(|out| out.commitment())
+			.chain(block.kernels().iter().map(|kernel| kernel.excess))
+			.collect();
+		BlockFilter::new(block.hash(), &items)
+	}
+
+	/// Builds a filter over the given items, keyed by the block hash.
+	pub fn new<T: AsRef<[u8]>>(block_hash: Hash, items: &[T]) -> BlockFilter {
+		let mut items: Vec<&[u8]> = items.iter().map(|item| item.as_ref()).collect();
+		items.sort_unstable();
+		items.dedup();
+
+		let n = items.len() as u64;
+		let mut values: Vec<u64> = items
+			.iter()
+			.map(|item| hash_to_range(&block_hash, item, n * FILTER_M))
+			.collect();
+		values.sort_unstable();
+
+		let mut writer = BitWriter::default();
+		let mut last = 0;
+		for value in values {
+			golomb_encode(&mut writer, value - last);
+			last = value;
+		}
+
+		BlockFilter {
+			block_hash,
+			n: n as u32,
+			data: writer.data,
+		}
+	}
+
+	/// Whether the item may be in the set. False positives happen with a
+	/// probability of 1/FILTER_M, false negatives never.
+	pub fn matches<T: AsRef<[u8]>>(&self, item: T) -> bool {
+		self.matches_any(&[item])
+	}
+
+	/// Whether any of the items may be in the set.
+	pub fn matches_any<T: AsRef<[u8]>>(&self, items: &[T]) -> bool {
+		if self.n == 0 || items.is_empty() {
+			return false;
+		}
+		let range = self.n as u64 * FILTER_M;
+		let mut queries: Vec<u64> = items
+			.iter()
+			.map(|item| hash_to_range(&self.block_hash, item.as_ref(), range))
+			.collect();
+		queries.sort_unstable();
+
+		let mut reader = BitReader::new(&self.data);
+		let mut queries = queries.into_iter().peekable();
+		let mut value = 0;
+		for _ in 0..self.n {
+			value += match golomb_decode(&mut reader) {
+				Some(delta) => delta,
+				None => return false,
+			};
+			while let Some(query) = queries.peek() {
+				if *query == value {
+					return true;
+				} else if *query > value {
+					break;
+				}
+				queries.next();
+			}
+			if queries.peek().is_none() {
+				return false;
+			}
+		}
+		false
+	}
+
+	/// The filter header, committing to this filter and, through the filter
+	/// header of the previous block, to the filters of all previous blocks.
+	/// The previous filter header of the genesis block is the zero hash.
+	pub fn header(&self, prev_header: &Hash) -> Hash {
+		(self.hash(), *prev_header).hash()
+	}
+}
+
+impl Writeable for BlockFilter {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		self.block_hash.write(writer)?;
+		writer.write_u32(self.n)?;
+		writer.write_bytes(&self.data)?;
+		Ok(())
+	}
+}
+
+impl Readable for BlockFilter {
+	fn read(reader: &mut dyn Reader) -> Result<BlockFilter, ser::Error> {
+		Ok(BlockFilter {
+			block_hash: Hash::read(reader)?,
+			n: reader.read_u32()?,
+			data: reader.read_bytes_len_prefix()?,
+		})
+	}
+}
+
+/// Maps the SipHash-2-4 of the item, keyed by the block hash, uniformly onto
+/// [0, range).
+fn hash_to_range(block_hash: &Hash, item: &[u8], range: u64) -> u64 {
+	// we "use" core::hash::Hash in the outer namespace
+	use std::hash::Hasher;
+
+	let k0 = LittleEndian::read_u64(&block_hash.as_bytes()[0..8]);
+	let k1 = LittleEndian::read_u64(&block_hash.as_bytes()[8..16]);
+	let mut sip_hasher = SipHasher24::new_with_keys(k0, k1);
+	sip_hasher.write(item);
+	((sip_hasher.finish() as u128 * range as u128) >> 64) as u64
+}
+
+fn golomb_encode(writer: &mut BitWriter, value: u64) {
+	let quotient = value >> FILTER_P;
+	for _ in 0..quotient {
+		writer.write_bit(true);
+	}
+	writer.write_bit(false);
+	writer.write_bits(value, FILTER_P);
+}
+
+fn golomb_decode(reader: &mut BitReader<'_>) -> Option<u64> {
+	let mut quotient = 0;
+	while reader.read_bit()? {
+		quotient += 1;
+	}
+	let remainder = reader.read_bits(FILTER_P)?;
+	Some((quotient << FILTER_P) + remainder)
+}
+
+/// Writes bits most significant first.
+#[derive(Default)]
+struct BitWriter {
+	data: Vec<u8>,
+	used: u8,
+}
+
+impl BitWriter {
+	fn write_bit(&mut self, bit: bool) {
+		if self.used == 0 {
+			self.data.push(0);
+		}
+		if bit {
+			*self.data.last_mut().unwrap() |= 0x80 >> self.used;
+		}
+		self.used = (self.used + 1) % 8;
+	}
+
+	fn write_bits(&mut self, value: u64, count: u8) {
+		for i in (0..count).rev() {
+			self.write_bit((value >> i) & 1 == 1);
+		}
+	}
+}
+
+struct BitReader<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+	fn new(data: &'a [u8]) -> BitReader<'a> {
+		BitReader { data, pos: 0 }
+	}
+
+	fn read_bit(&mut self) -> Option<bool> {
+		let byte = self.data.get(self.pos / 8)?;
+		let bit = byte & (0x80 >> (self.pos % 8)) != 0;
+		self.pos += 1;
+		Some(bit)
+	}
+
+	fn read_bits(&mut self, count: u8) -> Option<u64> {
+		let mut value = 0;
+		for _ in 0..count {
+			value = (value << 1) | self.read_bit()? as u64;
+		}
+		Some(value)
+	}
+}
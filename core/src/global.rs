@@ -304,7 +304,10 @@ pub fn get_allowed_policies() -> Vec<AllowPolicy> {
 }
 
 pub fn get_emitted_policy(height: u64) -> u8 {
-	let _policy_config = POLICY_CONFIG.read();
+	let policy_config = POLICY_CONFIG.read();
+	if !policy_config.emission_heights.is_empty() {
+		return policy_config.emitted_policy(height);
+	}
 	if height <= consensus::BLOCK_ERA_1 {
 		0
 	} else if height <= consensus::BLOCK_ERA_2 {
@@ -0,0 +1,73 @@
+// Copyright 2025 The Epic Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compact block filter construction, matching and serialization
+use epic_core as core;
+
+use self::core::core::hash::{Hash, Hashed, ZERO_HASH};
+use self::core::core::BlockFilter;
+use self::core::ser;
+
+fn items(count: u8) -> Vec<Vec<u8>> {
+	(0..count).map(|i| vec![i; 33]).collect()
+}
+
+fn block_hash() -> Hash {
+	(ZERO_HASH, 42u64).hash()
+}
+
+#[test]
+fn filter_matches_its_items() {
+	let filter = BlockFilter::new(block_hash(), &items(100));
+	assert_eq!(filter.n, 100);
+	for item in items(100) {
+		assert!(filter.matches(&item));
+	}
+	assert!(filter.matches_any(&[vec![200; 33], vec![50; 33]]));
+	assert!(!filter.matches(vec![200; 33]));
+	assert!(!filter.matches_any(&[vec![200; 33], vec![201; 33]]));
+}
+
+#[test]
+fn empty_filter() {
+	let filter = BlockFilter::new::<Vec<u8>>(block_hash(), &[]);
+	assert_eq!(filter.n, 0);
+	assert!(filter.data.is_empty());
+	assert!(!filter.matches(vec![0; 33]));
+}
+
+#[test]
+fn duplicate_items_counted_once() {
+	let mut dups = items(10);
+	dups.extend(items(10));
+	let filter = BlockFilter::new(block_hash(), &dups);
+	assert_eq!(filter, BlockFilter::new(block_hash(), &items(10)));
+}
+
+#[test]
+fn filter_keyed_by_block_hash() {
+	let filter = BlockFilter::new(block_hash(), &items(10));
+	let other = BlockFilter::new(ZERO_HASH, &items(10));
+	assert_ne!(filter.data, other.data);
+	assert_ne!(filter.header(&ZERO_HASH), other.header(&ZERO_HASH));
+}
+
+#[test]
+fn serialize_deserialize_filter() {
+	let filter = BlockFilter::new(block_hash(), &items(50));
+	let mut vec = Vec::new();
+	ser::serialize_default(&mut vec, &filter).expect("serialization failed");
+	let filter2: BlockFilter = ser::deserialize_default(&mut &vec[..]).unwrap();
+	assert_eq!(filter, filter2);
+	assert_eq!(filter.header(&ZERO_HASH), filter2.header(&ZERO_HASH));
+}
@@ -0,0 +1,112 @@
+// Copyright 2025 The Epic Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of block policy schedules loaded from the config file
+use epic_core as core;
+
+use self::core::core::feijoada::{
+	default_emission_heights, get_bottles_default, AllowPolicy, Policy, PolicyConfig, PolicyError,
+};
+use self::core::pow::PoWType;
+
+fn weights(randomx: u32, progpow: u32, cuckatoo: u32) -> Policy {
+	let mut policy = get_bottles_default();
+	policy.insert(PoWType::RandomX, randomx);
+	policy.insert(PoWType::ProgPow, progpow);
+	policy.insert(PoWType::Cuckatoo, cuckatoo);
+	policy
+}
+
+fn two_eras() -> PolicyConfig {
+	PolicyConfig {
+		allowed_policies: vec![
+			AllowPolicy {
+				height: 0,
+				value: 1,
+			},
+			AllowPolicy {
+				height: 1000,
+				value: 3,
+			},
+		],
+		policies: vec![weights(60, 38, 2), weights(50, 0, 50)],
+		emission_heights: vec![0, 1000],
+	}
+}
+
+#[test]
+fn built_in_policies_are_valid() {
+	assert_eq!(PolicyConfig::default().validate(), Ok(()));
+	assert_eq!(PolicyConfig::no_progpow().validate(), Ok(()));
+	assert_eq!(PolicyConfig::only_randomx().validate(), Ok(()));
+}
+
+#[test]
+fn custom_schedule() {
+	let config = two_eras();
+	assert_eq!(config.validate(), Ok(()));
+	assert_eq!(config.emitted_policy(0), 0);
+	assert_eq!(config.emitted_policy(999), 0);
+	assert_eq!(config.emitted_policy(1000), 1);
+	assert_eq!(config.emitted_policy(5000), 1);
+	assert_eq!(config.era_heights(), vec![0, 1000]);
+}
+
+#[test]
+fn invalid_schedules() {
+	let mut config = two_eras();
+	config.policies[1] = weights(50, 0, 40);
+	assert_eq!(config.validate(), Err(PolicyError::WeightsSum(1, 90)));
+
+	let mut config = two_eras();
+	config.allowed_policies[1].height = 0;
+	assert_eq!(config.validate(), Err(PolicyError::AllowedPoliciesOrder(0, 0)));
+
+	let mut config = two_eras();
+	config.allowed_policies[1].value = 4;
+	assert_eq!(config.validate(), Err(PolicyError::UnknownAllowedPolicy(4, 1000)));
+
+	let mut config = two_eras();
+	config.emission_heights = vec![0];
+	assert_eq!(config.validate(), Err(PolicyError::EmissionHeightsCount(2, 1)));
+
+	let mut config = two_eras();
+	config.emission_heights = vec![0, 500];
+	assert_eq!(config.validate(), Err(PolicyError::EmittedNotAllowed(1, 500)));
+
+	let mut config = two_eras();
+	config.emission_heights = vec![];
+	assert_eq!(config.validate(), Err(PolicyError::DefaultEmissionHeights(2, 6)));
+}
+
+#[test]
+fn default_emission_heights_are_checked() {
+	// Without emission heights the policies follow the mainnet eras
+	let config = PolicyConfig::no_progpow();
+	assert!(config.emission_heights.is_empty());
+	assert_eq!(config.era_heights(), default_emission_heights());
+	assert_eq!(config.validate(), Ok(()));
+
+	let mut config = PolicyConfig::no_progpow();
+	config.policies.pop();
+	config.allowed_policies.pop();
+	assert_eq!(config.validate(), Err(PolicyError::DefaultEmissionHeights(5, 6)));
+
+	// Every era must allow the policy emitted in it
+	let mut config = PolicyConfig::no_progpow();
+	config.allowed_policies[2].value = 1 << 1;
+	assert_eq!(
+		config.validate(),
+		Err(PolicyError::EmittedNotAllowed(2, default_emission_heights()[2]))
+	);
+}
@@ -16,10 +16,13 @@ use crate::conn::Tracker;
 use crate::core::core::hash::Hash;
 use crate::core::pow::Difficulty;
 use crate::core::ser::ProtocolVersion;
-use crate::msg::{read_message, write_message, Hand, Msg, Shake, Type, USER_AGENT};
+use crate::msg::{read_message, write_message, Hand, Msg, OnionProof, Shake, Type, USER_AGENT};
 use crate::peer::Peer;
-use crate::types::{Capabilities, Direction, Error, P2PConfig, PeerAddr, PeerInfo, PeerLiveInfo};
+use crate::types::{
+	Capabilities, Direction, Error, P2PConfig, PeerAddr, PeerEndpoint, PeerInfo, PeerLiveInfo,
+};
 use crate::util::RwLock;
+use ed25519_dalek::SigningKey as DalekSecretKey;
 use rand::{rng, Rng};
 use std::collections::VecDeque;
 use std::net::{SocketAddr, TcpStream};
@@ -49,6 +52,9 @@ pub struct Handshake {
 	config: P2PConfig,
 	protocol_version: ProtocolVersion,
 	tracker: Arc<Tracker>,
+	/// Key and p2p endpoint of our onion service, proving who we are to the
+	/// onion peers we connect to.
+	onion_identity: RwLock<Option<(DalekSecretKey, PeerEndpoint)>>,
 }
 
 impl Handshake {
@@ -61,9 +67,15 @@ impl Handshake {
 			config,
 			protocol_version: ProtocolVersion::local(),
 			tracker: Arc::new(Tracker::new()),
+			onion_identity: RwLock::new(None),
 		}
 	}
 
+	/// Sets the onion service we're reachable at for p2p.
+	pub fn set_onion_identity(&self, sec_key: DalekSecretKey, endpoint: PeerEndpoint) {
+		*self.onion_identity.write() = Some((sec_key, endpoint));
+	}
+
 	/// Select a protocol version here that we know is supported by both us and the remote peer.
 	///
 	/// Current strategy is to simply use `min(local, remote)`.
@@ -81,13 +93,20 @@ impl Handshake {
 		capabilities: Capabilities,
 		total_difficulty: Difficulty,
 		self_addr: PeerAddr,
+		peer_addr: PeerAddr,
 		conn: &mut TcpStream,
 	) -> Result<PeerInfo, Error> {
 		// prepare the first part of the handshake
 		let nonce = self.next_nonce();
-		let peer_addr = match conn.peer_addr() {
-			Ok(pa) => PeerAddr(pa),
-			Err(e) => return Err(Error::Connection(e)),
+		// only onion peers get to know our onion service
+		let sender_onion = match *self.onion_identity.read() {
+			Some((ref sec_key, ref endpoint)) if peer_addr.is_onion() => Some(OnionProof::sign(
+				sec_key,
+				endpoint.clone(),
+				peer_addr,
+				nonce,
+			)),
+			_ => None,
 		};
 
 		let hand = Hand {
@@ -99,6 +118,7 @@ impl Handshake {
 			sender_addr: self_addr,
 			receiver_addr: peer_addr,
 			user_agent: USER_AGENT.to_string(),
+			sender_onion,
 		};
 
 		// write and read the handshake response
@@ -158,10 +178,11 @@ impl Handshake {
 				us: self.genesis,
 				peer: hand.genesis,
 			});
-		} else {
+		}
+		let addr = self.inbound_peer_addr(&hand, &conn)?;
+		{
 			// check the nonce to see if we are trying to connect to ourselves
 			let nonces = self.nonces.read();
-			let addr = resolve_peer_addr(hand.sender_addr, &conn);
 			if nonces.contains(&hand.nonce) {
 				// save ip addresses of ourselves
 				let mut addrs = self.addrs.write();
@@ -179,7 +200,7 @@ impl Handshake {
 		let peer_info = PeerInfo {
 			capabilities: hand.capabilities,
 			user_agent: hand.user_agent,
-			addr: resolve_peer_addr(hand.sender_addr, &conn),
+			addr,
 			version: negotiated_version,
 			live_info: Arc::new(RwLock::new(PeerLiveInfo::new(hand.total_difficulty))),
 			direction: Direction::Inbound,
@@ -210,6 +231,31 @@ impl Handshake {
 		Ok(peer_info)
 	}
 
+	/// The address we key an inbound peer by. Peers coming in through our onion
+	/// service all connect from the local tor daemon, they are identified by
+	/// the onion service they prove to own instead.
+	fn inbound_peer_addr(&self, hand: &Hand, conn: &TcpStream) -> Result<PeerAddr, Error> {
+		let from_tor = conn.peer_addr().map_or(false, |a| a.ip().is_loopback());
+		match hand.sender_onion {
+			Some(ref proof) if from_tor => {
+				let identity = self.onion_identity.read();
+				let (_, endpoint) = identity
+					.as_ref()
+					.ok_or_else(|| Error::OnionIdentity("no onion service".to_owned()))?;
+				if hand.receiver_addr != endpoint.peer_addr() {
+					return Err(Error::OnionIdentity(format!(
+						"handshake meant for {}",
+						hand.receiver_addr
+					)));
+				}
+				proof.verify(hand.receiver_addr, hand.nonce)?;
+				Ok(proof.endpoint.peer_addr())
+			}
+			_ if self.config.onion_only() => Err(Error::OnionIdentity("no proof".to_owned())),
+			_ => Ok(resolve_peer_addr(hand.sender_addr, conn)),
+		}
+	}
+
 	/// Generate a new random nonce and store it in our ring buffer
 	fn next_nonce(&self) -> u64 {
 		let nonce = rng().random();
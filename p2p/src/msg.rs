@@ -16,14 +16,19 @@
 
 use crate::conn::Tracker;
 use crate::core::core::hash::Hash;
-use crate::core::core::BlockHeader;
+use crate::core::core::{BlockFilter, BlockHeader};
 use crate::core::pow::Difficulty;
 use crate::core::ser::{
 	self, FixedLength, ProtocolVersion, Readable, Reader, StreamingReader, Writeable, Writer,
 };
 use crate::core::{consensus, global};
+use crate::store::EndpointData;
 use crate::types::{
-	Capabilities, Error, PeerAddr, ReasonForBan, MAX_BLOCK_HEADERS, MAX_LOCATORS, MAX_PEER_ADDRS,
+	Capabilities, Error, PeerAddr, PeerEndpoint, ReasonForBan, MAX_BLOCK_HEADERS,
+	MAX_ENDPOINT_LEN, MAX_LOCATORS, MAX_PEER_ADDRS,
+};
+use ed25519_dalek::{
+	Signature, Signer, SigningKey as DalekSecretKey, Verifier, VerifyingKey as DalekPublicKey,
 };
 use num::FromPrimitive;
 use std::fs::File;
@@ -71,7 +76,10 @@ enum_from_primitive! {
 		FastHeaders = 24,
 		OnionAddressRequest = 25,
 		OnionAddressResponse = 26,
-
+		GetBlockFilter = 27,
+		BlockFilter = 28,
+		GetPeerAddrsV2 = 29,
+		PeerAddrsV2 = 30,
 	}
 }
 
@@ -89,7 +97,7 @@ fn default_max_msg_size() -> u64 {
 fn max_msg_size(msg_type: Type) -> u64 {
 	match msg_type {
 		Type::Error => 0,
-		Type::Hand => 128,
+		Type::Hand => 128 + 1 + (1 + 1 + 32 + 2) + 64,
 		Type::Shake => 88,
 		Type::Ping => 16,
 		Type::Pong => 16,
@@ -115,6 +123,12 @@ fn max_msg_size(msg_type: Type) -> u64 {
 		Type::KernelDataResponse => 8,
 		Type::OnionAddressRequest => 0,
 		Type::OnionAddressResponse => 256,
+		Type::GetBlockFilter => 32,
+		Type::BlockFilter => 32 + 4 + 8 + max_block_size() / 10 + 32,
+		Type::GetPeerAddrsV2 => 4,
+		Type::PeerAddrsV2 => {
+			4 + (1 + 1 + MAX_ENDPOINT_LEN as u64 + 2 + 4 + 8) * MAX_PEER_ADDRS as u64
+		}
 	}
 }
 
@@ -366,6 +380,8 @@ pub struct Hand {
 	pub receiver_addr: PeerAddr,
 	/// name of version of the software
 	pub user_agent: String,
+	/// onion service of the sender, when connecting through tor
+	pub sender_onion: Option<OnionProof>,
 }
 
 impl Writeable for Hand {
@@ -381,6 +397,11 @@ impl Writeable for Hand {
 		self.receiver_addr.write(writer)?;
 		writer.write_bytes(&self.user_agent)?;
 		self.genesis.write(writer)?;
+		// appended last, older peers ignore it
+		if let Some(ref proof) = self.sender_onion {
+			writer.write_u8(1)?;
+			proof.write(writer)?;
+		}
 		Ok(())
 	}
 }
@@ -396,6 +417,11 @@ impl Readable for Hand {
 		let ua = reader.read_bytes_len_prefix()?;
 		let user_agent = String::from_utf8(ua).map_err(|_| ser::Error::CorruptedData)?;
 		let genesis = Hash::read(reader)?;
+		// absent when sent by older peers or over clearnet
+		let sender_onion = match reader.read_u8() {
+			Ok(1) => Some(OnionProof::read(reader)?),
+			_ => None,
+		};
 		Ok(Hand {
 			version,
 			capabilities,
@@ -405,6 +431,78 @@ impl Readable for Hand {
 			sender_addr,
 			receiver_addr,
 			user_agent,
+			sender_onion,
+		})
+	}
+}
+
+/// Onion service of the sender of a Hand, with a signature by the service key
+/// proving it is theirs. Inbound peers reaching us through our own onion
+/// service all come from the local tor daemon, this is what identifies them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnionProof {
+	pub endpoint: PeerEndpoint,
+	pub signature: [u8; 64],
+}
+
+impl OnionProof {
+	/// Signs our onion endpoint for a handshake with the given nonce, sent to
+	/// `receiver_addr`.
+	pub fn sign(
+		sec_key: &DalekSecretKey,
+		endpoint: PeerEndpoint,
+		receiver_addr: PeerAddr,
+		nonce: u64,
+	) -> OnionProof {
+		let signature = sec_key.sign(&OnionProof::message(&endpoint, receiver_addr, nonce));
+		OnionProof {
+			endpoint,
+			signature: signature.to_bytes(),
+		}
+	}
+
+	/// Checks the signature was made by the onion service key, for a handshake
+	/// with the given nonce sent to `receiver_addr`.
+	pub fn verify(&self, receiver_addr: PeerAddr, nonce: u64) -> Result<(), Error> {
+		let pubkey = match self.endpoint {
+			PeerEndpoint::OnionV3 { ref pubkey, .. } => pubkey,
+			PeerEndpoint::Ip(_) => {
+				return Err(Error::OnionIdentity("not an onion endpoint".to_owned()))
+			}
+		};
+		let pubkey = DalekPublicKey::from_bytes(pubkey)
+			.map_err(|e| Error::OnionIdentity(format!("{}", e)))?;
+		let message = OnionProof::message(&self.endpoint, receiver_addr, nonce);
+		pubkey
+			.verify(&message, &Signature::from_bytes(&self.signature))
+			.map_err(|e| Error::OnionIdentity(format!("{}", e)))
+	}
+
+	fn message(endpoint: &PeerEndpoint, receiver_addr: PeerAddr, nonce: u64) -> Vec<u8> {
+		let mut message = b"epic-onion-hand".to_vec();
+		// writing to a vec can't fail
+		let _ = ser::serialize_default(&mut message, endpoint);
+		let _ = ser::serialize_default(&mut message, &receiver_addr);
+		message.extend(&nonce.to_be_bytes());
+		message
+	}
+}
+
+impl Writeable for OnionProof {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		self.endpoint.write(writer)?;
+		writer.write_fixed_bytes(&self.signature.to_vec())
+	}
+}
+
+impl Readable for OnionProof {
+	fn read(reader: &mut dyn Reader) -> Result<OnionProof, ser::Error> {
+		let endpoint = PeerEndpoint::read(reader)?;
+		let mut signature = [0u8; 64];
+		signature.copy_from_slice(&reader.read_fixed_bytes(64)?);
+		Ok(OnionProof {
+			endpoint,
+			signature,
 		})
 	}
 }
@@ -508,6 +606,41 @@ impl Readable for PeerAddrs {
 	}
 }
 
+/// Endpoints we know of that are fresh enough, in response to
+/// GetPeerAddrsV2. Unlike PeerAddrs it carries onion services as well as IP
+/// addresses, with their capabilities and when they were last seen.
+/// Endpoints of networks we don't know are skipped when reading.
+#[derive(Debug)]
+pub struct PeerAddrsV2 {
+	pub endpoints: Vec<EndpointData>,
+}
+
+impl Writeable for PeerAddrsV2 {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_u32(self.endpoints.len() as u32)?;
+		for e in &self.endpoints {
+			e.write(writer)?;
+		}
+		Ok(())
+	}
+}
+
+impl Readable for PeerAddrsV2 {
+	fn read(reader: &mut dyn Reader) -> Result<PeerAddrsV2, ser::Error> {
+		let count = reader.read_u32()?;
+		if count > MAX_PEER_ADDRS {
+			return Err(ser::Error::TooLargeReadErr);
+		}
+		let mut endpoints = Vec::with_capacity(count as usize);
+		for _ in 0..count {
+			if let Some(e) = EndpointData::read_opt(reader)? {
+				endpoints.push(e);
+			}
+		}
+		Ok(PeerAddrsV2 { endpoints })
+	}
+}
+
 /// We found some issue in the communication, sending an error back, usually
 /// followed by closing the connection.
 pub struct PeerError {
@@ -866,6 +999,32 @@ impl Readable for OnionAddressResponse {
 	}
 }
 
+/// Compact filter of a block, sent in response to a GetBlockFilter, along
+/// with its filter header so light clients can verify the filter chain.
+pub struct BlockFilterResponse {
+	pub filter: BlockFilter,
+	pub filter_header: Hash,
+}
+
+impl Writeable for BlockFilterResponse {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		self.filter.write(writer)?;
+		self.filter_header.write(writer)?;
+		Ok(())
+	}
+}
+
+impl Readable for BlockFilterResponse {
+	fn read(reader: &mut dyn Reader) -> Result<BlockFilterResponse, ser::Error> {
+		let filter = BlockFilter::read(reader)?;
+		let filter_header = Hash::read(reader)?;
+		Ok(BlockFilterResponse {
+			filter,
+			filter_header,
+		})
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
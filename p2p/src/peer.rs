@@ -34,6 +34,7 @@ use crate::msg::{
 	OnionAddressRequest, Ping, TxHashSetRequest, Type,
 };
 use crate::protocol::Protocol;
+use crate::store::EndpointData;
 use crate::types::{
 	Capabilities, ChainAdapter, Error, NetAdapter, P2PConfig, PeerAddr, PeerInfo, ReasonForBan,
 	TxHashSetRead,
@@ -142,11 +143,12 @@ impl Peer {
 		capab: Capabilities,
 		total_difficulty: Difficulty,
 		self_addr: PeerAddr,
+		peer_addr: PeerAddr,
 		hs: &Handshake,
 		adapter: Arc<dyn NetAdapter>,
 	) -> Result<Peer, Error> {
-		debug!("connect: handshaking with {:?}", conn.peer_addr());
-		let info = hs.initiate(capab, total_difficulty, self_addr, &mut conn);
+		debug!("connect: handshaking with {}", peer_addr);
+		let info = hs.initiate(capab, total_difficulty, self_addr, peer_addr, &mut conn);
 		match info {
 			Ok(info) => {
 				let peer = Peer::new(info, conn, adapter)?;
@@ -162,9 +164,8 @@ impl Peer {
 			}
 			Err(e) => {
 				debug!(
-					"connect: handshaking with {:?} failed with error: {:?}",
-					conn.peer_addr(),
-					e
+					"connect: handshaking with {} failed with error: {:?}",
+					peer_addr, e
 				);
 				if let Err(e) = conn.shutdown(Shutdown::Both) {
 					debug!("Error shutting down conn: {:?}", e);
@@ -426,13 +427,20 @@ impl Peer {
 		self.send(&h, msg::Type::GetCompactBlock)
 	}
 
+	/// Asks for more peers, with addrv2 gossip if the peer supports it so we
+	/// hear of onion endpoints too.
 	pub fn send_peerlist_request(&self, capab: Capabilities) -> Result<(), Error> {
 		info!("Asking {} for more peers.", self.info.addr);
+		let msg_type = if self.info.capabilities.contains(Capabilities::ADDR_V2) {
+			msg::Type::GetPeerAddrsV2
+		} else {
+			msg::Type::GetPeerAddrs
+		};
 		self.send(
 			&GetPeerAddrs {
 				capabilities: capab,
 			},
-			msg::Type::GetPeerAddrs,
+			msg_type,
 		)
 	}
 
@@ -611,6 +619,20 @@ impl ChainAdapter for TrackingAdapter {
 		self.adapter.get_block(h)
 	}
 
+	fn get_block_filter(&self, h: Hash) -> Option<(core::BlockFilter, Hash)> {
+		self.adapter.get_block_filter(h)
+	}
+
+	fn block_filter_received(
+		&self,
+		filter: core::BlockFilter,
+		filter_header: Hash,
+		peer_info: &PeerInfo,
+	) -> Result<bool, chain::Error> {
+		self.adapter
+			.block_filter_received(filter, filter_header, peer_info)
+	}
+
 	fn kernel_data_read(&self) -> Result<File, chain::Error> {
 		self.adapter.kernel_data_read()
 	}
@@ -668,6 +690,14 @@ impl NetAdapter for TrackingAdapter {
 		self.adapter.peer_addrs_received(addrs)
 	}
 
+	fn find_peer_endpoints(&self, capab: Capabilities) -> Vec<EndpointData> {
+		self.adapter.find_peer_endpoints(capab)
+	}
+
+	fn peer_endpoints_received(&self, endpoints: Vec<EndpointData>) {
+		self.adapter.peer_endpoints_received(endpoints)
+	}
+
 	fn peer_difficulty(&self, addr: PeerAddr, diff: Difficulty, height: u64, local_timestamp: i64) {
 		self.adapter
 			.peer_difficulty(addr, diff, height, local_timestamp)
@@ -28,10 +28,10 @@ use crate::core::core::hash::{Hash, Hashed};
 use crate::core::global;
 use crate::core::pow::Difficulty;
 use crate::peer::Peer;
-use crate::store::{PeerData, PeerStore, State};
+use crate::store::{EndpointData, PeerData, PeerStore, State};
 use crate::types::{
-	Capabilities, ChainAdapter, Error, NetAdapter, P2PConfig, PeerAddr, PeerInfo, ReasonForBan,
-	TxHashSetRead, MAX_PEER_ADDRS,
+	Capabilities, ChainAdapter, Error, NetAdapter, P2PConfig, PeerAddr, PeerEndpoint, PeerInfo,
+	ReasonForBan, TxHashSetRead, MAX_PEER_ADDRS,
 };
 use chrono::prelude::*;
 use chrono::Duration;
@@ -39,6 +39,9 @@ use epic_chain::types::SyncStatus;
 
 const LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
 
+/// Onion endpoints not seen for that long (in secs) are not gossiped anymore.
+const ENDPOINT_MAX_AGE: i64 = 7 * 24 * 3600;
+
 pub struct Peers {
 	pub adapter: Arc<dyn ChainAdapter>,
 	store: PeerStore,
@@ -500,6 +503,58 @@ impl Peers {
 		}
 	}
 
+	/// Onion endpoints in store with the provided capabilities, seen recently
+	/// enough
+	pub fn find_onion_endpoints(&self, cap: Capabilities, count: usize) -> Vec<EndpointData> {
+		let min_last_seen = Utc::now().timestamp() - ENDPOINT_MAX_AGE;
+		match self.store.find_endpoints(cap, min_last_seen, count) {
+			Ok(endpoints) => endpoints.into_iter().filter(|e| e.endpoint.is_onion()).collect(),
+			Err(e) => {
+				debug!("failed to find endpoints: {:?}", e);
+				vec![]
+			}
+		}
+	}
+
+	/// Saves an address we heard of but never connected to yet, unless we
+	/// already know it.
+	fn save_new_peer_addr(&self, addr: PeerAddr) {
+		if let Ok(true) = self.exists_peer(addr) {
+			return;
+		}
+		let peer = PeerData {
+			addr,
+			capabilities: Capabilities::UNKNOWN,
+			user_agent: "".to_string(),
+			flags: State::Defunct,
+			last_banned: 0,
+			ban_reason: ReasonForBan::None,
+			last_connected: 0,
+			local_timestamp: 0,
+		};
+		if let Err(e) = self.save_peer(&peer) {
+			error!("Could not save received peer address: {:?}", e);
+		}
+	}
+
+	/// The "<onion>.onion" host of the onion peer keyed by `addr`, if we know
+	/// its endpoint.
+	pub fn onion_host(&self, addr: PeerAddr) -> Option<String> {
+		match self.store.get_onion_endpoint(addr) {
+			Ok(e) => e.endpoint.onion_host(),
+			Err(epic_store::Error::NotFoundErr(_)) => None,
+			Err(e) => {
+				debug!("failed to find onion endpoint {}: {:?}", addr, e);
+				None
+			}
+		}
+	}
+
+	/// Our own onion endpoint, if we run an onion service
+	pub fn my_onion_endpoint(&self) -> Option<PeerEndpoint> {
+		self.my_onion_addr().and_then(|addr| PeerEndpoint::from_onion(&addr).ok())
+	}
+
 	/// Get peer in store by address
 	pub fn get_peer(&self, peer_addr: PeerAddr) -> Result<PeerData, Error> {
 		self.store.get_peer(peer_addr).map_err(From::from)
@@ -847,6 +902,20 @@ impl ChainAdapter for Peers {
 		self.adapter.get_block(h)
 	}
 
+	fn get_block_filter(&self, h: Hash) -> Option<(core::BlockFilter, Hash)> {
+		self.adapter.get_block_filter(h)
+	}
+
+	fn block_filter_received(
+		&self,
+		filter: core::BlockFilter,
+		filter_header: Hash,
+		peer_info: &PeerInfo,
+	) -> Result<bool, chain::Error> {
+		self.adapter
+			.block_filter_received(filter, filter_header, peer_info)
+	}
+
 	fn kernel_data_read(&self) -> Result<File, chain::Error> {
 		self.adapter.kernel_data_read()
 	}
@@ -914,30 +983,49 @@ impl NetAdapter for Peers {
 	/// addresses.
 	/// exceptions are made for loopback, unspecified, private and unique local addresses.
 	fn find_peer_addrs(&self, capab: Capabilities) -> Vec<PeerAddr> {
-		let peers = self.find_peers(State::Healthy, capab, MAX_PEER_ADDRS as usize);
-		peers
+		self.find_peers(State::Healthy, capab, MAX_PEER_ADDRS as usize)
 			.into_iter()
-			.filter(|p| {
-				let ip = p.addr.0.ip();
-				!ip.is_loopback()
-					&& !ip.is_unspecified()
-					&& match ip {
-						std::net::IpAddr::V4(ipv4) => {
-							let octets = ipv4.octets();
-							octets[0] != 10
-								&& !(octets[0] == 172 && (octets[1] >= 16 && octets[1] <= 31))
-								&& !(octets[0] == 192 && octets[1] == 168)
-						}
-						std::net::IpAddr::V6(ipv6) => !ipv6.is_unique_local(),
-					}
-			})
+			.filter(|p| is_public(&p.addr))
 			.map(|p| p.addr)
 			.collect()
 	}
 
+	/// Our own onion endpoint first, then onion endpoints for up to half of
+	/// the list and healthy IP peers for the rest.
+	fn find_peer_endpoints(&self, capab: Capabilities) -> Vec<EndpointData> {
+		let now = Utc::now().timestamp();
+		let mut endpoints = vec![];
+		if let Some(endpoint) = self.my_onion_endpoint() {
+			endpoints.push(EndpointData {
+				endpoint,
+				capabilities: self.config.capabilities | Capabilities::ADDR_V2,
+				last_seen: now,
+			});
+		}
+		let half = MAX_PEER_ADDRS as usize / 2;
+		endpoints.extend(self.find_onion_endpoints(capab, half));
+		let peers = self.find_peers(State::Healthy, capab, MAX_PEER_ADDRS as usize);
+		endpoints.extend(
+			peers
+				.into_iter()
+				.filter(|p| is_public(&p.addr))
+				.map(|p| EndpointData {
+					endpoint: PeerEndpoint::Ip(p.addr),
+					capabilities: p.capabilities,
+					last_seen: p.last_connected,
+				}),
+		);
+		endpoints.truncate(MAX_PEER_ADDRS as usize);
+		endpoints
+	}
+
 	/// A list of peers has been received from one of our peers.
 	fn peer_addrs_received(&self, peer_addrs: Vec<PeerAddr>) {
 		trace!("Received {} peer addrs, saving.", peer_addrs.len());
+		if self.config.onion_only() {
+			// we would never connect to them
+			return;
+		}
 		for pa in peer_addrs {
 			let ip = pa.0.ip();
 			if global::is_mainnet() {
@@ -960,24 +1048,41 @@ impl NetAdapter for Peers {
 				}
 			}
 
-			if let Ok(e) = self.exists_peer(pa) {
-				if e {
-					continue;
+			self.save_new_peer_addr(pa);
+		}
+	}
+
+	/// IP endpoints go through peer_addrs_received, onion endpoints are saved
+	/// with their capabilities and last seen time. In onion only mode they are
+	/// also the peers we connect to.
+	fn peer_endpoints_received(&self, endpoints: Vec<EndpointData>) {
+		trace!("Received {} peer endpoints, saving.", endpoints.len());
+		let now = Utc::now().timestamp();
+		let my_addr = self.my_onion_endpoint().map(|e| e.peer_addr());
+		let mut addrs = vec![];
+		for mut e in endpoints {
+			match e.endpoint {
+				PeerEndpoint::Ip(addr) => addrs.push(addr),
+				PeerEndpoint::OnionV3 { ref pubkey, .. } => {
+					if Some(e.endpoint.peer_addr()) == my_addr
+						|| e.last_seen < now - ENDPOINT_MAX_AGE
+					{
+						continue;
+					}
+					// nobody has seen it in the future
+					e.last_seen = e.last_seen.min(now);
+					if let Err(err) = self.store.save_endpoint(&e) {
+						error!("Could not save received endpoint {}: {:?}", e.endpoint, err);
+					}
+					if self.config.onion_only() {
+						let port = PeerAddr::default_port();
+						self.save_new_peer_addr(PeerAddr::from_onion(pubkey, port));
+					}
 				}
 			}
-			let peer = PeerData {
-				addr: pa,
-				capabilities: Capabilities::UNKNOWN,
-				user_agent: "".to_string(),
-				flags: State::Defunct,
-				last_banned: 0,
-				ban_reason: ReasonForBan::None,
-				last_connected: 0,
-				local_timestamp: 0,
-			};
-			if let Err(e) = self.save_peer(&peer) {
-				error!("Could not save received peer address: {:?}", e);
-			}
+		}
+		if !addrs.is_empty() {
+			self.peer_addrs_received(addrs);
 		}
 	}
 
@@ -1015,3 +1120,20 @@ impl NetAdapter for Peers {
 		self.my_onion_addr.read().clone()
 	}
 }
+
+/// Whether the address is worth gossiping: not loopback, unspecified, private
+/// or unique local.
+fn is_public(addr: &PeerAddr) -> bool {
+	let ip = addr.0.ip();
+	!ip.is_loopback()
+		&& !ip.is_unspecified()
+		&& match ip {
+			std::net::IpAddr::V4(ipv4) => {
+				let octets = ipv4.octets();
+				octets[0] != 10
+					&& !(octets[0] == 172 && (octets[1] >= 16 && octets[1] <= 31))
+					&& !(octets[0] == 192 && octets[1] == 168)
+			}
+			std::net::IpAddr::V6(ipv6) => !ipv6.is_unique_local(),
+		}
+}
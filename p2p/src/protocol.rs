@@ -18,10 +18,12 @@ use crate::core::core::{self, hash::Hash, hash::Hashed, CompactBlock};
 use crate::util::format::human_readable_size;
 
 use crate::msg::{
-	BanReason, FastHeaders, GetPeerAddrs, Headers, KernelDataResponse, Locator, LocatorFastSync,
-	Msg, OnionAddressResponse, PeerAddrs, Ping, Pong, TxHashSetArchive, TxHashSetRequest, Type,
+	BanReason, BlockFilterResponse, FastHeaders, GetPeerAddrs, Headers, KernelDataResponse,
+	Locator, LocatorFastSync, Msg, OnionAddressResponse, PeerAddrs, PeerAddrsV2, Ping, Pong,
+	TxHashSetArchive, TxHashSetRequest, Type,
 };
-use crate::types::{Error, NetAdapter, PeerInfo};
+use crate::store::EndpointData;
+use crate::types::{Error, NetAdapter, PeerEndpoint, PeerInfo};
 use chrono::prelude::Utc;
 use rand::{rng, Rng};
 use std::cmp;
@@ -191,6 +193,34 @@ impl MessageHandler for Protocol {
 				Ok(None)
 			}
 
+			Type::GetBlockFilter => {
+				let h: Hash = msg.body()?;
+				trace!("GetBlockFilter: {}, msg_len: {}", h, msg.header.msg_len);
+				if let Some((filter, filter_header)) = adapter.get_block_filter(h) {
+					Ok(Some(Msg::new(
+						Type::BlockFilter,
+						BlockFilterResponse {
+							filter,
+							filter_header,
+						},
+						self.peer_info.version,
+					)?))
+				} else {
+					Ok(None)
+				}
+			}
+
+			Type::BlockFilter => {
+				debug!("Received block filter: msg_len: {}", msg.header.msg_len);
+				let response: BlockFilterResponse = msg.body()?;
+				adapter.block_filter_received(
+					response.filter,
+					response.filter_header,
+					&self.peer_info,
+				)?;
+				Ok(None)
+			}
+
 			Type::GetHeaders => {
 				// load headers from the locator
 				let loc: Locator = msg.body()?;
@@ -286,6 +316,22 @@ impl MessageHandler for Protocol {
 				Ok(None)
 			}
 
+			Type::GetPeerAddrsV2 => {
+				let get_peers: GetPeerAddrs = msg.body()?;
+				let endpoints = adapter.find_peer_endpoints(get_peers.capabilities);
+				Ok(Some(Msg::new(
+					Type::PeerAddrsV2,
+					PeerAddrsV2 { endpoints },
+					self.peer_info.version,
+				)?))
+			}
+
+			Type::PeerAddrsV2 => {
+				let peer_addrs: PeerAddrsV2 = msg.body()?;
+				adapter.peer_endpoints_received(peer_addrs.endpoints);
+				Ok(None)
+			}
+
 			Type::KernelDataRequest => {
 				let kernel_data = self.adapter.kernel_data_read()?;
 				let bytes = kernel_data.metadata()?.len();
@@ -535,6 +581,16 @@ impl MessageHandler for Protocol {
 					info!("Received onion address from peer: {}", onion_msg.onion_addr);
 					let mut live_info = self.peer_info.live_info.write();
 					live_info.onion_addr = Some(onion_msg.onion_addr.clone());
+
+					// Remember the endpoint so we can gossip it.
+					match PeerEndpoint::from_onion(&onion_msg.onion_addr) {
+						Ok(endpoint) => self.adapter.peer_endpoints_received(vec![EndpointData {
+							endpoint,
+							capabilities: self.peer_info.capabilities,
+							last_seen: Utc::now().timestamp(),
+						}]),
+						Err(e) => warn!("Invalid onion address from peer: {:?}", e),
+					}
 				}
 				Ok(None)
 			}
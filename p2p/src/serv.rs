@@ -14,7 +14,7 @@
 
 use std::fs::File;
 use std::io::{self, Read};
-use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread;
@@ -28,15 +28,20 @@ use crate::core::pow::Difficulty;
 use crate::handshake::Handshake;
 use crate::peer::Peer;
 use crate::peers::Peers;
-use crate::store::PeerStore;
+use crate::store::{EndpointData, PeerStore};
+use crate::tor;
 use crate::types::{
-	Capabilities, ChainAdapter, Error, NetAdapter, P2PConfig, PeerAddr, PeerInfo, ReasonForBan,
-	TxHashSetRead,
+	Capabilities, ChainAdapter, Error, NetAdapter, P2PConfig, PeerAddr, PeerEndpoint, PeerInfo,
+	ReasonForBan, TxHashSetRead,
 };
-use crate::util::StopState;
+use crate::util::secp::key::SecretKey;
+use crate::util::{RwLock, StopState};
 use chrono::prelude::{DateTime, Utc};
 use epic_chain::types::SyncStatus;
 
+/// Connecting to an onion service takes a while to build the circuits.
+const ONION_CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// P2P server implementation, handling bootstrapping to find and connect to
 /// peers, receiving connections from other peers and keep track of all of them.
 pub struct Server {
@@ -45,6 +50,9 @@ pub struct Server {
 	handshake: Arc<Handshake>,
 	pub peers: Arc<Peers>,
 	stop_state: Arc<StopState>,
+	/// The tor socks proxy we reach onion peers through and the address our
+	/// own onion service is keyed by.
+	onion_transport: RwLock<Option<(SocketAddr, PeerAddr)>>,
 }
 
 // TODO TLS
@@ -61,7 +69,8 @@ impl Server {
 	) -> Result<Server, Error> {
 		Ok(Server {
 			config: config.clone(),
-			capabilities: capab,
+			// every node of this version gossips endpoints with addrv2
+			capabilities: capab | Capabilities::ADDR_V2,
 			handshake: Arc::new(Handshake::new(genesis, config.clone())),
 			peers: Arc::new(Peers::new(
 				PeerStore::new(db_root)?,
@@ -70,14 +79,36 @@ impl Server {
 				onion_addr,
 			)),
 			stop_state,
+			onion_transport: RwLock::new(None),
 		})
 	}
 
+	/// Connects to onion peers through the tor socks proxy at `socks_proxy`,
+	/// proving them we run the onion service of `sec_key`, which serves p2p on
+	/// the default port.
+	pub fn init_onion(&self, socks_proxy: SocketAddr, sec_key: &SecretKey) -> Result<(), Error> {
+		let (d_sec_key, d_pub_key) = tor::address::ed25519_keypair(sec_key)?;
+		let endpoint = PeerEndpoint::OnionV3 {
+			pubkey: d_pub_key.to_bytes(),
+			port: PeerAddr::default_port(),
+		};
+		info!("Connecting to onion peers as {}", endpoint);
+		*self.onion_transport.write() = Some((socks_proxy, endpoint.peer_addr()));
+		self.handshake.set_onion_identity(d_sec_key, endpoint);
+		Ok(())
+	}
+
 	/// Starts a new TCP server and listen to incoming connections. This is a
 	/// blocking call until the TCP server stops.
 	pub fn listen(&self) -> Result<(), Error> {
-		// Start TCP listener and handle incoming connections
-		let addr = SocketAddr::new(self.config.host, self.config.port);
+		// Start TCP listener and handle incoming connections, only reachable
+		// through our onion service in onion only mode
+		let host = if self.config.onion_only() {
+			IpAddr::V4(Ipv4Addr::LOCALHOST)
+		} else {
+			self.config.host
+		};
+		let addr = SocketAddr::new(host, self.config.port);
 
 		let listener = match TcpListener::bind(addr) {
 			Ok(listener) => listener,
@@ -130,6 +161,10 @@ impl Server {
 					}
 					match self.handle_new_peer(stream) {
 						Err(Error::ConnectionClose) => debug!("shutting down, ignoring a new peer"),
+						// all onion peers come from the local tor daemon
+						Err(e) if self.config.onion_only() => {
+							debug!("Error accepting onion peer: {:?}", e)
+						}
 						Err(e) => {
 							debug!("Error accepting peer {}: {:?}", peer_addr.to_string(), e);
 							let _ = self.peers.add_banned(peer_addr, ReasonForBan::BadHandshake);
@@ -185,16 +220,29 @@ impl Server {
 			self.config.port,
 			addr
 		);
-		match TcpStream::connect_timeout(&addr.0, Duration::from_secs(10)) {
+		let stream = if addr.is_onion() {
+			self.connect_onion(addr)
+		} else if self.config.onion_only() {
+			debug!("connect_peer: onion only, not connecting to {}", addr);
+			return Err(Error::ConnectionClose);
+		} else {
+			TcpStream::connect_timeout(&addr.0, Duration::from_secs(10))
+		};
+		match stream {
 			Ok(stream) => {
-				let addr = SocketAddr::new(self.config.host, self.config.port);
+				// onion peers only get to know our onion service
+				let self_addr = match *self.onion_transport.read() {
+					Some((_, onion_addr)) if addr.is_onion() => onion_addr,
+					_ => PeerAddr(SocketAddr::new(self.config.host, self.config.port)),
+				};
 				let total_diff = self.peers.total_difficulty()?;
 
 				let peer = Peer::connect(
 					stream,
 					self.capabilities,
 					total_diff,
-					PeerAddr(addr),
+					self_addr,
+					addr,
 					&self.handshake,
 					self.peers.clone(),
 				)?;
@@ -215,6 +263,19 @@ impl Server {
 		}
 	}
 
+	/// Opens a connection to an onion peer through the tor socks proxy.
+	fn connect_onion(&self, addr: PeerAddr) -> io::Result<TcpStream> {
+		let socks_proxy = match *self.onion_transport.read() {
+			Some((socks_proxy, _)) => socks_proxy,
+			None => return Err(io::Error::new(io::ErrorKind::Other, "no tor socks proxy")),
+		};
+		let host = self
+			.peers
+			.onion_host(addr)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unknown onion peer"))?;
+		tor::socks::connect(&socks_proxy, &host, addr.0.port(), ONION_CONNECT_TIMEOUT)
+	}
+
 	fn handle_new_peer(&self, stream: TcpStream) -> Result<(), Error> {
 		if self.stop_state.is_stopped() {
 			return Err(Error::ConnectionClose);
@@ -229,6 +290,13 @@ impl Server {
 			&self.handshake,
 			self.peers.clone(),
 		)?;
+		// onion peers are only known by their onion service once handshaked
+		let addr = peer.info.addr;
+		if addr.is_onion() && (self.peers.is_banned(addr) || self.peers.is_known(addr)?) {
+			debug!("Onion peer {} banned or already known, refusing.", addr);
+			peer.stop();
+			return Ok(());
+		}
 		self.peers.add_connected(Arc::new(peer))?;
 		Ok(())
 	}
@@ -363,6 +431,17 @@ impl ChainAdapter for DummyAdapter {
 	fn get_block(&self, _: Hash) -> Option<core::Block> {
 		None
 	}
+	fn get_block_filter(&self, _: Hash) -> Option<(core::BlockFilter, Hash)> {
+		None
+	}
+	fn block_filter_received(
+		&self,
+		_filter: core::BlockFilter,
+		_filter_header: Hash,
+		_peer_info: &PeerInfo,
+	) -> Result<bool, chain::Error> {
+		Ok(true)
+	}
 	fn kernel_data_read(&self) -> Result<File, chain::Error> {
 		unimplemented!()
 	}
@@ -413,6 +492,10 @@ impl NetAdapter for DummyAdapter {
 		vec![]
 	}
 	fn peer_addrs_received(&self, _: Vec<PeerAddr>) {}
+	fn find_peer_endpoints(&self, _: Capabilities) -> Vec<EndpointData> {
+		vec![]
+	}
+	fn peer_endpoints_received(&self, _: Vec<EndpointData>) {}
 	fn peer_difficulty(&self, _: PeerAddr, _: Difficulty, _: u64, _: i64) {}
 	fn is_banned(&self, _: PeerAddr) -> bool {
 		false
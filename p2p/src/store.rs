@@ -20,13 +20,15 @@ use rand::rng;
 use rand::seq::SliceRandom;
 
 use crate::core::ser::{self, Readable, Reader, Writeable, Writer};
-use crate::types::{Capabilities, PeerAddr, ReasonForBan};
+use crate::types::{Capabilities, PeerAddr, PeerEndpoint, ReasonForBan};
 use epic_store::{self, option_to_not_found, to_key, Error};
 
 const DB_NAME: &'static str = "peer";
 const STORE_SUBPATH: &'static str = "peers";
 
 const PEER_PREFIX: u8 = 'P' as u8;
+const ENDPOINT_PREFIX: u8 = 'E' as u8;
+const ONION_INDEX_PREFIX: u8 = 'O' as u8;
 
 //State of peer
 enum_from_primitive! {
@@ -119,6 +121,46 @@ impl Readable for PeerData {
 	}
 }
 
+/// An endpoint we heard of through addrv2 gossip, or learnt from a connected
+/// peer. Onion endpoints only live here, IP peers are tracked as PeerData.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointData {
+	/// Where the peer can be reached.
+	pub endpoint: PeerEndpoint,
+	/// Capabilities the peer advertises.
+	pub capabilities: Capabilities,
+	/// Last time the peer was known to be reachable there.
+	pub last_seen: i64,
+}
+
+impl Writeable for EndpointData {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		self.endpoint.write(writer)?;
+		writer.write_u32(self.capabilities.bits())?;
+		writer.write_i64(self.last_seen)
+	}
+}
+
+impl Readable for EndpointData {
+	fn read(reader: &mut dyn Reader) -> Result<EndpointData, ser::Error> {
+		EndpointData::read_opt(reader)?.ok_or(ser::Error::CorruptedData)
+	}
+}
+
+impl EndpointData {
+	/// Reads an endpoint entry, None if its network is unknown to us.
+	pub fn read_opt(reader: &mut dyn Reader) -> Result<Option<EndpointData>, ser::Error> {
+		let endpoint = PeerEndpoint::read_opt(reader)?;
+		let capabilities = Capabilities::from_bits_truncate(reader.read_u32()?);
+		let last_seen = reader.read_i64()?;
+		Ok(endpoint.map(|endpoint| EndpointData {
+			endpoint,
+			capabilities,
+			last_seen,
+		}))
+	}
+}
+
 /// Storage facility for peer data.
 pub struct PeerStore {
 	db: epic_store::Store,
@@ -128,7 +170,22 @@ impl PeerStore {
 	/// Instantiates a new peer store under the provided root path.
 	pub fn new(db_root: &str) -> Result<PeerStore, Error> {
 		let db = epic_store::Store::new(db_root, Some(DB_NAME), Some(STORE_SUBPATH), None)?;
-		Ok(PeerStore { db })
+		let store = PeerStore { db };
+		store.index_onion_endpoints()?;
+		Ok(store)
+	}
+
+	/// Indexes the onion endpoints saved before they were indexed by their
+	/// OnionCat address.
+	fn index_onion_endpoints(&self) -> Result<(), Error> {
+		let batch = self.db.batch()?;
+		for e in self.all_endpoints()? {
+			let key = onion_index_key(e.endpoint.peer_addr());
+			if e.endpoint.is_onion() && !batch.exists(&key)? {
+				batch.put_ser(&key, &e)?;
+			}
+		}
+		batch.commit()
 	}
 
 	pub fn save_peer(&self, p: &PeerData) -> Result<(), Error> {
@@ -243,6 +300,87 @@ impl PeerStore {
 		batch.commit()
 	}
 
+	/// Saves the endpoint, unless we already know it with a later last_seen.
+	pub fn save_endpoint(&self, e: &EndpointData) -> Result<(), Error> {
+		let batch = self.db.batch()?;
+		let key = endpoint_key(&e.endpoint);
+		if let Some(existing) = batch.get_ser::<EndpointData>(&key)? {
+			if existing.last_seen >= e.last_seen {
+				return Ok(());
+			}
+		}
+		batch.put_ser(&key, e)?;
+		if e.endpoint.is_onion() {
+			batch.put_ser(&onion_index_key(e.endpoint.peer_addr()), e)?;
+		}
+		batch.commit()
+	}
+
+	pub fn get_endpoint(&self, endpoint: &PeerEndpoint) -> Result<EndpointData, Error> {
+		option_to_not_found(self.db.get_ser(&endpoint_key(endpoint)), || {
+			format!("Endpoint: {}", endpoint)
+		})
+	}
+
+	/// The onion endpoint standing behind the OnionCat address `addr`.
+	pub fn get_onion_endpoint(&self, addr: PeerAddr) -> Result<EndpointData, Error> {
+		option_to_not_found(self.db.get_ser(&onion_index_key(addr)), || {
+			format!("Onion endpoint at address: {}", addr)
+		})
+	}
+
+	/// Up to `count` random endpoints with the capabilities, seen since
+	/// `min_last_seen`.
+	pub fn find_endpoints(
+		&self,
+		cap: Capabilities,
+		min_last_seen: i64,
+		count: usize,
+	) -> Result<Vec<EndpointData>, Error> {
+		let mut endpoints = self
+			.all_endpoints()?
+			.into_iter()
+			.filter(|e| e.capabilities.contains(cap) && e.last_seen >= min_last_seen)
+			.collect::<Vec<_>>();
+		endpoints[..].shuffle(&mut rng());
+		endpoints.truncate(count);
+		Ok(endpoints)
+	}
+
+	/// List all known endpoints
+	pub fn all_endpoints(&self) -> Result<Vec<EndpointData>, Error> {
+		let key = to_key(ENDPOINT_PREFIX, &mut "".to_string().into_bytes());
+		Ok(self
+			.db
+			.iter::<EndpointData>(&key)?
+			.map(|(_, v)| v)
+			.collect::<Vec<_>>())
+	}
+
+	/// Deletes endpoints that satisfy some condition `predicate`
+	pub fn delete_endpoints<F>(&self, predicate: F) -> Result<(), Error>
+	where
+		F: Fn(&EndpointData) -> bool,
+	{
+		let to_remove: Vec<_> = self
+			.all_endpoints()?
+			.into_iter()
+			.filter(|e| predicate(e))
+			.collect();
+		if !to_remove.is_empty() {
+			let batch = self.db.batch()?;
+			for e in to_remove {
+				batch.delete(&endpoint_key(&e.endpoint))?;
+				let index_key = onion_index_key(e.endpoint.peer_addr());
+				if e.endpoint.is_onion() && batch.exists(&index_key)? {
+					batch.delete(&index_key)?;
+				}
+			}
+			batch.commit()?;
+		}
+		Ok(())
+	}
+
 	/// Deletes peers from the storage that satisfy some condition `predicate`
 	pub fn delete_peers<F>(&self, predicate: F) -> Result<(), Error>
 	where
@@ -276,6 +414,14 @@ fn peer_key(peer_addr: PeerAddr) -> Vec<u8> {
 	to_key(PEER_PREFIX, &mut peer_addr.as_key().into_bytes())
 }
 
+fn endpoint_key(endpoint: &PeerEndpoint) -> Vec<u8> {
+	to_key(ENDPOINT_PREFIX, &mut endpoint.as_key().into_bytes())
+}
+
+fn onion_index_key(addr: PeerAddr) -> Vec<u8> {
+	to_key(ONION_INDEX_PREFIX, &mut addr.as_key().into_bytes())
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -311,4 +457,82 @@ mod tests {
 		assert_eq!(updated_peer.flags, State::Banned);
 		assert!(updated_peer.last_banned > 0);
 	}
+
+	#[test]
+	fn test_save_endpoint() {
+		let peer_store = PeerStore::new("/tmp/peer_store_endpoint_test").unwrap();
+		peer_store.delete_endpoints(|_| true).unwrap();
+
+		let onion = PeerEndpoint::from_onion(
+			"http://2a6at2obto3uvkpkitqp4wxcg6u36qf534eucbskqciturczzc5suyid.onion:3413",
+		)
+		.unwrap();
+		let ip = PeerEndpoint::Ip("1.2.3.4:3414".parse().map(PeerAddr).unwrap());
+		let data = EndpointData {
+			endpoint: onion.clone(),
+			capabilities: Capabilities::FULL_NODE,
+			last_seen: 100,
+		};
+		peer_store.save_endpoint(&data).unwrap();
+		peer_store
+			.save_endpoint(&EndpointData {
+				endpoint: ip.clone(),
+				capabilities: Capabilities::UNKNOWN,
+				last_seen: 200,
+			})
+			.unwrap();
+
+		// An older sighting does not overwrite a newer one.
+		peer_store
+			.save_endpoint(&EndpointData {
+				last_seen: 50,
+				..data.clone()
+			})
+			.unwrap();
+		assert_eq!(peer_store.get_endpoint(&onion).unwrap(), data);
+		assert_eq!(
+			peer_store.get_onion_endpoint(onion.peer_addr()).unwrap(),
+			data
+		);
+
+		assert_eq!(peer_store.all_endpoints().unwrap().len(), 2);
+		let found = peer_store
+			.find_endpoints(Capabilities::FULL_NODE, 0, 10)
+			.unwrap();
+		assert_eq!(found, vec![data.clone()]);
+		assert!(peer_store
+			.find_endpoints(Capabilities::UNKNOWN, 150, 10)
+			.unwrap()
+			.iter()
+			.all(|e| e.endpoint == ip));
+
+		peer_store.delete_endpoints(|e| e.endpoint.is_onion()).unwrap();
+		assert!(peer_store.get_endpoint(&onion).is_err());
+		assert!(peer_store.get_onion_endpoint(onion.peer_addr()).is_err());
+	}
+
+	#[test]
+	fn test_onion_endpoint_any_port() {
+		let peer_store = PeerStore::new("/tmp/peer_store_onion_index_test").unwrap();
+		peer_store.delete_endpoints(|_| true).unwrap();
+
+		// Gossiped with the api port, connected to on the p2p port.
+		let onion = PeerEndpoint::from_onion(
+			"http://2a6at2obto3uvkpkitqp4wxcg6u36qf534eucbskqciturczzc5suyid.onion:3413",
+		)
+		.unwrap();
+		let data = EndpointData {
+			endpoint: onion.clone(),
+			capabilities: Capabilities::FULL_NODE,
+			last_seen: 100,
+		};
+		peer_store.save_endpoint(&data).unwrap();
+
+		let pubkey = match onion {
+			PeerEndpoint::OnionV3 { pubkey, .. } => pubkey,
+			PeerEndpoint::Ip(_) => unreachable!(),
+		};
+		let p2p_addr = PeerAddr::from_onion(&pubkey, 3414);
+		assert_eq!(peer_store.get_onion_endpoint(p2p_addr).unwrap(), data);
+	}
 }
@@ -0,0 +1,356 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tor control protocol client, enough to publish an ephemeral onion service
+//! on an already running tor daemon instead of launching our own tor process
+//! with key files on disk.
+//! See https://spec.torproject.org/control-spec
+
+use crate::util::to_hex;
+
+use data_encoding::BASE64;
+use ed25519_dalek::SigningKey as DalekSecretKey;
+use sha2::{Digest, Sha512};
+
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+/// Status code of a successful reply.
+const REPLY_OK: u16 = 250;
+
+/// Status code of asynchronous event notifications.
+const REPLY_EVENT: u16 = 650;
+
+#[derive(Debug)]
+pub enum Error {
+	IO(io::Error),
+	/// Tor replied with an error status code
+	Tor(u16, String),
+	/// The reply does not follow the control protocol
+	Protocol(String),
+	/// None of the authentication methods offered by tor is usable
+	Authentication(String),
+	Timeout,
+}
+
+impl From<io::Error> for Error {
+	fn from(e: io::Error) -> Error {
+		Error::IO(e)
+	}
+}
+
+/// A reply line, the last one of a reply has a space separator after the
+/// status code, the others a dash (mid reply line) or a plus (data follows).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyLine {
+	pub status: u16,
+	pub text: String,
+	/// Data lines following a `+` line, without the terminating "."
+	pub data: Vec<String>,
+}
+
+/// Bootstrap progress of tor, from `GETINFO status/bootstrap-phase` or a
+/// `STATUS_CLIENT` event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BootstrapStatus {
+	pub progress: u8,
+	pub tag: String,
+	pub summary: String,
+}
+
+impl BootstrapStatus {
+	/// Parses the `BOOTSTRAP PROGRESS=.. TAG=.. SUMMARY=".."` part of a status
+	/// line, None if the line is about something else.
+	pub fn parse(line: &str) -> Option<BootstrapStatus> {
+		let start = line.find("BOOTSTRAP ")?;
+		let args = parse_key_values(&line[start + "BOOTSTRAP ".len()..]);
+		let get = |key: &str| {
+			args.iter()
+				.find(|(k, _)| k == key)
+				.map(|(_, v)| v.clone())
+		};
+		Some(BootstrapStatus {
+			progress: get("PROGRESS")?.parse().ok()?,
+			tag: get("TAG").unwrap_or_default(),
+			summary: get("SUMMARY").unwrap_or_default(),
+		})
+	}
+
+	pub fn is_done(&self) -> bool {
+		self.progress >= 100
+	}
+}
+
+/// Connection to the control port of a tor daemon.
+pub struct TorControl {
+	reader: BufReader<TcpStream>,
+	writer: TcpStream,
+	/// Events received while waiting on a command reply
+	events: Vec<ReplyLine>,
+}
+
+impl TorControl {
+	/// Connects to the control port at `addr`.
+	pub fn connect(addr: &SocketAddr, timeout: Duration) -> Result<TorControl, Error> {
+		let stream = TcpStream::connect_timeout(addr, timeout)?;
+		stream.set_read_timeout(Some(timeout))?;
+		stream.set_write_timeout(Some(timeout))?;
+		Ok(TorControl {
+			reader: BufReader::new(stream.try_clone()?),
+			writer: stream,
+			events: vec![],
+		})
+	}
+
+	/// Authenticates with the first method tor offers that we can use: none,
+	/// the cookie file or the password.
+	pub fn authenticate(&mut self, password: Option<&str>) -> Result<(), Error> {
+		let reply = self.command("PROTOCOLINFO 1")?;
+		let auth = reply
+			.iter()
+			.find(|line| line.text.starts_with("AUTH "))
+			.ok_or_else(|| Error::Protocol("no AUTH line in PROTOCOLINFO".to_owned()))?;
+		let args = parse_key_values(&auth.text["AUTH ".len()..]);
+		let arg = |key: &str| {
+			args.iter()
+				.find(|(k, _)| k == key)
+				.map(|(_, v)| v.clone())
+		};
+		let methods = arg("METHODS").unwrap_or_default();
+		let methods: Vec<&str> = methods.split(',').collect();
+
+		let credentials = if methods.contains(&"NULL") {
+			String::new()
+		} else if methods.contains(&"COOKIE") && arg("COOKIEFILE").is_some() {
+			let cookie = fs::read(arg("COOKIEFILE").unwrap())?;
+			format!(" {}", to_hex(cookie))
+		} else if methods.contains(&"HASHEDPASSWORD") && password.is_some() {
+			format!(" {}", quote(password.unwrap()))
+		} else {
+			return Err(Error::Authentication(format!(
+				"no usable method in {:?}",
+				methods
+			)));
+		};
+		self.command(&format!("AUTHENTICATE{}", credentials))
+			.map_err(|e| match e {
+				Error::Tor(_, msg) => Error::Authentication(msg),
+				e => e,
+			})?;
+		Ok(())
+	}
+
+	/// Publishes an onion service with the given ed25519 key, forwarding each
+	/// virtual port to its target (e.g. "127.0.0.1:3413"). Returns the
+	/// service id, the onion address without ".onion".
+	/// The service is removed when this connection closes.
+	pub fn add_onion(
+		&mut self,
+		sec_key: &DalekSecretKey,
+		ports: &[(u16, &str)],
+	) -> Result<String, Error> {
+		let ports: Vec<String> = ports
+			.iter()
+			.map(|(virtual_port, target)| format!(" Port={},{}", virtual_port, target))
+			.collect();
+		let reply = self.command(&format!(
+			"ADD_ONION ED25519-V3:{} Flags=DiscardPK{}",
+			BASE64.encode(&expanded_secret_key(sec_key)),
+			ports.concat()
+		))?;
+		reply
+			.iter()
+			.find_map(|line| line.text.strip_prefix("ServiceID="))
+			.map(|id| id.to_owned())
+			.ok_or_else(|| Error::Protocol("no ServiceID in ADD_ONION reply".to_owned()))
+	}
+
+	/// Removes an onion service published on this connection.
+	pub fn del_onion(&mut self, service_id: &str) -> Result<(), Error> {
+		self.command(&format!("DEL_ONION {}", service_id))?;
+		Ok(())
+	}
+
+	/// Current bootstrap status of tor.
+	pub fn bootstrap_status(&mut self) -> Result<BootstrapStatus, Error> {
+		let reply = self.command("GETINFO status/bootstrap-phase")?;
+		reply
+			.iter()
+			.find_map(|line| BootstrapStatus::parse(&line.text))
+			.ok_or_else(|| Error::Protocol("no bootstrap phase in GETINFO reply".to_owned()))
+	}
+
+	/// Waits for tor to finish bootstrapping, following the `STATUS_CLIENT`
+	/// events. Calls `progress` on every bootstrap status received.
+	pub fn wait_for_bootstrap<F>(&mut self, timeout: Duration, progress: F) -> Result<(), Error>
+	where
+		F: Fn(&BootstrapStatus),
+	{
+		let deadline = Instant::now() + timeout;
+		self.command("SETEVENTS STATUS_CLIENT")?;
+		let status = self.bootstrap_status()?;
+		progress(&status);
+		let mut done = status.is_done();
+		while !done {
+			if Instant::now() > deadline {
+				return Err(Error::Timeout);
+			}
+			let event = match self.next_event() {
+				Ok(event) => event,
+				Err(Error::IO(e))
+					if e.kind() == io::ErrorKind::WouldBlock
+						|| e.kind() == io::ErrorKind::TimedOut =>
+				{
+					continue;
+				}
+				Err(e) => return Err(e),
+			};
+			if let Some(status) = BootstrapStatus::parse(&event.text) {
+				progress(&status);
+				done = status.is_done();
+			}
+		}
+		self.command("SETEVENTS")?;
+		Ok(())
+	}
+
+	/// Sends a command and reads its reply, failing on an error status.
+	pub fn command(&mut self, command: &str) -> Result<Vec<ReplyLine>, Error> {
+		self.writer.write_all(format!("{}\r\n", command).as_bytes())?;
+		self.writer.flush()?;
+		loop {
+			let reply = self.read_reply()?;
+			if reply[0].status == REPLY_EVENT {
+				self.events.extend(reply);
+				continue;
+			}
+			let last = reply.last().unwrap();
+			if last.status != REPLY_OK {
+				return Err(Error::Tor(last.status, last.text.clone()));
+			}
+			return Ok(reply);
+		}
+	}
+
+	/// Next asynchronous event, blocking until one arrives or the read times out.
+	pub fn next_event(&mut self) -> Result<ReplyLine, Error> {
+		if !self.events.is_empty() {
+			return Ok(self.events.remove(0));
+		}
+		loop {
+			let mut reply = self.read_reply()?;
+			if reply[0].status == REPLY_EVENT {
+				let event = reply.remove(0);
+				self.events.extend(reply);
+				return Ok(event);
+			}
+		}
+	}
+
+	fn read_reply(&mut self) -> Result<Vec<ReplyLine>, Error> {
+		let mut reply = vec![];
+		loop {
+			let line = self.read_line()?;
+			if line.len() < 4 || !line.is_char_boundary(3) {
+				return Err(Error::Protocol(format!("invalid reply line {}", line)));
+			}
+			let status = line[..3]
+				.parse()
+				.map_err(|_| Error::Protocol(format!("invalid status in {}", line)))?;
+			let separator = line.as_bytes()[3];
+			let mut reply_line = ReplyLine {
+				status,
+				text: line[4..].to_owned(),
+				data: vec![],
+			};
+			match separator {
+				b' ' => {
+					reply.push(reply_line);
+					return Ok(reply);
+				}
+				b'-' => reply.push(reply_line),
+				b'+' => {
+					loop {
+						let data = self.read_line()?;
+						if data == "." {
+							break;
+						}
+						// leading dots are escaped by doubling them
+						let data = data.strip_prefix('.').unwrap_or(&data).to_owned();
+						reply_line.data.push(data);
+					}
+					reply.push(reply_line);
+				}
+				_ => return Err(Error::Protocol(format!("invalid reply line {}", line))),
+			}
+		}
+	}
+
+	fn read_line(&mut self) -> Result<String, Error> {
+		let mut line = String::new();
+		if self.reader.read_line(&mut line)? == 0 {
+			return Err(Error::Protocol("connection closed".to_owned()));
+		}
+		Ok(line.trim_end_matches(&['\r', '\n'][..]).to_owned())
+	}
+}
+
+/// The expanded form of an ed25519 secret key tor expects in ADD_ONION, the
+/// clamped scalar followed by the nonce prefix.
+pub fn expanded_secret_key(sec_key: &DalekSecretKey) -> [u8; 64] {
+	let hash = Sha512::digest(sec_key.to_bytes());
+	let mut expanded = [0u8; 64];
+	expanded.copy_from_slice(&hash);
+	expanded[0] &= 248;
+	expanded[31] &= 127;
+	expanded[31] |= 64;
+	expanded
+}
+
+fn quote(s: &str) -> String {
+	format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Splits `KEY=value KEY="quoted value" FLAG` arguments.
+fn parse_key_values(s: &str) -> Vec<(String, String)> {
+	let mut result = vec![];
+	let mut chars = s.trim().chars().peekable();
+	while chars.peek().is_some() {
+		let mut key = String::new();
+		while let Some(c) = chars.next_if(|c| *c != '=' && *c != ' ') {
+			key.push(c);
+		}
+		let mut value = String::new();
+		if chars.next_if_eq(&'=').is_some() {
+			if chars.next_if_eq(&'"').is_some() {
+				while let Some(c) = chars.next() {
+					match c {
+						'\\' => value.extend(chars.next()),
+						'"' => break,
+						c => value.push(c),
+					}
+				}
+			} else {
+				while let Some(c) = chars.next_if(|c| *c != ' ') {
+					value.push(c);
+				}
+			}
+		}
+		result.push((key, value));
+		while chars.next_if_eq(&' ').is_some() {}
+	}
+	result
+}
@@ -14,4 +14,6 @@
 
 pub mod address;
 pub mod config;
+pub mod control;
 pub mod process;
+pub mod socks;
@@ -287,6 +287,17 @@ impl TorProcess {
 		Ok(stdout)
 	}
 
+	/// Whether the tor child process was launched and has not exited yet.
+	pub fn is_running(&mut self) -> bool {
+		match self.process {
+			Some(ref mut process) => match process.try_wait() {
+				Ok(None) => true,
+				_ => false,
+			},
+			None => false,
+		}
+	}
+
 	pub fn kill(&mut self) -> Result<(), Error> {
 		if let Some(ref mut process) = self.process {
 			Ok(process
@@ -0,0 +1,84 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal SOCKS5 client (RFC 1928), enough to open a TCP connection to an
+//! onion service through the SOCKS proxy of the Tor process.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const SOCKS_VERSION: u8 = 5;
+const NO_AUTHENTICATION: u8 = 0;
+const CMD_CONNECT: u8 = 1;
+const ATYP_IPV4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+const ATYP_IPV6: u8 = 4;
+
+fn socks_error(msg: String) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, format!("socks5: {}", msg))
+}
+
+/// Connects to `host:port` through the SOCKS5 proxy. The host name is
+/// resolved by the proxy, as onion addresses have to be.
+pub fn connect(
+	proxy: &SocketAddr,
+	host: &str,
+	port: u16,
+	timeout: Duration,
+) -> io::Result<TcpStream> {
+	if host.is_empty() || host.len() > 255 {
+		return Err(socks_error(format!("invalid host name {}", host)));
+	}
+	let mut stream = TcpStream::connect_timeout(proxy, timeout)?;
+	stream.set_read_timeout(Some(timeout))?;
+	stream.set_write_timeout(Some(timeout))?;
+
+	stream.write_all(&[SOCKS_VERSION, 1, NO_AUTHENTICATION])?;
+	let mut reply = [0u8; 2];
+	stream.read_exact(&mut reply)?;
+	if reply != [SOCKS_VERSION, NO_AUTHENTICATION] {
+		return Err(socks_error("proxy requires authentication".to_owned()));
+	}
+
+	let mut request = vec![SOCKS_VERSION, CMD_CONNECT, 0, ATYP_DOMAIN, host.len() as u8];
+	request.extend_from_slice(host.as_bytes());
+	request.extend_from_slice(&port.to_be_bytes());
+	stream.write_all(&request)?;
+
+	let mut reply = [0u8; 4];
+	stream.read_exact(&mut reply)?;
+	if reply[0] != SOCKS_VERSION {
+		return Err(socks_error(format!("unexpected version {}", reply[0])));
+	}
+	if reply[1] != 0 {
+		return Err(socks_error(format!("connect failed with code {}", reply[1])));
+	}
+
+	// Skip the address the proxy bound for the connection.
+	let addr_len = match reply[3] {
+		ATYP_IPV4 => 4,
+		ATYP_IPV6 => 16,
+		ATYP_DOMAIN => {
+			let mut len = [0u8; 1];
+			stream.read_exact(&mut len)?;
+			len[0] as usize
+		}
+		atyp => return Err(socks_error(format!("unknown address type {}", atyp))),
+	};
+	let mut bound = vec![0u8; addr_len + 2];
+	stream.read_exact(&mut bound)?;
+
+	Ok(stream)
+}
@@ -29,7 +29,10 @@ use crate::core::core::hash::Hash;
 use crate::core::global;
 use crate::core::pow::Difficulty;
 use crate::core::ser::{self, ProtocolVersion, Readable, Reader, Writeable, Writer};
+use crate::store::EndpointData;
+use crate::tor;
 use bitflags::bitflags;
+use ed25519_dalek::VerifyingKey as DalekPublicKey;
 use epic_chain::types::SyncStatus;
 use epic_store;
 use serde::{Deserialize, Serialize};
@@ -91,6 +94,8 @@ pub enum Error {
 	NotOnion(String),
 
 	AddressDecoding(String),
+	/// The onion identity presented in a handshake does not check out
+	OnionIdentity(String),
 }
 
 impl From<ser::Error> for Error {
@@ -199,8 +204,34 @@ impl PeerAddr {
 	/// Convenient way of constructing a new peer_addr from an ip_addr
 	/// defaults to port 3414 on mainnet and 13414 on floonet.
 	pub fn from_ip(addr: IpAddr) -> PeerAddr {
-		let port = if global::is_floonet() { 13414 } else { 3414 };
-		PeerAddr(SocketAddr::new(addr, port))
+		PeerAddr(SocketAddr::new(addr, PeerAddr::default_port()))
+	}
+
+	/// Default p2p port, 3414 on mainnet and 13414 on floonet. Onion services
+	/// always serve p2p on it.
+	pub fn default_port() -> u16 {
+		if global::is_floonet() {
+			13414
+		} else {
+			3414
+		}
+	}
+
+	/// Address standing for an onion service in the peers map, store and ban
+	/// list, an IPv6 in the OnionCat range built from the service public key.
+	pub fn from_onion(pubkey: &[u8; 32], port: u16) -> PeerAddr {
+		let mut octets = [0u8; 16];
+		octets[..6].copy_from_slice(&ONION_ADDR_PREFIX);
+		octets[6..].copy_from_slice(&pubkey[..10]);
+		PeerAddr(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port))
+	}
+
+	/// Whether this address stands for an onion service, see from_onion.
+	pub fn is_onion(&self) -> bool {
+		match self.0.ip() {
+			IpAddr::V6(ip) => ip.octets()[..6] == ONION_ADDR_PREFIX,
+			IpAddr::V4(_) => false,
+		}
 	}
 
 	/// If the ip is loopback then our key is "ip:port" (mainly for local usernet testing).
@@ -214,6 +245,151 @@ impl PeerAddr {
 	}
 }
 
+/// Network ids of the endpoints in addrv2 gossip, values follow BIP155.
+const NETWORK_IPV4: u8 = 1;
+const NETWORK_IPV6: u8 = 2;
+const NETWORK_TORV3: u8 = 4;
+
+/// Longest endpoint address we accept, from any network.
+pub const MAX_ENDPOINT_LEN: u8 = 64;
+
+/// OnionCat prefix fd87:d87e:eb43::/48 of the addresses standing for onion
+/// services.
+const ONION_ADDR_PREFIX: [u8; 6] = [0xfd, 0x87, 0xd8, 0x7e, 0xeb, 0x43];
+
+/// Where a peer can be reached, an IP address or an onion v3 service behind
+/// tor, identified by its ed25519 public key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PeerEndpoint {
+	Ip(PeerAddr),
+	OnionV3 { pubkey: [u8; 32], port: u16 },
+}
+
+impl PeerEndpoint {
+	/// Parses an onion v3 address as advertised by peers, with or without
+	/// the http:// scheme and the port (80 by default).
+	pub fn from_onion(addr: &str) -> Result<PeerEndpoint, Error> {
+		let addr = addr.trim_start_matches("http://").trim_end_matches('/');
+		let (host, port) = match addr.rsplit_once(':') {
+			Some((host, port)) => (
+				host,
+				port.parse()
+					.map_err(|_| Error::NotOnion(format!("invalid port in {}", addr)))?,
+			),
+			None => (addr, 80),
+		};
+		let pubkey = tor::address::pubkey_from_onion_v3(host)?;
+		Ok(PeerEndpoint::OnionV3 {
+			pubkey: pubkey.to_bytes(),
+			port,
+		})
+	}
+
+	pub fn is_onion(&self) -> bool {
+		match self {
+			PeerEndpoint::OnionV3 { .. } => true,
+			PeerEndpoint::Ip(_) => false,
+		}
+	}
+
+	/// The address we key this endpoint by, see PeerAddr::from_onion.
+	pub fn peer_addr(&self) -> PeerAddr {
+		match self {
+			PeerEndpoint::Ip(addr) => *addr,
+			PeerEndpoint::OnionV3 { pubkey, port } => PeerAddr::from_onion(pubkey, *port),
+		}
+	}
+
+	/// The "<onion>.onion" host of an onion endpoint.
+	pub fn onion_host(&self) -> Option<String> {
+		match self {
+			PeerEndpoint::Ip(_) => None,
+			PeerEndpoint::OnionV3 { pubkey, .. } => {
+				let onion = DalekPublicKey::from_bytes(pubkey)
+					.ok()
+					.and_then(|key| tor::address::onion_v3_from_pubkey(&key).ok())
+					.unwrap_or_else(|| "invalid".to_owned());
+				Some(format!("{}.onion", onion))
+			}
+		}
+	}
+
+	/// Same as PeerAddr::as_key for IP endpoints, "<onion>.onion:<port>" otherwise.
+	pub fn as_key(&self) -> String {
+		match self {
+			PeerEndpoint::Ip(addr) => addr.as_key(),
+			PeerEndpoint::OnionV3 { .. } => self.to_string(),
+		}
+	}
+
+	/// Reads an endpoint, None if it belongs to a network we don't know
+	/// (yet), so newer networks can be gossiped through older nodes.
+	pub fn read_opt(reader: &mut dyn Reader) -> Result<Option<PeerEndpoint>, ser::Error> {
+		let network = reader.read_u8()?;
+		let len = reader.read_u8()?;
+		if len > MAX_ENDPOINT_LEN {
+			return Err(ser::Error::TooLargeReadErr);
+		}
+		let bytes = reader.read_fixed_bytes(len as usize)?;
+		let port = reader.read_u16()?;
+		let endpoint = match (network, len) {
+			(NETWORK_IPV4, 4) => {
+				let ip = Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]);
+				PeerEndpoint::Ip(PeerAddr(SocketAddr::new(IpAddr::V4(ip), port)))
+			}
+			(NETWORK_IPV6, 16) => {
+				let mut octets = [0u8; 16];
+				octets.copy_from_slice(&bytes);
+				let ip = Ipv6Addr::from(octets);
+				PeerEndpoint::Ip(PeerAddr(SocketAddr::new(IpAddr::V6(ip), port)))
+			}
+			(NETWORK_TORV3, 32) => {
+				let mut pubkey = [0u8; 32];
+				pubkey.copy_from_slice(&bytes);
+				PeerEndpoint::OnionV3 { pubkey, port }
+			}
+			(NETWORK_IPV4, _) | (NETWORK_IPV6, _) | (NETWORK_TORV3, _) => {
+				return Err(ser::Error::CorruptedData)
+			}
+			_ => return Ok(None),
+		};
+		Ok(Some(endpoint))
+	}
+}
+
+impl Writeable for PeerEndpoint {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		let (network, bytes, port) = match self {
+			PeerEndpoint::Ip(addr) => match addr.0 {
+				SocketAddr::V4(a) => (NETWORK_IPV4, a.ip().octets().to_vec(), a.port()),
+				SocketAddr::V6(a) => (NETWORK_IPV6, a.ip().octets().to_vec(), a.port()),
+			},
+			PeerEndpoint::OnionV3 { pubkey, port } => (NETWORK_TORV3, pubkey.to_vec(), *port),
+		};
+		writer.write_u8(network)?;
+		writer.write_u8(bytes.len() as u8)?;
+		writer.write_fixed_bytes(&bytes)?;
+		writer.write_u16(port)
+	}
+}
+
+impl Readable for PeerEndpoint {
+	fn read(reader: &mut dyn Reader) -> Result<PeerEndpoint, ser::Error> {
+		PeerEndpoint::read_opt(reader)?.ok_or(ser::Error::CorruptedData)
+	}
+}
+
+impl std::fmt::Display for PeerEndpoint {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			PeerEndpoint::Ip(addr) => write!(f, "{}", addr),
+			PeerEndpoint::OnionV3 { port, .. } => {
+				write!(f, "{}:{}", self.onion_host().unwrap_or_default(), port)
+			}
+		}
+	}
+}
+
 /// Configuration for the peer-to-peer server.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct P2PConfig {
@@ -251,6 +427,13 @@ pub struct P2PConfig {
 	pub dandelion_peer: Option<PeerAddr>,
 
 	pub my_onion_addr: Option<String>,
+
+	/// Only connect to onion peers through the tor socks proxy and only accept
+	/// peers through our own onion service, never exposing our IP.
+	pub onion_only: Option<bool>,
+
+	/// Onion addresses ("<onion>.onion:<port>") to seed from in onion only mode
+	pub onion_seeds: Option<Vec<String>>,
 }
 
 /// Default address for peer-to-peer connections.
@@ -273,6 +456,8 @@ impl Default for P2PConfig {
 			peer_listener_buffer_count: None,
 			dandelion_peer: None,
 			my_onion_addr: None,
+			onion_only: None,
+			onion_seeds: None,
 		}
 	}
 }
@@ -319,6 +504,11 @@ impl P2PConfig {
 			None => PEER_LISTENER_BUFFER_COUNT,
 		}
 	}
+
+	/// return whether p2p connections only go through tor
+	pub fn onion_only(&self) -> bool {
+		self.onion_only.unwrap_or(false)
+	}
 }
 
 /// Type of seeding the server will use to find other peers on the network.
@@ -362,6 +552,12 @@ bitflags! {
 		const HEADER_FASTSYNC = 0b00010000; // 31
 		/// Does support stem transactions via tor onion routing.
 		const ONIONSTEM = 0b00100000;
+		/// Does gossip peer endpoints, including onion services, with
+		/// GetPeerAddrsV2/PeerAddrsV2.
+		const ADDR_V2 = 0b01000000;
+		/// Can serve compact block filters along with their filter headers
+		/// back to genesis.
+		const BLOCK_FILTERS = 0b10000000;
 		/// All nodes right now are "full nodes".
 		/// Some nodes internally may maintain longer block histories (archival_mode)
 		/// but we do not advertise this to other nodes.
@@ -615,6 +811,18 @@ pub trait ChainAdapter: Sync + Send {
 	/// Gets a full block by its hash.
 	fn get_block(&self, h: Hash) -> Option<core::Block>;
 
+	/// Gets the compact filter of a block by its hash, along with its filter
+	/// header.
+	fn get_block_filter(&self, h: Hash) -> Option<(core::BlockFilter, Hash)>;
+
+	/// A compact block filter has been received from one of our peers.
+	fn block_filter_received(
+		&self,
+		filter: core::BlockFilter,
+		filter_header: Hash,
+		peer_info: &PeerInfo,
+	) -> Result<bool, chain::Error>;
+
 	fn kernel_data_read(&self) -> Result<File, chain::Error>;
 
 	fn kernel_data_write(&self, reader: &mut dyn Read) -> Result<bool, chain::Error>;
@@ -670,6 +878,13 @@ pub trait NetAdapter: ChainAdapter {
 	/// A list of peers has been received from one of our peers.
 	fn peer_addrs_received(&self, _: Vec<PeerAddr>);
 
+	/// Find fresh endpoints, IP and onion, with the provided capability for
+	/// addrv2 gossip.
+	fn find_peer_endpoints(&self, capab: Capabilities) -> Vec<EndpointData>;
+
+	/// A list of endpoints has been received from one of our peers.
+	fn peer_endpoints_received(&self, _: Vec<EndpointData>);
+
 	/// Heard total_difficulty from a connected peer (via ping/pong).
 	fn peer_difficulty(&self, _: PeerAddr, _: Difficulty, _: u64, _: i64);
 
@@ -25,7 +25,8 @@ use std::{thread, time};
 use crate::core::core::hash::Hash;
 use crate::core::pow::Difficulty;
 use crate::p2p::types::PeerAddr;
-use crate::p2p::Peer;
+use crate::p2p::{Peer, PeerEndpoint};
+use crate::util::secp::key::SecretKey;
 
 use chrono::prelude::Utc;
 
@@ -78,6 +79,7 @@ fn peer_handshake() {
 		p2p::Capabilities::UNKNOWN,
 		Difficulty::min(),
 		my_addr,
+		PeerAddr(addr),
 		&p2p::handshake::Handshake::new(Hash::from_vec(&vec![]), p2p_config.clone()),
 		net_adapter,
 	)
@@ -95,3 +97,85 @@ fn peer_handshake() {
 	assert_eq!(server_peer.info.total_difficulty(), Difficulty::min());
 	assert!(server.peers.peer_count() > 0);
 }
+
+// Starts an onion only server and connects to it as an onion peer would
+// through tor, from the loopback interface: it keys the peer by its onion
+// service and refuses peers not proving one.
+#[test]
+fn onion_peer_handshake() {
+	util::init_test_logger();
+
+	let p2p_config = p2p::P2PConfig {
+		host: "127.0.0.1".parse().unwrap(),
+		port: open_port(),
+		onion_only: Some(true),
+		..p2p::P2PConfig::default()
+	};
+	let genesis = Hash::from_vec(&vec![]);
+	let server = Arc::new(
+		p2p::Server::new(
+			".epic_onion",
+			p2p::Capabilities::UNKNOWN,
+			p2p_config.clone(),
+			Arc::new(p2p::DummyAdapter {}),
+			genesis,
+			Arc::new(StopState::new()),
+			None,
+		)
+		.unwrap(),
+	);
+	let server_key = onion_key(1);
+	server
+		.init_onion("127.0.0.1:9050".parse().unwrap(), &server_key)
+		.unwrap();
+	let server_addr = onion_endpoint(&server_key).peer_addr();
+
+	let p2p_inner = server.clone();
+	let _ = thread::spawn(move || p2p_inner.listen());
+	thread::sleep(time::Duration::from_secs(1));
+
+	let addr = SocketAddr::new(p2p_config.host, p2p_config.port);
+	let connect = |hs: &p2p::handshake::Handshake, self_addr: PeerAddr| {
+		let socket = TcpStream::connect_timeout(&addr, time::Duration::from_secs(10)).unwrap();
+		Peer::connect(
+			socket,
+			p2p::Capabilities::UNKNOWN,
+			Difficulty::min(),
+			self_addr,
+			server_addr,
+			hs,
+			Arc::new(p2p::DummyAdapter {}),
+		)
+	};
+
+	// no onion service to prove
+	let hs = p2p::handshake::Handshake::new(genesis, p2p_config.clone());
+	assert!(connect(&hs, PeerAddr("127.0.0.1:5000".parse().unwrap())).is_err());
+
+	let client_key = onion_key(2);
+	let (d_sec_key, _) = p2p::tor::address::ed25519_keypair(&client_key).unwrap();
+	let client_endpoint = onion_endpoint(&client_key);
+	let client_addr = client_endpoint.peer_addr();
+	hs.set_onion_identity(d_sec_key, client_endpoint);
+	let peer = connect(&hs, client_addr).unwrap();
+	assert_eq!(peer.info.addr, server_addr);
+
+	thread::sleep(time::Duration::from_secs(1));
+	let server_peer = server.peers.get_connected_peer(client_addr).unwrap();
+	assert!(server_peer.info.addr.is_onion());
+	assert_eq!(server.peers.peer_count(), 1);
+}
+
+fn onion_key(seed: u8) -> SecretKey {
+	let secp = util::static_secp_instance();
+	let secp = secp.lock();
+	SecretKey::from_slice(&secp, &[seed; 32]).unwrap()
+}
+
+fn onion_endpoint(sec_key: &SecretKey) -> PeerEndpoint {
+	let (_, d_pub_key) = p2p::tor::address::ed25519_keypair(sec_key).unwrap();
+	PeerEndpoint::OnionV3 {
+		pubkey: d_pub_key.to_bytes(),
+		port: PeerAddr::default_port(),
+	}
+}
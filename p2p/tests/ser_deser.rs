@@ -12,9 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use epic_core as core;
 use epic_p2p as p2p;
 
+use self::core::core::hash::Hash;
+use self::core::pow::Difficulty;
+use self::core::ser::{self, ProtocolVersion};
+use ed25519_dalek::SigningKey;
 use num::FromPrimitive;
+use p2p::msg::{Hand, OnionProof, PeerAddrsV2};
+use p2p::{Capabilities, EndpointData, PeerAddr, PeerEndpoint};
 
 // Test that Healthy == 0.
 #[test]
@@ -75,3 +82,126 @@ fn test_capabilities() {
 			.contains(p2p::types::Capabilities::TX_KERNEL_HASH)
 	);
 }
+
+#[test]
+fn test_peer_addrs_v2() {
+	let onion = PeerEndpoint::from_onion(
+		"2a6at2obto3uvkpkitqp4wxcg6u36qf534eucbskqciturczzc5suyid.onion:3414",
+	)
+	.unwrap();
+	assert_eq!(
+		onion.to_string(),
+		"2a6at2obto3uvkpkitqp4wxcg6u36qf534eucbskqciturczzc5suyid.onion:3414"
+	);
+	assert!(PeerEndpoint::from_onion("notanonion.onion:3414").is_err());
+
+	let endpoints = vec![
+		EndpointData {
+			endpoint: onion,
+			capabilities: Capabilities::FULL_NODE | Capabilities::ADDR_V2,
+			last_seen: 1_600_000_000,
+		},
+		EndpointData {
+			endpoint: PeerEndpoint::Ip("1.2.3.4:3414".parse().map(PeerAddr).unwrap()),
+			capabilities: Capabilities::PEER_LIST,
+			last_seen: 1_500_000_000,
+		},
+		EndpointData {
+			endpoint: PeerEndpoint::Ip("[2001:db8::1]:3414".parse().map(PeerAddr).unwrap()),
+			capabilities: Capabilities::UNKNOWN,
+			last_seen: 0,
+		},
+	];
+	let mut vec = Vec::new();
+	ser::serialize(
+		&mut vec,
+		ProtocolVersion::local(),
+		&PeerAddrsV2 {
+			endpoints: endpoints.clone(),
+		},
+	)
+	.unwrap();
+	let msg: PeerAddrsV2 = ser::deserialize(&mut &vec[..], ProtocolVersion::local()).unwrap();
+	assert_eq!(msg.endpoints, endpoints);
+
+	// An endpoint of a network we don't know is skipped.
+	let mut vec = vec![0, 0, 0, 2];
+	vec.extend_from_slice(&[9, 3, 1, 2, 3, 0x0d, 0x56]);
+	vec.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+	ser::serialize(&mut vec, ProtocolVersion::local(), &endpoints[1]).unwrap();
+	let msg: PeerAddrsV2 = ser::deserialize(&mut &vec[..], ProtocolVersion::local()).unwrap();
+	assert_eq!(msg.endpoints, vec![endpoints[1].clone()]);
+}
+
+#[test]
+fn test_onion_peer_addr() {
+	let onion = PeerEndpoint::from_onion(
+		"2a6at2obto3uvkpkitqp4wxcg6u36qf534eucbskqciturczzc5suyid.onion:3414",
+	)
+	.unwrap();
+	let addr = onion.peer_addr();
+	assert!(addr.is_onion());
+	assert_eq!(addr.0.port(), 3414);
+	assert!(addr.0.to_string().starts_with("[fd87:d87e:eb43:"));
+	assert_eq!(
+		onion.onion_host().unwrap(),
+		"2a6at2obto3uvkpkitqp4wxcg6u36qf534eucbskqciturczzc5suyid.onion"
+	);
+	// the onion service is the identity, whatever the port
+	let api = PeerEndpoint::from_onion(
+		"http://2a6at2obto3uvkpkitqp4wxcg6u36qf534eucbskqciturczzc5suyid.onion:3413",
+	)
+	.unwrap();
+	assert_eq!(api.peer_addr(), addr);
+
+	let ip = PeerEndpoint::Ip("1.2.3.4:3414".parse().map(PeerAddr).unwrap());
+	assert!(!ip.peer_addr().is_onion());
+	assert!(ip.onion_host().is_none());
+	assert!(!PeerAddr("[fd00::1]:3414".parse().unwrap()).is_onion());
+}
+
+#[test]
+fn test_hand_onion_proof() {
+	let key = SigningKey::from_bytes(&[3; 32]);
+	let endpoint = PeerEndpoint::OnionV3 {
+		pubkey: key.verifying_key().to_bytes(),
+		port: 3414,
+	};
+	let receiver = PeerEndpoint::OnionV3 {
+		pubkey: [5; 32],
+		port: 3414,
+	}
+	.peer_addr();
+	let proof = OnionProof::sign(&key, endpoint.clone(), receiver, 42);
+	proof.verify(receiver, 42).unwrap();
+	assert!(proof.verify(receiver, 43).is_err());
+	assert!(proof.verify(endpoint.peer_addr(), 42).is_err());
+	let forged = OnionProof {
+		endpoint: PeerEndpoint::OnionV3 {
+			pubkey: SigningKey::from_bytes(&[4; 32]).verifying_key().to_bytes(),
+			port: 3414,
+		},
+		..proof.clone()
+	};
+	assert!(forged.verify(receiver, 42).is_err());
+
+	let hand = |sender_onion| Hand {
+		version: ProtocolVersion::local(),
+		capabilities: Capabilities::FULL_NODE,
+		nonce: 42,
+		genesis: Hash::default(),
+		total_difficulty: Difficulty::min(),
+		sender_addr: endpoint.peer_addr(),
+		receiver_addr: receiver,
+		user_agent: "test".to_owned(),
+		sender_onion,
+	};
+	for sender_onion in vec![Some(proof.clone()), None] {
+		let mut vec = Vec::new();
+		ser::serialize(&mut vec, ProtocolVersion::local(), &hand(sender_onion.clone())).unwrap();
+		let read: Hand = ser::deserialize(&mut &vec[..], ProtocolVersion::local()).unwrap();
+		assert_eq!(read.sender_onion, sender_onion);
+		assert_eq!(read.receiver_addr, receiver);
+		assert_eq!(read.user_agent, "test");
+	}
+}
@@ -0,0 +1,196 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tor control port client against a scripted mock control port.
+use epic_p2p as p2p;
+
+use ed25519_dalek::SigningKey;
+use p2p::tor::control::{expanded_secret_key, BootstrapStatus, Error, TorControl};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Each step waits for a command starting with the expected prefix, if any,
+/// then sends the reply.
+fn mock_control_port(script: Vec<(Option<String>, String)>) -> (SocketAddr, JoinHandle<()>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+	let handle = thread::spawn(move || {
+		let (mut stream, _) = listener.accept().unwrap();
+		let mut reader = BufReader::new(stream.try_clone().unwrap());
+		for (expected, reply) in script {
+			if let Some(expected) = expected {
+				let mut line = String::new();
+				reader.read_line(&mut line).unwrap();
+				assert!(
+					line.starts_with(&expected) && line.ends_with("\r\n"),
+					"expected {}, got {}",
+					expected,
+					line
+				);
+			}
+			stream.write_all(reply.as_bytes()).unwrap();
+		}
+	});
+	(addr, handle)
+}
+
+fn step(expected: &str, reply: &str) -> (Option<String>, String) {
+	(Some(expected.to_owned()), reply.to_owned())
+}
+
+fn connect(addr: &SocketAddr) -> TorControl {
+	TorControl::connect(addr, Duration::from_secs(5)).unwrap()
+}
+
+fn protocol_info(auth: &str) -> String {
+	format!(
+		"250-PROTOCOLINFO 1\r\n250-AUTH {}\r\n250-VERSION Tor=\"0.4.8.10\"\r\n250 OK\r\n",
+		auth
+	)
+}
+
+#[test]
+fn add_and_delete_onion() {
+	let key = SigningKey::from_bytes(&[7; 32]);
+	let expanded = data_encoding::BASE64.encode(&expanded_secret_key(&key));
+	let add_onion = format!(
+		"ADD_ONION ED25519-V3:{} Flags=DiscardPK Port=3413,127.0.0.1:3413 \
+		 Port=3414,127.0.0.1:13414\r",
+		expanded
+	);
+	let (addr, mock) = mock_control_port(vec![
+		step("PROTOCOLINFO 1", &protocol_info("METHODS=NULL")),
+		step("AUTHENTICATE\r", "250 OK\r\n"),
+		step(&add_onion, "250-ServiceID=exampleonionid\r\n250 OK\r\n"),
+		step("DEL_ONION exampleonionid\r", "250 OK\r\n"),
+	]);
+
+	let mut control = connect(&addr);
+	control.authenticate(None).unwrap();
+	let ports = [(3413, "127.0.0.1:3413"), (3414, "127.0.0.1:13414")];
+	let service_id = control.add_onion(&key, &ports).unwrap();
+	assert_eq!(service_id, "exampleonionid");
+	control.del_onion(&service_id).unwrap();
+	mock.join().unwrap();
+}
+
+#[test]
+fn cookie_and_password_authentication() {
+	let cookie_file = std::env::temp_dir().join("epic_tor_control_cookie");
+	fs::write(&cookie_file, [0xab; 32]).unwrap();
+	let auth = format!(
+		"METHODS=COOKIE,SAFECOOKIE COOKIEFILE=\"{}\"",
+		cookie_file.display()
+	);
+	let (addr, mock) = mock_control_port(vec![
+		step("PROTOCOLINFO 1", &protocol_info(&auth)),
+		step(&format!("AUTHENTICATE {}\r", "ab".repeat(32)), "250 OK\r\n"),
+	]);
+	connect(&addr).authenticate(None).unwrap();
+	mock.join().unwrap();
+	fs::remove_file(&cookie_file).unwrap();
+
+	let (addr, mock) = mock_control_port(vec![
+		step("PROTOCOLINFO 1", &protocol_info("METHODS=HASHEDPASSWORD")),
+		step(
+			"AUTHENTICATE \"pass\\\"word\"\r",
+			"515 Authentication failed: Password did not match\r\n",
+		),
+	]);
+	match connect(&addr).authenticate(Some("pass\"word")) {
+		Err(Error::Authentication(msg)) => assert!(msg.contains("Password did not match")),
+		r => panic!("unexpected {:?}", r.err()),
+	}
+	mock.join().unwrap();
+
+	// A password protected control port without a configured password.
+	let (addr, mock) = mock_control_port(vec![step(
+		"PROTOCOLINFO 1",
+		&protocol_info("METHODS=HASHEDPASSWORD"),
+	)]);
+	assert!(matches!(
+		connect(&addr).authenticate(None),
+		Err(Error::Authentication(_))
+	));
+	mock.join().unwrap();
+}
+
+#[test]
+fn wait_for_bootstrap_events() {
+	let (addr, mock) = mock_control_port(vec![
+		step("SETEVENTS STATUS_CLIENT", "250 OK\r\n"),
+		step(
+			"GETINFO status/bootstrap-phase",
+			"650 STATUS_CLIENT NOTICE BOOTSTRAP PROGRESS=50 TAG=loading_descriptors \
+			 SUMMARY=\"Loading relay descriptors\"\r\n\
+			 250-status/bootstrap-phase=NOTICE BOOTSTRAP PROGRESS=50 TAG=loading_descriptors \
+			 SUMMARY=\"Loading relay descriptors\"\r\n250 OK\r\n",
+		),
+		(
+			None,
+			"650 STATUS_CLIENT NOTICE BOOTSTRAP PROGRESS=85 TAG=ap_conn SUMMARY=\"Connecting\"\r\n\
+			 650 STATUS_CLIENT NOTICE CIRCUIT_ESTABLISHED\r\n\
+			 650 STATUS_CLIENT NOTICE BOOTSTRAP PROGRESS=100 TAG=done SUMMARY=\"Done\"\r\n"
+				.to_owned(),
+		),
+		step("SETEVENTS\r", "250 OK\r\n"),
+	]);
+
+	let progress = std::cell::RefCell::new(vec![]);
+	connect(&addr)
+		.wait_for_bootstrap(Duration::from_secs(5), |status| {
+			progress.borrow_mut().push(status.clone())
+		})
+		.unwrap();
+	let progress: Vec<u8> = progress.into_inner().iter().map(|s| s.progress).collect();
+	// The event received before the GETINFO reply is replayed afterwards.
+	assert_eq!(progress, vec![50, 50, 85, 100]);
+	mock.join().unwrap();
+}
+
+#[test]
+fn error_replies() {
+	let (addr, mock) = mock_control_port(vec![step(
+		"DEL_ONION unknown",
+		"552 Unknown Onion Service id\r\n",
+	)]);
+	match connect(&addr).del_onion("unknown") {
+		Err(Error::Tor(552, msg)) => assert_eq!(msg, "Unknown Onion Service id"),
+		r => panic!("unexpected {:?}", r.err()),
+	}
+	mock.join().unwrap();
+}
+
+#[test]
+fn parse_bootstrap_status() {
+	let status = BootstrapStatus::parse(
+		"status/bootstrap-phase=NOTICE BOOTSTRAP PROGRESS=100 TAG=done SUMMARY=\"Done\"",
+	)
+	.unwrap();
+	assert_eq!(status.progress, 100);
+	assert_eq!(status.tag, "done");
+	assert_eq!(status.summary, "Done");
+	assert!(status.is_done());
+	assert!(BootstrapStatus::parse("STATUS_CLIENT NOTICE CIRCUIT_ESTABLISHED").is_none());
+}
+
+#[test]
+fn expanded_key_is_clamped() {
+	let expanded = expanded_secret_key(&SigningKey::from_bytes(&[1; 32]));
+	assert_eq!(expanded[0] & 7, 0);
+	assert_eq!(expanded[31] & 0xc0, 0x40);
+}
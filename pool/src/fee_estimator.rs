@@ -0,0 +1,220 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fee estimation, from the fee per weight unit of the transactions confirmed
+//! out of the txpool and the number of blocks they waited for.
+
+use chrono::prelude::{DateTime, Utc};
+use std::cmp;
+
+use self::core::consensus;
+use self::core::core::Transaction;
+use crate::types::PoolEntry;
+use epic_core as core;
+
+/// Largest confirmation target we estimate a fee for.
+pub const MAX_TARGET_BLOCKS: u64 = 24;
+
+/// Number of fee buckets, each 25% above the previous one.
+const FEE_BUCKETS: usize = 48;
+
+/// Share of the transactions of a bucket confirming within the target for
+/// its fee to be considered enough.
+const SUCCESS_RATE: f64 = 0.85;
+
+/// Confirmations (decayed) a range of buckets needs before we trust it.
+const MIN_BUCKET_SAMPLES: f64 = 4.0;
+
+/// Weight of past confirmations is multiplied by this on every block, so
+/// estimates follow the recent fee market (half life of ~6 hours).
+const DECAY: f64 = 0.998;
+
+/// Fee per weight unit of a transaction, comparable to the pool
+/// `accept_fee_base`.
+pub fn fee_base(tx: &Transaction) -> u64 {
+	tx.fee() / cmp::max(tx.tx_weight() as u64, 1)
+}
+
+/// Blocks a transaction added at `tx_at` waited for, at least 1.
+fn blocks_waited(tx_at: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+	let secs = cmp::max((now - tx_at).num_seconds(), 0) as u64;
+	cmp::max((secs + consensus::BLOCK_TIME_SEC - 1) / consensus::BLOCK_TIME_SEC, 1)
+}
+
+/// Fee needed for a transaction to confirm within a number of blocks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeEstimate {
+	/// Blocks the estimate is for
+	pub target_blocks: u64,
+	/// Fee per weight unit, to multiply by the transaction weight
+	pub fee_base: u64,
+	/// Whether enough transactions were confirmed to back the estimate, it
+	/// only accounts for the pool minimum fee and backlog otherwise
+	pub from_history: bool,
+	/// Transactions currently in the txpool by fee
+	pub histogram: Vec<FeeHistogramBucket>,
+}
+
+/// Transactions of the txpool in a range of fees.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeHistogramBucket {
+	/// Lowest fee per weight unit of the bucket
+	pub fee_base: u64,
+	/// Number of transactions
+	pub tx_count: u64,
+	/// Total block weight of the transactions
+	pub weight: u64,
+}
+
+/// Decayed counts of confirmed transactions per fee bucket.
+#[derive(Debug, Clone)]
+pub struct FeeEstimator {
+	/// Lowest fee per weight unit of each bucket, increasing
+	bounds: Vec<u64>,
+	/// Transactions confirmed within 1, 2, ... MAX_TARGET_BLOCKS blocks
+	confirmed: Vec<Vec<f64>>,
+	/// All the transactions confirmed, however long they waited
+	total: Vec<f64>,
+}
+
+impl FeeEstimator {
+	/// Buckets start at the minimum fee accepted by the pool.
+	pub fn new(accept_fee_base: u64) -> FeeEstimator {
+		let mut bounds = Vec::with_capacity(FEE_BUCKETS);
+		let mut bound = cmp::max(accept_fee_base, 1);
+		for _ in 0..FEE_BUCKETS {
+			bounds.push(bound);
+			bound = cmp::max(bound.saturating_mul(5) / 4, bound + 1);
+		}
+		FeeEstimator {
+			bounds,
+			confirmed: vec![vec![0.0; MAX_TARGET_BLOCKS as usize]; FEE_BUCKETS],
+			total: vec![0.0; FEE_BUCKETS],
+		}
+	}
+
+	fn bucket(&self, fee_base: u64) -> usize {
+		match self.bounds.binary_search(&fee_base) {
+			Ok(i) => i,
+			Err(i) => i.saturating_sub(1),
+		}
+	}
+
+	/// Records the txpool entries confirmed by a new block.
+	pub fn block_confirmed<'a, I>(&mut self, entries: I, now: DateTime<Utc>)
+	where
+		I: IntoIterator<Item = &'a PoolEntry>,
+	{
+		for counts in self.confirmed.iter_mut() {
+			counts.iter_mut().for_each(|c| *c *= DECAY);
+		}
+		self.total.iter_mut().for_each(|t| *t *= DECAY);
+
+		for entry in entries {
+			let bucket = self.bucket(fee_base(&entry.tx));
+			let waited = blocks_waited(entry.tx_at, now);
+			if waited <= MAX_TARGET_BLOCKS {
+				self.confirmed[bucket][waited as usize - 1] += 1.0;
+			}
+			self.total[bucket] += 1.0;
+		}
+	}
+
+	/// Fee for a transaction to confirm within the target, the highest of
+	/// what confirmed transactions paid, what is needed to get ahead of the
+	/// pool backlog with `block_weight` per block and the pool minimum fee.
+	pub fn fee_estimate(
+		&self,
+		target_blocks: u64,
+		entries: &[PoolEntry],
+		block_weight: usize,
+		now: DateTime<Utc>,
+	) -> FeeEstimate {
+		let target = cmp::min(cmp::max(target_blocks, 1), MAX_TARGET_BLOCKS);
+		let history = self.estimate(target, entries, now);
+		let mut histogram = self.histogram(entries);
+
+		// transactions paying more than us fill the blocks first
+		let capacity = (block_weight as u64).saturating_mul(target);
+		let mut backlog = 0;
+		let mut weight: u64 = 0;
+		for (i, bucket) in histogram.iter().enumerate().rev() {
+			weight = weight.saturating_add(bucket.weight);
+			if weight > capacity {
+				backlog = self.bounds.get(i + 1).copied().unwrap_or(bucket.fee_base);
+				break;
+			}
+		}
+		histogram.retain(|b| b.tx_count > 0);
+
+		FeeEstimate {
+			target_blocks: target,
+			fee_base: cmp::max(cmp::max(history.unwrap_or(0), backlog), self.bounds[0]),
+			from_history: history.is_some(),
+			histogram,
+		}
+	}
+
+	/// Lowest fee per weight unit that got most transactions confirmed within
+	/// the target, None without enough confirmed transactions. Transactions
+	/// still waiting in the pool past the target count as failures.
+	fn estimate(&self, target: u64, pending: &[PoolEntry], now: DateTime<Utc>) -> Option<u64> {
+		let mut unconfirmed = vec![0.0; FEE_BUCKETS];
+		for entry in pending {
+			if blocks_waited(entry.tx_at, now) > target {
+				unconfirmed[self.bucket(fee_base(&entry.tx))] += 1.0;
+			}
+		}
+
+		// group buckets from the highest fee down until they have enough
+		// samples, stopping at the first group failing the target
+		let mut estimate = None;
+		let (mut confirmed, mut total) = (0.0, 0.0);
+		for bucket in (0..FEE_BUCKETS).rev() {
+			confirmed += self.confirmed[bucket][..target as usize]
+				.iter()
+				.sum::<f64>();
+			total += self.total[bucket] + unconfirmed[bucket];
+			if total < MIN_BUCKET_SAMPLES {
+				continue;
+			}
+			if confirmed / total < SUCCESS_RATE {
+				break;
+			}
+			estimate = Some(self.bounds[bucket]);
+			confirmed = 0.0;
+			total = 0.0;
+		}
+		estimate
+	}
+
+	// transactions of the pool by fee bucket
+	fn histogram(&self, entries: &[PoolEntry]) -> Vec<FeeHistogramBucket> {
+		let mut histogram: Vec<FeeHistogramBucket> = self
+			.bounds
+			.iter()
+			.map(|bound| FeeHistogramBucket {
+				fee_base: *bound,
+				tx_count: 0,
+				weight: 0,
+			})
+			.collect();
+		for entry in entries {
+			let bucket = &mut histogram[self.bucket(fee_base(&entry.tx))];
+			bucket.tx_count += 1;
+			bucket.weight += entry.tx.tx_weight_as_block() as u64;
+		}
+		histogram
+	}
+}
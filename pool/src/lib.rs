@@ -30,12 +30,15 @@ extern crate serde_derive;
 #[macro_use]
 extern crate log;
 
+pub mod fee_estimator;
 mod pool;
 pub mod transaction_pool;
 pub mod types;
 
+pub use crate::fee_estimator::{FeeEstimate, FeeEstimator, FeeHistogramBucket};
 pub use crate::pool::Pool;
 pub use crate::transaction_pool::TransactionPool;
 pub use crate::types::{
-	BlockChain, DandelionConfig, PoolAdapter, PoolConfig, PoolEntry, PoolError, TxSource,
+	BlockChain, DandelionConfig, PoolAdapter, PoolConfig, PoolEntry, PoolError, TxAcceptance,
+	TxCheck, TxCheckKind, TxConflict, TxConflictKind, TxSource,
 };
@@ -21,7 +21,8 @@ use self::core::core::transaction;
 use self::core::core::{
 	Block, BlockHeader, BlockSums, Committed, Transaction, TxKernel, Weighting,
 };
-use crate::types::{BlockChain, PoolEntry, PoolError};
+use crate::fee_estimator::fee_base;
+use crate::types::{BlockChain, PoolEntry, PoolError, TxConflictKind};
 use epic_core as core;
 use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
@@ -57,6 +58,49 @@ where
 		self.entries.iter().any(|x| x.tx.hash() == hash)
 	}
 
+	/// The entry to evict first when the pool is full, the one paying the
+	/// lowest fee per weight unit and the most recent on equal fees. Txs
+	/// pushed through our api and txs other entries spend from are kept.
+	pub fn evictable_entry(&self) -> Option<&PoolEntry> {
+		let spent: HashSet<_> = self
+			.entries
+			.iter()
+			.flat_map(|x| x.tx.inputs().iter().map(|i| i.commitment()))
+			.collect();
+		self.entries
+			.iter()
+			.filter(|x| !x.local)
+			.filter(|x| x.tx.outputs().iter().all(|o| !spent.contains(&o.commitment())))
+			.min_by_key(|x| (fee_base(&x.tx), Reverse(x.tx_at)))
+	}
+
+	/// Entries conflicting with the tx, being the same tx or sharing one of
+	/// its inputs, outputs or kernels.
+	pub fn conflicting_entries(&self, tx: &Transaction) -> Vec<(&PoolEntry, TxConflictKind)> {
+		let hash = tx.hash();
+		let inputs: HashSet<_> = tx.inputs().iter().map(|x| x.commitment()).collect();
+		let outputs: HashSet<_> = tx.outputs().iter().map(|x| x.commitment()).collect();
+		let kernels: HashSet<_> = tx.kernels().iter().map(|x| x.excess()).collect();
+
+		self.entries
+			.iter()
+			.filter_map(|x| {
+				let conflict = if x.tx.hash() == hash {
+					TxConflictKind::DuplicateTx
+				} else if x.tx.inputs().iter().any(|i| inputs.contains(&i.commitment())) {
+					TxConflictKind::DoubleSpend
+				} else if x.tx.outputs().iter().any(|o| outputs.contains(&o.commitment())) {
+					TxConflictKind::DuplicateOutput
+				} else if x.tx.kernels().iter().any(|k| kernels.contains(&k.excess())) {
+					TxConflictKind::DuplicateKernel
+				} else {
+					return None;
+				};
+				Some((x, conflict))
+			})
+			.collect()
+	}
+
 	pub fn get_tx(&self, hash: Hash) -> Option<Transaction> {
 		self.entries
 			.iter()
@@ -213,7 +257,7 @@ where
 		);
 	}
 
-	fn validate_raw_tx(
+	pub(crate) fn validate_raw_tx(
 		&self,
 		tx: &Transaction,
 		header: &BlockHeader,
@@ -20,15 +20,70 @@
 use self::core::core::hash::{Hash, Hashed};
 use self::core::core::id::ShortId;
 use self::core::core::{transaction, Block, BlockHeader, Transaction, Weighting};
+use self::core::ser::{self, ProtocolVersion, Readable, Reader, Writeable, Writer};
+use self::util::secp::pedersen::Commitment;
 use self::util::RwLock;
+use crate::fee_estimator::{self, FeeEstimate, FeeEstimator};
 use crate::pool::Pool;
-use crate::types::{BlockChain, PoolAdapter, PoolConfig, PoolEntry, PoolError, TxSource};
+use crate::types::{
+	BlockChain, PoolAdapter, PoolConfig, PoolEntry, PoolError, TxAcceptance, TxCheck, TxCheckKind,
+	TxConflict, TxSource,
+};
 use chrono::prelude::*;
+use chrono::Duration;
 use epic_core as core;
 use epic_util as util;
+use std::cmp;
 use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::sync::Arc;
 
+/// Version of the file format the pool is saved in.
+const POOL_FILE_VERSION: u16 = 1;
+
+/// Reorg cache entries older than this are not worth reloading.
+const REORG_CACHE_MINS: i64 = 30;
+
+/// The minimum fee raised by evictions halves every 10 minutes.
+const MIN_FEE_HALF_LIFE_SECS: i64 = 600;
+
+/// Txpool, stempool and reorg cache entries, as saved to disk.
+struct PoolSnapshot {
+	txpool: Vec<PoolEntry>,
+	stempool: Vec<PoolEntry>,
+	reorg_cache: Vec<PoolEntry>,
+}
+
+impl Writeable for PoolSnapshot {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		for entries in [&self.txpool, &self.stempool, &self.reorg_cache] {
+			writer.write_u64(entries.len() as u64)?;
+			for entry in entries {
+				entry.write(writer)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl Readable for PoolSnapshot {
+	fn read(reader: &mut dyn Reader) -> Result<PoolSnapshot, ser::Error> {
+		let count = reader.read_u64()?;
+		let txpool = ser::read_multi(reader, count)?;
+		let count = reader.read_u64()?;
+		let stempool = ser::read_multi(reader, count)?;
+		let count = reader.read_u64()?;
+		let reorg_cache = ser::read_multi(reader, count)?;
+		Ok(PoolSnapshot {
+			txpool,
+			stempool,
+			reorg_cache,
+		})
+	}
+}
+
 /// Transaction pool implementation.
 pub struct TransactionPool<B, P>
 where
@@ -47,6 +102,11 @@ where
 	pub blockchain: Arc<B>,
 	/// The pool adapter
 	pub adapter: Arc<P>,
+	/// Fees of the transactions confirmed out of the txpool
+	pub fee_estimator: FeeEstimator,
+	/// Fee per weight unit raised by the last eviction, decaying from then
+	rolling_fee_base: u64,
+	rolling_fee_at: DateTime<Utc>,
 }
 
 impl<B, P> TransactionPool<B, P>
@@ -57,12 +117,15 @@ where
 	/// Create a new transaction pool
 	pub fn new(config: PoolConfig, chain: Arc<B>, adapter: Arc<P>) -> Self {
 		TransactionPool {
+			fee_estimator: FeeEstimator::new(config.accept_fee_base),
 			config,
 			txpool: Pool::new(chain.clone(), "txpool".to_string()),
 			stempool: Pool::new(chain.clone(), "stempool".to_string()),
 			reorg_cache: Arc::new(RwLock::new(VecDeque::new())),
 			blockchain: chain,
 			adapter,
+			rolling_fee_base: 0,
+			rolling_fee_at: Utc::now(),
 		}
 	}
 
@@ -126,6 +189,21 @@ where
 		tx: Transaction,
 		stem: bool,
 		header: &BlockHeader,
+	) -> Result<(), PoolError> {
+		let local = src.is_pushed();
+		self.add_to_pool_with_origin(src, tx, local, stem, header)
+	}
+
+	/// Add the given tx to the pool like `add_to_pool`, for txs moving on from
+	/// the stempool. `local` tells whether the tx was first pushed through our
+	/// api, to keep its fee and capacity allowances.
+	pub fn add_to_pool_with_origin(
+		&mut self,
+		src: TxSource,
+		tx: Transaction,
+		local: bool,
+		stem: bool,
+		header: &BlockHeader,
 	) -> Result<(), PoolError> {
 		// Quick check to deal with common case of seeing the *same* tx
 		// broadcast from multiple peers simultaneously.
@@ -134,9 +212,21 @@ where
 		}
 
 		// Do we have the capacity to accept this transaction?
-		let acceptability = self.is_acceptable(&tx, stem);
+		let acceptability = self.is_acceptable(&tx, local, stem);
 		let mut evict = false;
-		if !stem && acceptability.as_ref().err() == Some(&PoolError::OverCapacity) {
+		if !stem
+			&& !local
+			&& acceptability.as_ref().err() == Some(&PoolError::OverCapacity)
+		{
+			// Only make space for a tx paying more than the one it evicts.
+			let fee_base = match self.txpool.evictable_entry() {
+				Some(entry) => fee_estimator::fee_base(&entry.tx),
+				None => return Err(PoolError::OverCapacity),
+			};
+			let threshold = (tx.tx_weight() as u64) * (fee_base + 1);
+			if tx.fee() < threshold {
+				return Err(PoolError::LowFeeTransaction(threshold));
+			}
 			evict = true;
 		} else if acceptability.is_err() {
 			return acceptability;
@@ -157,6 +247,7 @@ where
 			src,
 			tx_at: Utc::now(),
 			tx,
+			local,
 		};
 
 		// If not stem then we are fluff.
@@ -181,26 +272,52 @@ where
 		Ok(())
 	}
 
-	// Remove the last transaction from the flattened bucket transactions.
-	// No other tx depends on it, it has low fee_to_weight and is unlikely to participate in any cut-through.
+	// Remove the txpool tx with the lowest fee_to_weight no other tx depends on,
+	// and raise the minimum fee above it so it does not come straight back.
 	pub fn evict_from_txpool(&mut self) {
-		// Get bucket transactions
-		let bucket_transactions = self.txpool.bucket_transactions(Weighting::NoLimit);
-
-		// Get last transaction and remove it
-		match bucket_transactions.last() {
-			Some(evictable_transaction) => {
-				// Remove transaction
-				self.txpool.entries = self
-					.txpool
-					.entries
-					.iter()
-					.filter(|x| x.tx != *evictable_transaction)
-					.map(|x| x.clone())
-					.collect::<Vec<_>>();
-			}
-			None => (),
-		}
+		let evicted = match self.txpool.evictable_entry() {
+			Some(entry) => entry.clone(),
+			None => return,
+		};
+		self.txpool.entries.retain(|x| x.tx != evicted.tx);
+
+		let increment = cmp::max(self.config.accept_fee_base, 1);
+		let fee_base = fee_estimator::fee_base(&evicted.tx) + increment;
+		self.rolling_fee_base = cmp::max(self.rolling_fee_base(), fee_base);
+		self.rolling_fee_at = Utc::now();
+		debug!(
+			"evicted tx {} from txpool, min fee base now {}",
+			evicted.tx.hash(),
+			self.rolling_fee_base
+		);
+	}
+
+	// Fee base raised by the last eviction, halved every half life since.
+	fn rolling_fee_base(&self) -> u64 {
+		let elapsed = Utc::now() - self.rolling_fee_at;
+		let half_lives = elapsed.num_seconds() / MIN_FEE_HALF_LIFE_SECS;
+		self.rolling_fee_base
+			.checked_shr(cmp::max(half_lives, 0) as u32)
+			.unwrap_or(0)
+	}
+
+	/// Lowest fee per weight unit for a tx from peers to enter the pool. From
+	/// accept_fee_base, it rises up to 5 times as much once the peers txs fill
+	/// half the pool, and after evictions.
+	pub fn min_fee_base(&self) -> u64 {
+		let half = cmp::max(self.config.max_pool_size / 2, 1) as u64;
+		let (_, received) = self.txpool_sizes();
+		let filling = cmp::min((received as u64).saturating_sub(half), half);
+		let accept_fee_base = self.config.accept_fee_base;
+		let fill_fee_base = accept_fee_base + accept_fee_base * 4 * filling / half;
+		cmp::max(fill_fee_base, self.rolling_fee_base())
+	}
+
+	// Number of txpool txs pushed through our api and received from peers.
+	fn txpool_sizes(&self) -> (usize, usize) {
+		let entries = &self.txpool.entries;
+		let pushed = entries.iter().filter(|x| x.local).count();
+		(pushed, entries.len() - pushed)
 	}
 
 	// Old txs will "age out" after 30 mins.
@@ -234,6 +351,13 @@ where
 	/// Reconcile the transaction pool (both txpool and stempool) against the
 	/// provided block.
 	pub fn reconcile_block(&mut self, block: &Block) -> Result<(), PoolError> {
+		// Track the fees of the txpool txs the block confirmed.
+		let confirmed = self.txpool.entries.iter().filter(|x| {
+			let kernels = x.tx.kernels();
+			kernels.iter().all(|k| block.kernels().contains(k))
+		});
+		self.fee_estimator.block_confirmed(confirmed, Utc::now());
+
 		// First reconcile the txpool.
 		self.txpool.reconcile_block(block);
 		self.txpool.reconcile(None, &block.header)?;
@@ -248,6 +372,57 @@ where
 		Ok(())
 	}
 
+	/// Removes the tx with the given kernel excess from the txpool, the
+	/// stempool and the reorg cache, along with the txs spending its outputs.
+	/// Returns the number of txs removed from the pools, 0 if no tx had the
+	/// kernel.
+	pub fn remove_tx_by_kernel_excess(&mut self, excess: &Commitment) -> Result<usize, PoolError> {
+		let has_kernel = |x: &PoolEntry| x.tx.kernels().iter().any(|k| k.excess() == *excess);
+		let size = self.txpool.size() + self.stempool.size();
+		self.txpool.entries.retain(|x| !has_kernel(x));
+		self.stempool.entries.retain(|x| !has_kernel(x));
+		self.reorg_cache.write().retain(|x| !has_kernel(x));
+		if self.txpool.size() + self.stempool.size() == size {
+			return Ok(0);
+		}
+
+		// Txs spending the outputs of the removed one are no longer valid.
+		let header = self.chain_head()?;
+		self.txpool.reconcile(None, &header)?;
+		let txpool_tx = self.txpool.all_transactions_aggregate()?;
+		self.stempool.reconcile(txpool_tx, &header)?;
+
+		Ok(size - self.txpool.size() - self.stempool.size())
+	}
+
+	/// Fluffs the stempool tx with the given kernel excess without waiting
+	/// for its embargo, moving it to the txpool and broadcasting it. The tx
+	/// keeps its source. Returns false if no stempool tx had the kernel.
+	pub fn fluff_stem_tx(&mut self, excess: &Commitment) -> Result<bool, PoolError> {
+		let entry = self
+			.stempool
+			.entries
+			.iter()
+			.find(|x| x.tx.kernels().iter().any(|k| k.excess() == *excess))
+			.cloned();
+		let entry = match entry {
+			Some(entry) => entry,
+			None => return Ok(false),
+		};
+		let header = self.chain_head()?;
+		let tx = entry.tx.clone();
+		self.add_to_pool_with_origin(entry.src, tx, entry.local, false, &header)?;
+		self.stempool.entries.retain(|x| x.tx != entry.tx);
+		Ok(true)
+	}
+
+	/// Drops all the txs of the txpool, the stempool and the reorg cache.
+	pub fn clear(&mut self) {
+		self.txpool.entries.clear();
+		self.stempool.entries.clear();
+		self.reorg_cache.write().clear();
+	}
+
 	/// Retrieve individual transaction for the given kernel hash.
 	pub fn retrieve_tx_by_kernel_hash(&self, hash: Hash) -> Option<Transaction> {
 		self.txpool.retrieve_tx_by_kernel_hash(hash)
@@ -266,28 +441,185 @@ where
 	}
 
 	/// Whether the transaction is acceptable to the pool, given both how
-	/// full the pool is and the transaction weight.
-	fn is_acceptable(&self, tx: &Transaction, stem: bool) -> Result<(), PoolError> {
-		if self.total_size() > self.config.max_pool_size {
-			return Err(PoolError::OverCapacity);
+	/// full the pool is and the transaction weight. Txs pushed through our
+	/// api only need the base fee and have their own room in the pool.
+	fn is_acceptable(&self, tx: &Transaction, local: bool, stem: bool) -> Result<(), PoolError> {
+		let threshold = self.min_fee(tx, local);
+		if tx.fee() < threshold {
+			return Err(PoolError::LowFeeTransaction(threshold));
 		}
+		self.check_capacity(local, stem)
+	}
 
+	// Whether the pool has room for one more local or received tx.
+	fn check_capacity(&self, local: bool, stem: bool) -> Result<(), PoolError> {
 		// Check that the stempool can accept this transaction
 		if stem && self.stempool.size() > self.config.max_stempool_size {
 			return Err(PoolError::OverCapacity);
-		} else if self.total_size() > self.config.max_pool_size {
+		}
+		let (pushed, received) = self.txpool_sizes();
+		if local && pushed > self.config.max_pushed_pool_size {
+			return Err(PoolError::OverCapacity);
+		} else if !local && received > self.config.max_pool_size {
 			return Err(PoolError::OverCapacity);
 		}
+		Ok(())
+	}
 
+	// Lowest fee the pool accepts for the local or received tx.
+	fn min_fee(&self, tx: &Transaction, local: bool) -> u64 {
 		// for a basic transaction (1 input, 2 outputs) -
 		// (-1 * 1) + (4 * 2) + 1 = 8
 		// 8 * 10 = 80
-		if self.config.accept_fee_base > 0 {
-			let threshold = (tx.tx_weight() as u64) * self.config.accept_fee_base;
-			if tx.fee() < threshold {
-				return Err(PoolError::LowFeeTransaction(threshold));
+		let fee_base = if local {
+			self.config.accept_fee_base
+		} else {
+			self.min_fee_base()
+		};
+		(tx.tx_weight() as u64) * fee_base
+	}
+
+	/// Runs the checks a tx pushed through our api goes through to enter the
+	/// pool, without adding it. Only txpool txs are reported as conflicts,
+	/// the stempool ones are under embargo.
+	pub fn test_accept(&self, tx: &Transaction) -> Result<TxAcceptance, PoolError> {
+		let header = self.chain_head()?;
+		let min_fee = self.min_fee(tx, true);
+		let fee = if tx.fee() < min_fee {
+			Err(PoolError::LowFeeTransaction(min_fee))
+		} else {
+			Ok(())
+		};
+
+		// the tx may spend txpool outputs, validate it on top of the txpool
+		let chain_state = self
+			.txpool
+			.all_transactions_aggregate()
+			.and_then(|agg| match agg {
+				Some(agg) => Ok(transaction::aggregate(vec![agg, tx.clone()])?),
+				None => Ok(tx.clone()),
+			})
+			.and_then(|agg| self.txpool.validate_raw_tx(&agg, &header, Weighting::NoLimit))
+			.map(|_| ());
+
+		let checks = vec![
+			TxCheck::new(
+				TxCheckKind::Weight,
+				tx.validate(Weighting::AsTransaction)
+					.map_err(PoolError::InvalidTx),
+			),
+			TxCheck::new(
+				TxCheckKind::LockHeight,
+				self.blockchain.verify_tx_lock_height(tx),
+			),
+			TxCheck::new(
+				TxCheckKind::CoinbaseMaturity,
+				self.blockchain.verify_coinbase_maturity(tx),
+			),
+			TxCheck::new(TxCheckKind::Fee, fee),
+			TxCheck::new(TxCheckKind::Capacity, self.check_capacity(true, false)),
+			TxCheck::new(TxCheckKind::ChainState, chain_state),
+		];
+		let conflicts: Vec<_> = self
+			.txpool
+			.conflicting_entries(tx)
+			.into_iter()
+			.map(|(entry, conflict)| TxConflict {
+				tx_hash: entry.tx.hash().to_hex(),
+				kernels: entry
+					.tx
+					.kernels()
+					.iter()
+					.map(|k| util::to_hex(k.excess().0.to_vec()))
+					.collect(),
+				conflict,
+			})
+			.collect();
+
+		Ok(TxAcceptance {
+			accepted: checks.iter().all(|x| x.passed) && conflicts.is_empty(),
+			fee: tx.fee(),
+			min_fee,
+			checks,
+			conflicts,
+		})
+	}
+
+	/// Saves the txpool, stempool and reorg cache to a file, so pending txs
+	/// survive a restart.
+	pub fn save(&self, path: &Path) -> Result<(), PoolError> {
+		let snapshot = PoolSnapshot {
+			txpool: self.txpool.entries.clone(),
+			stempool: self.stempool.entries.clone(),
+			reorg_cache: self.reorg_cache.read().iter().cloned().collect(),
+		};
+		// file version and protocol version of the txs, then the entries
+		let version = ProtocolVersion::local();
+		let mut data = POOL_FILE_VERSION.to_be_bytes().to_vec();
+		data.extend_from_slice(&version.0.to_be_bytes());
+		ser::serialize(&mut data, version, &snapshot)
+			.map_err(|e| PoolError::Other(format!("failed to serialize pool: {}", e)))?;
+
+		// write to a temporary file first not to lose the previous one
+		let tmp_path = path.with_extension("tmp");
+		fs::write(&tmp_path, &data)
+			.and_then(|_| fs::rename(&tmp_path, path))
+			.map_err(|e| PoolError::Other(format!("failed to write {}: {}", path.display(), e)))
+	}
+
+	/// Reloads the pool saved to a file, revalidating the txs against the
+	/// current chain head and dropping the stale or conflicting ones.
+	pub fn load(&mut self, path: &Path) -> Result<(), PoolError> {
+		let data = match fs::read(path) {
+			Ok(data) => data,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+			Err(e) => {
+				let msg = format!("failed to read {}: {}", path.display(), e);
+				return Err(PoolError::Other(msg));
 			}
+		};
+		if data.len() < 6 {
+			return Err(PoolError::Other(format!("{} is truncated", path.display())));
+		}
+		let file_version = u16::from_be_bytes([data[0], data[1]]);
+		if file_version != POOL_FILE_VERSION {
+			return Err(PoolError::Other(format!(
+				"unsupported pool file version {} in {}",
+				file_version,
+				path.display()
+			)));
+		}
+		let version = ProtocolVersion(u32::from_be_bytes([data[2], data[3], data[4], data[5]]));
+		let snapshot: PoolSnapshot = ser::deserialize(&mut &data[6..], version)
+			.map_err(|e| PoolError::Other(format!("failed to read pool: {}", e)))?;
+
+		let header = self.chain_head()?;
+		let txpool = revalidate(&self.txpool, snapshot.txpool, None, &header)?;
+		self.txpool.entries = txpool;
+		let txpool_tx = self.txpool.all_transactions_aggregate()?;
+		let stempool = revalidate(&self.stempool, snapshot.stempool, txpool_tx, &header)?;
+		self.stempool.entries = stempool;
+
+		// reorg cache entries are validated when a reorg replays them
+		let cutoff = Utc::now() - Duration::minutes(REORG_CACHE_MINS);
+		let mut cache = self.reorg_cache.write();
+		cache.clear();
+		cache.extend(
+			snapshot
+				.reorg_cache
+				.into_iter()
+				.filter(|x| x.tx_at >= cutoff),
+		);
+		while cache.len() > self.config.max_pool_size {
+			let _ = cache.pop_front();
 		}
+
+		info!(
+			"Reloaded {} txpool and {} stempool txs from {}",
+			self.txpool.size(),
+			self.stempool.size(),
+			path.display()
+		);
 		Ok(())
 	}
 
@@ -297,6 +629,19 @@ where
 		self.txpool.size()
 	}
 
+	/// Fee per weight unit for a transaction to confirm within the target
+	/// number of blocks, with the txpool fee histogram.
+	pub fn estimate_fee(&self, target_blocks: u64) -> FeeEstimate {
+		let mut estimate = self.fee_estimator.fee_estimate(
+			target_blocks,
+			&self.txpool.entries,
+			self.config.mineable_max_weight,
+			Utc::now(),
+		);
+		estimate.fee_base = cmp::max(estimate.fee_base, self.min_fee_base());
+		estimate
+	}
+
 	/// Returns a vector of transactions from the txpool so we can build a
 	/// block from them.
 	pub fn prepare_mineable_transactions(&self) -> Result<Vec<Transaction>, PoolError> {
@@ -304,3 +649,37 @@ where
 			.prepare_mineable_transactions(self.config.mineable_max_weight)
 	}
 }
+
+// Keeps the reloaded entries still valid against the chain head (and the extra
+// tx), logging why the others are dropped.
+fn revalidate<B: BlockChain>(
+	pool: &Pool<B>,
+	entries: Vec<PoolEntry>,
+	extra_tx: Option<Transaction>,
+	header: &BlockHeader,
+) -> Result<Vec<PoolEntry>, PoolError> {
+	let txs = entries.iter().map(|x| x.tx.clone()).collect::<Vec<_>>();
+	let valid_txs = pool.validate_raw_txs(&txs, extra_tx, header, Weighting::AsTransaction)?;
+	let mut valid_entries = vec![];
+	for entry in entries {
+		if valid_txs.contains(&entry.tx) {
+			valid_entries.push(entry);
+			continue;
+		}
+		let reason = entry
+			.tx
+			.validate(Weighting::AsTransaction)
+			.map_err(PoolError::InvalidTx)
+			.and_then(|_| pool.blockchain.validate_tx(&entry.tx))
+			.err()
+			.map(|e| e.to_string())
+			.unwrap_or_else(|| "conflicts with another pool tx".to_string());
+		warn!(
+			"Dropping {} tx {} reloaded from disk: {}",
+			pool.name,
+			entry.tx.hash(),
+			reason
+		);
+	}
+	Ok(valid_entries)
+}
@@ -15,13 +15,14 @@
 //! The primary module containing the implementations of the transaction pool
 //! and its top-level members.
 
-use chrono::prelude::{DateTime, Utc};
+use chrono::prelude::{DateTime, TimeZone, Utc};
 
 use self::core::core::block;
 use self::core::core::committed;
 use self::core::core::hash::Hash;
 use self::core::core::transaction::{self, Transaction};
 use self::core::core::{BlockHeader, BlockSums};
+use self::core::ser::{self, Readable, Reader, Writeable, Writer};
 use self::core::{consensus, global};
 use epic_core as core;
 use epic_keychain as keychain;
@@ -45,6 +46,13 @@ const DANDELION_STEM_PROBABILITY: u8 = 90;
 /// If set to false we will stem/fluff our txs as per current epoch.
 const DANDELION_ALWAYS_STEM_OUR_TXS: bool = true;
 
+/// Number of onion-capable peers a stem tx is relayed through when Tor is
+/// enabled.
+const DANDELION_ONION_STEM_HOPS: u8 = 3;
+
+/// Most hops we relay an onion stem tx for, whatever hop count it came with.
+pub const MAX_ONION_STEM_HOPS: u8 = 8;
+
 /// Configuration for "Dandelion".
 /// Note: shared between p2p and pool.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -65,6 +73,10 @@ pub struct DandelionConfig {
 	/// Default to always stem our txs as described in Dandelion++ paper.
 	#[serde(default = "default_dandelion_always_stem_our_txs")]
 	pub always_stem_our_txs: bool,
+	/// Number of onion-capable peers a stem tx is relayed through, hop by hop,
+	/// when Tor is enabled. Replaces the p2p stem relay, 0 to disable.
+	#[serde(default = "default_dandelion_onion_stem_hops")]
+	pub onion_stem_hops: u8,
 }
 
 impl Default for DandelionConfig {
@@ -75,6 +87,7 @@ impl Default for DandelionConfig {
 			aggregation_secs: default_dandelion_aggregation_secs(),
 			stem_probability: default_dandelion_stem_probability(),
 			always_stem_our_txs: default_dandelion_always_stem_our_txs(),
+			onion_stem_hops: default_dandelion_onion_stem_hops(),
 		}
 	}
 }
@@ -99,6 +112,10 @@ fn default_dandelion_always_stem_our_txs() -> bool {
 	DANDELION_ALWAYS_STEM_OUR_TXS
 }
 
+fn default_dandelion_onion_stem_hops() -> u8 {
+	DANDELION_ONION_STEM_HOPS
+}
+
 /// Transaction pool configuration
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct PoolConfig {
@@ -112,6 +129,12 @@ pub struct PoolConfig {
 	#[serde(default = "default_max_pool_size")]
 	pub max_pool_size: usize,
 
+	/// Maximum number of transactions pushed through our api in the pool, on
+	/// top of max_pool_size. They are never evicted to make room for
+	/// transactions received from peers.
+	#[serde(default = "default_max_pushed_pool_size")]
+	pub max_pushed_pool_size: usize,
+
 	/// Maximum capacity of the pool in number of transactions
 	#[serde(default = "default_max_stempool_size")]
 	pub max_stempool_size: usize,
@@ -128,6 +151,7 @@ impl Default for PoolConfig {
 		PoolConfig {
 			accept_fee_base: default_accept_fee_base(),
 			max_pool_size: default_max_pool_size(),
+			max_pushed_pool_size: default_max_pushed_pool_size(),
 			max_stempool_size: default_max_stempool_size(),
 			mineable_max_weight: default_mineable_max_weight(),
 		}
@@ -140,6 +164,9 @@ fn default_accept_fee_base() -> u64 {
 fn default_max_pool_size() -> usize {
 	50_000
 }
+fn default_max_pushed_pool_size() -> usize {
+	1_000
+}
 fn default_max_stempool_size() -> usize {
 	50_000
 }
@@ -157,6 +184,36 @@ pub struct PoolEntry {
 	pub tx_at: DateTime<Utc>,
 	/// The transaction itself.
 	pub tx: Transaction,
+	/// Whether the tx was pushed through our api. Unlike the source it
+	/// survives the tx being fluffed or deaggregated.
+	pub local: bool,
+}
+
+impl Writeable for PoolEntry {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		self.src.write(writer)?;
+		writer.write_i64(self.tx_at.timestamp_millis())?;
+		self.tx.write(writer)?;
+		writer.write_u8(self.local as u8)
+	}
+}
+
+impl Readable for PoolEntry {
+	fn read(reader: &mut dyn Reader) -> Result<PoolEntry, ser::Error> {
+		let src = TxSource::read(reader)?;
+		let tx_at = Utc
+			.timestamp_millis_opt(reader.read_i64()?)
+			.single()
+			.ok_or(ser::Error::CorruptedData)?;
+		let tx = Transaction::read(reader)?;
+		let local = reader.read_u8()? != 0;
+		Ok(PoolEntry {
+			src,
+			tx_at,
+			tx,
+			local,
+		})
+	}
 }
 
 /// Used to make decisions based on transaction acceptance priority from
@@ -172,6 +229,8 @@ pub enum TxSource {
 	Fluff,
 	EmbargoExpired,
 	Deaggregate,
+	/// Received in the onion stem phase, with the number of hops left
+	OnionStem(u8),
 }
 
 impl TxSource {
@@ -184,6 +243,114 @@ impl TxSource {
 	}
 }
 
+impl Writeable for TxSource {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		match self {
+			TxSource::PushApi => writer.write_u8(0),
+			TxSource::Broadcast => writer.write_u8(1),
+			TxSource::Fluff => writer.write_u8(2),
+			TxSource::EmbargoExpired => writer.write_u8(3),
+			TxSource::Deaggregate => writer.write_u8(4),
+			TxSource::OnionStem(hops) => {
+				writer.write_u8(5)?;
+				writer.write_u8(*hops)
+			}
+		}
+	}
+}
+
+impl Readable for TxSource {
+	fn read(reader: &mut dyn Reader) -> Result<TxSource, ser::Error> {
+		match reader.read_u8()? {
+			0 => Ok(TxSource::PushApi),
+			1 => Ok(TxSource::Broadcast),
+			2 => Ok(TxSource::Fluff),
+			3 => Ok(TxSource::EmbargoExpired),
+			4 => Ok(TxSource::Deaggregate),
+			5 => Ok(TxSource::OnionStem(reader.read_u8()?)),
+			_ => Err(ser::Error::CorruptedData),
+		}
+	}
+}
+
+/// Checks a transaction goes through to enter the pool.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxCheckKind {
+	/// Signatures, range proofs, sums and weight as a single tx
+	Weight,
+	/// Lock height of the kernels against the next block height
+	LockHeight,
+	/// Maturity of the spent coinbase outputs
+	CoinbaseMaturity,
+	/// Fee against the fee base accepted by the pool
+	Fee,
+	/// Room in the pool for txs of the source
+	Capacity,
+	/// Inputs unspent and outputs unique in the chain, with the txpool applied
+	ChainState,
+}
+
+/// Outcome of one of the checks, with the error if it failed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TxCheck {
+	/// The check that was run
+	pub check: TxCheckKind,
+	/// Whether the tx passed it
+	pub passed: bool,
+	/// Why the tx failed it
+	pub error: Option<String>,
+}
+
+impl TxCheck {
+	/// Outcome of a check from its result.
+	pub fn new(check: TxCheckKind, res: Result<(), PoolError>) -> TxCheck {
+		TxCheck {
+			check,
+			passed: res.is_ok(),
+			error: res.err().map(|e| e.to_string()),
+		}
+	}
+}
+
+/// How a pool tx conflicts with a tx.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxConflictKind {
+	/// The same tx is already in the pool
+	DuplicateTx,
+	/// Both txs spend the same output
+	DoubleSpend,
+	/// Both txs create the same output
+	DuplicateOutput,
+	/// Both txs have the same kernel
+	DuplicateKernel,
+}
+
+/// A txpool tx conflicting with a tx.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TxConflict {
+	/// Hash of the pool tx
+	pub tx_hash: String,
+	/// Kernel excesses of the pool tx, as known by wallets
+	pub kernels: Vec<String>,
+	/// How it conflicts
+	pub conflict: TxConflictKind,
+}
+
+/// Outcome of running a tx through the pool checks without adding it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TxAcceptance {
+	/// Whether the tx passed all the checks
+	pub accepted: bool,
+	/// Fee paid by the tx
+	pub fee: u64,
+	/// Lowest fee the pool accepts for the tx weight
+	pub min_fee: u64,
+	/// Outcome of each check, in the order they are run
+	pub checks: Vec<TxCheck>,
+	/// Txpool txs conflicting with the tx
+	pub conflicts: Vec<TxConflict>,
+}
+
 /// Possible errors when interacting with the transaction pool.
 #[derive(Debug, PartialEq, Error)]
 pub enum PoolError {
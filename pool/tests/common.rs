@@ -19,6 +19,7 @@ use self::chain::types::Tip;
 use self::core::core::hash::{Hash, Hashed};
 use self::core::core::{Block, BlockHeader, BlockSums, Committed, KernelFeatures, Transaction};
 use self::core::libtx;
+use self::core::pow::Difficulty;
 use self::keychain::{ExtKeychain, Keychain};
 use self::pool::types::*;
 use self::pool::{BlockChain, TransactionPool};
@@ -147,11 +148,40 @@ impl BlockChain for ChainAdapter {
 	}
 }
 
+/// Mines a block with the given txs on top of prev.
+pub fn add_block(
+	chain: &ChainAdapter,
+	keychain: &ExtKeychain,
+	prev: &BlockHeader,
+	txs: Vec<Transaction>,
+) -> BlockHeader {
+	let height = prev.height + 1;
+	let key_id = ExtKeychain::derive_key_id(1, height as u32, 0, 0, 0);
+	let fees = txs.iter().map(|tx| tx.fee()).sum();
+	let reward = libtx::reward::output(
+		keychain,
+		&libtx::ProofBuilder::new(keychain),
+		&key_id,
+		fees,
+		false,
+		height,
+	)
+	.unwrap();
+	let mut block = Block::new(prev, txs, Difficulty::min(), reward).unwrap();
+
+	// Set the prev_root to the prev hash for testing purposes (no MMR to obtain a root from).
+	block.header.prev_root = prev.hash();
+
+	chain.update_db_for_block(&block);
+	block.header
+}
+
 pub fn test_setup<B: BlockChain>(chain: Arc<B>) -> TransactionPool<B, NoopAdapter> {
 	TransactionPool::new(
 		PoolConfig {
 			accept_fee_base: 0,
 			max_pool_size: 50,
+			max_pushed_pool_size: 50,
 			max_stempool_size: 50,
 			mineable_max_weight: 10_000,
 		},
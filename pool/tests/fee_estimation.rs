@@ -0,0 +1,71 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::core::core::Transaction;
+use self::keychain::{ExtKeychain, Keychain};
+use self::pool::{FeeEstimator, PoolEntry};
+use crate::common::*;
+use chrono::prelude::Utc;
+use chrono::Duration;
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_pool as pool;
+
+fn entry(tx: &Transaction, waited_secs: i64) -> PoolEntry {
+	PoolEntry {
+		src: test_source(),
+		tx_at: Utc::now() - Duration::seconds(waited_secs),
+		tx: tx.clone(),
+		local: false,
+	}
+}
+
+#[test]
+fn test_fee_estimation() {
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+	// weight 4, fee 80 and 8, so a fee base of 20 and 2
+	let high_fee_tx = test_transaction(&keychain, vec![100], vec![20]);
+	let low_fee_tx = test_transaction(&keychain, vec![100], vec![92]);
+
+	// nothing confirmed yet, the pool backlog sets the fee
+	let estimator = FeeEstimator::new(1);
+	let pending: Vec<_> = (0..3).map(|_| entry(&high_fee_tx, 0)).collect();
+	let estimate = estimator.fee_estimate(1, &pending, 50, Utc::now());
+	assert!(!estimate.from_history);
+	assert_eq!(estimate.fee_base, 22);
+	assert_eq!(estimate.histogram.len(), 1);
+	assert_eq!(estimate.histogram[0].fee_base, 18);
+	assert_eq!(estimate.histogram[0].tx_count, 3);
+	let estimate = estimator.fee_estimate(2, &pending, 50, Utc::now());
+	assert_eq!(estimate.fee_base, 1);
+
+	// high fee txs confirm in the next block, low fee ones after 10
+	let mut estimator = FeeEstimator::new(1);
+	let mut confirmed: Vec<_> = (0..10).map(|_| entry(&high_fee_tx, 30)).collect();
+	confirmed.extend((0..10).map(|_| entry(&low_fee_tx, 600)));
+	estimator.block_confirmed(&confirmed, Utc::now());
+
+	let estimate = estimator.fee_estimate(1, &[], 50, Utc::now());
+	assert!(estimate.from_history);
+	assert_eq!(estimate.fee_base, 18);
+	let estimate = estimator.fee_estimate(12, &[], 50, Utc::now());
+	assert_eq!(estimate.fee_base, 2);
+
+	// low fee txs stuck in the pool count against their fee
+	let stuck: Vec<_> = (0..10).map(|_| entry(&low_fee_tx, 1200)).collect();
+	let estimate = estimator.fee_estimate(12, &stuck, 50_000, Utc::now());
+	assert_eq!(estimate.fee_base, 18);
+}
@@ -0,0 +1,165 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::core::core::hash::Hashed;
+use self::core::core::BlockHeader;
+use self::keychain::{ExtKeychain, Keychain};
+use self::pool::types::NoopAdapter;
+use self::pool::{PoolConfig, PoolError, TransactionPool, TxSource};
+use crate::common::*;
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_pool as pool;
+use std::sync::Arc;
+
+/// Test full pools evict the lowest fee txs from peers, and raise their fee.
+#[test]
+fn test_pool_eviction() {
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+
+	let db_root = ".epic_pool_eviction";
+	clean_output_dir(db_root);
+	{
+		let chain = Arc::new(ChainAdapter::init(db_root.to_string()).unwrap());
+		let mut pool = TransactionPool::new(
+			PoolConfig {
+				accept_fee_base: 1,
+				max_pool_size: 4,
+				max_pushed_pool_size: 1,
+				max_stempool_size: 50,
+				mineable_max_weight: 10_000,
+			},
+			chain.clone(),
+			Arc::new(NoopAdapter {}),
+		);
+
+		let header = add_block(&chain, &keychain, &BlockHeader::default(), vec![]);
+		let outputs = vec![100, 200, 300, 400, 500, 600, 700, 800, 900, 1000, 1100];
+		let initial_tx = test_transaction_spending_coinbase(&keychain, &header, outputs);
+		let header = add_block(&chain, &keychain, &header, vec![initial_tx]);
+
+		// All txs have a weight of 4, so a fee base of a quarter of their fee.
+		let mut add = |src: TxSource, input: u64, output: u64| {
+			let tx = test_transaction(&keychain, vec![input], vec![output]);
+			pool.add_to_pool(src, tx.clone(), false, &header).map(|_| tx)
+		};
+		let tx1 = add(TxSource::Broadcast, 100, 96).unwrap();
+		let tx2 = add(TxSource::Broadcast, 200, 192).unwrap();
+		add(TxSource::Broadcast, 300, 280).unwrap();
+
+		// Past half full the min fee base rises, 3 with 3 txs in the pool.
+		assert_eq!(
+			add(TxSource::Broadcast, 400, 392),
+			Err(PoolError::LowFeeTransaction(12))
+		);
+		add(TxSource::Broadcast, 400, 380).unwrap();
+		add(TxSource::Broadcast, 500, 476).unwrap();
+
+		// The pool is full, a higher fee tx evicts the lowest fee one.
+		add(TxSource::Broadcast, 600, 560).unwrap();
+		let pushed_tx = add(TxSource::PushApi, 700, 696).unwrap();
+		add(TxSource::Broadcast, 800, 760).unwrap();
+
+		// Spam paying no more than the pool txs can't evict them.
+		assert_eq!(
+			add(TxSource::Broadcast, 900, 880),
+			Err(PoolError::LowFeeTransaction(24))
+		);
+
+		// Pushed txs have their own room.
+		add(TxSource::PushApi, 1000, 996).unwrap();
+		assert_eq!(
+			add(TxSource::PushApi, 1100, 1096),
+			Err(PoolError::OverCapacity)
+		);
+
+		// Pushed txs are kept whatever their fee, tx1 and tx2 were evicted.
+		assert_eq!(pool.total_size(), 7);
+		assert!(pool.txpool.contains_tx(pushed_tx.hash()));
+		assert!(!pool.txpool.contains_tx(tx1.hash()));
+		assert!(!pool.txpool.contains_tx(tx2.hash()));
+		assert_eq!(pool.min_fee_base(), 5);
+		assert!(pool.estimate_fee(1).fee_base >= 5);
+	}
+	clean_output_dir(db_root);
+}
+
+/// Test txs pushed through our api keep their allowances once fluffed.
+#[test]
+fn test_pushed_stem_tx_eviction() {
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+
+	let db_root = ".epic_pushed_stem_tx_eviction";
+	clean_output_dir(db_root);
+	{
+		let chain = Arc::new(ChainAdapter::init(db_root.to_string()).unwrap());
+		let mut pool = TransactionPool::new(
+			PoolConfig {
+				accept_fee_base: 1,
+				max_pool_size: 2,
+				max_pushed_pool_size: 2,
+				max_stempool_size: 50,
+				mineable_max_weight: 10_000,
+			},
+			chain.clone(),
+			Arc::new(NoopAdapter {}),
+		);
+
+		let header = add_block(&chain, &keychain, &BlockHeader::default(), vec![]);
+		let outputs = vec![100, 200, 300, 400, 500];
+		let initial_tx = test_transaction_spending_coinbase(&keychain, &header, outputs);
+		let header = add_block(&chain, &keychain, &header, vec![initial_tx]);
+
+		// Our tx pays the base fee and is stemmed.
+		let pushed_tx = test_transaction(&keychain, vec![100], vec![96]);
+		pool.add_to_pool(TxSource::PushApi, pushed_tx.clone(), true, &header)
+			.unwrap();
+		assert_eq!(pool.stempool.size(), 1);
+		assert!(pool.stempool.entries[0].local);
+
+		// Txs from peers raise the min fee base to 5.
+		let mut add = |input: u64, output: u64| {
+			let tx = test_transaction(&keychain, vec![input], vec![output]);
+			pool.add_to_pool(TxSource::Broadcast, tx.clone(), false, &header)
+				.map(|_| tx)
+		};
+		let low_fee_tx = add(200, 184).unwrap();
+		add(300, 276).unwrap();
+
+		// Fluffed as a tx from anywhere it would pay too little.
+		let entry = pool.stempool.entries[0].clone();
+		assert_eq!(
+			pool.add_to_pool(TxSource::Fluff, entry.tx.clone(), false, &header),
+			Err(PoolError::LowFeeTransaction(20))
+		);
+		pool.add_to_pool_with_origin(TxSource::Fluff, entry.tx, entry.local, false, &header)
+			.unwrap();
+		assert!(pool.txpool.contains_tx(pushed_tx.hash()));
+		assert!(pool.txpool.entries.iter().all(|x| x.local == (x.tx == pushed_tx)));
+
+		// A full pool evicts the lowest fee tx from peers, not ours.
+		let mut add = |input: u64, output: u64| {
+			let tx = test_transaction(&keychain, vec![input], vec![output]);
+			pool.add_to_pool(TxSource::Broadcast, tx.clone(), false, &header)
+				.map(|_| tx)
+		};
+		add(400, 360).unwrap();
+		add(500, 440).unwrap();
+		assert!(pool.txpool.contains_tx(pushed_tx.hash()));
+		assert!(!pool.txpool.contains_tx(low_fee_tx.hash()));
+	}
+	clean_output_dir(db_root);
+}
@@ -0,0 +1,85 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::core::core::{BlockHeader, Transaction};
+use self::keychain::{ExtKeychain, Keychain};
+use self::util::secp::pedersen::Commitment;
+use self::pool::TxSource;
+use crate::common::*;
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_pool as pool;
+use epic_util as util;
+use std::sync::Arc;
+
+fn kernel_excess(tx: &Transaction) -> Commitment {
+	tx.kernels()[0].excess()
+}
+
+/// Test operators can remove, fluff and clear pool txs.
+#[test]
+fn test_pool_management() {
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+
+	let db_root = ".epic_pool_management";
+	clean_output_dir(db_root);
+	{
+		let chain = Arc::new(ChainAdapter::init(db_root.to_string()).unwrap());
+		let mut pool = test_setup(chain.clone());
+
+		let header = add_block(&chain, &keychain, &BlockHeader::default(), vec![]);
+		let initial_tx =
+			test_transaction_spending_coinbase(&keychain, &header, vec![100, 200, 300]);
+		let header = add_block(&chain, &keychain, &header, vec![initial_tx]);
+
+		let tx1 = test_transaction(&keychain, vec![100], vec![96]);
+		let child_tx = test_transaction(&keychain, vec![96], vec![90]);
+		let tx2 = test_transaction(&keychain, vec![200], vec![196]);
+		let stem_tx = test_transaction(&keychain, vec![300], vec![296]);
+		for tx in vec![tx1.clone(), child_tx, tx2.clone()] {
+			pool.add_to_pool(test_source(), tx, false, &header).unwrap();
+		}
+		pool.add_to_pool(test_source(), stem_tx.clone(), true, &header)
+			.unwrap();
+
+		// Removing tx1 drops its child with it.
+		assert_eq!(pool.remove_tx_by_kernel_excess(&kernel_excess(&tx1)), Ok(2));
+		assert_eq!(pool.txpool.all_transactions(), vec![tx2.clone()]);
+		assert_eq!(pool.remove_tx_by_kernel_excess(&kernel_excess(&tx1)), Ok(0));
+
+		// Fluffing moves the stem tx to the txpool.
+		assert_eq!(pool.fluff_stem_tx(&kernel_excess(&tx2)), Ok(false));
+		assert_eq!(pool.fluff_stem_tx(&kernel_excess(&stem_tx)), Ok(true));
+		assert_eq!(pool.stempool.size(), 0);
+		assert_eq!(pool.txpool.all_transactions(), vec![tx2, stem_tx]);
+		assert_eq!(pool.txpool.entries[1].src, test_source());
+
+		// Our own txs stay local once fluffed.
+		let pushed_tx = test_transaction(&keychain, vec![296], vec![290]);
+		pool.add_to_pool(TxSource::PushApi, pushed_tx.clone(), true, &header)
+			.unwrap();
+		assert_eq!(pool.fluff_stem_tx(&kernel_excess(&pushed_tx)), Ok(true));
+		let entry = pool.txpool.entries.last().unwrap();
+		assert_eq!(entry.tx, pushed_tx);
+		assert_eq!(entry.src, TxSource::PushApi);
+		assert!(entry.local);
+
+		pool.clear();
+		assert_eq!(pool.total_size(), 0);
+		assert!(pool.reorg_cache.read().is_empty());
+	}
+	clean_output_dir(db_root);
+}
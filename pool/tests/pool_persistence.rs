@@ -0,0 +1,75 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::core::core::BlockHeader;
+use self::keychain::{ExtKeychain, Keychain};
+use crate::common::*;
+use epic_core as core;
+use epic_keychain as keychain;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Test the pool is saved to disk and revalidated when reloaded.
+#[test]
+fn test_pool_save_and_load() {
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+
+	let db_root = ".epic_pool_persistence";
+	clean_output_dir(db_root);
+	{
+		let chain = Arc::new(ChainAdapter::init(db_root.to_string()).unwrap());
+		let pool_file = Path::new(db_root).join("txpool.bin");
+
+		let header = add_block(&chain, &keychain, &BlockHeader::default(), vec![]);
+		let initial_tx =
+			test_transaction_spending_coinbase(&keychain, &header, vec![10, 20, 30, 40]);
+		let header = add_block(&chain, &keychain, &header, vec![initial_tx]);
+
+		let tx1 = test_transaction(&keychain, vec![10], vec![8]);
+		let tx2 = test_transaction(&keychain, vec![8], vec![5]);
+		let stem_tx = test_transaction(&keychain, vec![20], vec![15]);
+
+		// Nothing saved yet, the pool starts empty.
+		let mut pool = test_setup(chain.clone());
+		pool.load(&pool_file).unwrap();
+		assert_eq!(pool.total_size(), 0);
+
+		pool.add_to_pool(test_source(), tx1.clone(), false, &header)
+			.unwrap();
+		pool.add_to_pool(test_source(), tx2.clone(), false, &header)
+			.unwrap();
+		pool.add_to_pool(test_source(), stem_tx.clone(), true, &header)
+			.unwrap();
+		pool.save(&pool_file).unwrap();
+
+		// A restarted node gets all the txs back.
+		let mut pool = test_setup(chain.clone());
+		pool.load(&pool_file).unwrap();
+		assert_eq!(pool.txpool.all_transactions(), vec![tx1, tx2]);
+		assert_eq!(pool.stempool.all_transactions(), vec![stem_tx.clone()]);
+
+		// A block spends the input of tx1 while we were down, tx1 and its
+		// child are dropped on reload.
+		let conflict_tx = test_transaction(&keychain, vec![10], vec![7]);
+		add_block(&chain, &keychain, &header, vec![conflict_tx]);
+
+		let mut pool = test_setup(chain.clone());
+		pool.load(&pool_file).unwrap();
+		assert_eq!(pool.total_size(), 0);
+		assert_eq!(pool.stempool.all_transactions(), vec![stem_tx]);
+	}
+	clean_output_dir(db_root);
+}
@@ -0,0 +1,81 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::core::core::hash::Hashed;
+use self::core::core::BlockHeader;
+use self::keychain::{ExtKeychain, Keychain};
+use self::pool::{TxCheckKind, TxConflictKind};
+use crate::common::*;
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_pool as pool;
+use std::sync::Arc;
+
+/// Test txs are run through the pool checks without being added.
+#[test]
+fn test_transaction_accept() {
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+
+	let db_root = ".epic_transaction_accept";
+	clean_output_dir(db_root);
+	{
+		let chain = Arc::new(ChainAdapter::init(db_root.to_string()).unwrap());
+		let mut pool = test_setup(chain.clone());
+
+		let header = add_block(&chain, &keychain, &BlockHeader::default(), vec![]);
+		let initial_tx = test_transaction_spending_coinbase(&keychain, &header, vec![100, 200]);
+		let header = add_block(&chain, &keychain, &header, vec![initial_tx]);
+
+		let tx1 = test_transaction(&keychain, vec![100], vec![96]);
+		pool.add_to_pool(test_source(), tx1.clone(), false, &header)
+			.unwrap();
+
+		// A tx spending a txpool output is fine.
+		let child_tx = test_transaction(&keychain, vec![96], vec![90]);
+		let res = pool.test_accept(&child_tx).unwrap();
+		assert!(res.accepted);
+		assert_eq!(res.checks.len(), 6);
+		assert!(res.checks.iter().all(|x| x.passed && x.error.is_none()));
+		assert!(res.conflicts.is_empty());
+
+		// The same tx again, and a double spend of its input.
+		let res = pool.test_accept(&tx1).unwrap();
+		assert!(!res.accepted);
+		assert_eq!(res.conflicts.len(), 1);
+		assert_eq!(res.conflicts[0].tx_hash, tx1.hash().to_hex());
+		assert_eq!(res.conflicts[0].conflict, TxConflictKind::DuplicateTx);
+
+		let double_spend_tx = test_transaction(&keychain, vec![100], vec![90]);
+		let res = pool.test_accept(&double_spend_tx).unwrap();
+		assert!(!res.accepted);
+		assert_eq!(res.conflicts[0].conflict, TxConflictKind::DoubleSpend);
+		let chain_state = res.checks.last().unwrap();
+		assert_eq!(chain_state.check, TxCheckKind::ChainState);
+		assert!(!chain_state.passed);
+
+		// A tx spending an output we know nothing of.
+		let unknown_tx = test_transaction(&keychain, vec![5000], vec![4990]);
+		let res = pool.test_accept(&unknown_tx).unwrap();
+		assert!(!res.accepted);
+		assert!(res.conflicts.is_empty());
+		assert_eq!(res.fee, 10);
+		assert!(!res.checks.last().unwrap().passed);
+
+		// None of them got in the pool.
+		assert_eq!(pool.txpool.all_transactions(), vec![tx1]);
+	}
+	clean_output_dir(db_root);
+}
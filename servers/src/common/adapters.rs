@@ -23,8 +23,10 @@ use std::sync::{Arc, Weak};
 use std::thread;
 use std::time::Instant;
 
-use crate::chain::{self, BlockStatus, ChainAdapter, Options, SyncState, SyncStatus};
+use crate::api;
+use crate::chain::{self, BlockStatus, ChainAdapter, Options, Reorg, SyncState, SyncStatus};
 use crate::common::hooks::{ChainEvents, NetEvents};
+use crate::common::onion_stem::OnionStem;
 use crate::common::types::{ChainValidationMode, DandelionEpoch, ServerConfig};
 use crate::core::core::hash::{Hash, Hashed};
 use crate::core::core::transaction::Transaction;
@@ -493,6 +495,35 @@ where
 		}
 	}
 
+	/// Gets the compact filter of a block by its hash, if we know its filter
+	/// header too.
+	fn get_block_filter(&self, h: Hash) -> Option<(core::BlockFilter, Hash)> {
+		let chain = self.chain();
+		match (chain.get_block_filter(&h), chain.get_block_filter_header(&h)) {
+			(Ok(filter), Ok(filter_header)) => Some((filter, filter_header)),
+			_ => None,
+		}
+	}
+
+	/// We build our own filters, a received one is only checked against ours.
+	fn block_filter_received(
+		&self,
+		filter: core::BlockFilter,
+		filter_header: Hash,
+		peer_info: &PeerInfo,
+	) -> Result<bool, chain::Error> {
+		match self.chain().get_block_filter_header(&filter.block_hash) {
+			Ok(ours) if ours != filter_header => {
+				warn!(
+					"Received block filter for {} from {} not matching ours",
+					filter.block_hash, peer_info.addr
+				);
+				Ok(false)
+			}
+			_ => Ok(true),
+		}
+	}
+
 	fn kernel_data_read(&self) -> Result<File, chain::Error> {
 		self.chain().kernel_data_read()
 	}
@@ -902,6 +933,7 @@ where
 	tx_pool: Arc<RwLock<pool::TransactionPool<B, P>>>,
 	peers: OneTime<Weak<p2p::Peers>>,
 	hooks: Vec<Box<dyn ChainEvents + Send + Sync>>,
+	block_templates: Arc<api::BlockTemplates>,
 }
 
 impl<B, P> ChainAdapter for ChainToPoolAndNetAdapter<B, P>
@@ -949,6 +981,18 @@ where
 		if is_reorg {
 			let _ = self.tx_pool.write().reconcile_reorg_cache(&b.header);
 		}
+
+		// Wake up the long polls for a block template once the txpool is
+		// reconciled, so the new template does not include mined txs.
+		if status == BlockStatus::Next || is_reorg {
+			self.block_templates.tip_changed();
+		}
+	}
+	fn reorg(&self, reorg: &Reorg) {
+		// reorgs are reported during sync too
+		for hook in &self.hooks {
+			hook.on_reorg(reorg);
+		}
 	}
 }
 
@@ -961,11 +1005,13 @@ where
 	pub fn new(
 		tx_pool: Arc<RwLock<pool::TransactionPool<B, P>>>,
 		hooks: Vec<Box<dyn ChainEvents + Send + Sync>>,
+		block_templates: Arc<api::BlockTemplates>,
 	) -> Self {
 		ChainToPoolAndNetAdapter {
 			tx_pool,
 			peers: OneTime::new(),
 			hooks,
+			block_templates,
 		}
 	}
 
@@ -988,6 +1034,8 @@ where
 pub struct PoolToNetAdapter {
 	peers: OneTime<Weak<p2p::Peers>>,
 	dandelion_epoch: Arc<RwLock<DandelionEpoch>>,
+	onion_stem: RwLock<Option<OnionStem>>,
+	block_templates: Arc<api::BlockTemplates>,
 }
 
 /// Adapter between the Dandelion monitor and the current Dandelion "epoch".
@@ -1019,9 +1067,14 @@ impl DandelionAdapter for PoolToNetAdapter {
 impl pool::PoolAdapter for PoolToNetAdapter {
 	fn tx_accepted(&self, entry: &pool::PoolEntry) {
 		self.peers().broadcast_transaction(&entry.tx);
+		self.block_templates.pool_changed();
 	}
 
 	fn stem_tx_accepted(&self, entry: &pool::PoolEntry) -> Result<(), pool::PoolError> {
+		if let Some(onion_stem) = self.onion_stem.read().as_ref() {
+			return self.onion_stem_tx_accepted(onion_stem, entry);
+		}
+
 		// Take write lock on the current epoch.
 		// We need to be able to update the current relay peer if not currently connected.
 		let mut epoch = self.dandelion_epoch.write();
@@ -1030,7 +1083,7 @@ impl pool::PoolAdapter for PoolToNetAdapter {
 		// Fallback to immediately fluffing the tx if we cannot stem for any reason.
 		// If "fluff" epoch then nothing to do right now (fluff via Dandelion monitor).
 		// If node is configured to always stem our (pushed via api) txs then do so.
-		if epoch.is_stem() || (entry.src.is_pushed() && epoch.always_stem_our_txs()) {
+		if epoch.is_stem() || (entry.local && epoch.always_stem_our_txs()) {
 			if let Some(peer) = epoch.relay_peer(&self.peers()) {
 				match peer.send_stem_transaction(&entry.tx) {
 					Ok(_) => {
@@ -1055,10 +1108,15 @@ impl pool::PoolAdapter for PoolToNetAdapter {
 
 impl PoolToNetAdapter {
 	/// Create a new pool to net adapter
-	pub fn new(config: pool::DandelionConfig) -> PoolToNetAdapter {
+	pub fn new(
+		config: pool::DandelionConfig,
+		block_templates: Arc<api::BlockTemplates>,
+	) -> PoolToNetAdapter {
 		PoolToNetAdapter {
 			peers: OneTime::new(),
 			dandelion_epoch: Arc::new(RwLock::new(DandelionEpoch::new(config))),
+			onion_stem: RwLock::new(None),
+			block_templates,
 		}
 	}
 
@@ -1067,6 +1125,29 @@ impl PoolToNetAdapter {
 		self.peers.init(Arc::downgrade(&peers));
 	}
 
+	/// Stem transactions through onion-capable peers from now on, instead of
+	/// relaying them to the Dandelion relay peer.
+	pub fn init_onion_stem(&self, onion_stem: OnionStem) {
+		*self.onion_stem.write() = Some(onion_stem);
+	}
+
+	// Onion stem txs are relayed as long as they have hops left, our own and
+	// p2p stem txs follow the stem/fluff state of the current epoch.
+	fn onion_stem_tx_accepted(
+		&self,
+		onion_stem: &OnionStem,
+		entry: &pool::PoolEntry,
+	) -> Result<(), pool::PoolError> {
+		if !matches!(entry.src, pool::TxSource::OnionStem(_)) {
+			let epoch = self.dandelion_epoch.read();
+			if !epoch.is_stem() && !(entry.local && epoch.always_stem_our_txs()) {
+				info!("Fluff epoch. Aggregating stem tx(s). Will fluff via Dandelion monitor.");
+				return Ok(());
+			}
+		}
+		onion_stem.stem_tx_accepted(entry, || self.peers().all_peer_onion_addresses())
+	}
+
 	fn peers(&self) -> Arc<p2p::Peers> {
 		self.peers
 			.borrow()
@@ -1126,7 +1207,7 @@ impl pool::BlockChain for PoolToChainAdapter {
 	fn validate_tx(&self, tx: &Transaction) -> Result<(), pool::PoolError> {
 		self.chain()
 			.validate_tx(tx)
-			.map_err(|_| pool::PoolError::Other(format!("failed to validate tx")))
+			.map_err(|e| pool::PoolError::Other(format!("failed to validate tx: {}", e)))
 	}
 
 	fn verify_coinbase_maturity(&self, tx: &Transaction) -> Result<(), pool::PoolError> {
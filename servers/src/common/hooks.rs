@@ -19,11 +19,12 @@ extern crate hyper;
 extern crate hyper_rustls;
 extern crate tokio;
 
-use crate::chain::BlockStatus;
-use crate::common::types::{ServerConfig, WebHooksConfig};
+use crate::chain::{BlockStatus, Reorg};
+use crate::common::types::{Error, EventSocketConfig, ServerConfig, WebHooksConfig};
 use crate::core::core;
 use crate::core::core::hash::Hashed;
 use crate::p2p::types::PeerAddr;
+use crate::util::Mutex;
 use epic_p2p::PeerInfo;
 
 use bytes::Bytes;
@@ -35,14 +36,25 @@ use hyper_util::rt::TokioExecutor;
 
 use hyper_util::client::legacy::connect::HttpConnector;
 use rustls::RootCertStore;
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 use tokio::runtime::{Builder, Runtime};
 
 use serde::Serialize;
-use serde_json::json;
+use serde_json::{json, Value};
 
 /// Returns the list of event hooks that will be initialized for network events
-pub fn init_net_hooks(config: &ServerConfig) -> Vec<Box<dyn NetEvents + Send + Sync>> {
+pub fn init_net_hooks(
+	config: &ServerConfig,
+	event_socket: &Option<EventSocket>,
+) -> Vec<Box<dyn NetEvents + Send + Sync>> {
 	let mut list: Vec<Box<dyn NetEvents + Send + Sync>> = Vec::new();
 	list.push(Box::new(EventLogger));
 	if config.webhook_config.block_received_url.is_some()
@@ -51,16 +63,27 @@ pub fn init_net_hooks(config: &ServerConfig) -> Vec<Box<dyn NetEvents + Send + S
 	{
 		list.push(Box::new(WebHook::from_config(&config.webhook_config)));
 	}
+	if let Some(event_socket) = event_socket {
+		list.push(Box::new(event_socket.clone()));
+	}
 	list
 }
 
 /// Returns the list of event hooks that will be initialized for chain events
-pub fn init_chain_hooks(config: &ServerConfig) -> Vec<Box<dyn ChainEvents + Send + Sync>> {
+pub fn init_chain_hooks(
+	config: &ServerConfig,
+	event_socket: &Option<EventSocket>,
+) -> Vec<Box<dyn ChainEvents + Send + Sync>> {
 	let mut list: Vec<Box<dyn ChainEvents + Send + Sync>> = Vec::new();
 	list.push(Box::new(EventLogger));
-	if config.webhook_config.block_accepted_url.is_some() {
+	if config.webhook_config.block_accepted_url.is_some()
+		|| config.webhook_config.reorg_url.is_some()
+	{
 		list.push(Box::new(WebHook::from_config(&config.webhook_config)));
 	}
+	if let Some(event_socket) = event_socket {
+		list.push(Box::new(event_socket.clone()));
+	}
 	list
 }
 
@@ -80,6 +103,28 @@ pub trait NetEvents {
 pub trait ChainEvents {
 	/// Triggers when a new block is accepted by the chain (might be a Reorg or a Fork)
 	fn on_block_accepted(&self, _block: &core::Block, _status: &BlockStatus) {}
+
+	/// Triggers when the chain switches to another branch, before the
+	/// on_block_accepted of the block that became the new head
+	fn on_reorg(&self, _reorg: &Reorg) {}
+}
+
+/// JSON description of a reorg, with the hashes and heights of the blocks of
+/// both branches
+fn reorg_json(reorg: &Reorg) -> Value {
+	let blocks = |blocks: &[(u64, core::hash::Hash)]| -> Vec<Value> {
+		blocks
+			.iter()
+			.map(|(height, hash)| json!({ "height": height, "hash": hash.to_hex() }))
+			.collect()
+	};
+	json!({
+		"fork_height": reorg.fork_height,
+		"fork_hash": reorg.fork_hash.to_hex(),
+		"depth": reorg.depth(),
+		"detached": blocks(&reorg.detached),
+		"attached": blocks(&reorg.attached)
+	})
 }
 
 /// Basic Logger
@@ -149,6 +194,22 @@ impl ChainEvents for EventLogger {
 			}
 		}
 	}
+	fn on_reorg(&self, reorg: &Reorg) {
+		let hashes = |blocks: &[(u64, core::hash::Hash)]| -> Vec<String> {
+			blocks
+				.iter()
+				.map(|(height, hash)| format!("{}@{}", hash, height))
+				.collect()
+		};
+		warn!(
+			"reorg: depth {}, fork point {} at {}, detached [{}], attached [{}]",
+			reorg.depth(),
+			reorg.fork_hash,
+			reorg.fork_height,
+			hashes(&reorg.detached).join(", "),
+			hashes(&reorg.attached).join(", "),
+		);
+	}
 }
 
 fn parse_url(value: &Option<String>) -> Option<hyper::Uri> {
@@ -181,6 +242,8 @@ struct WebHook {
 	block_received_url: Option<hyper::Uri>,
 	/// url to POST block data when a new block is accepted by our node (might be a reorg or a fork)
 	block_accepted_url: Option<hyper::Uri>,
+	/// url to POST the detached and attached blocks when our chain reorgs
+	reorg_url: Option<hyper::Uri>,
 	/// The hyper client to be used for all requests
 	client: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
 
@@ -195,6 +258,7 @@ impl WebHook {
 		header_received_url: Option<hyper::Uri>,
 		block_received_url: Option<hyper::Uri>,
 		block_accepted_url: Option<hyper::Uri>,
+		reorg_url: Option<hyper::Uri>,
 		nthreads: u16,
 		timeout: u16,
 	) -> WebHook {
@@ -227,6 +291,7 @@ impl WebHook {
 			block_received_url,
 			header_received_url,
 			block_accepted_url,
+			reorg_url,
 			client,
 			runtime: Builder::new_multi_thread()
 				.worker_threads(nthreads as usize)
@@ -243,6 +308,7 @@ impl WebHook {
 			parse_url(&config.header_received_url),
 			parse_url(&config.block_received_url),
 			parse_url(&config.block_accepted_url),
+			parse_url(&config.reorg_url),
 			config.nthreads,
 			config.timeout,
 		)
@@ -317,6 +383,11 @@ impl ChainEvents for WebHook {
 			);
 		}
 	}
+	fn on_reorg(&self, reorg: &Reorg) {
+		if !self.make_request(&reorg_json(reorg), &self.reorg_url) {
+			error!("Failed to serialize reorg at fork point {}", reorg.fork_hash);
+		}
+	}
 }
 
 impl NetEvents for WebHook {
@@ -363,3 +434,210 @@ impl NetEvents for WebHook {
 		}
 	}
 }
+
+/// How long a write to a subscriber may block before the subscriber is dropped
+const EVENT_SOCKET_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Subscriber = Box<dyn Write + Send>;
+
+/// Publishes chain and network events to every subscriber connected to a
+/// local TCP or Unix domain socket, as one JSON object per line.
+/// Every event carries a sequence number, incremented by one per event, so
+/// subscribers can detect events they missed, either because they were not
+/// connected yet or because they could not keep up and the queue overflowed.
+#[derive(Clone)]
+pub struct EventSocket {
+	/// Sequence number of the last published event
+	seq: Arc<Mutex<u64>>,
+	/// Queue of serialized events, drained by the writer thread
+	sender: SyncSender<String>,
+	/// Subscribers accepted but not yet handed to the writer thread
+	new_subscribers: Arc<Mutex<Vec<Subscriber>>>,
+	/// Number of connected subscribers
+	subscriber_count: Arc<AtomicUsize>,
+}
+
+impl EventSocket {
+	/// Binds the event socket if one is configured. The address is either a
+	/// TCP socket address or a path prefixed with `unix:`.
+	pub fn from_config(config: &EventSocketConfig) -> Result<Option<EventSocket>, Error> {
+		let addr = match config.listen_addr {
+			Some(ref addr) => addr,
+			None => return Ok(None),
+		};
+		let (sender, receiver) = mpsc::sync_channel(config.max_queued_events);
+		let socket = EventSocket {
+			seq: Arc::new(Mutex::new(0)),
+			sender,
+			new_subscribers: Arc::new(Mutex::new(vec![])),
+			subscriber_count: Arc::new(AtomicUsize::new(0)),
+		};
+
+		if let Some(path) = addr.strip_prefix("unix:") {
+			socket.listen_unix(path)?;
+		} else {
+			let addr: SocketAddr = addr.parse().map_err(|_| {
+				Error::Configuration(format!("Invalid event socket address {}", addr))
+			})?;
+			let listener = TcpListener::bind(addr)?;
+			let socket = socket.clone();
+			thread::Builder::new()
+				.name("event_socket".to_string())
+				.spawn(move || {
+					for stream in listener.incoming() {
+						match stream {
+							Ok(stream) => {
+								let _ = stream.set_nodelay(true);
+								let _ = stream.set_write_timeout(Some(EVENT_SOCKET_WRITE_TIMEOUT));
+								socket.subscribe(Box::new(stream));
+							}
+							Err(e) => warn!("Failed to accept event subscriber: {}", e),
+						}
+					}
+				})?;
+		}
+		info!("Publishing events on {}", addr);
+
+		let new_subscribers = socket.new_subscribers.clone();
+		let subscriber_count = socket.subscriber_count.clone();
+		thread::Builder::new()
+			.name("event_publisher".to_string())
+			.spawn(move || publish_loop(receiver, new_subscribers, subscriber_count))?;
+
+		Ok(Some(socket))
+	}
+
+	#[cfg(unix)]
+	fn listen_unix(&self, path: &str) -> Result<(), Error> {
+		use std::os::unix::fs::FileTypeExt;
+
+		// A socket left over from a previous run would make the bind fail.
+		if let Ok(metadata) = std::fs::metadata(path) {
+			if metadata.file_type().is_socket() {
+				std::fs::remove_file(path)?;
+			}
+		}
+		let listener = UnixListener::bind(path)?;
+		let socket = self.clone();
+		thread::Builder::new()
+			.name("event_socket".to_string())
+			.spawn(move || {
+				for stream in listener.incoming() {
+					match stream {
+						Ok(stream) => {
+							let _ = stream.set_write_timeout(Some(EVENT_SOCKET_WRITE_TIMEOUT));
+							socket.subscribe(Box::new(stream));
+						}
+						Err(e) => warn!("Failed to accept event subscriber: {}", e),
+					}
+				}
+			})?;
+		Ok(())
+	}
+
+	#[cfg(not(unix))]
+	fn listen_unix(&self, path: &str) -> Result<(), Error> {
+		Err(Error::Configuration(format!(
+			"Unix domain sockets are not supported on this platform ({})",
+			path
+		)))
+	}
+
+	fn subscribe(&self, subscriber: Subscriber) {
+		self.new_subscribers.lock().push(subscriber);
+		self.subscriber_count.fetch_add(1, Ordering::SeqCst);
+	}
+
+	/// Queues an event for the subscribers, dropping it if the queue is full.
+	/// The sequence number is consumed either way.
+	fn publish(&self, event: &str, mut payload: Value) {
+		let mut seq = self.seq.lock();
+		*seq += 1;
+		if self.subscriber_count.load(Ordering::SeqCst) == 0 {
+			return;
+		}
+		payload["seq"] = json!(*seq);
+		payload["event"] = json!(event);
+		match self.sender.try_send(payload.to_string()) {
+			Ok(()) => {}
+			Err(TrySendError::Full(_)) => {
+				debug!("Event socket queue full, dropping {} event {}", event, *seq);
+			}
+			Err(TrySendError::Disconnected(_)) => {
+				error!("Event publisher thread is gone, dropping {} event {}", event, *seq);
+			}
+		}
+	}
+}
+
+/// Writes the queued events to all subscribers, dropping the subscribers we
+/// fail to write to.
+fn publish_loop(
+	receiver: Receiver<String>,
+	new_subscribers: Arc<Mutex<Vec<Subscriber>>>,
+	subscriber_count: Arc<AtomicUsize>,
+) {
+	let mut subscribers: Vec<Subscriber> = vec![];
+	for mut line in receiver {
+		line.push('\n');
+		subscribers.append(&mut new_subscribers.lock());
+		subscribers.retain_mut(|subscriber| {
+			let res = subscriber
+				.write_all(line.as_bytes())
+				.and_then(|_| subscriber.flush());
+			if let Err(e) = res {
+				debug!("Dropping event subscriber: {}", e);
+				subscriber_count.fetch_sub(1, Ordering::SeqCst);
+				return false;
+			}
+			true
+		});
+	}
+}
+
+impl ChainEvents for EventSocket {
+	fn on_block_accepted(&self, block: &core::Block, status: &BlockStatus) {
+		let status_str = match status {
+			BlockStatus::Reorg(_) => "reorg",
+			BlockStatus::Fork => "fork",
+			BlockStatus::Next => "head",
+		};
+		self.publish(
+			"block_accepted",
+			json!({
+				"hash": block.header.hash().to_hex(),
+				"height": block.header.height,
+				"status": status_str,
+				"data": block
+			}),
+		);
+	}
+	fn on_reorg(&self, reorg: &Reorg) {
+		self.publish("reorg", reorg_json(reorg));
+	}
+}
+
+impl NetEvents for EventSocket {
+	fn on_transaction_received(&self, tx: &core::Transaction, peer_info: &PeerInfo) {
+		self.publish(
+			"tx_received",
+			json!({
+				"hash": tx.hash().to_hex(),
+				"peer": peer_info.addr,
+				"data": tx
+			}),
+		);
+	}
+
+	fn on_header_received(&self, header: &core::BlockHeader, addr: &PeerAddr) {
+		self.publish(
+			"header_received",
+			json!({
+				"hash": header.hash().to_hex(),
+				"height": header.height,
+				"peer": addr,
+				"data": header
+			}),
+		);
+	}
+}
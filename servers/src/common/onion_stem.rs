@@ -0,0 +1,184 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Onion routed stem phase, replacing the Dandelion++ p2p stem relay when
+//! Tor is enabled. A stem transaction is posted to the `/v2/tor` API of a
+//! random onion-capable peer along with the number of hops left, each hop
+//! picking the next one among its own peers, until the last hop fluffs it.
+//! Every hop keeps the transaction in its stempool, so the Dandelion monitor
+//! fluffs it once the embargo timer expires if the relay broke down.
+
+use crate::common::types::Error;
+use crate::core::core::hash::Hashed;
+use crate::core::core::Transaction;
+use crate::p2p::tor::socks;
+use crate::pool::types::MAX_ONION_STEM_HOPS;
+use crate::pool::{PoolEntry, PoolError, TxSource};
+
+use rand::rng;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::thread;
+use std::time::Duration;
+
+/// How long we wait on the SOCKS proxy and the next hop.
+const ONION_STEM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Most peers we try to relay a stem transaction to before giving up and
+/// leaving it to the embargo timer.
+const ONION_STEM_ATTEMPTS: usize = 3;
+
+/// Relays stem transactions to the Tor API of onion services through the
+/// SOCKS proxy of our Tor process.
+#[derive(Debug, Clone)]
+pub struct OnionStem {
+	socks_proxy_addr: SocketAddr,
+	hops: u8,
+	timeout: Duration,
+}
+
+impl OnionStem {
+	/// Relays through the SOCKS proxy at `socks_proxy_addr`, stemming our
+	/// own transactions through `hops` peers.
+	pub fn new(socks_proxy_addr: SocketAddr, hops: u8) -> OnionStem {
+		OnionStem {
+			socks_proxy_addr,
+			hops,
+			timeout: ONION_STEM_TIMEOUT,
+		}
+	}
+
+	/// Number of peers our own stem transactions are relayed through.
+	pub fn hops(&self) -> u8 {
+		self.hops
+	}
+
+	/// Relays a transaction accepted to our stempool to the next hop, picked
+	/// among the onion urls returned by `candidates`. Onion stem transactions
+	/// keep the hops they came with, any other one starts with ours. Relaying
+	/// happens in the background, if it fails the transaction stays in our
+	/// stempool until the embargo timer fluffs it. An error means there is no
+	/// next hop and the transaction has to be fluffed right away.
+	pub fn stem_tx_accepted<F>(&self, entry: &PoolEntry, candidates: F) -> Result<(), PoolError>
+	where
+		F: FnOnce() -> Vec<String>,
+	{
+		let hops = match entry.src {
+			TxSource::OnionStem(hops) => hops.min(MAX_ONION_STEM_HOPS),
+			_ => self.hops,
+		};
+		if hops == 0 {
+			debug!("Last onion stem hop. Fluffing.");
+			return Err(PoolError::DandelionError);
+		}
+
+		let candidates = candidates();
+		if candidates.is_empty() {
+			error!("No onion relay peer. Fluffing.");
+			return Err(PoolError::DandelionError);
+		}
+		self.relay(candidates, entry.tx.clone(), hops - 1);
+		Ok(())
+	}
+
+	/// Relays the transaction to one of the candidate onion urls, picked at
+	/// random, in a background thread. The next hop relays it `hops` more
+	/// times.
+	pub fn relay(&self, mut candidates: Vec<String>, tx: Transaction, hops: u8) {
+		let onion_stem = self.clone();
+		candidates.shuffle(&mut rng());
+		let _ = thread::Builder::new()
+			.name("onion_stem".to_string())
+			.spawn(move || {
+				for url in candidates.iter().take(ONION_STEM_ATTEMPTS) {
+					match onion_stem.send(url, &tx, hops) {
+						Ok(()) => {
+							info!("Onion stem: relayed tx {} to {}", tx.hash(), url);
+							return;
+						}
+						Err(e) => warn!(
+							"Onion stem: relaying tx {} to {} failed, {}",
+							tx.hash(),
+							url,
+							e
+						),
+					}
+				}
+				warn!(
+					"Onion stem: no relay for tx {}, fluffing when the embargo expires",
+					tx.hash()
+				);
+			});
+	}
+
+	/// Posts the transaction to the `/v2/tor` API behind the onion url.
+	pub fn send(&self, url: &str, tx: &Transaction, hops: u8) -> Result<(), Error> {
+		let (host, port) = parse_onion_url(url)?;
+		let body = json!({
+			"jsonrpc": "2.0",
+			"id": 1,
+			"method": "stem_transaction",
+			"params": [tx, hops]
+		})
+		.to_string();
+
+		let mut stream = socks::connect(&self.socks_proxy_addr, &host, port, self.timeout)?;
+		write!(
+			stream,
+			"POST /v2/tor HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+			 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+			host,
+			body.len(),
+			body
+		)?;
+		let mut response = String::new();
+		stream.read_to_string(&mut response)?;
+
+		let (head, body) = response
+			.split_once("\r\n\r\n")
+			.ok_or_else(|| Error::General("truncated http response".to_owned()))?;
+		let status = head.split_whitespace().nth(1).unwrap_or("");
+		if status != "200" {
+			return Err(Error::General(format!("http status {}", status)));
+		}
+		let reply: Value = serde_json::from_str(body)
+			.map_err(|e| Error::General(format!("invalid json-rpc reply: {}", e)))?;
+		match reply.get("result").and_then(|r| r.get("Ok")) {
+			Some(_) => Ok(()),
+			None => Err(Error::General(format!("stem_transaction failed: {}", reply))),
+		}
+	}
+}
+
+/// Splits an onion url as advertised by our peers (`http://<addr>.onion:<port>`)
+/// into host and port.
+fn parse_onion_url(url: &str) -> Result<(String, u16), Error> {
+	let addr = url.strip_prefix("http://").unwrap_or(url);
+	let addr = addr.trim_end_matches('/');
+	let (host, port) = match addr.rsplit_once(':') {
+		Some((host, port)) => {
+			let port = port
+				.parse()
+				.map_err(|_| Error::General(format!("invalid onion url {}", url)))?;
+			(host, port)
+		}
+		None => (addr, 80),
+	};
+	if !host.ends_with(".onion") {
+		return Err(Error::General(format!("not an onion url {}", url)));
+	}
+	Ok((host.to_owned(), port))
+}
@@ -68,6 +68,8 @@ pub struct ServerStats {
 	pub tx_stats: Option<TxStats>,
 	/// Disk usage in GB
 	pub disk_usage_gb: String,
+	/// Disk usage in bytes
+	pub disk_usage_bytes: u64,
 }
 
 /// Chain Statistics
@@ -22,9 +22,11 @@ use rand::prelude::*;
 use crate::api;
 use crate::chain;
 use crate::core::global;
+use crate::core::core::feijoada::PolicyConfig;
 use crate::core::global::ChainTypes;
 use crate::core::{consensus, core, libtx, pow};
 use crate::keychain;
+use crate::mining::share_ledger::DEFAULT_SHARE_RETENTION_SECS;
 use crate::p2p;
 use crate::pool;
 use crate::pool::types::DandelionConfig;
@@ -152,6 +154,11 @@ pub struct ServerConfig {
 	/// Location of secret for basic auth on Rest API HTTP server.
 	pub api_secret_path: Option<String>,
 
+	/// Optional address for the Prometheus metrics listener (GET /metrics),
+	/// disabled when not set.
+	#[serde(default)]
+	pub metrics_http_addr: Option<String>,
+
 	/// Location of secret for basic auth on v2 Foreign API server.
 	pub foreign_api_secret_path: Option<String>,
 
@@ -174,6 +181,11 @@ pub struct ServerConfig {
 	/// Whether this node is a full archival node or a fast-sync, pruned node
 	pub archive_mode: Option<bool>,
 
+	/// Whether to maintain an index of kernel excess commitments, allowing
+	/// kernel lookups without scanning the kernel MMR
+	#[serde(default)]
+	pub kernel_index: Option<bool>,
+
 	/// Disable pow validation in checkpointed range, fully validate all blocks
 	/// Outside of checkpointed range
 	pub skip_pow_validation: Option<bool>,
@@ -182,6 +194,26 @@ pub struct ServerConfig {
 	/// skip_pow_validation is also set to 'true'
 	pub disable_checkpoints: Option<bool>,
 
+	/// File of trusted checkpoints replacing the built-in ones, one
+	/// `<height> <block hash>` pair per line
+	#[serde(default)]
+	pub checkpoints_file: Option<String>,
+
+	/// blake2b-256 hash of the checkpoints file, the file is refused unless
+	/// it matches
+	#[serde(default)]
+	pub checkpoints_file_hash: Option<String>,
+
+	/// Skip rangeproof and kernel signature verification for blocks at or
+	/// below the last checkpoint during body sync
+	#[serde(default)]
+	pub assume_valid: Option<bool>,
+
+	/// Refuse (and log an error for) blocks that would rewind more than this
+	/// many blocks of our chain
+	#[serde(default)]
+	pub max_reorg_depth: Option<u64>,
+
 	/// Whether to skip the sync timeout on startup
 	/// (To assist testing on solo chains)
 	pub skip_sync_wait: Option<bool>,
@@ -226,12 +258,23 @@ pub struct ServerConfig {
 	/// Configuration for the webhooks that trigger on certain events
 	#[serde(default)]
 	pub webhook_config: WebHooksConfig,
-	//#[serde(default)]
-	// Configuration for the proportions policy on EPIC
-	//pub policy_config: PolicyConfig,
+
+	/// Configuration for the local socket events are published on
+	#[serde(default)]
+	pub event_socket_config: EventSocketConfig,
+
+	/// Block policy schedule (eras, allowed policies and PoW weights), only
+	/// used on test networks
+	#[serde(default)]
+	pub policy_config: Option<PolicyConfig>,
+
 	/// Tor configuration for this node
 	#[serde(default)]
 	pub tor: TorConfig,
+
+	/// Thresholds for the periodic node health checks
+	#[serde(default)]
+	pub health_check_config: HealthCheckConfig,
 }
 
 impl Default for ServerConfig {
@@ -240,6 +283,7 @@ impl Default for ServerConfig {
 			db_root: "epic_chain".to_string(),
 			api_http_addr: "127.0.0.1:3413".to_string(),
 			api_secret_path: Some(".api_secret".to_string()),
+			metrics_http_addr: None,
 			foreign_api_secret_path: Some(".foreign_api_secret".to_string()),
 			foundation_path: "foundation".to_string(),
 			tls_certificate_file: None,
@@ -249,8 +293,13 @@ impl Default for ServerConfig {
 			stratum_mining_config: Some(StratumServerConfig::default()),
 			chain_type: ChainTypes::default(),
 			archive_mode: Some(false),
+			kernel_index: Some(false),
 			skip_pow_validation: Some(true),
 			disable_checkpoints: Some(true),
+			checkpoints_file: None,
+			checkpoints_file_hash: None,
+			assume_valid: Some(false),
+			max_reorg_depth: None,
 			chain_validation_mode: ChainValidationMode::default(),
 			pool_config: pool::PoolConfig::default(),
 			skip_sync_wait: Some(false),
@@ -261,8 +310,10 @@ impl Default for ServerConfig {
 			run_test_miner: Some(false),
 			test_miner_wallet_url: None,
 			webhook_config: WebHooksConfig::default(),
+			event_socket_config: EventSocketConfig::default(),
 			tor: TorConfig::default(),
-			//policy_config: PolicyConfig::default(),
+			health_check_config: HealthCheckConfig::default(),
+			policy_config: None,
 		}
 	}
 }
@@ -277,6 +328,27 @@ pub struct StratumServerConfig {
 	/// If enabled, the address and port to listen on
 	pub stratum_server_addr: Option<String>,
 
+	/// If set, the address and port to also listen on for TLS connections
+	#[serde(default)]
+	pub stratum_tls_server_addr: Option<String>,
+
+	/// Certificate file of the TLS listener
+	#[serde(default)]
+	pub stratum_tls_certificate_file: Option<String>,
+
+	/// Private key of the TLS listener certificate
+	#[serde(default)]
+	pub stratum_tls_certificate_key: Option<String>,
+
+	/// Worker logins as `login:password`, if set (or a password file is) only
+	/// these workers can log in and mine
+	#[serde(default)]
+	pub worker_credentials: Option<Vec<String>>,
+
+	/// File of worker logins as `login:salt:hex(sha256(salt + password))` lines
+	#[serde(default)]
+	pub worker_password_file: Option<String>,
+
 	/// How long to wait before stopping the miner, recollecting transactions
 	/// and starting again
 	pub attempt_time_per_block: u32,
@@ -290,6 +362,33 @@ pub struct StratumServerConfig {
 	/// Progpow Minimum difficulty for worker shares
 	pub progpow_minimum_share_difficulty: u64,
 
+	/// Cuckatoo maximum difficulty vardiff may request from a worker, the
+	/// network difficulty if not set
+	#[serde(default)]
+	pub cuckatoo_maximum_share_difficulty: Option<u64>,
+
+	/// Randomx maximum difficulty vardiff may request from a worker
+	#[serde(default)]
+	pub randomx_maximum_share_difficulty: Option<u64>,
+
+	/// Progpow maximum difficulty vardiff may request from a worker
+	#[serde(default)]
+	pub progpow_maximum_share_difficulty: Option<u64>,
+
+	/// Seconds between shares vardiff aims for with each worker, 0 disables
+	/// vardiff and all workers mine at the minimum share difficulty
+	#[serde(default)]
+	pub vardiff_target_share_time: Option<u64>,
+
+	/// Seconds of shares vardiff looks at before retargeting a worker
+	#[serde(default)]
+	pub vardiff_retarget_time: Option<u64>,
+
+	/// Seconds accepted shares are kept for the PPLNS and PPS windows, the
+	/// older ones are pruned
+	#[serde(default)]
+	pub share_retention_secs: Option<u64>,
+
 	/// Base address to the HTTP wallet receiver
 	pub wallet_listener_url: String,
 
@@ -309,8 +408,19 @@ impl Default for StratumServerConfig {
 				cuckatoo_minimum_share_difficulty: consensus::MIN_DIFFICULTY,
 				randomx_minimum_share_difficulty: consensus::MIN_DIFFICULTY_RANDOMX_TESTING,
 				progpow_minimum_share_difficulty: consensus::MIN_DIFFICULTY_PROGPOW,
+				cuckatoo_maximum_share_difficulty: None,
+				randomx_maximum_share_difficulty: None,
+				progpow_maximum_share_difficulty: None,
+				vardiff_target_share_time: Some(10),
+				vardiff_retarget_time: Some(60),
+				share_retention_secs: Some(DEFAULT_SHARE_RETENTION_SECS),
 				enable_stratum_server: Some(true),
 				stratum_server_addr: Some("127.0.0.1:3416".to_string()),
+				stratum_tls_server_addr: None,
+				stratum_tls_certificate_file: None,
+				stratum_tls_certificate_key: None,
+				worker_credentials: None,
+				worker_password_file: None,
 			},
 			_ => StratumServerConfig {
 				wallet_listener_url: "http://127.0.0.1:3415".to_string(),
@@ -319,8 +429,19 @@ impl Default for StratumServerConfig {
 				cuckatoo_minimum_share_difficulty: consensus::MIN_DIFFICULTY,
 				randomx_minimum_share_difficulty: consensus::MIN_DIFFICULTY_RANDOMX,
 				progpow_minimum_share_difficulty: consensus::MIN_DIFFICULTY_PROGPOW,
+				cuckatoo_maximum_share_difficulty: None,
+				randomx_maximum_share_difficulty: None,
+				progpow_maximum_share_difficulty: None,
+				vardiff_target_share_time: Some(10),
+				vardiff_retarget_time: Some(60),
+				share_retention_secs: Some(DEFAULT_SHARE_RETENTION_SECS),
 				enable_stratum_server: Some(false),
 				stratum_server_addr: Some("127.0.0.1:3416".to_string()),
+				stratum_tls_server_addr: None,
+				stratum_tls_certificate_file: None,
+				stratum_tls_certificate_key: None,
+				worker_credentials: None,
+				worker_password_file: None,
 			},
 		}
 	}
@@ -337,6 +458,9 @@ pub struct WebHooksConfig {
 	pub block_received_url: Option<String>,
 	/// url to POST block data when a new block is accepted by our node (might be a reorg or a fork)
 	pub block_accepted_url: Option<String>,
+	/// url to POST the detached and attached blocks when our chain reorgs
+	#[serde(default)]
+	pub reorg_url: Option<String>,
 	/// number of worker threads in the tokio runtime
 	#[serde(default = "default_nthreads")]
 	pub nthreads: u16,
@@ -360,12 +484,83 @@ impl Default for WebHooksConfig {
 			header_received_url: None,
 			block_received_url: None,
 			block_accepted_url: None,
+			reorg_url: None,
 			nthreads: default_nthreads(),
 			timeout: default_timeout(),
 		}
 	}
 }
 
+/// Event socket configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventSocketConfig {
+	/// TCP address (or Unix domain socket path prefixed with `unix:`) to
+	/// publish block, header, transaction and reorg events on
+	pub listen_addr: Option<String>,
+	/// number of events queued for the subscribers before new events get
+	/// dropped
+	#[serde(default = "default_max_queued_events")]
+	pub max_queued_events: usize,
+}
+
+fn default_max_queued_events() -> usize {
+	1024
+}
+
+impl Default for EventSocketConfig {
+	fn default() -> EventSocketConfig {
+		EventSocketConfig {
+			listen_addr: None,
+			max_queued_events: default_max_queued_events(),
+		}
+	}
+}
+
+/// Node health check configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthCheckConfig {
+	/// seconds between two runs of the health checks
+	#[serde(default = "default_health_check_interval")]
+	pub check_interval: u64,
+	/// how many blocks we can be behind the best peer and still be ready
+	#[serde(default = "default_max_sync_lag")]
+	pub max_sync_lag: u64,
+	/// how many blocks the header head can be ahead of the body head and
+	/// still be ready
+	#[serde(default = "default_max_header_lag")]
+	pub max_header_lag: u64,
+	/// warn when the txpool is filled above this percentage of max_pool_size
+	#[serde(default = "default_pool_warn_percent")]
+	pub pool_warn_percent: u8,
+}
+
+fn default_health_check_interval() -> u64 {
+	30
+}
+
+fn default_max_sync_lag() -> u64 {
+	5
+}
+
+fn default_max_header_lag() -> u64 {
+	60
+}
+
+fn default_pool_warn_percent() -> u8 {
+	90
+}
+
+impl Default for HealthCheckConfig {
+	fn default() -> HealthCheckConfig {
+		HealthCheckConfig {
+			check_interval: default_health_check_interval(),
+			max_sync_lag: default_max_sync_lag(),
+			max_header_lag: default_max_header_lag(),
+			pool_warn_percent: default_pool_warn_percent(),
+		}
+	}
+}
+
 /// A node is either "stem" of "fluff" for the duration of a single epoch.
 /// A node also maintains an outbound relay peer for the epoch.
 #[derive(Debug)]
@@ -466,6 +661,17 @@ pub struct TorConfig {
 	pub socks_proxy_addr: String,
 	/// Send configuration directory
 	pub send_config_dir: String,
+	/// Control port of a running tor daemon, if set we publish an ephemeral
+	/// onion service on it instead of launching our own tor process
+	#[serde(default)]
+	pub control_addr: Option<String>,
+	/// Password for the control port, if it uses HashedControlPassword
+	#[serde(default)]
+	pub control_password: Option<String>,
+	/// Keep the key of our onion service under db_root, so the onion address
+	/// survives restarts. Off by default, the key then only lives in memory
+	#[serde(default)]
+	pub persist_onion_key: bool,
 }
 
 impl Default for TorConfig {
@@ -473,6 +679,9 @@ impl Default for TorConfig {
 		TorConfig {
 			socks_proxy_addr: "127.0.0.1:9050".to_owned(),
 			send_config_dir: ".".into(),
+			control_addr: None,
+			control_password: None,
+			persist_onion_key: false,
 		}
 	}
 }
@@ -15,6 +15,9 @@
 //! Epic P2P / API server
 
 pub mod dandelion_monitor;
+pub mod health;
+pub mod metrics;
+pub mod pool_saver;
 pub mod seed;
 pub mod server;
 pub mod sync;
@@ -15,10 +15,12 @@
 use crate::common::adapters::DandelionAdapter;
 use crate::core::core::hash::Hashed;
 use crate::core::core::transaction;
-use crate::pool::{BlockChain, DandelionConfig, Pool, PoolEntry, PoolError, TxSource};
-use crate::util::StopState;
+use crate::pool::{
+	BlockChain, DandelionConfig, Pool, PoolAdapter, PoolEntry, PoolError, TransactionPool,
+	TxSource,
+};
+use crate::util::{RwLock, StopState};
 
-use crate::ServerTxPool;
 use chrono::prelude::Utc;
 use rand::{rng, Rng};
 use std::sync::Arc;
@@ -32,12 +34,18 @@ use std::time::{Duration, Instant};
 /// stempool and test if the timer is expired for each transaction. In that case
 /// the transaction will be sent in fluff phase (to multiple peers) instead of
 /// sending only to the peer relay.
-pub fn monitor_transactions(
+/// With the onion stem the same timer fluffs transactions whose relay to the
+/// next onion hop failed.
+pub fn monitor_transactions<B, P>(
 	dandelion_config: DandelionConfig,
-	tx_pool: ServerTxPool,
+	tx_pool: Arc<RwLock<TransactionPool<B, P>>>,
 	adapter: Arc<dyn DandelionAdapter>,
 	stop_state: Arc<StopState>,
-) -> std::io::Result<thread::JoinHandle<()>> {
+) -> std::io::Result<thread::JoinHandle<()>>
+where
+	B: BlockChain + 'static,
+	P: PoolAdapter + 'static,
+{
 	debug!("Started Dandelion transaction monitor.");
 
 	thread::Builder::new()
@@ -96,11 +104,15 @@ where
 		.collect()
 }
 
-fn process_fluff_phase(
+fn process_fluff_phase<B, P>(
 	dandelion_config: &DandelionConfig,
-	tx_pool: &ServerTxPool,
+	tx_pool: &RwLock<TransactionPool<B, P>>,
 	adapter: &Arc<dyn DandelionAdapter>,
-) -> Result<(), PoolError> {
+) -> Result<(), PoolError>
+where
+	B: BlockChain,
+	P: PoolAdapter,
+{
 	// Take a write lock on the txpool for the duration of this processing.
 	let mut tx_pool = tx_pool.write();
 
@@ -123,7 +135,7 @@ fn process_fluff_phase(
 
 	let fluffable_txs = {
 		let txpool_tx = tx_pool.txpool.all_transactions_aggregate()?;
-		let txs: Vec<_> = all_entries.into_iter().map(|x| x.tx).collect();
+		let txs: Vec<_> = all_entries.iter().map(|x| x.tx.clone()).collect();
 		tx_pool.stempool.validate_raw_txs(
 			&txs,
 			txpool_tx,
@@ -137,17 +149,31 @@ fn process_fluff_phase(
 		fluffable_txs.len()
 	);
 
-	let agg_tx = transaction::aggregate(fluffable_txs)?;
-	agg_tx.validate(transaction::Weighting::AsTransaction)?;
-
-	tx_pool.add_to_pool(TxSource::Fluff, agg_tx, false, &header)?;
+	// Our own txs and the ones received from peers are aggregated apart, so
+	// the received ones never get the allowances of local txs.
+	let (local_txs, received_txs): (Vec<_>, Vec<_>) = fluffable_txs
+		.into_iter()
+		.partition(|tx| all_entries.iter().any(|x| x.local && x.tx == *tx));
+	for (txs, local) in vec![(local_txs, true), (received_txs, false)] {
+		if txs.is_empty() {
+			continue;
+		}
+		let agg_tx = transaction::aggregate(txs)?;
+		agg_tx.validate(transaction::Weighting::AsTransaction)?;
+
+		tx_pool.add_to_pool_with_origin(TxSource::Fluff, agg_tx, local, false, &header)?;
+	}
 	Ok(())
 }
 
-fn process_expired_entries(
+fn process_expired_entries<B, P>(
 	dandelion_config: &DandelionConfig,
-	tx_pool: &ServerTxPool,
-) -> Result<(), PoolError> {
+	tx_pool: &RwLock<TransactionPool<B, P>>,
+) -> Result<(), PoolError>
+where
+	B: BlockChain,
+	P: PoolAdapter,
+{
 	// Take a write lock on the txpool for the duration of this processing.
 	let mut tx_pool = tx_pool.write();
 
@@ -164,7 +190,8 @@ fn process_expired_entries(
 
 	for entry in expired_entries {
 		let txhash = entry.tx.hash();
-		match tx_pool.add_to_pool(TxSource::EmbargoExpired, entry.tx, false, &header) {
+		let src = TxSource::EmbargoExpired;
+		match tx_pool.add_to_pool_with_origin(src, entry.tx, entry.local, false, &header) {
 			Ok(_) => info!(
 				"dand_mon: embargo expired for {}, fluffed successfully.",
 				txhash
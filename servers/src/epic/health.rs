@@ -0,0 +1,264 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Periodic node health checks, feeding the liveness/readiness report served
+//! by the `/v2/health` endpoints and the owner API.
+
+use crate::api::{HealthCheck, HealthReport, HealthState};
+use crate::chain;
+use crate::common::stats::StratumStats;
+use crate::common::types::HealthCheckConfig;
+use crate::p2p;
+use crate::p2p::tor::process::TorProcess;
+use crate::util::{Mutex, RwLock, StopState};
+use crate::ServerTxPool;
+use chrono::prelude::Utc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Everything the health checks need to look at.
+pub struct HealthChecker {
+	pub config: HealthCheckConfig,
+	pub chain: Arc<chain::Chain>,
+	pub peers: Arc<p2p::Peers>,
+	pub tx_pool: ServerTxPool,
+	pub stratum_stats: Arc<RwLock<StratumStats>>,
+	/// Only set when the node launched its own tor process
+	pub tor_process: Option<Arc<Mutex<Option<TorProcess>>>>,
+}
+
+impl HealthChecker {
+	/// Runs all the checks once and builds a report valid for a few check
+	/// intervals.
+	pub fn run(&self) -> HealthReport {
+		let checks = vec![
+			self.check_store(),
+			self.check_sync_lag(),
+			self.check_header_divergence(),
+			self.check_tor(),
+			self.check_stratum(),
+			self.check_pool(),
+		];
+		let now = Utc::now().timestamp();
+		let expires_at = now + 3 * self.config.check_interval as i64;
+		HealthReport::new(checks, now, expires_at)
+	}
+
+	fn check_store(&self) -> HealthCheck {
+		let name = "store";
+		if let Err(e) = self.chain.head() {
+			return HealthCheck::new(
+				name,
+				HealthState::Critical,
+				true,
+				format!("can't read chain head: {}", e),
+			);
+		}
+		match self.chain.store().needs_resize() {
+			Ok(false) => HealthCheck::new(name, HealthState::Ok, true, "chain db readable".into()),
+			Ok(true) => HealthCheck::new(
+				name,
+				HealthState::Warning,
+				true,
+				"lmdb map nearly full, will resize on next write".into(),
+			),
+			Err(e) => HealthCheck::new(
+				name,
+				HealthState::Warning,
+				true,
+				format!("can't read lmdb env info: {}", e),
+			),
+		}
+	}
+
+	fn check_sync_lag(&self) -> HealthCheck {
+		let name = "sync_lag";
+		let height = match self.chain.head() {
+			Ok(head) => head.height,
+			Err(e) => {
+				return HealthCheck::new(name, HealthState::Critical, false, format!("{}", e))
+			}
+		};
+		let best_peer_height = self
+			.peers
+			.connected_peers()
+			.iter()
+			.map(|p| p.info.live_info.read().height)
+			.max();
+		match best_peer_height {
+			None => HealthCheck::new(
+				name,
+				HealthState::Critical,
+				false,
+				"no connected peers".into(),
+			),
+			Some(peer_height) => {
+				let lag = peer_height.saturating_sub(height);
+				let state = if lag > self.config.max_sync_lag {
+					HealthState::Critical
+				} else {
+					HealthState::Ok
+				};
+				HealthCheck::new(
+					name,
+					state,
+					false,
+					format!(
+						"{} blocks behind best peer (height {})",
+						lag, peer_height
+					),
+				)
+			}
+		}
+	}
+
+	fn check_header_divergence(&self) -> HealthCheck {
+		let name = "header_divergence";
+		match (self.chain.head(), self.chain.header_head()) {
+			(Ok(head), Ok(header_head)) => {
+				let lag = header_head.height.saturating_sub(head.height);
+				let state = if lag > self.config.max_header_lag {
+					HealthState::Critical
+				} else {
+					HealthState::Ok
+				};
+				HealthCheck::new(
+					name,
+					state,
+					false,
+					format!(
+						"header head {} is {} blocks ahead of body head {}",
+						header_head.height, lag, head.height
+					),
+				)
+			}
+			(Err(e), _) | (_, Err(e)) => {
+				HealthCheck::new(name, HealthState::Critical, false, format!("{}", e))
+			}
+		}
+	}
+
+	fn check_tor(&self) -> HealthCheck {
+		let name = "tor";
+		match self.tor_process {
+			None => HealthCheck::new(name, HealthState::Ok, true, "disabled".into()),
+			Some(ref tor) => match tor.lock().as_mut() {
+				Some(process) if process.is_running() => {
+					HealthCheck::new(name, HealthState::Ok, true, "tor process running".into())
+				}
+				_ => HealthCheck::new(
+					name,
+					HealthState::Critical,
+					true,
+					"tor process is not running".into(),
+				),
+			},
+		}
+	}
+
+	fn check_stratum(&self) -> HealthCheck {
+		let name = "stratum";
+		let stats = self.stratum_stats.read();
+		if !stats.is_enabled {
+			HealthCheck::new(name, HealthState::Ok, false, "disabled".into())
+		} else if !stats.is_running {
+			HealthCheck::new(
+				name,
+				HealthState::Critical,
+				false,
+				"stratum server is enabled but not running".into(),
+			)
+		} else if stats.num_workers == 0 {
+			HealthCheck::new(
+				name,
+				HealthState::Warning,
+				false,
+				"no connected workers".into(),
+			)
+		} else {
+			HealthCheck::new(
+				name,
+				HealthState::Ok,
+				false,
+				format!("{} connected workers", stats.num_workers),
+			)
+		}
+	}
+
+	fn check_pool(&self) -> HealthCheck {
+		let name = "tx_pool";
+		let pool = self.tx_pool.read();
+		let size = pool.total_size();
+		let max = pool.config.max_pool_size;
+		let state = if size * 100 > max * self.config.pool_warn_percent as usize {
+			HealthState::Warning
+		} else {
+			HealthState::Ok
+		};
+		HealthCheck::new(
+			name,
+			state,
+			false,
+			format!(
+				"{} txs in txpool (max {}), {} in stempool",
+				pool.txpool.size(),
+				max,
+				pool.stempool.size()
+			),
+		)
+	}
+}
+
+/// Runs the health checks every `check_interval` seconds and publishes the
+/// result in `report`. The first run happens before returning, so the report
+/// is meaningful as soon as the API is up.
+pub fn monitor(
+	checker: HealthChecker,
+	report: Arc<RwLock<HealthReport>>,
+	stop_state: Arc<StopState>,
+) -> std::io::Result<thread::JoinHandle<()>> {
+	*report.write() = checker.run();
+	debug!("Started health check monitor.");
+
+	thread::Builder::new()
+		.name("health".to_string())
+		.spawn(move || {
+			let run_interval = Duration::from_secs(checker.config.check_interval);
+			let mut last_run = Instant::now();
+			loop {
+				if stop_state.is_stopped() {
+					break;
+				}
+
+				if last_run.elapsed() > run_interval {
+					let new_report = checker.run();
+					if !new_report.ready {
+						let failing: Vec<_> = new_report
+							.checks
+							.iter()
+							.filter(|c| c.state == HealthState::Critical)
+							.map(|c| format!("{}: {}", c.name, c.message))
+							.collect();
+						debug!("health: node not ready: {:?}", failing);
+					}
+					*report.write() = new_report;
+					last_run = Instant::now();
+				}
+
+				// Check stop flag every second.
+				thread::sleep(Duration::from_secs(1));
+			}
+		})
+}
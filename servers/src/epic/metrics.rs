@@ -0,0 +1,547 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Prometheus exporter for the server stats. Serves the Prometheus text
+//! exposition format on `GET /metrics` of a dedicated listener.
+
+use crate::common::stats::{ChainStats, DiffBlock, ServerStats};
+use crate::common::types::Error;
+use crate::core::pow::PoWType;
+use crate::epic::server::StatsSource;
+use crate::util::StopState;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const ALGOS: [(PoWType, &str); 3] = [
+	(PoWType::Cuckatoo, "cuckatoo"),
+	(PoWType::RandomX, "randomx"),
+	(PoWType::ProgPow, "progpow"),
+];
+
+/// Accumulates metrics in the Prometheus text format, writing the HELP and
+/// TYPE lines once per metric family. The samples of a family have to follow
+/// its HELP and TYPE lines, before the next family starts.
+struct MetricsWriter {
+	out: String,
+}
+
+impl MetricsWriter {
+	fn new() -> MetricsWriter {
+		MetricsWriter { out: String::new() }
+	}
+
+	fn family(&mut self, name: &str, kind: &str, help: &str) -> &mut Self {
+		let _ = writeln!(self.out, "# HELP {} {}", name, help);
+		let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+		self
+	}
+
+	fn sample<V: std::fmt::Display>(
+		&mut self,
+		name: &str,
+		labels: &[(&str, &str)],
+		value: V,
+	) -> &mut Self {
+		self.out.push_str(name);
+		if !labels.is_empty() {
+			let labels: Vec<String> = labels
+				.iter()
+				.map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+				.collect();
+			let _ = write!(self.out, "{{{}}}", labels.join(","));
+		}
+		let _ = writeln!(self.out, " {}", value);
+		self
+	}
+}
+
+fn escape_label(value: &str) -> String {
+	value
+		.replace('\\', "\\\\")
+		.replace('"', "\\\"")
+		.replace('\n', "\\n")
+}
+
+fn bool_value(b: bool) -> u8 {
+	if b {
+		1
+	} else {
+		0
+	}
+}
+
+/// Name of the sync status variant, without its fields.
+fn sync_status_name(stats: &ServerStats) -> String {
+	let debug = format!("{:?}", stats.sync_status);
+	debug
+		.split(|c: char| c == ' ' || c == '(' || c == '{')
+		.next()
+		.unwrap_or("")
+		.to_owned()
+}
+
+/// Renders the server stats as Prometheus metrics.
+pub fn render(stats: &ServerStats) -> String {
+	let mut m = MetricsWriter::new();
+
+	m.family("epic_peer_count", "gauge", "Number of connected peers.")
+		.sample("epic_peer_count", &[], stats.peer_count);
+
+	m.family(
+		"epic_sync_status",
+		"gauge",
+		"Current sync status, as a label on a constant 1.",
+	)
+	.sample(
+		"epic_sync_status",
+		&[("status", sync_status_name(stats).as_str())],
+		1,
+	);
+
+	m.family("epic_disk_usage_bytes", "gauge", "Size of the chain data dir.")
+		.sample("epic_disk_usage_bytes", &[], stats.disk_usage_bytes);
+
+	// Chain
+	let heads: [(&str, &ChainStats); 2] = [
+		("body", &stats.chain_stats),
+		("header", &stats.header_stats),
+	];
+	m.family("epic_chain_height", "gauge", "Height of the body or header head.");
+	for (head, chain) in heads.iter() {
+		m.sample("epic_chain_height", &[("head", *head)], chain.height);
+	}
+	m.family(
+		"epic_chain_latest_timestamp_seconds",
+		"gauge",
+		"Timestamp of the body or header head.",
+	);
+	for (head, chain) in heads.iter() {
+		m.sample(
+			"epic_chain_latest_timestamp_seconds",
+			&[("head", *head)],
+			chain.latest_timestamp.timestamp(),
+		);
+	}
+	m.family(
+		"epic_chain_total_difficulty",
+		"gauge",
+		"Total difficulty of the body or header head, per algorithm.",
+	);
+	for (head, chain) in heads.iter() {
+		for (algo, name) in ALGOS.iter() {
+			m.sample(
+				"epic_chain_total_difficulty",
+				&[("head", *head), ("algo", *name)],
+				chain.total_difficulty.to_num(algo.clone()),
+			);
+		}
+	}
+
+	// Difficulty, latest and averaged over the window, per algorithm
+	let mut by_algo: HashMap<&str, Vec<&DiffBlock>> = HashMap::new();
+	for b in stats.diff_stats.last_blocks.iter() {
+		by_algo.entry(b.algorithm.as_str()).or_default().push(b);
+	}
+	let algo_blocks: Vec<(&str, Vec<&DiffBlock>)> = ALGOS
+		.iter()
+		.map(|(_, name)| {
+			let display = match *name {
+				"cuckatoo" => "Cuckatoo",
+				"randomx" => "RandomX",
+				_ => "ProgPow",
+			};
+			(*name, by_algo.get(display).cloned().unwrap_or_default())
+		})
+		.collect();
+	m.family(
+		"epic_difficulty",
+		"gauge",
+		"Network difficulty of the latest block mined with each algorithm.",
+	);
+	for (name, blocks) in algo_blocks.iter() {
+		if let Some(last) = blocks.last() {
+			m.sample("epic_difficulty", &[("algo", *name)], last.difficulty);
+		}
+	}
+	m.family(
+		"epic_difficulty_window_avg",
+		"gauge",
+		"Average difficulty per algorithm over the difficulty window.",
+	);
+	for (name, blocks) in algo_blocks.iter().filter(|(_, b)| !b.is_empty()) {
+		let diff_sum: u64 = blocks.iter().map(|b| b.difficulty).sum();
+		let avg = diff_sum / blocks.len() as u64;
+		m.sample("epic_difficulty_window_avg", &[("algo", *name)], avg);
+	}
+	m.family(
+		"epic_block_time_window_avg_seconds",
+		"gauge",
+		"Average block time per algorithm over the difficulty window.",
+	);
+	for (name, blocks) in algo_blocks.iter().filter(|(_, b)| !b.is_empty()) {
+		let time_sum: u64 = blocks.iter().map(|b| b.duration).sum();
+		let avg = time_sum / blocks.len() as u64;
+		m.sample("epic_block_time_window_avg_seconds", &[("algo", *name)], avg);
+	}
+	m.family(
+		"epic_difficulty_window_blocks",
+		"gauge",
+		"Number of blocks per algorithm in the difficulty window.",
+	);
+	for (name, blocks) in algo_blocks.iter() {
+		m.sample("epic_difficulty_window_blocks", &[("algo", *name)], blocks.len());
+	}
+
+	// Transaction pool
+	if let Some(tx) = &stats.tx_stats {
+		m.family("epic_pool_transactions", "gauge", "Transactions in the pool.")
+			.sample("epic_pool_transactions", &[("pool", "txpool")], tx.tx_pool_size)
+			.sample(
+				"epic_pool_transactions",
+				&[("pool", "stempool")],
+				tx.stem_pool_size,
+			);
+		m.family("epic_pool_kernels", "gauge", "Kernels in the pool.")
+			.sample("epic_pool_kernels", &[("pool", "txpool")], tx.tx_pool_kernels)
+			.sample(
+				"epic_pool_kernels",
+				&[("pool", "stempool")],
+				tx.stem_pool_kernels,
+			);
+	}
+
+	// Stratum
+	let stratum = &stats.stratum_stats;
+	m.family("epic_stratum_enabled", "gauge", "Whether stratum is enabled.")
+		.sample("epic_stratum_enabled", &[], bool_value(stratum.is_enabled));
+	m.family("epic_stratum_running", "gauge", "Whether stratum is running.")
+		.sample("epic_stratum_running", &[], bool_value(stratum.is_running));
+	m.family("epic_stratum_workers", "gauge", "Connected stratum workers.")
+		.sample("epic_stratum_workers", &[], stratum.num_workers);
+	m.family(
+		"epic_stratum_block_height",
+		"gauge",
+		"Height of the block being mined.",
+	)
+	.sample("epic_stratum_block_height", &[], stratum.block_height);
+	m.family(
+		"epic_stratum_network_difficulty",
+		"gauge",
+		"Network difficulty of the block being mined, per algorithm.",
+	);
+	for (algo, name) in ALGOS.iter() {
+		if let Some(diff) = stratum.network_difficulty.get(algo) {
+			m.sample("epic_stratum_network_difficulty", &[("algo", *name)], diff);
+		}
+	}
+	m.family(
+		"epic_stratum_worker_shares_total",
+		"counter",
+		"Shares submitted per worker and result.",
+	);
+	for w in stratum.worker_stats.iter() {
+		let id = w.id.as_str();
+		m.sample(
+			"epic_stratum_worker_shares_total",
+			&[("worker", id), ("result", "accepted")],
+			w.num_accepted,
+		)
+		.sample(
+			"epic_stratum_worker_shares_total",
+			&[("worker", id), ("result", "rejected")],
+			w.num_rejected,
+		)
+		.sample(
+			"epic_stratum_worker_shares_total",
+			&[("worker", id), ("result", "stale")],
+			w.num_stale,
+		);
+	}
+	m.family(
+		"epic_stratum_worker_blocks_found_total",
+		"counter",
+		"Blocks found per worker.",
+	);
+	for w in stratum.worker_stats.iter() {
+		m.sample(
+			"epic_stratum_worker_blocks_found_total",
+			&[("worker", w.id.as_str())],
+			w.num_blocks_found,
+		);
+	}
+	m.family(
+		"epic_stratum_worker_difficulty",
+		"gauge",
+		"Share difficulty requested from each worker.",
+	);
+	for w in stratum.worker_stats.iter() {
+		m.sample(
+			"epic_stratum_worker_difficulty",
+			&[("worker", w.id.as_str())],
+			w.pow_difficulty,
+		);
+	}
+
+	// Peers
+	m.family("epic_peer_height", "gauge", "Height reported by each peer.");
+	for p in stats.peer_stats.iter() {
+		m.sample(
+			"epic_peer_height",
+			&[("peer", p.addr.as_str()), ("direction", p.direction.as_str())],
+			p.height,
+		);
+	}
+	m.family(
+		"epic_peer_sent_bytes_per_second",
+		"gauge",
+		"Bytes per second sent to each peer, over the last minute.",
+	);
+	for p in stats.peer_stats.iter() {
+		m.sample(
+			"epic_peer_sent_bytes_per_second",
+			&[("peer", p.addr.as_str())],
+			p.sent_bytes_per_sec,
+		);
+	}
+	m.family(
+		"epic_peer_received_bytes_per_second",
+		"gauge",
+		"Bytes per second received from each peer, over the last minute.",
+	);
+	for p in stats.peer_stats.iter() {
+		m.sample(
+			"epic_peer_received_bytes_per_second",
+			&[("peer", p.addr.as_str())],
+			p.received_bytes_per_sec,
+		);
+	}
+
+	m.out
+}
+
+fn handle(source: &StatsSource, req: &Request<hyper::body::Incoming>) -> Response<Full<Bytes>> {
+	let (status, body) = if req.method() != Method::GET || req.uri().path() != "/metrics" {
+		(StatusCode::NOT_FOUND, String::new())
+	} else {
+		match source.get_server_stats() {
+			Ok(stats) => (StatusCode::OK, render(&stats)),
+			Err(e) => (
+				StatusCode::INTERNAL_SERVER_ERROR,
+				format!("failed to collect server stats: {:?}", e),
+			),
+		}
+	};
+	let mut resp = Response::new(Full::new(Bytes::from(body)));
+	*resp.status_mut() = status;
+	resp.headers_mut().insert(
+		hyper::header::CONTENT_TYPE,
+		hyper::header::HeaderValue::from_static("text/plain; version=0.0.4"),
+	);
+	resp
+}
+
+/// Starts the metrics listener on a separate thread. Stats are collected
+/// on every scrape.
+pub fn start_metrics_server(
+	addr: &str,
+	source: StatsSource,
+	stop_state: Arc<StopState>,
+) -> Result<thread::JoinHandle<()>, Error> {
+	let addr: SocketAddr = addr
+		.parse()
+		.map_err(|e| Error::Configuration(format!("invalid metrics address {}: {}", addr, e)))?;
+	// Bind synchronously so a bad address fails the server startup.
+	let listener = std::net::TcpListener::bind(addr)?;
+	listener.set_nonblocking(true)?;
+	info!("Starting Prometheus metrics listener at {}.", addr);
+
+	let source = Arc::new(source);
+	let thread = thread::Builder::new()
+		.name("metrics".to_string())
+		.spawn(move || {
+			let task = async move {
+				let listener = match tokio::net::TcpListener::from_std(listener) {
+					Ok(l) => l,
+					Err(e) => {
+						error!("metrics: failed to set up listener: {}", e);
+						return;
+					}
+				};
+				loop {
+					if stop_state.is_stopped() {
+						break;
+					}
+					let conn = tokio::time::timeout(Duration::from_secs(1), listener.accept());
+					let stream = match conn.await {
+						Ok(Ok((stream, _))) => stream,
+						Ok(Err(e)) => {
+							warn!("metrics: accept error: {}", e);
+							continue;
+						}
+						// timeout, check the stop flag again
+						Err(_) => continue,
+					};
+					let source = source.clone();
+					tokio::task::spawn(async move {
+						let io = hyper_util::rt::TokioIo::new(stream);
+						let service = service_fn(move |req: Request<hyper::body::Incoming>| {
+							let source = source.clone();
+							async move {
+								// Collecting stats takes locks, keep it off the runtime threads.
+								let resp = tokio::task::spawn_blocking(move || handle(&source, &req))
+									.await
+									.unwrap_or_else(|_| {
+										let mut r = Response::new(Full::new(Bytes::new()));
+										*r.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+										r
+									});
+								Ok::<_, Infallible>(resp)
+							}
+						});
+						if let Err(e) = http1::Builder::new().serve_connection(io, service).await {
+							debug!("metrics: failed to serve connection: {:?}", e);
+						}
+					});
+				}
+			};
+
+			let rt = tokio::runtime::Builder::new_multi_thread()
+				.worker_threads(1)
+				.enable_all()
+				.build()
+				.unwrap();
+			rt.block_on(task);
+		})?;
+	Ok(thread)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::chain::SyncStatus;
+	use crate::common::stats::{DiffStats, StratumStats, TxStats, WorkerStats};
+	use crate::core::core::hash::ZERO_HASH;
+	use crate::core::pow::Difficulty;
+	use chrono::Utc;
+	use std::collections::HashSet;
+
+	fn chain_stats(height: u64) -> ChainStats {
+		ChainStats {
+			height,
+			last_block_h: ZERO_HASH,
+			total_difficulty: Difficulty::min(),
+			latest_timestamp: Utc::now(),
+		}
+	}
+
+	fn diff_block(algorithm: &str, difficulty: u64) -> DiffBlock {
+		DiffBlock {
+			block_height: 1,
+			block_hash: ZERO_HASH,
+			difficulty,
+			time: 60,
+			duration: 60,
+			secondary_scaling: 0,
+			is_secondary: false,
+			algorithm: algorithm.to_owned(),
+		}
+	}
+
+	#[test]
+	fn escapes_label_values() {
+		assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+	}
+
+	#[test]
+	fn writes_families_and_samples() {
+		let mut m = MetricsWriter::new();
+		m.family("epic_test", "gauge", "A test metric.")
+			.sample("epic_test", &[], 1)
+			.sample("epic_test", &[("peer", "1.2.3.4:3414"), ("algo", "randomx")], 2);
+		assert_eq!(
+			m.out,
+			"# HELP epic_test A test metric.\n\
+			 # TYPE epic_test gauge\n\
+			 epic_test 1\n\
+			 epic_test{peer=\"1.2.3.4:3414\",algo=\"randomx\"} 2\n"
+		);
+	}
+
+	#[test]
+	fn families_are_contiguous() {
+		let mut stratum_stats = StratumStats::default();
+		stratum_stats.worker_stats = vec![WorkerStats::default(), WorkerStats::default()];
+		let stats = ServerStats {
+			peer_count: 0,
+			chain_stats: chain_stats(10),
+			header_stats: chain_stats(12),
+			sync_status: SyncStatus::NoSync,
+			stratum_stats,
+			peer_stats: vec![],
+			diff_stats: DiffStats {
+				height: 10,
+				last_blocks: vec![
+					diff_block("RandomX", 100),
+					diff_block("ProgPow", 200),
+					diff_block("RandomX", 300),
+				],
+				average_block_time: "60".to_owned(),
+				average_difficulty: "200".to_owned(),
+				window_size: 3,
+			},
+			tx_stats: Some(TxStats {
+				tx_pool_size: 1,
+				tx_pool_kernels: 1,
+				stem_pool_size: 0,
+				stem_pool_kernels: 0,
+			}),
+			disk_usage_gb: "0.000".to_owned(),
+			disk_usage_bytes: 0,
+		};
+
+		// Every family starts with its HELP and TYPE lines, followed by its
+		// samples only, and shows up once.
+		let mut seen = HashSet::new();
+		let mut current = String::new();
+		let mut samples = 0;
+		for line in render(&stats).lines() {
+			if let Some(help) = line.strip_prefix("# HELP ") {
+				let name = help.split(' ').next().unwrap().to_owned();
+				assert!(seen.insert(name.clone()), "{} is split", name);
+				current = name;
+			} else if let Some(kind) = line.strip_prefix("# TYPE ") {
+				assert_eq!(kind.split(' ').next().unwrap(), current);
+			} else {
+				let name = line.split(|c: char| c == '{' || c == ' ').next().unwrap();
+				assert_eq!(name, current, "sample of {} after {}", name, current);
+				samples += 1;
+			}
+		}
+		assert!(seen.contains("epic_chain_total_difficulty"));
+		assert!(seen.contains("epic_difficulty_window_avg"));
+		assert!(seen.contains("epic_stratum_worker_shares_total"));
+		assert!(samples > seen.len());
+	}
+}
@@ -0,0 +1,62 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Saves the transaction pool to disk periodically and on shutdown, so the
+//! pending txs are reloaded on the next start.
+
+use crate::util::StopState;
+use crate::ServerTxPool;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Name of the file the pool is saved to, under the db root.
+pub const POOL_FILE: &str = "txpool.bin";
+
+/// How often the pool is saved while the node runs.
+const SAVE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Path of the saved pool for a db root.
+pub fn pool_file(db_root: &str) -> PathBuf {
+	Path::new(db_root).join(POOL_FILE)
+}
+
+/// Saves the pool every few minutes, and a last time once we are stopping.
+pub fn save_pool(
+	tx_pool: ServerTxPool,
+	path: PathBuf,
+	stop_state: Arc<StopState>,
+) -> std::io::Result<thread::JoinHandle<()>> {
+	debug!("Started transaction pool saver.");
+
+	thread::Builder::new()
+		.name("pool_saver".to_string())
+		.spawn(move || {
+			let mut last_run = Instant::now();
+			loop {
+				let stopping = stop_state.is_stopped();
+				if stopping || last_run.elapsed() > SAVE_INTERVAL {
+					if let Err(e) = tx_pool.read().save(&path) {
+						error!("pool_saver: failed to save the transaction pool: {}", e);
+					}
+					last_run = Instant::now();
+				}
+				if stopping {
+					break;
+				}
+				thread::sleep(Duration::from_secs(1));
+			}
+		})
+}
@@ -19,6 +19,7 @@
 use std::fs;
 use std::fs::File;
 use std::io::prelude::*;
+use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::{mpsc, Arc};
 use std::{
@@ -28,36 +29,56 @@ use std::{
 
 use crate::api;
 use crate::api::TLSConfig;
+use crate::chain::archive::{self, ArchiveSummary};
 use crate::chain::{self, SyncState, SyncStatus};
 use crate::common::adapters::{
 	ChainToPoolAndNetAdapter, NetToChainAdapter, PoolToChainAdapter, PoolToNetAdapter,
 };
-use crate::common::hooks::{init_chain_hooks, init_net_hooks};
+use crate::common::hooks::{init_chain_hooks, init_net_hooks, EventSocket};
+use crate::common::onion_stem::OnionStem;
 use crate::common::stats::{
 	ChainStats, DiffBlock, DiffStats, PeerStats, ServerStateInfo, ServerStats, TxStats,
 };
 use crate::p2p::Capabilities;
 use crate::common::types::{Error, ServerConfig, StratumServerConfig};
 use crate::core::core::feijoada::PolicyConfig;
+use crate::core::core::Block;
 use crate::core::core::hash::Hashed;
 use crate::core::core::hash::{Hash, ZERO_HASH};
 use crate::core::pow::{PoWType, Proof};
 use crate::core::ser::ProtocolVersion;
 use crate::core::{consensus, genesis, global, pow};
-use crate::epic::{dandelion_monitor, seed, sync, version};
+use crate::epic::health::{self, HealthChecker};
+use crate::epic::metrics;
+use crate::epic::{dandelion_monitor, pool_saver, seed, sync, version};
+use crate::mining::share_ledger::{ShareStore, DEFAULT_SHARE_RETENTION_SECS};
 use crate::mining::stratumserver;
 use crate::mining::test_miner::Miner;
 use crate::p2p;
+use crate::p2p::tor::address::ed25519_keypair;
+use crate::p2p::tor::control::{self as tor_control, TorControl};
 use crate::p2p::tor::process::TorProcess;
-use crate::p2p::types::PeerAddr;
+use crate::p2p::types::{NetAdapter, PeerAddr};
 use crate::pool;
 use crate::util::file::get_first_line;
-use crate::util::{RwLock, StopState};
+use crate::util::secp::key::SecretKey;
+use crate::util::static_secp_instance;
+use crate::util::{from_hex, to_hex, Mutex, RwLock, StopState};
+use chrono::Utc;
 use clokwerk::{/*ScheduleHandle,*/ Scheduler, TimeUnits};
 use epic_util::logger::LogEntry;
 use fs2::FileExt;
 use walkdir::WalkDir;
 
+/// Timeout of the connection to the tor control port.
+const TOR_CONTROL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long we wait for the tor daemon to finish bootstrapping.
+const TOR_BOOTSTRAP_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// File under db_root holding the key of our onion service, when persisted.
+const ONION_KEY_FILE: &str = "onion_key";
+
 fn is_test_network() -> bool {
 	match *global::CHAIN_TYPE.read() {
 		global::ChainTypes::Mainnet => false,
@@ -82,6 +103,10 @@ pub struct Server {
 	pub sync_state: Arc<SyncState>,
 	/// To be passed around to collect stats and info
 	state_info: ServerStateInfo,
+	/// Latest result of the node health checks
+	pub health: Arc<RwLock<api::HealthReport>>,
+	/// Shares accepted by the stratum server, kept when it is enabled
+	share_store: Option<Arc<ShareStore>>,
 	/// Stop flag
 	pub stop_state: Arc<StopState>,
 	/// Maintain a lock_file so we do not run multiple Epic nodes from same dir.
@@ -89,7 +114,13 @@ pub struct Server {
 	connect_thread: Option<JoinHandle<()>>,
 	sync_thread: JoinHandle<()>,
 	dandelion_thread: JoinHandle<()>,
-	tor_process: Option<TorProcess>,
+	pool_thread: JoinHandle<()>,
+	health_thread: JoinHandle<()>,
+	metrics_thread: Option<JoinHandle<()>>,
+	tor_process: Arc<Mutex<Option<TorProcess>>>,
+	/// Control port connection to the system tor daemon and the id of the
+	/// onion service we published on it
+	tor_control: Option<(Mutex<TorControl>, String)>,
 }
 
 impl Server {
@@ -109,38 +140,7 @@ impl Server {
 	where
 		F: FnMut(Server, Option<mpsc::Receiver<LogEntry>>),
 	{
-		/*let policy_config = config.policy_config.clone();
-		for i in 0..policy_config.policies.len() {
-			if policy_config.policies[i]
-				.values()
-				.fold(0, |acc, &x| x + acc)
-				!= 100
-			{
-				panic!("Error reading the .toml file!\n The values of the policy number <{}> must sum to 100 and be integers!\n", i);
-			};
-		}
-
-		// set the policies configs from the .toml file
-		global::set_policy_config(policy_config);*/
-
-		if is_test_network() {
-			// guard against lack of presence in old config files
-			// otherwise unwrapping non-existent value causes runtime crash
-			if config.no_progpow.is_some() {
-				let no_progpow = config.no_progpow.unwrap();
-				if no_progpow {
-					global::set_policy_config(PolicyConfig::no_progpow());
-					info!("printing no_progpow value: {}", no_progpow);
-				}
-			}
-			if config.only_randomx.is_some() {
-				let only_randomx = config.only_randomx.unwrap();
-				if only_randomx {
-					global::set_policy_config(PolicyConfig::only_randomx());
-					info!("printing only_randomx value: {}", only_randomx);
-				}
-			}
-		}
+		Server::init_policy_config(&config)?;
 
 		global::set_foundation_path(config.foundation_path.clone().to_owned());
 		let policy_config = global::get_policy_config();
@@ -184,7 +184,7 @@ impl Server {
 						let mut stratum_stats = serv.state_info.stratum_stats.write();
 						stratum_stats.is_enabled = true;
 					}
-					serv.start_stratum_server(c.clone());
+					serv.start_stratum_server(c.clone())?;
 				}
 			}
 		}
@@ -224,6 +224,95 @@ impl Server {
 		Ok(Arc::new(lock_file))
 	}
 
+	// Set the block policy for test networks from the (optional) config flags,
+	// or from the [server.policy_config] section when present.
+	fn init_policy_config(config: &ServerConfig) -> Result<(), Error> {
+		if is_test_network() {
+			// guard against lack of presence in old config files
+			// otherwise unwrapping non-existent value causes runtime crash
+			if config.no_progpow.is_some() {
+				let no_progpow = config.no_progpow.unwrap();
+				if no_progpow {
+					global::set_policy_config(PolicyConfig::no_progpow());
+					info!("printing no_progpow value: {}", no_progpow);
+				}
+			}
+			if config.only_randomx.is_some() {
+				let only_randomx = config.only_randomx.unwrap();
+				if only_randomx {
+					global::set_policy_config(PolicyConfig::only_randomx());
+					info!("printing only_randomx value: {}", only_randomx);
+				}
+			}
+			if let Some(ref policy_config) = config.policy_config {
+				policy_config.validate().map_err(|e| {
+					Error::Configuration(format!("invalid [server.policy_config]: {}", e))
+				})?;
+				global::set_policy_config(policy_config.clone());
+				info!(
+					"Using the policy schedule from the config file, {} policies",
+					policy_config.policies.len()
+				);
+			}
+		} else if config.policy_config.is_some() {
+			warn!("Ignoring [server.policy_config], only test networks can change the policy");
+		}
+		Ok(())
+	}
+
+	fn genesis_block(chain_type: &global::ChainTypes) -> Block {
+		match chain_type {
+			global::ChainTypes::AutomatedTesting => genesis::genesis_dev(),
+			global::ChainTypes::UserTesting => genesis::genesis_dev(),
+			global::ChainTypes::Floonet => genesis::genesis_floo(),
+			global::ChainTypes::Mainnet => genesis::genesis_main(),
+		}
+	}
+
+	// Opens the chain db without any of the networking or pool machinery, for
+	// offline maintenance commands. Holds the lock_file for as long as the
+	// returned handle lives so a running node cannot write underneath us.
+	fn open_chain_offline(config: &ServerConfig) -> Result<(Arc<File>, chain::Chain), Error> {
+		let lock_file = Server::one_epic_at_a_time(config)?;
+		Server::init_policy_config(config)?;
+		global::set_foundation_path(config.foundation_path.clone().to_owned());
+
+		let chain = chain::Chain::init(
+			config.db_root.clone(),
+			Arc::new(chain::types::NoopAdapter {}),
+			Server::genesis_block(&config.chain_type),
+			pow::verify_size,
+			config.archive_mode.unwrap_or(false),
+		)?;
+		Ok((lock_file, chain))
+	}
+
+	/// Exports the full blocks from `from_height` up to the current head into
+	/// a portable chain archive at `path`. The node must not be running.
+	pub fn export_chain(
+		config: &ServerConfig,
+		path: &Path,
+		from_height: u64,
+	) -> Result<ArchiveSummary, Error> {
+		let (_lock_file, chain) = Server::open_chain_offline(config)?;
+		Ok(archive::export(&chain, path, from_height)?)
+	}
+
+	/// Verifies the chain archive at `path` and replays its blocks into our
+	/// chain db, as if they had been received during sync. The node must not
+	/// be running.
+	pub fn import_chain(config: &ServerConfig, path: &Path) -> Result<ArchiveSummary, Error> {
+		let (_lock_file, chain) = Server::open_chain_offline(config)?;
+		Ok(archive::import(&chain, path, chain::Options::SYNC)?)
+	}
+
+	/// Rebuilds the kernel excess index from scratch, returning the number of
+	/// kernels indexed. The node must not be running.
+	pub fn rebuild_kernel_index(config: &ServerConfig) -> Result<u64, Error> {
+		let (_lock_file, chain) = Server::open_chain_offline(config)?;
+		Ok(chain.rebuild_kernel_index()?)
+	}
+
 	/// Instantiates a new server associated with the provided future reactor.
 	pub fn new(
 		config: ServerConfig,
@@ -251,8 +340,12 @@ impl Server {
 			Arc::new(StopState::new())
 		};
 
+		let block_templates = Arc::new(api::BlockTemplates::new());
 		let pool_adapter = Arc::new(PoolToChainAdapter::new());
-		let pool_net_adapter = Arc::new(PoolToNetAdapter::new(config.dandelion_config.clone()));
+		let pool_net_adapter = Arc::new(PoolToNetAdapter::new(
+			config.dandelion_config.clone(),
+			block_templates.clone(),
+		));
 		let tx_pool = Arc::new(RwLock::new(pool::TransactionPool::new(
 			config.pool_config.clone(),
 			pool_adapter.clone(),
@@ -263,17 +356,15 @@ impl Server {
 
 		let sync_state = Arc::new(SyncState::new());
 
+		let event_socket = EventSocket::from_config(&config.event_socket_config)?;
+
 		let chain_adapter = Arc::new(ChainToPoolAndNetAdapter::new(
 			tx_pool.clone(),
-			init_chain_hooks(&config),
+			init_chain_hooks(&config, &event_socket),
+			block_templates.clone(),
 		));
 
-		let genesis = match config.chain_type {
-			global::ChainTypes::AutomatedTesting => genesis::genesis_dev(),
-			global::ChainTypes::UserTesting => genesis::genesis_dev(),
-			global::ChainTypes::Floonet => genesis::genesis_floo(),
-			global::ChainTypes::Mainnet => genesis::genesis_main(),
-		};
+		let genesis = Server::genesis_block(&config.chain_type);
 
 		info!(
 			"Warm up Epic node server from genesis({}), ...",
@@ -288,24 +379,62 @@ impl Server {
 			archive_mode,
 		)?);
 
+		if let Some(kernel_index) = config.kernel_index {
+			shared_chain.set_kernel_index(kernel_index)?;
+		}
+
+		if let Some(ref path) = config.checkpoints_file {
+			let hash = config.checkpoints_file_hash.as_ref().ok_or_else(|| {
+				Error::Configuration("checkpoints_file requires checkpoints_file_hash".to_owned())
+			})?;
+			shared_chain.set_checkpoints(chain::BlockchainCheckpoints::from_file(path, hash)?);
+		}
+		shared_chain.set_assume_valid(config.assume_valid.unwrap_or(false));
+		shared_chain.set_max_reorg_depth(config.max_reorg_depth);
+
 		pool_adapter.set_chain(shared_chain.clone());
 
+		let pool_file = pool_saver::pool_file(&config.db_root);
+		if let Err(e) = tx_pool.write().load(&pool_file) {
+			warn!("Failed to reload the transaction pool from {}: {}", pool_file.display(), e);
+		}
+
 		let net_adapter = Arc::new(NetToChainAdapter::new(
 			sync_state.clone(),
 			shared_chain.clone(),
 			tx_pool.clone(),
 			config.clone(),
-			init_net_hooks(&config),
+			init_net_hooks(&config, &event_socket),
 		));
 
 		// set up tor send process if needed
 
 		let mut tor = TorProcess::new();
+		let mut tor_control = None;
 		let mut onion_api_addr = None;
-		
+		let tor_enabled = config.tor.socks_proxy_addr != ""
+			&& config.p2p_config.capabilities.contains(Capabilities::ONIONSTEM);
+		let tor_launched = tor_enabled && config.tor.control_addr.is_none();
+		let onion_only = config.p2p_config.onion_only();
+		let mut onion_key = None;
+
+		if onion_only && (!tor_enabled || tor_launched) {
+			// we need the onion service key to prove who we are to peers
+			return Err(Error::Configuration(
+				"onion_only requires the onion_stem capability and a tor control_addr".to_owned(),
+			));
+		}
 
-		if config.tor.socks_proxy_addr != ""
-		&& config.p2p_config.capabilities.contains(Capabilities::ONIONSTEM) {
+		if tor_enabled && !tor_launched {
+			// Publish an ephemeral onion service on the running tor daemon.
+			let (control, service_id, sec_key) = add_onion_service(&config)?;
+			if onion_only {
+				onion_key = Some(sec_key);
+			}
+			let api_port = config.api_http_addr.split(':').last().unwrap_or("3413");
+			onion_api_addr = Some(format!("http://{}.onion:{}", service_id, api_port));
+			tor_control = Some((Mutex::new(control), service_id));
+		} else if tor_launched {
 			let tor_dir = config.tor.send_config_dir.clone();
 			warn!(
 				"Starting TOR Process for send at {:?}",
@@ -357,9 +486,17 @@ impl Server {
 		if let Some(ref addr) = onion_api_addr {
 			info!("This peer's onion addr: {}", addr);
 		} 
+		// Only advertise block filters if we can serve their filter headers.
+		let mut capabilities = config.p2p_config.capabilities;
+		let serves_filters = shared_chain.has_block_filter_headers();
+		capabilities.set(Capabilities::BLOCK_FILTERS, serves_filters);
+		if !serves_filters {
+			info!("Not serving block filters, missing the blocks down to genesis");
+		}
+
 		let p2p_server = Arc::new(p2p::Server::new(
 			&config.db_root,
-			config.p2p_config.capabilities,
+			capabilities,
 			config.p2p_config.clone(),
 			net_adapter.clone(),
 			genesis.hash(),
@@ -372,6 +509,19 @@ impl Server {
 		pool_net_adapter.init(p2p_server.peers.clone());
 		net_adapter.init(p2p_server.peers.clone());
 
+		// Stem our txs through the Tor API of onion peers once we have one ourselves.
+		let onion_stem_hops = config.dandelion_config.onion_stem_hops;
+		if onion_api_addr.is_some() && onion_stem_hops > 0 {
+			let socks_proxy_addr = socks_proxy_addr(&config)?;
+			info!("Stemming transactions through {} onion hops", onion_stem_hops);
+			pool_net_adapter.init_onion_stem(OnionStem::new(socks_proxy_addr, onion_stem_hops));
+		}
+
+		// Connect to and accept peers through tor only.
+		if let Some(ref sec_key) = onion_key {
+			p2p_server.init_onion(socks_proxy_addr(&config)?, sec_key)?;
+		}
+
 		let mut connect_thread = None;
 
 		if config.p2p_config.seeding_type != p2p::Seeding::Programmatic {
@@ -380,6 +530,10 @@ impl Server {
 					warn!("No seed configured, will stay solo until connected to");
 					seed::predefined_seeds(vec![])
 				}
+				// neither dns lookups nor clearnet seeds when only using tor
+				_ if onion_only => {
+					seed::predefined_seeds(onion_seeds(&config.p2p_config, &p2p_server.peers)?)
+				}
 				p2p::Seeding::List => match &config.p2p_config.seeds {
 					Some(seeds) => seed::predefined_seeds(seeds.clone()),
 					None => {
@@ -440,12 +594,71 @@ impl Server {
 		};
 
 		// TODO fix API shutdown and join this thread
+		let state_info = ServerStateInfo {
+			..Default::default()
+		};
+		let tor_process = Arc::new(Mutex::new(Some(tor)));
+
+		let health_report = Arc::new(RwLock::new(api::HealthReport::default()));
+		let health_checker = HealthChecker {
+			config: config.health_check_config.clone(),
+			chain: shared_chain.clone(),
+			peers: p2p_server.peers.clone(),
+			tx_pool: tx_pool.clone(),
+			stratum_stats: state_info.stratum_stats.clone(),
+			tor_process: if tor_launched {
+				Some(tor_process.clone())
+			} else {
+				None
+			},
+		};
+		let health_thread =
+			health::monitor(health_checker, health_report.clone(), stop_state.clone())?;
+
+		let metrics_thread = match config.metrics_http_addr {
+			Some(ref metrics_addr) => {
+				let stats_source = StatsSource {
+					chain: shared_chain.clone(),
+					peers: p2p_server.peers.clone(),
+					tx_pool: tx_pool.clone(),
+					sync_state: sync_state.clone(),
+					state_info: state_info.clone(),
+					db_root: config.db_root.clone(),
+				};
+				Some(metrics::start_metrics_server(
+					metrics_addr,
+					stats_source,
+					stop_state.clone(),
+				)?)
+			}
+			None => None,
+		};
+
+		let stratum_enabled = config
+			.stratum_mining_config
+			.as_ref()
+			.and_then(|c| c.enable_stratum_server)
+			.unwrap_or(false);
+		let share_store = if stratum_enabled {
+			let retention_secs = config
+				.stratum_mining_config
+				.as_ref()
+				.and_then(|c| c.share_retention_secs)
+				.unwrap_or(DEFAULT_SHARE_RETENTION_SECS);
+			Some(Arc::new(ShareStore::new(&config.db_root, retention_secs)?))
+		} else {
+			None
+		};
+
 		api::node_apis(
 			&config.api_http_addr,
 			shared_chain.clone(),
 			tx_pool.clone(),
 			p2p_server.peers.clone(),
 			sync_state.clone(),
+			health_report.clone(),
+			share_store.clone().map(|s| s as Arc<dyn api::ShareLedger>),
+			block_templates,
 			api_secret.clone(),
 			foreign_api_secret.clone(),
 			tls_conf.clone(),
@@ -461,6 +674,8 @@ impl Server {
 			stop_state.clone(),
 		)?;
 
+		let pool_thread = pool_saver::save_pool(tx_pool.clone(), pool_file, stop_state.clone())?;
+
 		info!("Starting the version checker monitor!");
 		let mut scheduler = Scheduler::new();
 		scheduler.every(15.minutes()).run(|| {
@@ -494,15 +709,19 @@ impl Server {
 			chain: shared_chain,
 			tx_pool,
 			sync_state,
-			state_info: ServerStateInfo {
-				..Default::default()
-			},
+			state_info,
+			health: health_report,
+			share_store,
 			stop_state,
 			lock_file,
 			connect_thread,
 			sync_thread,
 			dandelion_thread,
-			tor_process: Some(tor),
+			pool_thread,
+			health_thread,
+			metrics_thread,
+			tor_process,
+			tor_control,
 		})
 	}
 
@@ -526,7 +745,7 @@ impl Server {
 	}
 
 	/// Start a minimal "stratum" mining service on a separate thread
-	pub fn start_stratum_server(&self, config: StratumServerConfig) {
+	pub fn start_stratum_server(&self, config: StratumServerConfig) -> Result<(), Error> {
 		let edge_bits = global::min_edge_bits();
 		let proof_size = global::proofsize();
 		let sync_state = self.sync_state.clone();
@@ -536,12 +755,14 @@ impl Server {
 			self.chain.clone(),
 			self.tx_pool.clone(),
 			self.state_info.stratum_stats.clone(),
-		);
+			self.share_store.clone(),
+		)?;
 		let _ = thread::Builder::new()
 			.name("stratum_server".to_string())
 			.spawn(move || {
 				stratum_server.run_loop(edge_bits as u32, proof_size, sync_state);
 			});
+		Ok(())
 	}
 
 	/// Start mining for blocks internally on a separate thread. Relies on
@@ -564,10 +785,21 @@ impl Server {
 			burn_reward: false,
 			enable_stratum_server: None,
 			stratum_server_addr: None,
+			stratum_tls_server_addr: None,
+			stratum_tls_certificate_file: None,
+			stratum_tls_certificate_key: None,
+			worker_credentials: None,
+			worker_password_file: None,
 			wallet_listener_url: config_wallet_url,
 			cuckatoo_minimum_share_difficulty: 1,
 			randomx_minimum_share_difficulty: 1,
 			progpow_minimum_share_difficulty: 1,
+			cuckatoo_maximum_share_difficulty: None,
+			randomx_maximum_share_difficulty: None,
+			progpow_maximum_share_difficulty: None,
+			vardiff_target_share_time: Some(0),
+			vardiff_retarget_time: None,
+			share_retention_secs: None,
 		};
 
 		let mut miner = Miner::new(
@@ -602,6 +834,110 @@ impl Server {
 	/// can be updated over time to include any information needed by tests or
 	/// other
 	/// consumers
+	pub fn get_server_stats(&self) -> Result<ServerStats, Error> {
+		self.stats_source().get_server_stats()
+	}
+
+	/// Handles needed to compute the server stats from another thread.
+	pub fn stats_source(&self) -> StatsSource {
+		StatsSource {
+			chain: self.chain.clone(),
+			peers: self.p2p.peers.clone(),
+			tx_pool: self.tx_pool.clone(),
+			sync_state: self.sync_state.clone(),
+			state_info: self.state_info.clone(),
+			db_root: self.config.db_root.clone(),
+		}
+	}
+
+	/// Stop the server.
+	pub fn stop(self) {
+		{
+			self.sync_state.update(SyncStatus::Shutdown);
+			self.stop_state.stop();
+
+			if let Some(connect_thread) = self.connect_thread {
+				match connect_thread.join() {
+					Err(e) => error!("failed to join to connect_and_monitor thread: {:?}", e),
+					Ok(_) => info!("Connect and monitor thread stopped"),
+				}
+			} else {
+				info!("No active connect and monitor thread")
+			}
+
+			match self.sync_thread.join() {
+				Err(e) => error!("failed to join to sync thread: {:?}", e),
+				Ok(_) => info!("Sync thread stopped"),
+			}
+
+			match self.dandelion_thread.join() {
+				Err(e) => error!("failed to join to dandelion_monitor thread: {:?}", e),
+				Ok(_) => info!("Dandelion monitor thread stopped"),
+			}
+
+			match self.pool_thread.join() {
+				Err(e) => error!("failed to join to pool saver thread: {:?}", e),
+				Ok(_) => info!("Pool saver thread stopped"),
+			}
+
+			match self.health_thread.join() {
+				Err(e) => error!("failed to join to health monitor thread: {:?}", e),
+				Ok(_) => info!("Health monitor thread stopped"),
+			}
+
+			if let Some(metrics_thread) = self.metrics_thread {
+				match metrics_thread.join() {
+					Err(e) => error!("failed to join to metrics thread: {:?}", e),
+					Ok(_) => info!("Metrics thread stopped"),
+				}
+			}
+
+			self.tor_process.lock().take(); // Explicitly drop TorProcess to kill Tor
+			if let Some((ref control, ref service_id)) = self.tor_control {
+				if let Err(e) = control.lock().del_onion(service_id) {
+					warn!("Failed to remove onion service {}: {:?}", service_id, e);
+				}
+			}
+		}
+		self.p2p.stop();
+
+		let _ = self.lock_file;
+	}
+
+	/// Pause the p2p server.
+	pub fn pause(&self) {
+		self.stop_state.pause();
+		thread::sleep(time::Duration::from_secs(1));
+		self.p2p.pause();
+	}
+
+	/// Resume p2p server.
+	/// TODO - We appear not to resume the p2p server (peer connections) here?
+	pub fn resume(&self) {
+		self.stop_state.resume();
+	}
+
+	/// Stops the test miner without stopping the p2p layer
+	pub fn stop_test_miner(&self, stop: Arc<StopState>) {
+		stop.stop();
+		info!("stop_test_miner - stop",);
+	}
+}
+
+/// Shareable handles needed to compute `ServerStats`, so stats consumers
+/// (TUI, metrics exporter) don't need to hold on to the `Server` itself.
+#[derive(Clone)]
+pub struct StatsSource {
+	chain: Arc<chain::Chain>,
+	peers: Arc<p2p::Peers>,
+	tx_pool: ServerTxPool,
+	sync_state: Arc<SyncState>,
+	state_info: ServerStateInfo,
+	db_root: String,
+}
+
+impl StatsSource {
+	/// Returns a set of stats about the server.
 	pub fn get_server_stats(&self) -> Result<ServerStats, Error> {
 		let stratum_stats = self.state_info.stratum_stats.read().clone();
 
@@ -616,7 +952,7 @@ impl Server {
 					.into_iter()
 					.collect();
 
-			let tip_height = self.head()?.height as i64;
+			let tip_height = self.chain.head()?.height as i64;
 			let mut height = tip_height as i64 - last_blocks.len() as i64 + 1;
 
 			let diff_entries: Vec<DiffBlock> = last_blocks
@@ -698,7 +1034,6 @@ impl Server {
 		};
 
 		let peer_stats = self
-			.p2p
 			.peers
 			.connected_peers()
 			.into_iter()
@@ -733,7 +1068,7 @@ impl Server {
 			total_difficulty: header.total_difficulty(),
 		};
 
-		let disk_usage_bytes = WalkDir::new(&self.config.db_root)
+		let disk_usage_bytes = WalkDir::new(&self.db_root)
 			.min_depth(1)
 			.max_depth(3)
 			.into_iter()
@@ -745,68 +1080,18 @@ impl Server {
 		let disk_usage_gb = format!("{:.*}", 3, (disk_usage_bytes as f64 / 1_000_000_000 as f64));
 
 		Ok(ServerStats {
-			peer_count: self.peer_count(),
+			peer_count: self.peers.peer_count(),
 			chain_stats: head_stats,
 			header_stats,
 			sync_status: self.sync_state.status(),
 			disk_usage_gb,
+			disk_usage_bytes,
 			stratum_stats,
 			peer_stats,
 			diff_stats,
 			tx_stats,
 		})
 	}
-
-	/// Stop the server.
-	pub fn stop(self) {
-		{
-			self.sync_state.update(SyncStatus::Shutdown);
-			self.stop_state.stop();
-
-			if let Some(connect_thread) = self.connect_thread {
-				match connect_thread.join() {
-					Err(e) => error!("failed to join to connect_and_monitor thread: {:?}", e),
-					Ok(_) => info!("Connect and monitor thread stopped"),
-				}
-			} else {
-				info!("No active connect and monitor thread")
-			}
-
-			match self.sync_thread.join() {
-				Err(e) => error!("failed to join to sync thread: {:?}", e),
-				Ok(_) => info!("Sync thread stopped"),
-			}
-
-			match self.dandelion_thread.join() {
-				Err(e) => error!("failed to join to dandelion_monitor thread: {:?}", e),
-				Ok(_) => info!("Dandelion monitor thread stopped"),
-			}
-
-			drop(self.tor_process); // Explicitly drop TorProcess to kill Tor
-		}
-		self.p2p.stop();
-
-		let _ = self.lock_file;
-	}
-
-	/// Pause the p2p server.
-	pub fn pause(&self) {
-		self.stop_state.pause();
-		thread::sleep(time::Duration::from_secs(1));
-		self.p2p.pause();
-	}
-
-	/// Resume p2p server.
-	/// TODO - We appear not to resume the p2p server (peer connections) here?
-	pub fn resume(&self) {
-		self.stop_state.resume();
-	}
-
-	/// Stops the test miner without stopping the p2p layer
-	pub fn stop_test_miner(&self, stop: Arc<StopState>) {
-		stop.stop();
-		info!("stop_test_miner - stop",);
-	}
 }
 
 fn get_difficulty_info_average(diff_entries: Vec<DiffBlock>) -> (String, String) {
@@ -822,3 +1107,106 @@ fn get_difficulty_info_average(diff_entries: Vec<DiffBlock>) -> (String, String)
 		("NaN".to_owned(), "NaN".to_owned())
 	}
 }
+
+/// Publishes our onion service through the tor control port, serving the api
+/// and in onion only mode p2p. Returns the control connection keeping it
+/// published, the service id and its key.
+fn add_onion_service(config: &ServerConfig) -> Result<(TorControl, String, SecretKey), Error> {
+	let tor_err = |e: tor_control::Error| Error::TorConfig(format!("{:?}", e));
+	let control_addr = config.tor.control_addr.clone().unwrap_or_default();
+	let control_addr = control_addr
+		.parse()
+		.map_err(|_| Error::Configuration(format!("invalid tor control_addr {}", control_addr)))?;
+	let api_port: u16 = config
+		.api_http_addr
+		.split(':')
+		.last()
+		.and_then(|port| port.parse().ok())
+		.unwrap_or(3413);
+
+	info!("Connecting to the tor control port at {}", control_addr);
+	let mut control =
+		TorControl::connect(&control_addr, TOR_CONTROL_TIMEOUT).map_err(tor_err)?;
+	control
+		.authenticate(config.tor.control_password.as_deref())
+		.map_err(tor_err)?;
+	control
+		.wait_for_bootstrap(TOR_BOOTSTRAP_TIMEOUT, |status| {
+			info!("Tor bootstrap {}%: {}", status.progress, status.summary)
+		})
+		.map_err(tor_err)?;
+
+	let sec_key = onion_secret_key(config)?;
+	let (d_sec_key, _) =
+		ed25519_keypair(&sec_key).map_err(|e| Error::TorConfig(format!("{:?}", e)))?;
+	let p2p_target = format!("127.0.0.1:{}", config.p2p_config.port);
+	let mut ports = vec![(api_port, config.api_http_addr.as_str())];
+	if config.p2p_config.onion_only() {
+		ports.push((PeerAddr::default_port(), p2p_target.as_str()));
+	}
+	let service_id = control.add_onion(&d_sec_key, &ports).map_err(tor_err)?;
+	info!("Published onion service {}.onion", service_id);
+	Ok((control, service_id, sec_key))
+}
+
+/// Key of our onion service. A fresh one kept in memory only, unless
+/// `persist_onion_key` is set: the key is then loaded from db_root, and
+/// generated and saved there on first use.
+fn onion_secret_key(config: &ServerConfig) -> Result<SecretKey, Error> {
+	let key_err = |e| Error::TorConfig(format!("{:?}", e));
+	let path = Path::new(&config.db_root).join(ONION_KEY_FILE);
+	let secp = static_secp_instance();
+	let secp = secp.lock();
+	if config.tor.persist_onion_key {
+		if let Some(line) = get_first_line(Some(path.to_string_lossy().into_owned())) {
+			let bytes = from_hex(line.trim().to_owned())
+				.map_err(|_| Error::TorConfig(format!("invalid onion key in {:?}", path)))?;
+			return SecretKey::from_slice(&secp, &bytes).map_err(key_err);
+		}
+	}
+
+	let sec_key = SecretKey::from_slice(&secp, &rand::random::<[u8; 32]>()).map_err(key_err)?;
+	if !config.tor.persist_onion_key {
+		return Ok(sec_key);
+	}
+	fs::create_dir_all(&config.db_root)?;
+	let mut options = fs::OpenOptions::new();
+	options.write(true).create_new(true);
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::OpenOptionsExt;
+		options.mode(0o600);
+	}
+	let mut file = options.open(&path)?;
+	writeln!(file, "{}", to_hex(sec_key.0.to_vec()))?;
+	info!("Saved a new onion service key to {:?}", path);
+	Ok(sec_key)
+}
+
+fn socks_proxy_addr(config: &ServerConfig) -> Result<SocketAddr, Error> {
+	config.tor.socks_proxy_addr.parse().map_err(|_| {
+		Error::Configuration(format!(
+			"invalid tor socks_proxy_addr: {}",
+			config.tor.socks_proxy_addr
+		))
+	})
+}
+
+/// Onion seeds from the config, saved as endpoints so we know how to reach
+/// them.
+fn onion_seeds(config: &p2p::P2PConfig, peers: &p2p::Peers) -> Result<Vec<PeerAddr>, Error> {
+	let now = Utc::now().timestamp();
+	let mut endpoints = vec![];
+	for seed in config.onion_seeds.clone().unwrap_or_default() {
+		let endpoint = p2p::PeerEndpoint::from_onion(&seed)
+			.map_err(|_| Error::Configuration(format!("invalid onion seed {}", seed)))?;
+		endpoints.push(p2p::EndpointData {
+			endpoint,
+			capabilities: Capabilities::UNKNOWN,
+			last_seen: now,
+		});
+	}
+	let addrs = endpoints.iter().map(|e| e.endpoint.peer_addr()).collect();
+	peers.peer_endpoints_received(endpoints);
+	Ok(addrs)
+}
@@ -42,4 +42,5 @@ mod mining;
 
 pub use crate::common::stats::{DiffBlock, PeerStats, ServerStats, StratumStats, WorkerStats};
 pub use crate::common::types::{ServerConfig, StratumServerConfig};
-pub use crate::epic::server::{Server, ServerTxPool};
+pub use crate::epic::dandelion_monitor::monitor_transactions;
+pub use crate::epic::server::{Server, ServerTxPool, StatsSource};
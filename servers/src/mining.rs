@@ -15,5 +15,8 @@
 //! Mining + Mining server
 
 pub mod mine_block;
+pub mod share_ledger;
 pub mod stratumserver;
 pub mod test_miner;
+pub mod vardiff;
+pub mod worker_auth;
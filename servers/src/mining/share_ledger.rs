@@ -0,0 +1,376 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Persistent log of the shares accepted by the stratum server, so pools can
+//! compute PPLNS and PPS payouts from the node.
+
+use std::collections::BTreeMap;
+
+use crate::api::{self, ShareWindow, WorkerShares};
+use crate::core::ser::{self, Readable, Reader, Writeable, Writer};
+use crate::store::{self, option_to_not_found, to_key, to_key_u64, u64_to_key, Error};
+use crate::util::Mutex;
+use chrono::Utc;
+
+const DB_NAME: &'static str = "share";
+const STORE_SUBPATH: &'static str = "shares";
+
+const SHARE_PREFIX: u8 = 'S' as u8;
+const NEXT_SEQ_PREFIX: u8 = 'N' as u8;
+const ALGO_SHARE_PREFIX: u8 = 'A' as u8;
+const ALGO_NEXT_SEQ_PREFIX: u8 = 'n' as u8;
+
+/// How long shares are kept by default, a week.
+pub const DEFAULT_SHARE_RETENTION_SECS: u64 = 7 * 24 * 3600;
+
+/// Number of shares logged between two prunings of the old ones.
+const PRUNE_INTERVAL: u64 = 1_000;
+
+/// A share accepted by the stratum server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Share {
+	/// Login of the worker, or its connection id if it did not log in
+	pub login: String,
+	/// Algorithm the share was mined with
+	pub algorithm: String,
+	/// Difficulty the share was requested at
+	pub difficulty: u64,
+	/// Height of the block the share was mined on
+	pub height: u64,
+	/// Unix timestamp the share was accepted at
+	pub timestamp: i64,
+	/// Whether the share was a block
+	pub block_found: bool,
+}
+
+impl Writeable for Share {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&self.login)?;
+		writer.write_bytes(&self.algorithm)?;
+		writer.write_u64(self.difficulty)?;
+		writer.write_u64(self.height)?;
+		writer.write_i64(self.timestamp)?;
+		writer.write_u8(self.block_found as u8)
+	}
+}
+
+impl Readable for Share {
+	fn read(reader: &mut dyn Reader) -> Result<Share, ser::Error> {
+		let login = reader.read_bytes_len_prefix()?;
+		let algorithm = reader.read_bytes_len_prefix()?;
+		Ok(Share {
+			login: String::from_utf8(login).map_err(|_| ser::Error::CorruptedData)?,
+			algorithm: String::from_utf8(algorithm).map_err(|_| ser::Error::CorruptedData)?,
+			difficulty: reader.read_u64()?,
+			height: reader.read_u64()?,
+			timestamp: reader.read_i64()?,
+			block_found: reader.read_u8()? != 0,
+		})
+	}
+}
+
+/// Storage of the shares, keyed by a sequence number in the order they were
+/// accepted, so also in time order. Each share is also kept under a sequence
+/// number of its algorithm, so PPLNS windows only read the shares of theirs.
+/// Shares older than the retention time are pruned as new ones come in.
+pub struct ShareStore {
+	db: store::Store,
+	next_seq: Mutex<u64>,
+	retention_secs: u64,
+}
+
+impl ShareStore {
+	/// Opens the share store under the provided root path, keeping shares
+	/// for `retention_secs`.
+	pub fn new(db_root: &str, retention_secs: u64) -> Result<ShareStore, Error> {
+		let db = store::Store::new(db_root, Some(DB_NAME), Some(STORE_SUBPATH), None)?;
+		let next_seq = db.get_ser(&next_seq_key())?.unwrap_or(0);
+		Ok(ShareStore {
+			db,
+			next_seq: Mutex::new(next_seq),
+			retention_secs,
+		})
+	}
+
+	/// Logs an accepted share.
+	pub fn add_share(&self, share: &Share) -> Result<(), Error> {
+		let mut next_seq = self.next_seq.lock();
+		let batch = self.db.batch()?;
+		let algo_seq: u64 = batch
+			.get_ser(&algo_next_seq_key(&share.algorithm))?
+			.unwrap_or(0);
+		batch.put_ser(&u64_to_key(SHARE_PREFIX, *next_seq), share)?;
+		batch.put_ser(&algo_share_key(&share.algorithm, algo_seq), share)?;
+		batch.put_ser(&next_seq_key(), &(*next_seq + 1))?;
+		batch.put_ser(&algo_next_seq_key(&share.algorithm), &(algo_seq + 1))?;
+		batch.commit()?;
+		*next_seq += 1;
+
+		if *next_seq % PRUNE_INTERVAL == 0 {
+			self.prune(Utc::now().timestamp())?;
+		}
+		Ok(())
+	}
+
+	/// Deletes the shares accepted more than the retention time before `now`.
+	fn prune(&self, now: i64) -> Result<(), Error> {
+		let cutoff = now.saturating_sub(self.retention_secs as i64);
+		let mut prefixes = vec![to_key(SHARE_PREFIX, &mut vec![])];
+		let algo_seqs = self
+			.db
+			.iter::<u64>(&to_key(ALGO_NEXT_SEQ_PREFIX, &mut vec![]))?;
+		for (key, _) in algo_seqs {
+			let algorithm = String::from_utf8_lossy(&key[2..]).into_owned();
+			prefixes.push(algo_share_prefix(&algorithm));
+		}
+
+		let batch = self.db.batch()?;
+		for prefix in prefixes {
+			let old_keys = self
+				.db
+				.iter::<Share>(&prefix)?
+				.take_while(|(_, share)| share.timestamp < cutoff)
+				.map(|(key, _)| key)
+				.collect::<Vec<_>>();
+			for key in old_keys {
+				batch.delete(&key)?;
+			}
+		}
+		batch.commit()
+	}
+
+	// sequence number of the oldest share we still have
+	fn first_seq(&self) -> Result<Option<u64>, Error> {
+		let first = self
+			.db
+			.iter::<Share>(&to_key(SHARE_PREFIX, &mut vec![]))?
+			.next();
+		Ok(first.map(|(key, _)| u64::from_be_bytes(key[2..].try_into().unwrap())))
+	}
+
+	fn get_share(&self, seq: u64) -> Result<Share, Error> {
+		option_to_not_found(self.db.get_ser(&u64_to_key(SHARE_PREFIX, seq)), || {
+			format!("share {}", seq)
+		})
+	}
+
+	/// The last shares of an algorithm adding up to `window_difficulty`,
+	/// ending at the shares of block `height` or at the latest.
+	pub fn pplns_window(
+		&self,
+		algorithm: &str,
+		window_difficulty: u64,
+		height: Option<u64>,
+	) -> Result<ShareWindow, Error> {
+		let mut seq: u64 = self
+			.db
+			.get_ser(&algo_next_seq_key(algorithm))?
+			.unwrap_or(0);
+		let mut shares = vec![];
+		let mut difficulty: u64 = 0;
+		while seq > 0 && difficulty < window_difficulty {
+			seq -= 1;
+			// the older shares were pruned
+			let share: Share = match self.db.get_ser(&algo_share_key(algorithm, seq))? {
+				Some(share) => share,
+				None => break,
+			};
+			if height.map_or(false, |h| share.height > h) {
+				continue;
+			}
+			difficulty = difficulty.saturating_add(share.difficulty);
+			shares.push(share);
+		}
+		Ok(share_window(&shares))
+	}
+
+	/// All the shares accepted between two unix timestamps, inclusive.
+	pub fn pps_window(&self, from: i64, to: i64) -> Result<ShareWindow, Error> {
+		// binary search the first share of the window
+		let end = *self.next_seq.lock();
+		let (mut start, mut end) = (self.first_seq()?.unwrap_or(end), end);
+		while start < end {
+			let mid = start + (end - start) / 2;
+			if self.get_share(mid)?.timestamp < from {
+				start = mid + 1;
+			} else {
+				end = mid;
+			}
+		}
+		let shares = self
+			.db
+			.iter_from::<Share>(
+				&to_key(SHARE_PREFIX, &mut vec![]),
+				&u64_to_key(SHARE_PREFIX, start),
+			)?
+			.map(|(_, share)| share)
+			.take_while(|share| share.timestamp <= to)
+			.collect::<Vec<_>>();
+		Ok(share_window(&shares))
+	}
+}
+
+fn next_seq_key() -> Vec<u8> {
+	to_key(NEXT_SEQ_PREFIX, &mut vec![])
+}
+
+fn algo_next_seq_key(algorithm: &str) -> Vec<u8> {
+	to_key(ALGO_NEXT_SEQ_PREFIX, &mut algorithm.as_bytes().to_vec())
+}
+
+// the separator keeps the shares of an algorithm apart from the ones of
+// another algorithm whose name it starts
+fn algo_share_prefix(algorithm: &str) -> Vec<u8> {
+	to_key(ALGO_SHARE_PREFIX, &mut format!("{}:", algorithm).into_bytes())
+}
+
+fn algo_share_key(algorithm: &str, seq: u64) -> Vec<u8> {
+	to_key_u64(ALGO_SHARE_PREFIX, &mut format!("{}:", algorithm).into_bytes(), seq)
+}
+
+// sum up the shares by worker and algorithm
+fn share_window(shares: &[Share]) -> ShareWindow {
+	let mut workers: BTreeMap<(String, String), WorkerShares> = BTreeMap::new();
+	for share in shares {
+		let key = (share.login.clone(), share.algorithm.clone());
+		let worker = workers.entry(key).or_insert_with(|| WorkerShares {
+			login: share.login.clone(),
+			algorithm: share.algorithm.clone(),
+			shares: 0,
+			difficulty: 0,
+			blocks_found: 0,
+		});
+		worker.shares += 1;
+		worker.difficulty = worker.difficulty.saturating_add(share.difficulty);
+		worker.blocks_found += share.block_found as u64;
+	}
+	ShareWindow {
+		from: shares.iter().map(|s| s.timestamp).min().unwrap_or(0),
+		to: shares.iter().map(|s| s.timestamp).max().unwrap_or(0),
+		difficulty: workers
+			.values()
+			.fold(0u64, |sum, w| sum.saturating_add(w.difficulty)),
+		workers: workers.into_values().collect(),
+	}
+}
+
+impl api::ShareLedger for ShareStore {
+	fn pplns_window(
+		&self,
+		algorithm: &str,
+		window_difficulty: u64,
+		height: Option<u64>,
+	) -> Result<ShareWindow, api::Error> {
+		ShareStore::pplns_window(self, algorithm, window_difficulty, height)
+			.map_err(|e| api::Error::Internal(format!("share ledger: {}", e)))
+	}
+
+	fn pps_window(&self, from: i64, to: i64) -> Result<ShareWindow, api::Error> {
+		ShareStore::pps_window(self, from, to)
+			.map_err(|e| api::Error::Internal(format!("share ledger: {}", e)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn share(login: &str, algorithm: &str, difficulty: u64, height: u64, timestamp: i64) -> Share {
+		Share {
+			login: login.to_string(),
+			algorithm: algorithm.to_string(),
+			difficulty,
+			height,
+			timestamp,
+			block_found: false,
+		}
+	}
+
+	#[test]
+	fn share_windows() {
+		let db_root = "target/.epic_share_ledger";
+		let _ = std::fs::remove_dir_all(db_root);
+		{
+			let store = ShareStore::new(db_root, DEFAULT_SHARE_RETENTION_SECS).unwrap();
+			store.add_share(&share("rig1", "randomx", 100, 10, 1000)).unwrap();
+			store.add_share(&share("rig2", "progpow", 50, 10, 1010)).unwrap();
+			store.add_share(&share("rig2", "randomx", 100, 11, 1020)).unwrap();
+			let mut block = share("rig1", "randomx", 200, 11, 1030);
+			block.block_found = true;
+			store.add_share(&block).unwrap();
+		}
+		// the sequence survives a restart
+		let store = ShareStore::new(db_root, DEFAULT_SHARE_RETENTION_SECS).unwrap();
+		store.add_share(&share("rig1", "randomx", 200, 12, 1040)).unwrap();
+
+		// the last 400 of randomx difficulty up to block 11
+		let window = store.pplns_window("randomx", 400, Some(11)).unwrap();
+		assert_eq!((window.from, window.to, window.difficulty), (1000, 1030, 400));
+		assert_eq!(window.workers.len(), 2);
+		assert_eq!(window.workers[0].login, "rig1");
+		assert_eq!(
+			(window.workers[0].shares, window.workers[0].difficulty),
+			(2, 300)
+		);
+		assert_eq!(window.workers[0].blocks_found, 1);
+		assert_eq!(window.workers[1].difficulty, 100);
+
+		let window = store.pplns_window("randomx", 300, None).unwrap();
+		assert_eq!((window.from, window.difficulty), (1030, 400));
+
+		let window = store.pps_window(1005, 1030).unwrap();
+		assert_eq!((window.from, window.to, window.difficulty), (1010, 1030, 350));
+		assert_eq!(window.workers.len(), 3);
+		assert_eq!(window.workers[1].algorithm, "progpow");
+
+		let window = store.pps_window(2000, 3000).unwrap();
+		assert!(window.workers.is_empty());
+		let _ = std::fs::remove_dir_all(db_root);
+	}
+
+	#[test]
+	fn prune_old_shares() {
+		let db_root = "target/.epic_share_ledger_prune";
+		let _ = std::fs::remove_dir_all(db_root);
+		let store = ShareStore::new(db_root, 100).unwrap();
+		store.add_share(&share("rig1", "randomx", 100, 10, 1000)).unwrap();
+		store.add_share(&share("rig2", "progpow", 50, 10, 1010)).unwrap();
+		store.add_share(&share("rig1", "randomx2", 10, 10, 1020)).unwrap();
+		store.add_share(&share("rig2", "randomx", 100, 11, 1200)).unwrap();
+
+		// everything accepted before 1150 goes
+		store.prune(1250).unwrap();
+		let window = store.pplns_window("randomx", 1000, None).unwrap();
+		assert_eq!((window.from, window.difficulty), (1200, 100));
+		assert!(store
+			.pplns_window("progpow", 1000, None)
+			.unwrap()
+			.workers
+			.is_empty());
+		assert!(store
+			.pplns_window("randomx2", 1000, None)
+			.unwrap()
+			.workers
+			.is_empty());
+
+		let window = store.pps_window(0, 2000).unwrap();
+		assert_eq!((window.from, window.difficulty), (1200, 100));
+
+		// the sequence goes on after the pruned shares
+		store.add_share(&share("rig1", "randomx", 100, 12, 1300)).unwrap();
+		let window = store.pplns_window("randomx", 1000, None).unwrap();
+		assert_eq!((window.from, window.to, window.difficulty), (1200, 1300, 200));
+		let _ = std::fs::remove_dir_all(db_root);
+	}
+}
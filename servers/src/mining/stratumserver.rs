@@ -14,7 +14,7 @@
 
 //! Mining Stratum Server
 
-use crate::util::RwLock;
+use crate::util::{Mutex, RwLock};
 use chrono::prelude::Utc;
 use futures::channel::mpsc;
 use futures::pin_mut;
@@ -23,18 +23,21 @@ use serde;
 use serde_json;
 use serde_json::Value;
 use std::collections::HashMap;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
 
 use tokio_util::codec::{Framed, LinesCodec};
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use std::{cmp, thread};
 
 use crate::chain::{self, SyncState};
 use crate::common::stats::{StratumStats, WorkerStats};
-use crate::common::types::StratumServerConfig;
+use crate::api::TLSConfig;
+use crate::common::types::{Error, StratumServerConfig};
 
 use crate::core::core::hash::Hashed;
 use crate::core::core::Block;
@@ -42,6 +45,9 @@ use crate::core::pow::{DifficultyNumber, PoWType};
 use crate::core::{pow, ser};
 use crate::keychain;
 use crate::mining::mine_block;
+use crate::mining::share_ledger::{Share, ShareStore};
+use crate::mining::vardiff::{Vardiff, VardiffConfig};
+use crate::mining::worker_auth::{LoginLimiter, WorkerCredentials};
 
 use crate::util;
 
@@ -51,6 +57,19 @@ use epic_core::ser::Writeable;
 
 type Tx = mpsc::UnboundedSender<String>;
 
+/// Algorithms jobs carry a share difficulty for, in the order of the job.
+const JOB_ALGORITHMS: [PoWType; 3] = [PoWType::Cuckatoo, PoWType::RandomX, PoWType::ProgPow];
+
+/// How often workers that stopped submitting shares are retargeted.
+const IDLE_RETARGET_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a worker has to log in when worker credentials are configured,
+/// and to complete the TLS handshake.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest request line a worker may send.
+const MAX_LINE_LENGTH: usize = 16 * 1024;
+
 // ----------------------------------------
 // http://www.jsonrpc.org/specification
 // RPC Methods
@@ -127,6 +146,12 @@ impl RpcError {
 			message: "Invalid Request".to_string(),
 		}
 	}
+	pub fn login_first() -> Self {
+		RpcError {
+			code: -32500,
+			message: "Login first".to_string(),
+		}
+	}
 }
 
 impl From<RpcError> for Value {
@@ -172,7 +197,7 @@ struct SubmitParams {
 	pow: AlgorithmParams,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JobTemplate {
 	height: u64,
 	job_id: u64,
@@ -238,6 +263,9 @@ struct Handler {
 	sync_state: Arc<SyncState>,
 	chain: Arc<chain::Chain>,
 	current_state: Arc<RwLock<State>>,
+	credentials: Option<WorkerCredentials>,
+	login_limiter: Mutex<LoginLimiter>,
+	share_store: Option<Arc<ShareStore>>,
 }
 
 impl Handler {
@@ -246,14 +274,29 @@ impl Handler {
 		stratum_stats: Arc<RwLock<StratumStats>>,
 		sync_state: Arc<SyncState>,
 		minimum_share_difficulty: DifficultyNumber,
+		vardiff_config: Option<VardiffConfig>,
+		credentials: Option<WorkerCredentials>,
+		share_store: Option<Arc<ShareStore>>,
 		chain: Arc<chain::Chain>,
 	) -> Self {
+		let share_bounds = minimum_share_difficulty
+			.iter()
+			.map(|(pow, min)| (*pow, (*min, u64::MAX)))
+			.collect();
 		Handler {
 			id,
-			workers: Arc::new(WorkersList::new(stratum_stats.clone())),
+			workers: Arc::new(WorkersList::new(
+				stratum_stats.clone(),
+				vardiff_config,
+				share_bounds,
+				credentials.is_some(),
+			)),
 			sync_state,
 			chain,
 			current_state: Arc::new(RwLock::new(State::new(minimum_share_difficulty))),
+			credentials,
+			login_limiter: Mutex::new(LoginLimiter::default()),
+			share_store,
 		}
 	}
 	pub fn from_stratum(stratum: &StratumServer) -> Self {
@@ -274,11 +317,23 @@ impl Handler {
 			PoWType::ProgPow,
 			stratum.config.progpow_minimum_share_difficulty,
 		);
+		let vardiff_config = match stratum.config.vardiff_target_share_time.unwrap_or(10) {
+			0 => None,
+			target_share_time => Some(VardiffConfig {
+				target_share_time: Duration::from_secs(target_share_time),
+				retarget_time: Duration::from_secs(
+					stratum.config.vardiff_retarget_time.unwrap_or(60),
+				),
+			}),
+		};
 		Handler::new(
 			stratum.id.clone(),
 			stratum.stratum_stats.clone(),
 			stratum.sync_state.clone(),
 			minimum_share_difficulty,
+			vardiff_config,
+			stratum.credentials.clone(),
+			stratum.share_store.clone(),
 			stratum.chain.clone(),
 		)
 	}
@@ -287,7 +342,11 @@ impl Handler {
 		debug!("request: {:?}", request);
 		// Call the handler function for requested method
 		let response = match request.method.as_str() {
-			"login" => self.handle_login(request.params, worker_id),
+			method if !self.is_authorized(worker_id, method) => Err(self.reject_worker(worker_id)),
+			// the password hash is slow on purpose, do not hold up other workers
+			"login" => {
+				tokio::task::block_in_place(|| self.handle_login(request.params, worker_id))
+			}
 			"submit" => {
 				let res = self.handle_submit(request.params, worker_id);
 				// this key_id has been used now, reset
@@ -301,7 +360,7 @@ impl Handler {
 				if self.sync_state.is_syncing() {
 					Err(RpcError::node_is_syncing())
 				} else {
-					self.handle_getjobtemplate(request.params)
+					self.handle_getjobtemplate(request.params, worker_id)
 				}
 			}
 			"status" => self.handle_status(worker_id),
@@ -332,10 +391,56 @@ impl Handler {
 	}
 	fn handle_login(&self, params: Option<Value>, worker_id: usize) -> Result<Value, RpcError> {
 		let params: LoginParams = parse_params(params)?;
+		if let Some(credentials) = &self.credentials {
+			if !credentials.verify(&params.login, &params.pass) {
+				warn!(
+					"(Server ID: {}) Worker {} failed to log in as {}",
+					self.id, worker_id, params.login,
+				);
+				return Err(self.reject_worker(worker_id));
+			}
+			let addr = self.workers.get_worker(worker_id)?.addr;
+			self.login_limiter.lock().login_succeeded(addr);
+		}
 		self.workers.login(worker_id, params.login, params.agent)?;
 		return Ok("ok".into());
 	}
 
+	// Whether the worker may call the method, with worker credentials
+	// configured only logging in is allowed before having done so
+	fn is_authorized(&self, worker_id: usize, method: &str) -> bool {
+		self.credentials.is_none()
+			|| method == "login"
+			|| method == "keepalive"
+			|| self.workers.is_authenticated(worker_id)
+	}
+
+	// Reject a worker failing to log in or calling methods without, it is
+	// disconnected once the response is sent and its address refused for a
+	// while after too many failures
+	fn reject_worker(&self, worker_id: usize) -> RpcError {
+		if let Ok(worker) = self.workers.get_worker(worker_id) {
+			self.login_failed(worker.addr);
+		}
+		self.workers.reject(worker_id);
+		RpcError::login_first()
+	}
+
+	// Count a failed login from the address, logging when it gets banned
+	fn login_failed(&self, addr: IpAddr) {
+		if self.login_limiter.lock().login_failed(addr, Instant::now()) {
+			warn!(
+				"(Server ID: {}) Refusing workers from {} after too many failed logins",
+				self.id, addr,
+			);
+		}
+	}
+
+	// Whether connections from the address are refused
+	fn is_banned(&self, addr: IpAddr) -> bool {
+		self.login_limiter.lock().is_banned(addr, Instant::now())
+	}
+
 	// Handle KEEPALIVE message
 	fn handle_keepalive(&self) -> Result<Value, RpcError> {
 		return Ok("ok".into());
@@ -365,10 +470,15 @@ impl Handler {
 	}
 
 	// Handle GETJOBTEMPLATE message
-	fn handle_getjobtemplate(&self, params: Option<Value>) -> Result<Value, RpcError> {
+	fn handle_getjobtemplate(
+		&self,
+		params: Option<Value>,
+		worker_id: usize,
+	) -> Result<Value, RpcError> {
 		let _params: JobParams = parse_params(params)?;
 		// Build a JobTemplate from a BlockHeader and return JSON
-		let job_template = self.build_block_template();
+		let worker = self.workers.get_worker(worker_id)?;
+		let job_template = worker.job_template(&self.build_block_template());
 		let response = serde_json::to_value(&job_template).unwrap();
 		debug!(
 			"(Server ID: {}) sending block {} with id {} to single worker",
@@ -437,11 +547,9 @@ impl Handler {
 		}
 
 		// Serialize the block header into pre and post nonce strings
-		let algorithms = vec![PoWType::Cuckatoo, PoWType::RandomX, PoWType::ProgPow];
-
 		let difficulty = {
 			let state = self.current_state.read();
-			algorithms
+			JOB_ALGORITHMS
 				.iter()
 				.map(|x| (x.to_str(), state.get_minimum_difficulty(*x)))
 				.collect::<Vec<(String, u64)>>()
@@ -449,7 +557,7 @@ impl Handler {
 
 		let block_difficulty = {
 			let state = self.current_state.read();
-			algorithms
+			JOB_ALGORITHMS
 				.iter()
 				.map(|x| (x.to_str(), state.get_current_difficulty(*x)))
 				.collect::<Vec<(String, u64)>>()
@@ -542,7 +650,12 @@ impl Handler {
 			.to_difficulty(&b.header.pre_pow(), b.header.height, b.header.pow.nonce)
 			.to_num((&b.header.pow.proof).into());
 
-		let minimum_share_difficulty = state.get_minimum_difficulty((&b.header.pow.proof).into());
+		let b_pow_type: PoWType = (&b.header.pow.proof).into();
+
+		let minimum_share_difficulty = self
+			.workers
+			.share_target(worker_id, b_pow_type)
+			.unwrap_or_else(|| state.get_minimum_difficulty(b_pow_type));
 		// If the difficulty is too low its an error
 		if share_difficulty < minimum_share_difficulty {
 			// Return error status
@@ -555,8 +668,6 @@ impl Handler {
 			return Err(RpcError::too_low_difficulty());
 		}
 
-		let b_pow_type: PoWType = (&b.header.pow.proof).into();
-
 		let current_difficulty = state.get_current_difficulty(b_pow_type.clone());
 		// If the difficulty is high enough, submit it (which also validates it)
 		if share_difficulty >= current_difficulty && pow_type == b_pow_type {
@@ -628,8 +739,24 @@ impl Handler {
 				current_difficulty,
 				submitted_by,
 			);
-		self.workers
-			.update_stats(worker_id, |worker_stats| worker_stats.num_accepted += 1);
+		self.workers.share_accepted(
+			worker_id,
+			b_pow_type,
+			state.get_minimum_difficulty(b_pow_type),
+		);
+		if let Some(share_store) = &self.share_store {
+			let share = Share {
+				login: submitted_by,
+				algorithm: b_pow_type.to_str(),
+				difficulty: minimum_share_difficulty,
+				height: b.header.height,
+				timestamp: Utc::now().timestamp(),
+				block_found: share_is_block,
+			};
+			if let Err(e) = share_store.add_share(&share) {
+				error!("(Server ID: {}) Failed to log share: {}", self.id, e);
+			}
+		}
 		let submit_response;
 		if share_is_block {
 			submit_response = format!("blockfound - {}", b.hash().to_hex());
@@ -646,21 +773,43 @@ impl Handler {
 		debug!("broadcast job");
 		// Package new block into RpcRequest
 		let job_template = self.build_block_template();
-		let job_template_json = serde_json::to_string(&job_template).unwrap();
-		// Issue #1159 - use a serde_json Value type to avoid extra quoting
-		let job_template_value: Value = serde_json::from_str(&job_template_json).unwrap();
-		let job_request = RpcRequest {
-			id: Id::String(String::from("Stratum")),
-			jsonrpc: String::from("2.0"),
-			method: String::from("job"),
-			params: Some(job_template_value),
-		};
-		let job_request_json = serde_json::to_string(&job_request).unwrap();
 		debug!(
 			"(Server ID: {}) sending block {} with id {} to stratum clients",
 			self.id, job_template.height, job_template.job_id,
 		);
-		self.workers.broadcast(job_request_json.clone());
+		self.workers
+			.broadcast_with(|worker| job_request(&worker.job_template(&job_template)));
+	}
+
+	// Send a job at its new share difficulty to a worker vardiff retargeted
+	// when it submitted its last share
+	fn send_retargeted_job(&self, worker_id: usize) {
+		if !self.workers.take_retargeted(worker_id) {
+			return;
+		}
+		let job_template = self.build_block_template();
+		debug!(
+			"(Server ID: {}) sending block {} with id {} to retargeted worker {}",
+			self.id, job_template.height, job_template.job_id, worker_id,
+		);
+		self.workers.send_with(worker_id, |worker| {
+			job_request(&worker.job_template(&job_template))
+		});
+	}
+
+	// Retarget the workers whose vardiff window is over without them
+	// submitting a share, and send them a job at their new share difficulty
+	fn retarget_idle_workers(&self) {
+		let worker_ids = self.workers.retarget_idle();
+		if worker_ids.is_empty() {
+			return;
+		}
+		let job_template = self.build_block_template();
+		for worker_id in worker_ids {
+			self.workers.send_with(worker_id, |worker| {
+				job_request(&worker.job_template(&job_template))
+			});
+		}
 	}
 
 	pub fn run(&self, config: &StratumServerConfig, tx_pool: &ServerTxPool) {
@@ -668,6 +817,7 @@ impl Handler {
 		let mut deadline: i64 = 0;
 		let mut head = self.chain.head().unwrap();
 		let mut current_hash = head.prev_block_h;
+		let mut last_idle_retarget = Instant::now();
 
 		//let mut d_block = (Block::default(), mine_block::BlockFees{fees: 0, height, key_id: None}, PoWType::Cuckaroo);
 		loop {
@@ -682,7 +832,7 @@ impl Handler {
 			if (current_hash != latest_hash || Utc::now().timestamp() >= deadline)
 				&& self.workers.count() > 0
 			{
-				{
+				let share_bounds = {
 					debug!("resend updated block");
 					let mut state = self.current_state.write();
 					let mut wallet_listener_url: Option<String> = None;
@@ -768,11 +918,31 @@ impl Handler {
 						state.current_block_versions.clear();
 					}
 					state.current_block_versions.push((new_block, pow_type));
-					// Send this job to all connected workers
-				}
+
+					// vardiff moves within the minimum and the network difficulty, as
+					// such a share is a block
+					state
+						.minimum_share_difficulty
+						.iter()
+						.map(|(pow, min)| {
+							let max = cmp::min(
+								maximum_share_difficulty(config, *pow),
+								state.get_current_difficulty(*pow),
+							);
+							(*pow, (*min, max))
+						})
+						.collect()
+				};
+				self.workers.update_share_bounds(share_bounds);
+				// Send this job to all connected workers
 				self.broadcast_job();
 			}
 
+			if last_idle_retarget.elapsed() >= IDLE_RETARGET_INTERVAL && self.workers.count() > 0 {
+				last_idle_retarget = Instant::now();
+				self.retarget_idle_workers();
+			}
+
 			// sleep before restarting loop
 			thread::sleep(Duration::from_millis(5));
 		} // Main Loop
@@ -784,73 +954,123 @@ impl Handler {
 async fn handle_worker_respo(h: Arc<Handler>, request: RpcRequest, worker_id: usize) {
 	let resp = h.handle_rpc_requests(request, worker_id).await;
 	h.workers.send_to(worker_id, resp).await;
+	if h.workers.is_rejected(worker_id) {
+		// dropping the worker closes the connection once the response is out
+		h.workers.remove_worker(worker_id);
+		return;
+	}
+	h.send_retargeted_job(worker_id);
 }
 
 // ----------------------------------------
 // Worker Factory Thread Function
 #[tokio::main]
-async fn accept_connections(listen_addr: SocketAddr, handler: Arc<Handler>) {
+async fn accept_connections(
+	listeners: Vec<(SocketAddr, Option<TlsAcceptor>)>,
+	handler: Arc<Handler>,
+) {
 	//info!("Start tokio stratum server");
 
+	let listeners = listeners
+		.into_iter()
+		.map(|(listen_addr, tls_acceptor)| listen(listen_addr, tls_acceptor, handler.clone()));
+	futures::future::join_all(listeners).await;
+}
+
+async fn listen(listen_addr: SocketAddr, tls_acceptor: Option<TlsAcceptor>, handler: Arc<Handler>) {
 	let listener = TcpListener::bind(&listen_addr)
 		.await
 		.unwrap_or_else(|_| panic!("Stratum: Failed to bind to listen address {}", listen_addr));
 
 	loop {
 		let handler = handler.clone();
-		let (socket, _) = listener.accept().await.unwrap();
-		// Spawn a task to process the connection
-		let (tx, mut rx) = mpsc::unbounded();
-
-		let worker_id = handler.workers.add_worker(tx).await;
-		match socket.peer_addr() {
-			Ok(addr) => {
-				info!("Worker {}/{:?} connected", worker_id, addr);
+		let (socket, addr) = listener.accept().await.unwrap();
+		if handler.is_banned(addr.ip()) {
+			debug!("Refusing worker connection from banned {}", addr);
+			continue;
+		}
+		match tls_acceptor.clone() {
+			None => {
+				tokio::spawn(handle_connection(socket, addr, handler));
 			}
-			Err(e) => {
-				error!("Error on Socket {:?}", e);
+			Some(tls_acceptor) => {
+				tokio::spawn(async move {
+					let accept = tls_acceptor.accept(socket);
+					match tokio::time::timeout(LOGIN_TIMEOUT, accept).await {
+						Ok(Ok(stream)) => handle_connection(stream, addr, handler).await,
+						Ok(Err(e)) => debug!("TLS handshake with worker {} failed: {}", addr, e),
+						Err(_) => {
+							debug!("TLS handshake with worker {} timed out", addr);
+							handler.login_failed(addr.ip());
+						}
+					}
+				});
 			}
-		};
-		let framed = Framed::new(socket, LinesCodec::new());
-		let (mut writer, mut reader) = framed.split();
+		}
+	}
+}
 
-		let h = handler.clone();
-		let read = async move {
-			while let Some(line) = reader.try_next().await.unwrap() {
-				let request = serde_json::from_str(&line)
-					.map_err(|e| error!("error serializing line: {}", e))?;
+async fn handle_connection<S>(stream: S, addr: SocketAddr, handler: Arc<Handler>)
+where
+	S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+	// Spawn a task to process the connection
+	let (tx, mut rx) = mpsc::unbounded();
 
-				tokio::task::spawn(handle_worker_respo(h.clone(), request, worker_id));
+	let worker_id = handler.workers.add_worker(tx, addr.ip()).await;
+	info!("Worker {}/{:?} connected", worker_id, addr);
+	if handler.credentials.is_some() {
+		let h = handler.clone();
+		tokio::spawn(async move {
+			tokio::time::sleep(LOGIN_TIMEOUT).await;
+			// dropping the worker closes the connection
+			if h.workers.is_connected(worker_id) && !h.workers.is_authenticated(worker_id) {
+				debug!("Worker {} did not log in in time, disconnecting", worker_id);
+				h.login_failed(addr.ip());
+				h.workers.remove_worker(worker_id);
 			}
+		});
+	}
+	let framed = Framed::new(stream, LinesCodec::new_with_max_length(MAX_LINE_LENGTH));
+	let (mut writer, mut reader) = framed.split();
+
+	let h = handler.clone();
+	let read = async move {
+		while let Some(line) = reader
+			.try_next()
+			.await
+			.map_err(|e| debug!("Worker {} sent an invalid line: {}", worker_id, e))?
+		{
+			let request = serde_json::from_str(&line)
+				.map_err(|e| error!("error serializing line: {}", e))?;
 
-			Result::<_, ()>::Ok(())
-		};
+			tokio::task::spawn(handle_worker_respo(h.clone(), request, worker_id));
+		}
 
-		let write = async move {
-			while let Some(line) = rx.next().await {
-				writer
-					.send(line)
-					.await
-					.map_err(|e| error!("error writing line: {}", e))?;
-			}
+		Result::<_, ()>::Ok(())
+	};
 
-			Result::<_, ()>::Ok(())
-		};
+	let write = async move {
+		while let Some(line) = rx.next().await {
+			writer
+				.send(line)
+				.await
+				.map_err(|e| error!("error writing line: {}", e))?;
+		}
 
-		let task = async move {
-			pin_mut!(read, write);
-			match futures::future::select(read, write).await {
-				futures::future::Either::Left(_) => {
-					trace!("Worker {} disconnected", worker_id);
-				}
-				futures::future::Either::Right(_) => {
-					trace!("Worker {} disconnected", worker_id);
-					handler.workers.remove_worker(worker_id);
-				}
-			};
-		};
-		tokio::spawn(task);
-	}
+		Result::<_, ()>::Ok(())
+	};
+
+	pin_mut!(read, write);
+	match futures::future::select(read, write).await {
+		futures::future::Either::Left(_) => {
+			trace!("Worker {} disconnected", worker_id);
+		}
+		futures::future::Either::Right(_) => {
+			trace!("Worker {} disconnected", worker_id);
+			handler.workers.remove_worker(worker_id);
+		}
+	};
 }
 
 // ----------------------------------------
@@ -862,56 +1082,101 @@ pub struct Worker {
 	agent: String,
 	login: Option<String>,
 	authenticated: bool,
+	/// Failed to log in, disconnected once the response is sent
+	rejected: bool,
+	addr: IpAddr,
 	tx: Tx,
+	/// Share difficulty of each algorithm, empty when vardiff is disabled
+	vardiff: HashMap<PoWType, Vardiff>,
+	/// Algorithm of the last accepted share
+	pow_type: Option<PoWType>,
+	/// Whether the last accepted share retargeted the worker
+	retargeted: bool,
 }
 
 impl Worker {
 	/// Creates a new Stratum Worker.
-	pub fn new(id: usize, tx: Tx) -> Worker {
+	pub fn new(id: usize, addr: IpAddr, tx: Tx, vardiff: HashMap<PoWType, Vardiff>) -> Worker {
 		Worker {
 			id,
 			agent: String::from(""),
 			login: None,
 			authenticated: false,
+			rejected: false,
+			addr,
 			tx,
+			vardiff,
+			pow_type: None,
+			retargeted: false,
 		}
 	}
+
+	/// The job template at the share difficulty of this worker.
+	pub fn job_template(&self, job_template: &JobTemplate) -> JobTemplate {
+		let mut job_template = job_template.clone();
+		let difficulties = JOB_ALGORITHMS.iter().zip(job_template.difficulty.iter_mut());
+		for (pow, (_, difficulty)) in difficulties {
+			if let Some(vardiff) = self.vardiff.get(pow) {
+				*difficulty = vardiff.difficulty();
+			}
+		}
+		job_template
+	}
 } // impl Worker
 
 struct WorkersList {
 	workers_list: Arc<RwLock<HashMap<usize, Worker>>>,
 	stratum_stats: Arc<RwLock<StratumStats>>,
+	vardiff_config: Option<VardiffConfig>,
+	// minimum and maximum share difficulty of each algorithm
+	share_bounds: RwLock<HashMap<PoWType, (u64, u64)>>,
+	// whether workers get jobs only once logged in
+	login_required: bool,
 }
 
 impl WorkersList {
-	pub fn new(stratum_stats: Arc<RwLock<StratumStats>>) -> Self {
+	pub fn new(
+		stratum_stats: Arc<RwLock<StratumStats>>,
+		vardiff_config: Option<VardiffConfig>,
+		share_bounds: HashMap<PoWType, (u64, u64)>,
+		login_required: bool,
+	) -> Self {
 		WorkersList {
 			workers_list: Arc::new(RwLock::new(HashMap::new())),
 			stratum_stats,
+			vardiff_config,
+			share_bounds: RwLock::new(share_bounds),
+			login_required,
 		}
 	}
 
-	pub async fn add_worker(&self, tx: Tx) -> usize {
+	pub async fn add_worker(&self, tx: Tx, addr: IpAddr) -> usize {
+		let vardiff = match self.vardiff_config {
+			Some(config) => self
+				.share_bounds
+				.read()
+				.iter()
+				.map(|(pow, (min, max))| (*pow, Vardiff::new(config, *min, *max)))
+				.collect(),
+			None => HashMap::new(),
+		};
 		let mut stratum_stats = self.stratum_stats.write();
 		let worker_id = stratum_stats.worker_stats.len();
-		let worker = Worker::new(worker_id, tx);
+		let worker = Worker::new(worker_id, addr, tx, vardiff);
 		let mut workers_list = self.workers_list.write();
 		workers_list.insert(worker_id, worker);
 
 		let mut worker_stats = WorkerStats::default();
 		worker_stats.is_connected = true;
 		worker_stats.id = worker_id.to_string();
-		worker_stats.pow_difficulty = 1; // XXX TODO
 		stratum_stats.worker_stats.push(worker_stats);
 		stratum_stats.num_workers = workers_list.len();
 		worker_id
 	}
 	pub fn remove_worker(&self, worker_id: usize) {
 		self.update_stats(worker_id, |ws| ws.is_connected = false);
-		self.workers_list
-			.write()
-			.remove(&worker_id)
-			.expect("Stratum: no such addr in map");
+		// a rejected worker is removed before its connection closes
+		self.workers_list.write().remove(&worker_id);
 		self.stratum_stats.write().num_workers = self.workers_list.read().len();
 	}
 
@@ -925,6 +1190,30 @@ impl WorkersList {
 		Ok(())
 	}
 
+	pub fn is_connected(&self, worker_id: usize) -> bool {
+		self.workers_list.read().contains_key(&worker_id)
+	}
+
+	pub fn is_authenticated(&self, worker_id: usize) -> bool {
+		self.workers_list
+			.read()
+			.get(&worker_id)
+			.map_or(false, |worker| worker.authenticated)
+	}
+
+	pub fn reject(&self, worker_id: usize) {
+		if let Some(worker) = self.workers_list.write().get_mut(&worker_id) {
+			worker.rejected = true;
+		}
+	}
+
+	pub fn is_rejected(&self, worker_id: usize) -> bool {
+		self.workers_list
+			.read()
+			.get(&worker_id)
+			.map_or(false, |worker| worker.rejected)
+	}
+
 	pub fn get_worker(&self, worker_id: usize) -> Result<Worker, RpcError> {
 		self.workers_list
 			.read()
@@ -956,18 +1245,121 @@ impl WorkersList {
 	}
 
 	pub async fn send_to(&self, worker_id: usize, msg: String) {
-		let _ = self
-			.workers_list
+		if let Some(worker) = self.workers_list.read().get(&worker_id) {
+			let _ = worker.tx.unbounded_send(msg);
+		}
+	}
+
+	/// Sends each worker its own message, such as a job at its share
+	/// difficulty. Workers yet to log in are skipped when a login is
+	/// required.
+	pub fn broadcast_with(&self, f: impl Fn(&Worker) -> String) {
+		for worker in self.workers_list.read().values() {
+			if self.login_required && !worker.authenticated {
+				continue;
+			}
+			let _ = worker.tx.unbounded_send(f(worker));
+		}
+	}
+
+	pub fn send_with(&self, worker_id: usize, f: impl FnOnce(&Worker) -> String) {
+		if let Some(worker) = self.workers_list.read().get(&worker_id) {
+			let _ = worker.tx.unbounded_send(f(worker));
+		}
+	}
+
+	/// Lowest difficulty of a share accepted from the worker, None when
+	/// vardiff is disabled.
+	pub fn share_target(&self, worker_id: usize, pow: PoWType) -> Option<u64> {
+		let now = Instant::now();
+		self.workers_list
 			.read()
 			.get(&worker_id)
-			.unwrap()
-			.tx
-			.unbounded_send(msg);
+			.and_then(|worker| worker.vardiff.get(&pow))
+			.map(|vardiff| vardiff.share_target(now))
 	}
 
-	pub fn broadcast(&self, msg: String) {
-		for worker in self.workers_list.read().values() {
-			let _ = worker.tx.unbounded_send(msg.clone());
+	/// Counts an accepted share, retargeting the worker and reporting its
+	/// share difficulty, the minimum one when vardiff is disabled.
+	pub fn share_accepted(&self, worker_id: usize, pow: PoWType, minimum_difficulty: u64) {
+		let difficulty = {
+			let mut workers_list = self.workers_list.write();
+			match workers_list.get_mut(&worker_id) {
+				Some(worker) => {
+					worker.pow_type = Some(pow);
+					match worker.vardiff.get_mut(&pow) {
+						Some(vardiff) => {
+							if vardiff.share_accepted(Instant::now()).is_some() {
+								worker.retargeted = true;
+							}
+							vardiff.difficulty()
+						}
+						None => minimum_difficulty,
+					}
+				}
+				None => minimum_difficulty,
+			}
+		};
+		self.update_stats(worker_id, |ws| {
+			ws.num_accepted += 1;
+			ws.pow_difficulty = difficulty;
+		});
+	}
+
+	/// Whether the worker was retargeted by its last share, clearing it.
+	pub fn take_retargeted(&self, worker_id: usize) -> bool {
+		match self.workers_list.write().get_mut(&worker_id) {
+			Some(worker) => std::mem::replace(&mut worker.retargeted, false),
+			None => false,
+		}
+	}
+
+	/// Retargets the workers whose vardiff window is over, returning the
+	/// ones with a new share difficulty.
+	pub fn retarget_idle(&self) -> Vec<usize> {
+		let now = Instant::now();
+		let mut worker_ids = vec![];
+		for worker in self.workers_list.write().values_mut() {
+			let mut retargeted = false;
+			for vardiff in worker.vardiff.values_mut() {
+				retargeted |= vardiff.retarget(now).is_some();
+			}
+			if retargeted {
+				worker_ids.push(worker.id);
+			}
+		}
+		self.update_pow_difficulty();
+		worker_ids
+	}
+
+	/// Sets the bounds vardiff moves the share difficulty within, the jobs
+	/// sent next carry the new difficulty.
+	pub fn update_share_bounds(&self, share_bounds: HashMap<PoWType, (u64, u64)>) {
+		for worker in self.workers_list.write().values_mut() {
+			for (pow, vardiff) in worker.vardiff.iter_mut() {
+				if let Some((min, max)) = share_bounds.get(pow) {
+					vardiff.set_bounds(*min, *max);
+				}
+			}
+		}
+		*self.share_bounds.write() = share_bounds;
+		self.update_pow_difficulty();
+	}
+
+	// report the share difficulty of the algorithm each worker last mined
+	fn update_pow_difficulty(&self) {
+		let difficulties: Vec<(usize, u64)> = self
+			.workers_list
+			.read()
+			.values()
+			.filter_map(|worker| {
+				let vardiff = worker.vardiff.get(&worker.pow_type?)?;
+				Some((worker.id, vardiff.difficulty()))
+			})
+			.collect();
+		let mut stratum_stats = self.stratum_stats.write();
+		for (worker_id, difficulty) in difficulties {
+			stratum_stats.worker_stats[worker_id].pow_difficulty = difficulty;
 		}
 	}
 
@@ -996,24 +1388,51 @@ pub struct StratumServer {
 	pub tx_pool: ServerTxPool,
 	sync_state: Arc<SyncState>,
 	stratum_stats: Arc<RwLock<StratumStats>>,
+	credentials: Option<WorkerCredentials>,
+	tls_acceptor: Option<TlsAcceptor>,
+	share_store: Option<Arc<ShareStore>>,
 }
 
 impl StratumServer {
-	/// Creates a new Stratum Server.
+	/// Creates a new Stratum Server, loading the worker credentials and TLS
+	/// certificate if configured.
 	pub fn new(
 		config: StratumServerConfig,
 		chain: Arc<chain::Chain>,
 		tx_pool: ServerTxPool,
 		stratum_stats: Arc<RwLock<StratumStats>>,
-	) -> StratumServer {
-		StratumServer {
+		share_store: Option<Arc<ShareStore>>,
+	) -> Result<StratumServer, Error> {
+		let credentials = WorkerCredentials::load(
+			config.worker_credentials.as_deref(),
+			config.worker_password_file.as_deref(),
+		)?;
+		let tls_acceptor = match config.stratum_tls_server_addr {
+			None => None,
+			Some(_) => {
+				let file = config.stratum_tls_certificate_file.clone();
+				let key = config.stratum_tls_certificate_key.clone();
+				let tls_config = match (file, key) {
+					(Some(file), Some(key)) => TLSConfig::new(file, key),
+					_ => {
+						let msg = "Stratum TLS certificate file or key is not set";
+						return Err(Error::Configuration(msg.to_string()));
+					}
+				};
+				Some(TlsAcceptor::from(tls_config.build_server_config()?))
+			}
+		};
+		Ok(StratumServer {
 			id: String::from("0"),
 			config,
 			chain,
 			tx_pool,
 			sync_state: Arc::new(SyncState::new()),
 			stratum_stats,
-		}
+			credentials,
+			tls_acceptor,
+			share_store,
+		})
 	}
 
 	/// "main()" - Starts the stratum-server.  Creates a thread to Listens for
@@ -1036,12 +1455,21 @@ impl StratumServer {
 			.unwrap()
 			.parse()
 			.expect("Stratum: Incorrect address ");
+		let mut listeners = vec![(listen_addr, None)];
+		if let (Some(tls_addr), Some(tls_acceptor)) = (
+			self.config.stratum_tls_server_addr.clone(),
+			self.tls_acceptor.clone(),
+		) {
+			let tls_addr = tls_addr.parse().expect("Stratum: Incorrect TLS address ");
+			info!("Stratum server accepting TLS connections on {}", tls_addr);
+			listeners.push((tls_addr, Some(tls_acceptor)));
+		}
 
 		let handler = Arc::new(Handler::from_stratum(&self));
 		let h = handler.clone();
 
 		let _listener_th = thread::spawn(move || {
-			accept_connections(listen_addr, h);
+			accept_connections(listeners, h);
 		});
 
 		// We have started
@@ -1065,6 +1493,30 @@ impl StratumServer {
 	} // fn run_loop()
 } // StratumServer
 
+// Package a job template into a job RpcRequest
+fn job_request(job_template: &JobTemplate) -> String {
+	let job_template_json = serde_json::to_string(job_template).unwrap();
+	// Issue #1159 - use a serde_json Value type to avoid extra quoting
+	let job_template_value: Value = serde_json::from_str(&job_template_json).unwrap();
+	let job_request = RpcRequest {
+		id: Id::String(String::from("Stratum")),
+		jsonrpc: String::from("2.0"),
+		method: String::from("job"),
+		params: Some(job_template_value),
+	};
+	serde_json::to_string(&job_request).unwrap()
+}
+
+// Maximum share difficulty configured for an algorithm
+fn maximum_share_difficulty(config: &StratumServerConfig, pow: PoWType) -> u64 {
+	match pow {
+		PoWType::Cuckatoo | PoWType::Cuckaroo => config.cuckatoo_maximum_share_difficulty,
+		PoWType::RandomX => config.randomx_maximum_share_difficulty,
+		PoWType::ProgPow => config.progpow_maximum_share_difficulty,
+	}
+	.unwrap_or(u64::MAX)
+}
+
 // Utility function to parse a JSON RPC parameter object, returning a proper
 // error if things go wrong.
 fn parse_params<T>(params: Option<Value>) -> Result<T, RpcError>
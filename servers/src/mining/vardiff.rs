@@ -0,0 +1,214 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Variable share difficulty (vardiff), retargeting the share difficulty of
+//! each worker from the rate it submits shares at, so small and big miners
+//! alike submit a share every few seconds.
+
+use std::cmp;
+use std::time::{Duration, Instant};
+
+/// Largest factor the difficulty moves by in a single retarget.
+const MAX_RETARGET_FACTOR: u64 = 4;
+
+/// Difficulty changes smaller than 1/RETARGET_THRESHOLD are not worth a new
+/// job.
+const RETARGET_THRESHOLD: u64 = 10;
+
+/// How often shares should come in and how often we retarget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VardiffConfig {
+	pub target_share_time: Duration,
+	pub retarget_time: Duration,
+}
+
+impl VardiffConfig {
+	/// Shares we expect in a retarget window at the right difficulty.
+	fn expected_shares(&self) -> u64 {
+		let target = cmp::max(self.target_share_time.as_millis(), 1);
+		cmp::max(self.retarget_time.as_millis() / target, 1) as u64
+	}
+}
+
+/// Share difficulty of a worker for one algorithm.
+#[derive(Debug, Clone)]
+pub struct Vardiff {
+	config: VardiffConfig,
+	min_difficulty: u64,
+	max_difficulty: u64,
+	difficulty: u64,
+	/// Difficulty before the last retarget with its time, shares for it are
+	/// still accepted for a target share time as they may be in flight
+	previous: Option<(u64, Instant)>,
+	window_start: Instant,
+	window_shares: u64,
+}
+
+impl Vardiff {
+	/// Starts at the minimum difficulty.
+	pub fn new(config: VardiffConfig, min_difficulty: u64, max_difficulty: u64) -> Vardiff {
+		let max_difficulty = cmp::max(min_difficulty, max_difficulty);
+		Vardiff {
+			config,
+			min_difficulty,
+			max_difficulty,
+			difficulty: min_difficulty,
+			previous: None,
+			window_start: Instant::now(),
+			window_shares: 0,
+		}
+	}
+
+	/// Difficulty requested from the worker.
+	pub fn difficulty(&self) -> u64 {
+		self.difficulty
+	}
+
+	/// Lowest difficulty of a share we accept.
+	pub fn share_target(&self, now: Instant) -> u64 {
+		match self.previous {
+			Some((previous, at)) if now < at + self.config.target_share_time => {
+				cmp::min(previous, self.difficulty)
+			}
+			_ => self.difficulty,
+		}
+	}
+
+	/// Counts an accepted share, returns the new difficulty if retargeted.
+	pub fn share_accepted(&mut self, now: Instant) -> Option<u64> {
+		self.window_shares += 1;
+		self.retarget(now)
+	}
+
+	/// Retargets once the window is over, or early when shares come in twice
+	/// as fast as they should. Returns the new difficulty if it changed.
+	pub fn retarget(&mut self, now: Instant) -> Option<u64> {
+		let elapsed = now.saturating_duration_since(self.window_start);
+		if elapsed < self.config.retarget_time
+			&& self.window_shares < 2 * self.config.expected_shares()
+		{
+			return None;
+		}
+		let difficulty = if self.window_shares == 0 {
+			self.difficulty / MAX_RETARGET_FACTOR
+		} else {
+			// scale by the ratio of the actual share rate to the target one
+			let target = self.config.target_share_time.as_millis();
+			let elapsed = cmp::max(elapsed.as_millis(), 1);
+			let scaled = self.difficulty as u128 * self.window_shares as u128 * target / elapsed;
+			cmp::min(scaled, u64::MAX as u128) as u64
+		};
+		let difficulty = cmp::min(
+			cmp::max(difficulty, self.difficulty / MAX_RETARGET_FACTOR),
+			self.difficulty.saturating_mul(MAX_RETARGET_FACTOR),
+		);
+		self.window_start = now;
+		self.window_shares = 0;
+		let delta = cmp::max(difficulty, self.difficulty) - cmp::min(difficulty, self.difficulty);
+		if delta <= self.difficulty / RETARGET_THRESHOLD {
+			return None;
+		}
+		self.set_difficulty(difficulty, now)
+	}
+
+	/// Updates the bounds, the maximum usually being the network difficulty
+	/// as such a share is a block. Returns the new difficulty if it changed.
+	pub fn set_bounds(&mut self, min_difficulty: u64, max_difficulty: u64) -> Option<u64> {
+		self.min_difficulty = min_difficulty;
+		self.max_difficulty = cmp::max(min_difficulty, max_difficulty);
+		self.set_difficulty(self.difficulty, Instant::now())
+	}
+
+	fn set_difficulty(&mut self, difficulty: u64, now: Instant) -> Option<u64> {
+		let difficulty = cmp::min(
+			cmp::max(difficulty, self.min_difficulty),
+			self.max_difficulty,
+		);
+		if difficulty == self.difficulty {
+			return None;
+		}
+		self.previous = Some((self.difficulty, now));
+		self.difficulty = difficulty;
+		Some(difficulty)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn vardiff(min: u64, max: u64) -> Vardiff {
+		let config = VardiffConfig {
+			target_share_time: Duration::from_secs(10),
+			retarget_time: Duration::from_secs(60),
+		};
+		Vardiff::new(config, min, max)
+	}
+
+	#[test]
+	fn fast_worker_gets_harder_shares() {
+		let mut v = vardiff(100, 1_000_000);
+		let start = v.window_start;
+		// 12 shares in 6s, 20 times too fast: capped to 4x, retargeted early
+		for i in 1..12 {
+			assert_eq!(v.share_accepted(start + Duration::from_millis(500 * i)), None);
+		}
+		assert_eq!(v.share_accepted(start + Duration::from_secs(6)), Some(400));
+		assert_eq!(v.difficulty(), 400);
+		// shares for the previous difficulty are still accepted for a while
+		let now = start + Duration::from_secs(7);
+		assert_eq!(v.share_target(now), 100);
+		assert_eq!(v.share_target(now + Duration::from_secs(10)), 400);
+	}
+
+	#[test]
+	fn slow_and_idle_workers_get_easier_shares() {
+		let mut v = vardiff(100, 1_000_000);
+		v.set_bounds(100, 1_000_000);
+		v.difficulty = 10_000;
+		let start = v.window_start;
+		// 3 shares in 60s instead of 6
+		v.share_accepted(start + Duration::from_secs(20));
+		v.share_accepted(start + Duration::from_secs(40));
+		assert_eq!(v.share_accepted(start + Duration::from_secs(60)), Some(5_000));
+		// no share at all
+		assert_eq!(v.retarget(start + Duration::from_secs(120)), Some(1_250));
+		// never below the minimum
+		assert_eq!(v.retarget(start + Duration::from_secs(180)), Some(312));
+		assert_eq!(v.retarget(start + Duration::from_secs(240)), Some(100));
+		assert_eq!(v.retarget(start + Duration::from_secs(300)), None);
+	}
+
+	#[test]
+	fn steady_worker_keeps_its_difficulty() {
+		let mut v = vardiff(100, 1_000_000);
+		let start = v.window_start;
+		for i in 1..6 {
+			v.share_accepted(start + Duration::from_secs(10 * i));
+		}
+		// 6.5 shares worth of time for 6 shares, within the threshold
+		assert_eq!(v.share_accepted(start + Duration::from_secs(65)), None);
+		assert_eq!(v.difficulty(), 100);
+	}
+
+	#[test]
+	fn bounded_by_network_difficulty() {
+		let mut v = vardiff(100, 1_000_000);
+		v.difficulty = 800;
+		assert_eq!(v.set_bounds(100, 500), Some(500));
+		assert_eq!(v.set_bounds(100, 5000), None);
+		// the network difficulty dropped below the configured minimum
+		assert_eq!(v.set_bounds(50, 50), Some(50));
+	}
+}
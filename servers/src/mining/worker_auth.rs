@@ -0,0 +1,273 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stratum worker logins, checked against credentials from the config or a
+//! password file, and rate limiting of the addresses failing them.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use hmac::Hmac;
+use pbkdf2::pbkdf2;
+use sha2::Sha256;
+
+use crate::common::types::Error;
+use crate::util;
+
+/// Failed logins from an address before it gets banned.
+const MAX_LOGIN_FAILURES: u32 = 5;
+
+/// Window failed logins are counted in.
+const LOGIN_FAILURE_WINDOW: Duration = Duration::from_secs(60);
+
+/// How long an address failing too many logins is refused for.
+const LOGIN_BAN_TIME: Duration = Duration::from_secs(600);
+
+/// PBKDF2 rounds of the worker password hashes, slow enough that a leaked
+/// password file can't be brute-forced cheaply.
+#[cfg(not(test))]
+const PASSWORD_HASH_ROUNDS: u32 = 600_000;
+#[cfg(test)]
+const PASSWORD_HASH_ROUNDS: u32 = 1_000;
+
+/// Salted PBKDF2-HMAC-SHA256 of a worker password.
+#[derive(Debug, Clone, PartialEq)]
+struct PasswordHash {
+	salt: String,
+	hash: [u8; 32],
+}
+
+impl PasswordHash {
+	fn new(salt: &str, password: &str) -> PasswordHash {
+		let mut hash = [0; 32];
+		let _ = pbkdf2::<Hmac<Sha256>>(
+			password.as_bytes(),
+			salt.as_bytes(),
+			PASSWORD_HASH_ROUNDS,
+			&mut hash,
+		);
+		PasswordHash {
+			salt: salt.to_string(),
+			hash,
+		}
+	}
+
+	fn verify(&self, password: &str) -> bool {
+		constant_time_eq(&PasswordHash::new(&self.salt, password).hash, &self.hash)
+	}
+}
+
+// Compares without stopping at the first difference, not to tell how much
+// of the hash matched through the time taken
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Logins and passwords workers may connect with.
+#[derive(Debug, Clone, Default)]
+pub struct WorkerCredentials {
+	passwords: HashMap<String, PasswordHash>,
+}
+
+impl WorkerCredentials {
+	/// Credentials from `login:password` entries and a password file of
+	/// `login:salt:hex(pbkdf2_hmac_sha256(password, salt))` lines, with
+	/// 600000 rounds. None when neither is configured, any login is accepted
+	/// then.
+	pub fn load(
+		credentials: Option<&[String]>,
+		password_file: Option<&str>,
+	) -> Result<Option<WorkerCredentials>, Error> {
+		if credentials.is_none() && password_file.is_none() {
+			return Ok(None);
+		}
+		let mut passwords = HashMap::new();
+		for entry in credentials.unwrap_or_default() {
+			let (login, password) = entry.split_once(':').ok_or_else(|| {
+				Error::Configuration(format!("Worker credentials {} are not login:password", entry))
+			})?;
+			passwords.insert(login.to_string(), PasswordHash::new("", password));
+		}
+		if let Some(path) = password_file {
+			let file = File::open(path).map_err(|e| {
+				Error::Configuration(format!("Cannot open worker password file {}: {}", path, e))
+			})?;
+			for (n, line) in BufReader::new(file).lines().enumerate() {
+				let line = line.map_err(|e| {
+					let msg = format!("Cannot read worker password file {}: {}", path, e);
+					Error::Configuration(msg)
+				})?;
+				let line = line.trim();
+				if line.is_empty() || line.starts_with('#') {
+					continue;
+				}
+				let (login, hash) = parse_password_line(line).ok_or_else(|| {
+					Error::Configuration(format!(
+						"Invalid line {} in worker password file {}",
+						n + 1,
+						path
+					))
+				})?;
+				passwords.insert(login, hash);
+			}
+		}
+		Ok(Some(WorkerCredentials { passwords }))
+	}
+
+	/// Whether the worker login exists with this password.
+	pub fn verify(&self, login: &str, password: &str) -> bool {
+		self.passwords
+			.get(login)
+			.map_or(false, |hash| hash.verify(password))
+	}
+}
+
+fn parse_password_line(line: &str) -> Option<(String, PasswordHash)> {
+	let mut parts = line.splitn(3, ':');
+	let login = parts.next()?;
+	let salt = parts.next()?;
+	let hash = util::from_hex(parts.next()?.to_string()).ok()?;
+	if login.is_empty() || hash.len() != 32 {
+		return None;
+	}
+	let mut password_hash = PasswordHash {
+		salt: salt.to_string(),
+		hash: [0; 32],
+	};
+	password_hash.hash.copy_from_slice(&hash);
+	Some((login.to_string(), password_hash))
+}
+
+#[derive(Debug)]
+struct LoginFailures {
+	count: u32,
+	window_start: Instant,
+	banned_until: Option<Instant>,
+}
+
+/// Failed logins of each address, banning the ones failing too often.
+#[derive(Debug, Default)]
+pub struct LoginLimiter {
+	failures: HashMap<IpAddr, LoginFailures>,
+}
+
+impl LoginLimiter {
+	/// Whether connections from the address are refused.
+	pub fn is_banned(&self, addr: IpAddr, now: Instant) -> bool {
+		match self.failures.get(&addr) {
+			Some(LoginFailures {
+				banned_until: Some(until),
+				..
+			}) => now < *until,
+			_ => false,
+		}
+	}
+
+	/// Counts a failed login, returns whether the address is now banned.
+	pub fn login_failed(&mut self, addr: IpAddr, now: Instant) -> bool {
+		self.prune(now);
+		let failures = self.failures.entry(addr).or_insert(LoginFailures {
+			count: 0,
+			window_start: now,
+			banned_until: None,
+		});
+		if now >= failures.window_start + LOGIN_FAILURE_WINDOW {
+			failures.count = 0;
+			failures.window_start = now;
+		}
+		failures.count += 1;
+		if failures.count >= MAX_LOGIN_FAILURES {
+			failures.banned_until = Some(now + LOGIN_BAN_TIME);
+		}
+		failures.banned_until.is_some()
+	}
+
+	/// Forgets the failures of an address that logged in.
+	pub fn login_succeeded(&mut self, addr: IpAddr) {
+		self.failures.remove(&addr);
+	}
+
+	// forget the failures neither banning nor counting anymore
+	fn prune(&mut self, now: Instant) {
+		self.failures.retain(|_, failures| match failures.banned_until {
+			Some(until) => now < until,
+			None => now < failures.window_start + LOGIN_FAILURE_WINDOW,
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	#[test]
+	fn credentials_from_config_and_password_file() {
+		let path = std::env::temp_dir().join("epic_worker_passwords");
+		let hash = util::to_hex(PasswordHash::new("pepper", "secret2").hash.to_vec());
+		let mut file = File::create(&path).unwrap();
+		writeln!(file, "# login:salt:pbkdf2").unwrap();
+		writeln!(file, "rig2:pepper:{}", hash).unwrap();
+		drop(file);
+
+		let entries = vec!["rig1:secret1".to_string()];
+		let credentials =
+			WorkerCredentials::load(Some(&entries), Some(path.to_str().unwrap())).unwrap();
+		let credentials = credentials.unwrap();
+		assert!(credentials.verify("rig1", "secret1"));
+		assert!(credentials.verify("rig2", "secret2"));
+		assert!(!credentials.verify("rig1", "secret2"));
+		assert!(!credentials.verify("rig3", "secret1"));
+		let _ = std::fs::remove_file(&path);
+
+		assert!(WorkerCredentials::load(None, None).unwrap().is_none());
+		let entries = vec!["rig1".to_string()];
+		assert!(WorkerCredentials::load(Some(&entries), None).is_err());
+	}
+
+	#[test]
+	fn hashes_compare_in_full() {
+		assert!(constant_time_eq(&[1, 2, 3], &[1, 2, 3]));
+		assert!(!constant_time_eq(&[1, 2, 3], &[1, 2, 4]));
+		assert!(!constant_time_eq(&[0, 2, 3], &[1, 2, 3]));
+		assert!(!constant_time_eq(&[1, 2, 3], &[1, 2]));
+	}
+
+	#[test]
+	fn addresses_failing_logins_get_banned() {
+		let mut limiter = LoginLimiter::default();
+		let addr: IpAddr = "10.0.0.1".parse().unwrap();
+		let start = Instant::now();
+		for i in 1..MAX_LOGIN_FAILURES {
+			assert!(!limiter.login_failed(addr, start + Duration::from_secs(i as u64)));
+		}
+		// failures older than the window do not count
+		let later = start + LOGIN_FAILURE_WINDOW + Duration::from_secs(1);
+		assert!(!limiter.login_failed(addr, later));
+		assert!(!limiter.is_banned(addr, later));
+
+		for _ in 1..MAX_LOGIN_FAILURES {
+			limiter.login_failed(addr, later);
+		}
+		assert!(limiter.is_banned(addr, later));
+		assert!(!limiter.is_banned("10.0.0.2".parse().unwrap(), later));
+		assert!(!limiter.is_banned(addr, later + LOGIN_BAN_TIME));
+
+		limiter.login_succeeded(addr);
+		assert!(!limiter.is_banned(addr, later));
+	}
+}
@@ -0,0 +1,325 @@
+// Copyright 2025 The Epic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Onion stem relaying through a local stand-in for the Tor SOCKS proxy,
+//! which answers the `/v2/tor` requests itself.
+use epic_core as core;
+use epic_keychain as keychain;
+use epic_pool as pool;
+use epic_servers as servers;
+use epic_util as util;
+
+use self::core::core::hash::{Hash, Hashed};
+use self::core::core::{BlockHeader, BlockSums, KernelFeatures, Transaction};
+use self::core::libtx;
+use self::keychain::{ExtKeychain, Keychain};
+use self::pool::{
+	BlockChain, DandelionConfig, PoolAdapter, PoolConfig, PoolEntry, PoolError, TransactionPool,
+	TxSource,
+};
+use self::servers::common::adapters::DandelionAdapter;
+use self::servers::common::onion_stem::OnionStem;
+use self::servers::monitor_transactions;
+use self::util::{RwLock, StopState};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A CONNECT request as seen by the stand-in, with the JSON-RPC body posted
+/// through it.
+struct Request {
+	host: String,
+	port: u16,
+	body: Value,
+}
+
+/// Serves `replies.len()` connections, replying to each request with the next
+/// JSON-RPC reply.
+fn socks_stand_in(replies: Vec<&'static str>) -> (SocketAddr, Receiver<Request>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+	let (tx, rx) = channel();
+	thread::spawn(move || {
+		for reply in replies {
+			let (stream, _) = listener.accept().unwrap();
+			tx.send(serve(stream, reply)).unwrap();
+		}
+	});
+	(addr, rx)
+}
+
+fn serve(mut stream: TcpStream, reply: &str) -> Request {
+	let mut greeting = [0u8; 3];
+	stream.read_exact(&mut greeting).unwrap();
+	assert_eq!(greeting, [5, 1, 0]);
+	stream.write_all(&[5, 0]).unwrap();
+
+	let mut request = [0u8; 5];
+	stream.read_exact(&mut request).unwrap();
+	assert_eq!(request[..4], [5, 1, 0, 3]);
+	let mut host = vec![0u8; request[4] as usize];
+	stream.read_exact(&mut host).unwrap();
+	let mut port = [0u8; 2];
+	stream.read_exact(&mut port).unwrap();
+	stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+
+	let mut reader = BufReader::new(stream.try_clone().unwrap());
+	let mut content_length = 0;
+	loop {
+		let mut line = String::new();
+		reader.read_line(&mut line).unwrap();
+		if line == "\r\n" {
+			break;
+		}
+		if let Some(len) = line.strip_prefix("Content-Length: ") {
+			content_length = len.trim().parse().unwrap();
+		}
+	}
+	let mut body = vec![0u8; content_length];
+	reader.read_exact(&mut body).unwrap();
+
+	write!(
+		stream,
+		"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+		reply.len(),
+		reply
+	)
+	.unwrap();
+
+	Request {
+		host: String::from_utf8(host).unwrap(),
+		port: u16::from_be_bytes(port),
+		body: serde_json::from_slice(&body).unwrap(),
+	}
+}
+
+const OK_REPLY: &str = r#"{"jsonrpc":"2.0","result":{"Ok":null},"id":1}"#;
+const ERR_REPLY: &str = r#"{"jsonrpc":"2.0","result":{"Err":{"Internal":"pool"}},"id":1}"#;
+
+#[test]
+fn send_stem_transaction() {
+	let (addr, requests) = socks_stand_in(vec![OK_REPLY, ERR_REPLY]);
+	let onion_stem = OnionStem::new(addr, 3);
+	let tx = Transaction::empty();
+
+	onion_stem.send("http://abc.onion:3413", &tx, 2).unwrap();
+	let request = requests.recv().unwrap();
+	assert_eq!(request.host, "abc.onion");
+	assert_eq!(request.port, 3413);
+	assert_eq!(request.body["method"], "stem_transaction");
+	assert_eq!(request.body["params"][1], 2);
+	let sent: Transaction = serde_json::from_value(request.body["params"][0].clone()).unwrap();
+	assert_eq!(sent, tx);
+
+	// The next hop refusing the tx is an error, so we try another one.
+	assert!(onion_stem.send("http://abc.onion:3413", &tx, 2).is_err());
+	requests.recv().unwrap();
+}
+
+#[test]
+fn refuse_non_onion_urls() {
+	let (addr, _requests) = socks_stand_in(vec![]);
+	let onion_stem = OnionStem::new(addr, 3);
+	assert!(onion_stem
+		.send("http://127.0.0.1:3413", &Transaction::empty(), 2)
+		.is_err());
+}
+
+#[test]
+fn relay_to_next_candidate() {
+	// The first peer tried refuses the tx, the second one takes it.
+	let (addr, requests) = socks_stand_in(vec![ERR_REPLY, OK_REPLY]);
+	let onion_stem = OnionStem::new(addr, 3);
+	let candidates = vec![
+		"http://abc.onion:3413".to_owned(),
+		"http://def.onion:3413".to_owned(),
+	];
+	onion_stem.relay(candidates, Transaction::empty(), 1);
+
+	let timeout = Duration::from_secs(10);
+	let first = requests.recv_timeout(timeout).unwrap();
+	let second = requests.recv_timeout(timeout).unwrap();
+	assert_ne!(first.host, second.host);
+	assert_eq!(second.body["params"][1], 1);
+}
+
+/// Chain at genesis taking any transaction, the pool only needs a head and
+/// its block sums here.
+struct TestChain;
+
+impl BlockChain for TestChain {
+	fn verify_coinbase_maturity(&self, _tx: &Transaction) -> Result<(), PoolError> {
+		Ok(())
+	}
+
+	fn verify_tx_lock_height(&self, _tx: &Transaction) -> Result<(), PoolError> {
+		Ok(())
+	}
+
+	fn validate_tx(&self, _tx: &Transaction) -> Result<(), PoolError> {
+		Ok(())
+	}
+
+	fn chain_head(&self) -> Result<BlockHeader, PoolError> {
+		Ok(BlockHeader::default())
+	}
+
+	fn get_block_header(&self, _hash: &Hash) -> Result<BlockHeader, PoolError> {
+		Ok(BlockHeader::default())
+	}
+
+	fn get_block_sums(&self, _hash: &Hash) -> Result<BlockSums, PoolError> {
+		Ok(BlockSums::default())
+	}
+}
+
+/// Stems like the pool adapter of a node running the onion stem, with a
+/// single onion peer to relay to.
+struct OnionHop {
+	onion_stem: OnionStem,
+	next_hop: String,
+}
+
+impl PoolAdapter for OnionHop {
+	fn tx_accepted(&self, _entry: &PoolEntry) {}
+
+	fn stem_tx_accepted(&self, entry: &PoolEntry) -> Result<(), PoolError> {
+		self.onion_stem.stem_tx_accepted(entry, || vec![self.next_hop.clone()])
+	}
+}
+
+/// Dandelion epoch stuck in the fluff phase.
+struct FluffEpoch;
+
+impl DandelionAdapter for FluffEpoch {
+	fn is_stem(&self) -> bool {
+		false
+	}
+
+	fn is_expired(&self) -> bool {
+		false
+	}
+
+	fn next_epoch(&self) {}
+}
+
+type TestPool = Arc<RwLock<TransactionPool<TestChain, OnionHop>>>;
+
+fn test_pool(socks_proxy_addr: SocketAddr, next_hop: &str) -> TestPool {
+	let config = PoolConfig {
+		accept_fee_base: 0,
+		max_pool_size: 50,
+		max_pushed_pool_size: 50,
+		max_stempool_size: 50,
+		mineable_max_weight: 10_000,
+	};
+	let adapter = OnionHop {
+		onion_stem: OnionStem::new(socks_proxy_addr, 2),
+		next_hop: format!("http://{}:3413", next_hop),
+	};
+	Arc::new(RwLock::new(TransactionPool::new(config, Arc::new(TestChain), Arc::new(adapter))))
+}
+
+/// Stands in for the Tor network, adding the transactions posted to an
+/// onion service to the stempool of its node, as its `/v2/tor` API does.
+fn onion_network(listener: TcpListener, nodes: HashMap<String, TestPool>) {
+	thread::spawn(move || {
+		for stream in listener.incoming() {
+			let request = serve(stream.unwrap(), OK_REPLY);
+			let tx: Transaction =
+				serde_json::from_value(request.body["params"][0].clone()).unwrap();
+			let hops = request.body["params"][1].as_u64().unwrap() as u8;
+			let mut pool = nodes[&request.host].write();
+			let header = pool.chain_head().unwrap();
+			pool.add_to_pool(TxSource::OnionStem(hops), tx, true, &header).unwrap();
+		}
+	});
+}
+
+fn wait_for<F: Fn() -> bool>(condition: F) {
+	let start = Instant::now();
+	while !condition() {
+		assert!(start.elapsed() < Duration::from_secs(20), "timed out");
+		thread::sleep(Duration::from_millis(100));
+	}
+}
+
+fn test_transaction(keychain: &ExtKeychain) -> Transaction {
+	let input_id = ExtKeychain::derive_key_id(1, 1, 0, 0, 0);
+	let output_id = ExtKeychain::derive_key_id(1, 2, 0, 0, 0);
+	libtx::build::transaction(
+		KernelFeatures::Plain { fee: 100 },
+		vec![
+			libtx::build::input(1_000, input_id),
+			libtx::build::output(900, output_id),
+		],
+		keychain,
+		&libtx::ProofBuilder::new(keychain),
+	)
+	.unwrap()
+}
+
+#[test]
+fn stem_relay_fluff() {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let socks_proxy_addr = listener.local_addr().unwrap();
+
+	// We stem through two hops, alice relays our tx to bob who fluffs it.
+	let ours = test_pool(socks_proxy_addr, "alice.onion");
+	let alice = test_pool(socks_proxy_addr, "bob.onion");
+	let bob = test_pool(socks_proxy_addr, "carol.onion");
+	let mut nodes = HashMap::new();
+	nodes.insert("alice.onion".to_owned(), alice.clone());
+	nodes.insert("bob.onion".to_owned(), bob.clone());
+	onion_network(listener, nodes);
+
+	let keychain = ExtKeychain::from_random_seed(false).unwrap();
+	let tx = test_transaction(&keychain);
+	{
+		let mut pool = ours.write();
+		let header = pool.chain_head().unwrap();
+		pool.add_to_pool(TxSource::PushApi, tx.clone(), true, &header).unwrap();
+	}
+
+	wait_for(|| bob.read().txpool.contains_tx(tx.hash()));
+	for pool in &[&ours, &alice] {
+		let pool = pool.read();
+		assert!(pool.stempool.contains_tx(tx.hash()));
+		assert_eq!(pool.txpool.size(), 0);
+	}
+
+	// Once the epoch of a hop turns to fluff, its Dandelion monitor fluffs
+	// the stem txs older than aggregation_secs, counted in whole seconds.
+	thread::sleep(Duration::from_millis(1_100));
+	let stop_state = Arc::new(StopState::new());
+	let dandelion_config = DandelionConfig {
+		aggregation_secs: 0,
+		..DandelionConfig::default()
+	};
+	let monitor = monitor_transactions(
+		dandelion_config,
+		alice.clone(),
+		Arc::new(FluffEpoch),
+		stop_state.clone(),
+	)
+	.unwrap();
+	wait_for(|| alice.read().txpool.contains_tx(tx.hash()));
+	stop_state.stop();
+	monitor.join().unwrap();
+}
@@ -22,6 +22,8 @@ use crate::config::GlobalConfig;
 use crate::p2p;
 use crate::servers::ServerConfig;
 use crate::util::file::get_first_line;
+use serde::Deserialize;
+use serde_json::{json, Value};
 use term;
 
 pub fn client_command(client_args: &ArgMatches, global_config: GlobalConfig) -> i32 {
@@ -58,6 +60,24 @@ pub fn client_command(client_args: &ArgMatches, global_config: GlobalConfig) ->
 				panic!("Invalid peer address format");
 			}
 		}
+		Some(("liststempool", _)) => {
+			list_stempool(&server_config, api_secret);
+		}
+		Some(("removetx", tx_args)) => {
+			let kernel = tx_args
+				.get_one::<String>("kernel")
+				.expect("kernel argument missing");
+			remove_pool_tx(&server_config, kernel, api_secret);
+		}
+		Some(("fluffstemtx", tx_args)) => {
+			let kernel = tx_args
+				.get_one::<String>("kernel")
+				.expect("kernel argument missing");
+			fluff_stem_tx(&server_config, kernel, api_secret);
+		}
+		Some(("clearpool", _)) => {
+			clear_pool(&server_config, api_secret);
+		}
 		_ => panic!("No client command provided, use 'epic client --help' for details"),
 	}
 	0
@@ -153,6 +173,93 @@ pub fn list_connected_peers(config: &ServerConfig, api_secret: Option<String>) {
 	e.reset().unwrap();
 }
 
+pub fn list_stempool(config: &ServerConfig, api_secret: Option<String>) {
+	let mut e = term::stdout().unwrap();
+	let entries = owner_rpc::<Vec<api::PoolEntryPrintable>>(
+		config,
+		api_secret,
+		"get_stempool_transactions",
+		json!([]),
+	);
+
+	match entries {
+		Ok(entries) => {
+			writeln!(e, "{} stempool transactions", entries.len()).unwrap();
+			for entry in entries {
+				println!();
+				writeln!(e, "Transaction: {}", entry.tx_hash).unwrap();
+				writeln!(e, "Kernels: {}", entry.kernels.join(", ")).unwrap();
+				writeln!(e, "Source: {:?}", entry.src).unwrap();
+				writeln!(e, "Age: {}s", entry.age).unwrap();
+				writeln!(e, "Fee: {}", entry.fee).unwrap();
+				writeln!(e, "Weight: {}", entry.weight).unwrap();
+			}
+		}
+		Err(err) => writeln!(e, "Failed to get stempool transactions: {}", err).unwrap(),
+	};
+
+	e.reset().unwrap();
+}
+
+pub fn remove_pool_tx(config: &ServerConfig, kernel: &str, api_secret: Option<String>) {
+	let mut e = term::stdout().unwrap();
+	let params = json!([kernel]);
+	match owner_rpc::<usize>(config, api_secret, "remove_pool_transaction", params) {
+		Ok(removed) => writeln!(e, "Removed {} transactions from the pool", removed).unwrap(),
+		Err(err) => writeln!(e, "Failed to remove transaction {}: {}", kernel, err).unwrap(),
+	};
+	e.reset().unwrap();
+}
+
+pub fn fluff_stem_tx(config: &ServerConfig, kernel: &str, api_secret: Option<String>) {
+	let mut e = term::stdout().unwrap();
+	let params = json!([kernel]);
+	match owner_rpc::<()>(config, api_secret, "fluff_stem_transaction", params) {
+		Ok(_) => writeln!(e, "Successfully fluffed transaction {}", kernel).unwrap(),
+		Err(err) => writeln!(e, "Failed to fluff transaction {}: {}", kernel, err).unwrap(),
+	};
+	e.reset().unwrap();
+}
+
+pub fn clear_pool(config: &ServerConfig, api_secret: Option<String>) {
+	let mut e = term::stdout().unwrap();
+	match owner_rpc::<()>(config, api_secret, "clear_pool", json!([])) {
+		Ok(_) => writeln!(e, "Successfully cleared the transaction pool").unwrap(),
+		Err(err) => writeln!(e, "Failed to clear the transaction pool: {}", err).unwrap(),
+	};
+	e.reset().unwrap();
+}
+
+/// Calls a method of the v2 owner api, returning its result or the error
+/// the node replied with.
+fn owner_rpc<T>(
+	config: &ServerConfig,
+	api_secret: Option<String>,
+	method: &str,
+	params: Value,
+) -> Result<T, String>
+where
+	for<'de> T: Deserialize<'de>,
+{
+	let url = format!("http://{}/v2/owner", config.api_http_addr);
+	let req = json!({
+		"jsonrpc": "2.0",
+		"method": method,
+		"params": params,
+		"id": 1,
+	});
+	let timeout = api::client::TimeOut::default();
+	let res: Value = api::client::post(url.as_str(), api_secret, &req, timeout)
+		.map_err(|e| e.to_string())?;
+	if let Some(err) = res.get("error") {
+		return Err(err.to_string());
+	}
+	match res["result"].get("Ok") {
+		Some(ok) => serde_json::from_value(ok.clone()).map_err(|e| e.to_string()),
+		None => Err(res["result"]["Err"].to_string()),
+	}
+}
+
 fn get_status_from_node(
 	config: &ServerConfig,
 	api_secret: Option<String>,
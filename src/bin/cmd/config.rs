@@ -13,9 +13,32 @@
 // limitations under the License.
 
 /// Epic configuration file output command
+use crate::config::config::init_api_secret;
 use crate::config::GlobalConfig;
 use crate::core::global;
+use crate::p2p::Capabilities;
+use crate::servers::ServerConfig;
 use std::env;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+/// Settings asked by the wizard, the only ones it changes in an existing
+/// config file.
+const WIZARD_KEYS: [&str; 11] = [
+	"server.archive_mode",
+	"server.api_http_addr",
+	"server.tls_certificate_file",
+	"server.tls_certificate_key",
+	"server.stratum_mining_config.enable_stratum_server",
+	"server.stratum_mining_config.stratum_server_addr",
+	"server.tor.socks_proxy_addr",
+	"server.p2p_config.capabilities",
+	"server.api_secret_path",
+	"server.foreign_api_secret_path",
+	"server.foundation_path",
+];
 
 /// Create a config file in the current directory
 pub fn config_command_server(chain_type: &global::ChainTypes, file_name: &str) {
@@ -43,3 +66,304 @@ pub fn config_command_server(chain_type: &global::ChainTypes, file_name: &str) {
 		file_name
 	);
 }
+
+/// Upgrade the config file in the current directory to the current version,
+/// keeping every value already set in it.
+pub fn config_migrate_server(chain_type: &global::ChainTypes, file_name: &str) {
+	let config_file_name = config_file_in_current_dir(file_name);
+	let (_, added) = migrate(&config_file_name, chain_type);
+	if added.is_empty() {
+		println!("{} is already up to date", file_name);
+	} else {
+		println!("{} migrated, added settings:", file_name);
+		for key in added {
+			println!("  {}", key);
+		}
+	}
+}
+
+/// Interactively create, or update, the config file in the current directory.
+pub fn config_wizard_server(chain_type: &global::ChainTypes, file_name: &str) {
+	let current_dir = env::current_dir().unwrap_or_else(|e| {
+		panic!("Error creating config file: {}", e);
+	});
+	let config_file_name = config_file_in_current_dir(file_name);
+
+	let exists = config_file_name.exists();
+	let mut config = if exists {
+		if !ask(
+			&format!("{} already exists, update it?", file_name),
+			"y",
+			parse_yes_no,
+		) {
+			return;
+		}
+		// Bring the file up to date first, its values become the defaults below.
+		let (config, added) = migrate(&config_file_name, chain_type);
+		if !added.is_empty() {
+			println!("Added {} settings missing from the existing file", added.len());
+		}
+		config
+	} else {
+		let chain_type = ask(
+			"Chain type (mainnet, floonet, usernet)",
+			chain_type.shortname().as_str(),
+			parse_chain_type,
+		);
+		let mut config = GlobalConfig::for_chain(&chain_type);
+		config.update_paths(&current_dir, &chain_type);
+		config
+	};
+
+	{
+		let server = &mut config.members.as_mut().unwrap().server;
+		// check_foundation compares against the hash for the configured chain
+		global::set_mining_mode(server.chain_type.clone());
+		ask_server_config(server, &current_dir);
+	}
+
+	// An existing file only gets the answered settings changed, keeping the
+	// user's comments and edits.
+	let name = config_file_name.to_str().unwrap();
+	let written = if exists {
+		config.update_file(name, &WIZARD_KEYS)
+	} else {
+		config.write_to_file(name)
+	};
+	written.unwrap_or_else(|e| {
+		panic!("Error writing config file: {}", e);
+	});
+	println!("{} written to {}", file_name, current_dir.display());
+}
+
+fn ask_server_config(server: &mut ServerConfig, current_dir: &Path) {
+	server.archive_mode = Some(ask(
+		"Run as an archive node (keep the full block history)?",
+		yes_no(server.archive_mode.unwrap_or(false)),
+		parse_yes_no,
+	));
+
+	server.api_http_addr = ask_bind_addr("API listen address", &server.api_http_addr);
+
+	let tls = ask(
+		"Serve the API over TLS?",
+		yes_no(server.tls_certificate_file.is_some()),
+		parse_yes_no,
+	);
+	if tls {
+		server.tls_certificate_file = Some(ask(
+			"TLS certificate file",
+			server.tls_certificate_file.as_deref().unwrap_or(""),
+			parse_existing_file,
+		));
+		server.tls_certificate_key = Some(ask(
+			"TLS certificate private key file",
+			server.tls_certificate_key.as_deref().unwrap_or(""),
+			parse_existing_file,
+		));
+	} else {
+		server.tls_certificate_file = None;
+		server.tls_certificate_key = None;
+	}
+
+	if let Some(ref mut stratum) = server.stratum_mining_config {
+		let enabled = ask(
+			"Enable the stratum mining server?",
+			yes_no(stratum.enable_stratum_server.unwrap_or(false)),
+			parse_yes_no,
+		);
+		stratum.enable_stratum_server = Some(enabled);
+		if enabled {
+			let addr = stratum
+				.stratum_server_addr
+				.clone()
+				.unwrap_or("127.0.0.1:3416".to_owned());
+			stratum.stratum_server_addr = Some(ask_bind_addr("Stratum listen address", &addr));
+		}
+	}
+
+	let tor = ask(
+		"Enable Tor (launches a tor process for onion stem routing)?",
+		yes_no(
+			server.tor.socks_proxy_addr != ""
+				&& server
+					.p2p_config
+					.capabilities
+					.contains(Capabilities::ONIONSTEM),
+		),
+		parse_yes_no,
+	);
+	if tor {
+		let default = match server.tor.socks_proxy_addr.as_str() {
+			"" => "127.0.0.1:9050",
+			addr => addr,
+		};
+		server.tor.socks_proxy_addr = ask("Tor socks proxy address", default, |a| {
+			parse_socket_addr(a).map(|a| a.to_string())
+		});
+		server.p2p_config.capabilities.insert(Capabilities::ONIONSTEM);
+	} else {
+		server.p2p_config.capabilities.remove(Capabilities::ONIONSTEM);
+	}
+
+	server.api_secret_path = ask_secret(
+		"Protect the owner API with a secret?",
+		&server.api_secret_path,
+		current_dir.join(".api_secret"),
+	);
+	server.foreign_api_secret_path = ask_secret(
+		"Protect the foreign API with a secret?",
+		&server.foreign_api_secret_path,
+		current_dir.join(".foreign_api_secret"),
+	);
+
+	server.foundation_path = ask(
+		"Foundation file",
+		&server.foundation_path,
+		parse_foundation_file,
+	);
+}
+
+/// Asks whether to protect an API with a basic auth secret, generating the
+/// secret file when it doesn't exist yet.
+fn ask_secret(
+	question: &str,
+	current: &Option<String>,
+	default_path: PathBuf,
+) -> Option<String> {
+	if !ask(question, yes_no(current.is_some()), parse_yes_no) {
+		return None;
+	}
+	let default_path = current
+		.clone()
+		.unwrap_or(default_path.to_str().unwrap().to_owned());
+	let path = ask("Secret file", &default_path, |a| Ok(a.to_owned()));
+	let secret_file = PathBuf::from(&path);
+	if secret_file.exists() {
+		println!("  keeping the existing secret in {}", path);
+	} else {
+		init_api_secret(&secret_file).unwrap_or_else(|e| {
+			panic!("Error creating secret file {}: {}", path, e);
+		});
+		println!("  generated a new secret in {}", path);
+	}
+	Some(path)
+}
+
+/// Asks for a listen address, warning when it's already in use.
+fn ask_bind_addr(question: &str, default: &str) -> String {
+	loop {
+		let addr = ask(question, default, parse_socket_addr);
+		match TcpListener::bind(addr) {
+			Ok(_) => return addr.to_string(),
+			Err(e) => {
+				println!("  can't listen on {}: {}", addr, e);
+				if ask(
+					"  Use it anyway (e.g. the node is running right now)?",
+					"n",
+					parse_yes_no,
+				) {
+					return addr.to_string();
+				}
+			}
+		}
+	}
+}
+
+/// Prints `question` and reads answers from stdin until `parse` accepts
+/// one. An empty answer picks `default`, the end of input aborts the wizard
+/// without writing the config file.
+fn ask<T, F>(question: &str, default: &str, parse: F) -> T
+where
+	F: Fn(&str) -> Result<T, String>,
+{
+	loop {
+		if default.is_empty() {
+			print!("{}: ", question);
+		} else {
+			print!("{} [{}]: ", question, default);
+		}
+		io::stdout().flush().unwrap();
+
+		let mut line = String::new();
+		if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+			println!();
+			eprintln!("Config wizard aborted, no more input");
+			exit(1);
+		}
+		let answer = match line.trim() {
+			"" => default,
+			answer => answer,
+		};
+		match parse(answer) {
+			Ok(value) => return value,
+			Err(e) => println!("  {}", e),
+		}
+	}
+}
+
+fn yes_no(value: bool) -> &'static str {
+	if value {
+		"y"
+	} else {
+		"n"
+	}
+}
+
+fn parse_yes_no(answer: &str) -> Result<bool, String> {
+	match answer.to_lowercase().as_str() {
+		"y" | "yes" => Ok(true),
+		"n" | "no" => Ok(false),
+		_ => Err("please answer y or n".to_owned()),
+	}
+}
+
+fn parse_chain_type(answer: &str) -> Result<global::ChainTypes, String> {
+	match answer.to_lowercase().as_str() {
+		"main" | "mainnet" => Ok(global::ChainTypes::Mainnet),
+		"floo" | "floonet" => Ok(global::ChainTypes::Floonet),
+		"user" | "usernet" => Ok(global::ChainTypes::UserTesting),
+		_ => Err("please answer mainnet, floonet or usernet".to_owned()),
+	}
+}
+
+fn parse_socket_addr(answer: &str) -> Result<SocketAddr, String> {
+	answer
+		.parse::<SocketAddr>()
+		.map_err(|e| format!("not a valid ip:port address: {}", e))
+}
+
+fn parse_existing_file(answer: &str) -> Result<String, String> {
+	if answer.is_empty() {
+		return Err("a file is required".to_owned());
+	}
+	if !Path::new(answer).is_file() {
+		return Err(format!("{} does not exist", answer));
+	}
+	Ok(answer.to_owned())
+}
+
+fn parse_foundation_file(answer: &str) -> Result<String, String> {
+	let path = parse_existing_file(answer)?;
+	if !global::check_foundation(path.clone()) {
+		return Err(format!("{} is not the foundation file for this chain type", path));
+	}
+	Ok(path)
+}
+
+fn config_file_in_current_dir(file_name: &str) -> PathBuf {
+	let mut config_file_name = env::current_dir().unwrap_or_else(|e| {
+		panic!("Error reading current directory: {}", e);
+	});
+	config_file_name.push(file_name);
+	config_file_name
+}
+
+fn migrate(
+	config_file_name: &Path,
+	chain_type: &global::ChainTypes,
+) -> (GlobalConfig, Vec<String>) {
+	GlobalConfig::migrate(config_file_name.to_str().unwrap(), chain_type).unwrap_or_else(|e| {
+		panic!("Error migrating config file: {}", e);
+	})
+}
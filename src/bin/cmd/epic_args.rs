@@ -123,11 +123,58 @@ pub fn build_cli() -> Command {
                 )
                 .subcommand(
                     Command::new("config")
-                        .about("Generate a configuration epic-server.toml file in the current directory"),
+                        .about("Create or update the epic-server.toml configuration file in the current directory. Asks for the main settings when run in a terminal.")
+                        .arg(
+                            Arg::new("non_interactive")
+                                .long("non-interactive")
+                                .help("Write the default configuration without asking any questions")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("migrate")
+                                .long("migrate")
+                                .help("Add the settings missing from an existing epic-server.toml, keeping the values already set (a .bak copy is kept)")
+                                .conflicts_with("non_interactive")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
                 )
                 .subcommand(
                     Command::new("run")
                         .about("Run the Epic server in this console"),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Export the chain to a portable archive file (server must be stopped)")
+                        .arg(
+                            Arg::new("to")
+                                .long("to")
+                                .help("Path of the archive file to write (e.g. chain.epicarchive)")
+                                .required(true)
+                                .value_name("FILE"),
+                        )
+                        .arg(
+                            Arg::new("from_height")
+                                .long("from-height")
+                                .help("Height of the first block to export. Exporting below the horizon requires an archive node.")
+                                .default_value("0")
+                                .value_parser(clap::value_parser!(u64))
+                                .value_name("HEIGHT"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Verify and import the blocks from a chain archive file (server must be stopped)")
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .help("Path of the archive file to read")
+                                .required(true)
+                                .value_name("FILE"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("rebuild-kernel-index")
+                        .about("Rebuild the kernel excess index from scratch (server must be stopped)"),
                 ),
         )
         .subcommand(
@@ -163,6 +210,38 @@ pub fn build_cli() -> Command {
                                 .help("Peer ip and port (e.g. 10.12.12.13:13414)")
                                 .required(true),
                         ),
+                )
+                .subcommand(
+                    Command::new("liststempool")
+                        .about("Print the transactions of the stempool, under Dandelion embargo"),
+                )
+                .subcommand(
+                    Command::new("removetx")
+                        .about("Remove a transaction and its descendants from the pool")
+                        .arg(
+                            Arg::new("kernel")
+                                .short('k')
+                                .long("kernel")
+                                .help("Excess or hash of one of the transaction kernels, in hex")
+                                .required(true)
+                                .value_name("KERNEL"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("fluffstemtx")
+                        .about("Fluff a stempool transaction without waiting for its embargo")
+                        .arg(
+                            Arg::new("kernel")
+                                .short('k')
+                                .long("kernel")
+                                .help("Excess or hash of one of the transaction kernels, in hex")
+                                .required(true)
+                                .value_name("KERNEL"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("clearpool")
+                        .about("Drop all the transactions of the txpool and the stempool"),
                 ),
         )
 }
@@ -20,6 +20,7 @@ use crate::tui::ui;
 use clap::ArgMatches;
 use ctrlc;
 use epic_util::logger::LogEntry;
+use std::path::Path;
 use std::process::exit;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
@@ -175,6 +176,56 @@ pub fn server_command(
             Some(("run", _)) => {
                 start_server(server_config, logs_rx, api_chan);
             }
+            Some(("export", args)) => {
+                let path = args.get_one::<String>("to").expect("required");
+                let from_height = *args.get_one::<u64>("from_height").unwrap_or(&0);
+                match servers::Server::export_chain(&server_config, Path::new(path), from_height) {
+                    Ok(summary) => println!(
+                        "Exported {} blocks ({} to {}) to {}, checksum {}",
+                        summary.blocks,
+                        summary.header.from_height,
+                        summary.header.to_height,
+                        path,
+                        summary.checksum.to_hex()
+                    ),
+                    Err(e) => {
+                        error!("Chain export failed: {:?}", e);
+                        println!("Chain export failed: {:?}", e);
+                        return 1;
+                    }
+                }
+            }
+            Some(("import", args)) => {
+                let path = args.get_one::<String>("from").expect("required");
+                match servers::Server::import_chain(&server_config, Path::new(path)) {
+                    Ok(summary) => println!(
+                        "Imported {} blocks ({} already known) from {}",
+                        summary.blocks - summary.skipped,
+                        summary.skipped,
+                        path
+                    ),
+                    Err(e) => {
+                        error!("Chain import failed: {:?}", e);
+                        println!("Chain import failed: {:?}", e);
+                        return 1;
+                    }
+                }
+            }
+            Some(("rebuild-kernel-index", _)) => {
+                match servers::Server::rebuild_kernel_index(&server_config) {
+                    Ok(count) => {
+                        println!("Kernel index rebuilt, {} kernels indexed", count);
+                        if server_config.kernel_index != Some(true) {
+                            println!("Set kernel_index = true in the config file to keep it");
+                        }
+                    }
+                    Err(e) => {
+                        error!("Kernel index rebuild failed: {:?}", e);
+                        println!("Kernel index rebuild failed: {:?}", e);
+                        return 1;
+                    }
+                }
+            }
             Some(("", _)) => {
                 println!("Subcommand required, use 'epic server --help' for details");
             }
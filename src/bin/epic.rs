@@ -32,6 +32,7 @@ use epic_util as util;
 use epic_util::logger::LogEntry;
 use servers::foundation::create_foundation;
 use std::env;
+use std::io::{self, IsTerminal};
 use std::path::Path;
 use std::sync::mpsc;
 mod cmd;
@@ -145,8 +146,14 @@ fn real_main() -> i32 {
 	match args.subcommand() {
 		Some(("server", server_args)) => {
 			// If it's just a server config command, do it and exit
-			if let Some(("config", _)) = server_args.subcommand() {
-				cmd::config_command_server(&chain_type, SERVER_CONFIG_FILE_NAME);
+			if let Some(("config", config_args)) = server_args.subcommand() {
+				if config_args.get_flag("migrate") {
+					cmd::config_migrate_server(&chain_type, SERVER_CONFIG_FILE_NAME);
+				} else if config_args.get_flag("non_interactive") || !io::stdin().is_terminal() {
+					cmd::config_command_server(&chain_type, SERVER_CONFIG_FILE_NAME);
+				} else {
+					cmd::config_wizard_server(&chain_type, SERVER_CONFIG_FILE_NAME);
+				}
 				return 0;
 			}
 		}
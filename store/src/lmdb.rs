@@ -263,6 +263,17 @@ impl Store {
 	/// Produces an iterator of (key, value) pairs, where values are `Readable` types
 	/// moving forward from the provided key.
 	pub fn iter<T: ser::Readable>(&self, from: &[u8]) -> Result<SerIterator<T>, Error> {
+		self.iter_from(from, from)
+	}
+
+	/// Produces an iterator of (key, value) pairs over the keys starting with
+	/// `prefix`, beginning at the first key greater or equal to `from`. Used to
+	/// scan a range of an index with ordered keys.
+	pub fn iter_from<T: ser::Readable>(
+		&self,
+		prefix: &[u8],
+		from: &[u8],
+	) -> Result<SerIterator<T>, Error> {
 		let db = self.db.read();
 		let tx = Arc::new(lmdb::ReadTransaction::new(self.env.clone())?);
 		let cursor = Arc::new(tx.cursor(db.as_ref().unwrap().clone()).unwrap());
@@ -270,7 +281,8 @@ impl Store {
 			tx,
 			cursor,
 			seek: false,
-			prefix: from.to_vec(),
+			prefix: prefix.to_vec(),
+			from: from.to_vec(),
 			version: self.version,
 			_marker: marker::PhantomData,
 		})
@@ -381,6 +393,7 @@ where
 	cursor: Arc<lmdb::Cursor<'static, 'static>>,
 	seek: bool,
 	prefix: Vec<u8>,
+	from: Vec<u8>,
 	version: ProtocolVersion,
 	_marker: marker::PhantomData<T>,
 }
@@ -399,7 +412,7 @@ where
 			self.seek = true;
 			Arc::get_mut(&mut self.cursor)
 				.unwrap()
-				.seek_range_k(&access, &self.prefix[..])
+				.seek_range_k(&access, &self.from[..])
 		};
 		match kv {
 			Ok((k, v)) => self.deser_if_prefix_match(k, v),